@@ -0,0 +1,96 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::package_manager::{PackageManager, PackageUpdate, UpdateChecker};
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::CommandRunner;
+
+/// Package managers found in another root, with the binary that shows the
+/// root uses it. dnf and zypper roots are recognized, to tell they aren't
+/// supported.
+const PACKAGE_MANAGERS: [(PackageManager, &str); 6] = [
+    (PackageManager::Pacman, "pacman"),
+    (PackageManager::Apt, "apt-get"),
+    (PackageManager::Dnf, "dnf"),
+    (PackageManager::Zypper, "zypper"),
+    (PackageManager::Apk, "apk"),
+    (PackageManager::Xbps, "xbps-install"),
+];
+
+/// Where the binaries are looked for inside the root; Alpine keeps apk in /sbin.
+const BINARY_DIRS: [&str; 4] = ["usr/bin", "usr/sbin", "bin", "sbin"];
+
+/// Result of checking the alternate root.
+#[derive(Debug, Clone)]
+pub struct RootUpdates {
+    pub root: String,
+    pub package_manager: Option<PackageManager>,
+    pub packages: Vec<PackageUpdate>,
+    /// Why the root wasn't checked, or its check failed
+    pub error: Option<String>,
+}
+
+impl RootUpdates {
+    pub fn update_command(&self, privilege: PrivilegeEscalation) -> Option<String> {
+        self.package_manager?.backend().root_update_command(privilege, &self.root)
+    }
+}
+
+/// The package manager the installation under `root` uses.
+pub fn detect_package_manager(root: &Path) -> Option<PackageManager> {
+    PACKAGE_MANAGERS.iter()
+        .find(|(_, binary)| BINARY_DIRS.iter().any(|dir| root.join(dir).join(binary).exists()))
+        .map(|(package_manager, _)| *package_manager)
+}
+
+/// Checks the installation under `root` with the running system's copy of
+/// its package manager.
+pub async fn check(runner: Arc<dyn CommandRunner>, root: String) -> RootUpdates {
+    let mut result = RootUpdates { root, package_manager: None, packages: Vec::new(), error: None };
+    if !Path::new(&result.root).is_dir() {
+        result.error = Some("not a directory".to_string());
+        return result;
+    }
+    let Some(package_manager) = detect_package_manager(Path::new(&result.root)) else {
+        result.error = Some("no supported package manager".to_string());
+        return result;
+    };
+    result.package_manager = Some(package_manager);
+
+    let checker = UpdateChecker::new(package_manager).root(&result.root).runner(runner);
+    match checker.check_updates(false).await {
+        Ok(update_info) => {
+            result.error = update_info.sources.into_iter().find_map(|source| source.error);
+            result.packages = update_info.packages;
+        }
+        Err(e) => result.error = Some(e.to_string()),
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::{CommandOutput, ScriptedRunner};
+
+    #[tokio::test]
+    async fn checks_root_with_its_package_manager() {
+        let root = std::env::temp_dir().join(format!("package-updater-root-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("usr/bin")).expect("create directory");
+        std::fs::write(root.join("usr/bin/pacman"), "").expect("write file");
+
+        // pacman's query of the root, whatever the root's path
+        let runner = ScriptedRunner::new([("pacman", CommandOutput::new(0, "linux 6.7.4.arch1-1 -> 6.7.5.arch1-1\n", ""))]);
+        let updates = check(Arc::new(runner), root.display().to_string()).await;
+        assert_eq!(updates.package_manager, Some(PackageManager::Pacman));
+        assert_eq!(updates.error, None);
+        assert_eq!(updates.packages.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), ["linux"]);
+        std::fs::remove_dir_all(&root).expect("remove directory");
+
+        let updates = RootUpdates { root: "/mnt/it's".to_string(), ..updates };
+        assert_eq!(
+            updates.update_command(PrivilegeEscalation::Sudo).as_deref(),
+            Some(r"sudo pacman --sysroot '/mnt/it'\''s' -Syu")
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use anyhow::Result;
+
+use crate::backend::{self, Backend, Capabilities};
+use crate::package_manager::{CheckMode, PackageUpdate};
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::shell_quote;
+
+/// Alpine Linux.
+pub struct Apk;
+
+impl Backend for Apk {
+    fn name(&self) -> &str {
+        "apk"
+    }
+
+    fn detect(&self) -> bool {
+        backend::on_path("apk")
+    }
+
+    /// apk keeps its indexes in a root-owned cache.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { selective_update: true, needs_privileges: true, ..Capabilities::default() }
+    }
+
+    fn check_command(&self, _mode: CheckMode) -> Option<(&str, Vec<&str>)> {
+        Some(("apk", vec!["-u", "list"]))
+    }
+
+    /// "package-version-rN arch {origin} (license) [upgradable from: package-old-version-rN]"
+    fn parse_line(&self, line: &str) -> Option<PackageUpdate> {
+        let (_, from) = line.split_once("[upgradable from: ")?;
+        let (name, new_version) = split_package(line.split_whitespace().next()?);
+        let current_version = match from.split_once(']') {
+            Some((old, _)) => split_package(old).1,
+            None => "unknown",
+        };
+        Some(PackageUpdate::new(name, current_version, new_version))
+    }
+
+    fn root_check_command(&self, root: &str) -> Result<(&str, Vec<String>)> {
+        Ok(("apk", ["--root", root, "-u", "list"].map(str::to_string).to_vec()))
+    }
+
+    fn update_command(&self, privilege: PrivilegeEscalation) -> Option<String> {
+        Some(privilege.wrap("apk upgrade"))
+    }
+
+    fn packages_update_command(&self, privilege: PrivilegeEscalation, packages: &[String]) -> Option<String> {
+        if packages.is_empty() {
+            return None;
+        }
+        Some(privilege.wrap(&format!("apk add --upgrade {}", packages.join(" "))))
+    }
+
+    fn root_update_command(&self, privilege: PrivilegeEscalation, root: &str) -> Option<String> {
+        Some(privilege.wrap(&format!("apk --root {} upgrade", shell_quote(root))))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; 'apk fix' repairs half-installed packages."
+    }
+}
+
+/// Splits an apk "name-version-rN" string, e.g. "py3-urllib3-1.26.18-r0"
+/// into ("py3-urllib3", "1.26.18-r0").
+fn split_package(package: &str) -> (&str, &str) {
+    let mut dashes = package.rmatch_indices('-').map(|(idx, _)| idx);
+    match (dashes.next(), dashes.next()) {
+        (Some(_), Some(idx)) => (&package[..idx], &package[idx + 1..]),
+        _ => (package, "unknown"),
+    }
+}
@@ -1,17 +1,103 @@
+use chrono::Timelike;
 use cosmic::app::{Core, Task};
 use cosmic::cosmic_config::Config;
-use cosmic::iced::{time, Subscription, window::Id, Limits};
+use cosmic::iced::{event, keyboard, time, Subscription, window::Id, Limits};
 use cosmic::iced::platform_specific::shell::wayland::commands::popup::{destroy_popup, get_popup};
 use cosmic::iced::window;
 use cosmic::widget::{
     button, column, row, text, text_input, toggler, Space, horizontal_space, divider, scrollable, autosize
 };
+use cosmic::widget::scrollable::{RelativeOffset, Viewport};
 use cosmic::Element;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::time::{Duration, Instant};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
 
+use crate::alternate_root::{self, RootUpdates};
+use crate::app_id;
+use crate::appimage::{self, AppImageUpdate};
+use crate::backend::{self, Backend};
+use crate::busy::{self, BusyReason};
 use crate::config::PackageUpdaterConfig;
-use crate::package_manager::{PackageManager, PackageManagerDetector, UpdateChecker, UpdateInfo};
+use crate::containers::{self, ContainerUpdates, Tool};
+use crate::custom_source::{CustomSource, Parser};
+use crate::demo;
+use crate::diagnostics;
+use crate::export::{self, Severity, StatusSnapshot};
+use crate::firmware::{self, StagedFirmware};
+use crate::health::{self, SourceHealth};
+use crate::held_back::{self, HeldBack};
+use crate::history::{self, CountChange, HistoryEntry, RunOutcome, Summary, WEEK_SECS};
+use crate::http;
+use crate::icons::{self, BadgePosition, BadgeSource, IconRole, IconSource};
+use crate::kernel::{self, Highlights, KernelUpdate, KernelVersion, ReleaseKind};
+use crate::leader::{self, Membership};
+use crate::locale;
+use crate::merge;
+use crate::metrics::{self, Metrics};
+use crate::mqtt::{self, Broker, MqttStatus};
+use crate::notifications;
+use crate::os_release::OsRelease;
+use crate::merge::Source;
+use crate::package_manager::{CheckMode, CheckOrigin, FlatpakInstallation, PackageManager, PackageManagerDetector, PackageUpdate, UpdateChecker, UpdateInfo};
+use crate::plugin;
+use crate::preflight::{self, Preflight};
+use crate::privilege::PrivilegeEscalation;
+use crate::readiness;
+use crate::recovery::{self, SurfaceLoss, SurfaceRecovery, ViewSnapshot};
+use crate::remote::{self, HostUpdates, RemoteHost};
+use crate::risk;
+use crate::runner::SystemRunner;
+use crate::schedule::{self, Reminder};
+use crate::selection::Selection;
+use crate::self_update;
+use crate::sound::{self, Cue};
+use crate::state::{self, PersistentState, Session, Snooze};
+use crate::sync;
+use crate::sysinfo::{self, SystemSummary};
+use crate::terminal;
+use crate::topgrade;
+use crate::update_all::{self, RunProgress, Step};
+use crate::trust::{self, ClockStatus, TrustFailure};
+use crate::webhook::{self, Report, WebhookFormat};
+
+/// The package list keeps its scroll position if the popup is reopened within this time.
+const SCROLL_MEMORY: Duration = Duration::from_secs(5 * 60);
+
+static PACKAGE_LIST_ID: LazyLock<cosmic::widget::Id> = LazyLock::new(|| cosmic::widget::Id::new("package-list"));
+
+/// Minimum time between manual quick checks.
+const QUICK_CHECK_COOLDOWN_SECS: u64 = 10;
+
+/// Minimum time between manual metadata refreshes, to go easy on the mirrors.
+const FORCED_REFRESH_COOLDOWN_SECS: i64 = 15 * 60;
+
+/// Checks of other instances following one within this time are not reported again.
+const WEBHOOK_DEDUP_SECS: i64 = 30;
+
+/// Minimum time between clock comparisons with the mirror after successful checks.
+const CLOCK_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How often installed package managers are looked for again, on top of
+/// changes to the package databases.
+const REDETECT_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// Longest gap between the clicks of a triple click.
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(600);
+
+/// Length limit of a package note, which is shown in the list.
+const MAX_NOTE_CHARS: usize = 200;
+
+/// Entries of the package list built before "Show all" is pressed.
+const PACKAGE_LIST_PREVIEW: usize = 50;
+
+/// How often a check held back by a game or do not disturb looks again.
+const BUSY_RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Package databases that change when packages are installed or removed.
+const PACKAGE_DATABASES: [&str; 5] = ["/var/lib/pacman/local", "/var/lib/dpkg", "/var/lib/rpm", "/usr/lib/sysimage/rpm", "/lib/apk/db"];
 
 pub struct CosmicAppletPackageUpdater {
     core: Core,
@@ -21,47 +107,317 @@ pub struct CosmicAppletPackageUpdater {
     config_handler: Config,
     update_info: UpdateInfo,
     last_check: Option<Instant>,
+    /// Another instance whose check the last check followed, `None` for our own
+    last_check_origin: Option<CheckOrigin>,
     checking_updates: bool,
+    /// When the running check started, for the diagnostics
+    check_started: Option<Instant>,
     error_message: Option<String>,
     available_package_managers: Vec<PackageManager>,
-    ignore_next_sync: bool,
+    /// The running distribution, `None` in demo mode or without os-release
+    os_release: Option<OsRelease>,
+    /// Manager to offer instead of the configured one: one superseding it
+    /// was installed, or the configured one was uninstalled
+    suggested_package_manager: Option<PackageManager>,
+    /// Last sync message from another instance that was acted upon
+    last_sync_check_id: Option<String>,
+    /// Registration among the running instances, `None` if it failed and
+    /// this instance checks on its own
+    membership: Option<Membership>,
+    read_only: bool,
+    demo: bool,
+    /// Updates a demo update took off the list
+    demo_installed: HashSet<String>,
+    active_terminal: Option<String>,
+    /// From launching an update until its terminal closes, including the
+    /// time before the terminal reported back
+    update_running: bool,
+    /// Steps of the "Update Everything" run in the terminal
+    update_all_steps: Option<Vec<Step>>,
+    fwupd_available: bool,
+    appimageupdatetool_available: bool,
+    /// AppImages in the configured directory with a newer release
+    appimage_updates: Vec<AppImageUpdate>,
+    /// Installed container tools (Distrobox, Toolbox)
+    container_tools: Vec<Tool>,
+    /// Last check of each container
+    container_updates: Vec<ContainerUpdates>,
+    remote_updates: Vec<HostUpdates>,
+    /// What the next topgrade run would do, as of the last check
+    topgrade_steps: Option<Result<Vec<topgrade::Step>, String>>,
+    /// Host being added in the settings
+    remote_host_draft: String,
+    /// Last check of the alternate root, `None` while none is configured
+    root_updates: Option<RootUpdates>,
+    /// Custom sources of the config and plugins, checked after the package managers
+    custom_sources: Vec<Arc<dyn Backend>>,
+    /// Why custom sources of the config were left out
+    custom_source_errors: Vec<String>,
+    /// Plugins in the plugin directories that aren't enabled
+    disabled_plugins: Vec<String>,
+    terminal_fallbacks_input: String,
+    available_privilege_tools: Vec<PrivilegeEscalation>,
+    expanded_groups: HashSet<String>,
+    /// Sources folded in the package list
+    collapsed_sources: HashSet<Source>,
+    risk_details_expanded: bool,
+    held_back_expanded: bool,
+    /// The earlier count changes are listed under the latest
+    count_changes_expanded: bool,
+    /// The whole package list is built, not only its first entries
+    package_list_expanded: bool,
+    /// Packages picked for "Update Selected"
+    selection: Selection,
+    /// Shift is held, so a click on a package selects a range
+    shift_held: bool,
+    /// Package whose note is being edited, with the text typed so far
+    note_editor: Option<(String, String)>,
+    /// Custom source being defined in the settings
+    custom_source_draft: CustomSource,
+    /// Why the draft couldn't be added
+    custom_source_draft_error: Option<String>,
+    preflight_running: bool,
+    /// Problems the last dry run found, until the update is confirmed or dismissed
+    preflight: Option<Preflight>,
+    /// Skip the dry run for the next launch
+    preflight_confirmed: bool,
+    /// New binary of the applet, if an update replaced the running one
+    replaced_binary: Option<PathBuf>,
+    /// Set while the popup and subscriptions are torn down after a display error
+    surface_recovery: SurfaceRecovery,
+    deferred_check_pending: bool,
+    /// Why the last scheduled check was held back, until it could run
+    session_busy: Option<BusyReason>,
+    state: PersistentState,
+    history: Vec<HistoryEntry>,
+    /// Pending packages when the last update run finished, compared against
+    /// the next check to find out what was actually installed
+    pre_update_packages: Option<Vec<PackageUpdate>>,
+    /// Packages the running update was started for, all pending ones
+    /// unless a single group is updated
+    update_targets: Option<Vec<PackageUpdate>>,
+    /// What the last update run did; its failed updates are marked until
+    /// they are installed
+    last_run_outcome: Option<RunOutcome>,
+    run_outcome_dismissed: bool,
+    /// Id of the last "updates available" notification, to match its actions
+    update_notification_id: Option<u32>,
+    /// Upstream changelog summary for the pending stable kernel release
+    kernel_highlights: Option<(KernelVersion, Result<Highlights, String>)>,
+    /// Set when the last check failed on a certificate or signature
+    trust_failure: Option<TrustFailure>,
+    clock_status: Option<ClockStatus>,
+    last_clock_check: Option<Instant>,
+    system_summary: Option<SystemSummary>,
+    /// Whether the user can elevate with the configured tool, `None` until checked
+    admin_rights: Option<bool>,
+    /// Tab the popup was last closed on
+    last_tab: Option<PopupTab>,
+    popup_closed_at: Option<Instant>,
+    package_list_offset: RelativeOffset,
+    /// Last pointer or keyboard input in the applet, for the idle timeout
+    popup_last_activity: Instant,
+    started: Instant,
+    last_timer_tick: Option<Instant>,
+    /// Recent messages, for the scheduler view
+    message_log: diagnostics::MessageLog,
+    /// Recent clicks on the version string; three in a row open the scheduler view
+    version_clicks: Vec<Instant>,
+    /// Scheduler state as of opening or refreshing the view, `None` while hidden
+    scheduler_view: Option<Vec<String>>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Default)]
+pub struct Flags {
+    /// Render only the update count and severity, without popup or actions.
+    pub read_only: bool,
+    /// Show made-up updates from a fake backend and run nothing (`--demo`)
+    pub demo: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PopupTab {
     Updates,
+    History,
     Settings,
 }
 
+impl PopupTab {
+    pub const ALL: [PopupTab; 3] = [PopupTab::Updates, PopupTab::History, PopupTab::Settings];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PopupTab::Updates => "Updates",
+            PopupTab::History => "History",
+            PopupTab::Settings => "Settings",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Message {
     TogglePopup,
     PopupClosed(Id),
     SwitchTab(PopupTab),
-    CheckForUpdates,
+    CheckForUpdates(CheckMode),
     DelayedStartupCheck,
     UpdatesChecked(Result<UpdateInfo, String>),
-    ConfigChanged(PackageUpdaterConfig),
+    ConfigChanged(Box<PackageUpdaterConfig>),
     LaunchTerminalUpdate,
-    TerminalFinished,
+    /// Runs every source's update in one terminal session
+    UpdateEverything,
+    ResumeUpdateEverything,
+    DismissRunOutcome,
+    TerminalLaunched(Result<String, String>),
+    TerminalFinished(Duration),
     Timer,
+    DeferredCheckDue,
+    SessionBusyChecked(Option<BusyReason>),
+    ToggleDeferWhenBusy(bool),
     DiscoverPackageManagers,
+    RedetectPackageManagers,
+    PackageManagersDetected(Vec<PackageManager>),
+    SwitchToSuggestedPackageManager,
+    MovePackageManagerPreference(PackageManager, bool),
+    ResetPackageManagerPreference,
+    DismissPackageManagerSuggestion,
     SelectPackageManager(PackageManager),
+    TogglePackageManager(PackageManager, bool),
     SetCheckInterval(u32),
+    SetStartupDelay(u32),
+    ToggleWaitForGraphicalSession(bool),
     ToggleAutoCheck(bool),
+    ToggleClockSkewCheck(bool),
     ToggleIncludeAur(bool),
     ToggleShowNotifications(bool),
     ToggleShowUpdateCount(bool),
+    ToggleHighContrastIndicators(bool),
+    SelectIconSource(IconSource),
+    SelectBadgeSource(BadgeSource),
+    SelectHorizontalBadgePosition(BadgePosition),
+    SelectVerticalBadgePosition(BadgePosition),
     SetPreferredTerminal(String),
+    SetTerminalFallbacks(String),
+    SelectPrivilegeEscalation(PrivilegeEscalation),
+    ToggleCheckWindow(bool),
+    ToggleSoundOnUpdates(bool),
+    ToggleSoundOnUpdateFinished(bool),
+    ToggleSoundQuietHours(bool),
+    SetSoundQuietStart(u8),
+    SetSoundQuietEnd(u8),
+    SoundPlayed(Result<(), String>),
+    SetCheckWindowStart(u8),
+    SetCheckWindowEnd(u8),
+    ToggleGroup(String),
+    ToggleSource(Source),
+    TogglePackageSelected(String),
+    SelectOfficial,
+    SelectSecurity,
+    UpdateSecurity,
+    DeselectAur,
+    ClearSelection,
+    UpdateSelected,
+    ShiftHeld(bool),
+    EditNote(String),
+    NoteChanged(String),
+    SaveNote,
+    RemoveNote,
+    CancelNote,
+    MarkChangesReviewed,
+    ToggleRiskDetails,
+    ToggleHeldBack,
+    HeldBackExplained(Vec<HeldBack>),
+    ToggleCountChanges,
+    /// A click on the version string in the settings
+    VersionClicked,
+    RefreshSchedulerView,
+    ShowAllPackages,
+    PreflightChecked(Result<Preflight, String>),
+    ConfirmUpdate,
+    RestartApplet,
+    /// The windowing layer reported a surface of the applet gone
+    SurfaceLost(SurfaceLoss),
+    RestoreView,
+    DismissPreflight,
+    TogglePreflightCheck(bool),
+    UpdateGroup(String),
     SyncFileChanged,
+    FirmwareStagedChecked(Result<Vec<StagedFirmware>, String>),
+    AppImagesChecked(Result<Vec<AppImageUpdate>, String>),
+    SetAppImageDirectory(String),
+    UpdateAppImages,
+    ContainersChecked(Result<Vec<ContainerUpdates>, String>),
+    RemoteHostsChecked(Vec<HostUpdates>),
+    RemoteHostDraftChanged(String),
+    AddRemoteHost,
+    RemoveRemoteHost(String),
+    UpdateRemoteHost(String),
+    ToggleCheckContainers(bool),
+    ToggleStartStoppedContainers(bool),
+    ToggleUseTopgrade(bool),
+    TopgradeChecked(Result<Vec<topgrade::Step>, String>),
+    ToggleShowAllPackageManagers(bool),
+    AlternateRootChecked(RootUpdates),
+    SetAlternateRoot(String),
+    CustomSourceDraftChanged(CustomSource),
+    AddCustomSource,
+    RemoveCustomSource(String),
+    TogglePlugin(String, bool),
+    UpdateAlternateRoot,
+    UpdateContainer(String),
+    UpdateFlatpak(FlatpakInstallation),
+    UpdateCustomSource(String),
+    RebootToApplyFirmware,
+    ToggleWeeklySummary(bool),
+    SelectDefaultTab(PopupTab),
+    ToggleRememberLastTab(bool),
+    PackageListScrolled(Viewport),
+    ToggleCloseOnUpdate(bool),
+    TogglePinDuringUpdate(bool),
+    SetPopupIdleTimeout(u32),
+    SetReservedCountDigits(u8),
+    PopupActivity,
+    PopupFocused(Id),
+    PopupIdleTick,
+    NotificationSent(Result<u32, String>),
+    UpdateNotificationSent(Result<u32, String>),
+    NotificationAction(u32, String),
+    Snooze(Reminder),
+    CancelSnooze,
+    SnoozeExpired,
+    KernelHighlightsFetched(KernelVersion, Result<Highlights, String>),
+    ClockChecked(ClockStatus),
+    SystemProbed(SystemSummary),
+    OpenUrl(String),
+    SetProxy(String),
+    SetWebhookUrl(String),
+    SetMetricsTextfile(String),
+    ToggleDiagnostics(bool),
+    ToggleStrictParsing(bool),
+    ToggleRepositoryCounted(String, bool),
+    SaveDiagnosticsReport,
+    CopyDiagnosticsReport,
+    ResetDiagnostics,
+    SetMqttBroker(String),
+    SetMqttUsername(String),
+    SetMqttPassword(String),
+    MqttPublished(Result<(), String>),
+    SelectWebhookFormat(WebhookFormat),
+    WebhookPosted(Result<(), String>),
+    PrivilegesChecked(bool),
+    DismissReportOnlyIntro,
+    ExportReport,
+    EmailReport,
+    /// SIGTERM, SIGINT or SIGHUP: the panel restarts or the session ends
+    Shutdown,
 }
 
 impl cosmic::Application for CosmicAppletPackageUpdater {
     type Executor = cosmic::executor::Default;
-    type Flags = ();
+    type Flags = Flags;
     type Message = Message;
 
-    const APP_ID: &'static str = "com.cosmic.PackageUpdater";
+    const APP_ID: &'static str = app_id::APP_ID;
 
     fn core(&self) -> &Core {
         &self.core
@@ -75,41 +431,132 @@ impl cosmic::Application for CosmicAppletPackageUpdater {
         Some(cosmic::applet::style())
     }
 
-    fn init(core: Core, _flags: Self::Flags) -> (Self, Task<Self::Message>) {
-        let (config_handler, config) = PackageUpdaterConfig::load();
-        let available_package_managers = PackageManagerDetector::detect_available();
+    fn init(core: Core, flags: Self::Flags) -> (Self, Task<Self::Message>) {
+        let (config_handler, mut config) = PackageUpdaterConfig::load();
+        let mut available_package_managers = PackageManagerDetector::detect_available();
+        if flags.demo {
+            config = demo::config();
+            available_package_managers = demo::PACKAGE_MANAGERS.to_vec();
+        }
 
-        let app = Self {
+        let mut app = Self {
             core,
             popup: None,
-            active_tab: PopupTab::Updates,
+            active_tab: config.default_tab,
             config,
             config_handler,
             update_info: UpdateInfo::new(),
             last_check: None,
+            last_check_origin: None,
             checking_updates: false,
+            check_started: None,
             error_message: None,
             available_package_managers,
-            ignore_next_sync: true,
+            os_release: OsRelease::load().filter(|_| !flags.demo),
+            suggested_package_manager: None,
+            last_sync_check_id: None,
+            membership: Membership::join()
+                .map_err(|e| eprintln!("Failed to register with the other instances: {}", e))
+                .ok(),
+            read_only: flags.read_only,
+            demo: flags.demo,
+            demo_installed: HashSet::new(),
+            active_terminal: None,
+            update_running: false,
+            update_all_steps: None,
+            fwupd_available: firmware::is_available(),
+            appimageupdatetool_available: appimage::is_available(),
+            appimage_updates: Vec::new(),
+            container_tools: Tool::ALL.into_iter().filter(Tool::is_available).collect(),
+            container_updates: Vec::new(),
+            remote_updates: Vec::new(),
+            topgrade_steps: None,
+            remote_host_draft: String::new(),
+            root_updates: None,
+            custom_sources: Vec::new(),
+            custom_source_errors: Vec::new(),
+            disabled_plugins: Vec::new(),
+            terminal_fallbacks_input: String::new(),
+            available_privilege_tools: PrivilegeEscalation::ALL
+                .into_iter()
+                .filter(PrivilegeEscalation::is_available)
+                .collect(),
+            expanded_groups: HashSet::new(),
+            collapsed_sources: HashSet::new(),
+            risk_details_expanded: false,
+            held_back_expanded: false,
+            count_changes_expanded: false,
+            package_list_expanded: false,
+            selection: Selection::default(),
+            shift_held: false,
+            note_editor: None,
+            custom_source_draft: CustomSource::default(),
+            custom_source_draft_error: None,
+            preflight_running: false,
+            preflight: None,
+            preflight_confirmed: false,
+            replaced_binary: None,
+            surface_recovery: SurfaceRecovery::default(),
+            deferred_check_pending: false,
+            session_busy: None,
+            state: PersistentState::load(),
+            history: history::load(),
+            pre_update_packages: None,
+            update_targets: None,
+            last_run_outcome: None,
+            run_outcome_dismissed: false,
+            update_notification_id: None,
+            kernel_highlights: None,
+            trust_failure: None,
+            clock_status: None,
+            last_clock_check: None,
+            system_summary: None,
+            admin_rights: None,
+            last_tab: None,
+            popup_closed_at: None,
+            package_list_offset: RelativeOffset::START,
+            popup_last_activity: Instant::now(),
+            started: Instant::now(),
+            last_timer_tick: None,
+            message_log: diagnostics::MessageLog::default(),
+            version_clicks: Vec::new(),
+            scheduler_view: None,
         };
 
-        let mut tasks = vec![];
+        app.terminal_fallbacks_input = app.config.terminal_fallbacks.join(", ");
+        if !app.read_only && !app.demo {
+            if let Some(session) = Session::take() {
+                app.restore_session(session);
+            }
+        }
+        (app.custom_sources, app.custom_source_errors, app.disabled_plugins) = backend::user_backends(&app.config.custom_sources, &app.config.enabled_plugins);
+
+        // The selected manager was uninstalled while the applet wasn't running
+        if app.config.package_manager().is_some_and(|pm| !app.available_package_managers.contains(&pm)) {
+            app.suggested_package_manager = PackageManagerDetector::preferred(&app.listed_package_managers(), &app.config.preference_order());
+        }
+
+        // Refresh the persisted firmware state, it is cleared once the reboot happened
+        let mut tasks = vec![
+            app.check_staged_firmware(),
+            app.probe_system(),
+            app.check_privileges(app.config.privilege_escalation),
+        ];
+
+        // Re-arm a reminder set before the applet restarted
+        if let Some(snooze) = app.state.snooze.as_ref().filter(|_| !app.read_only) {
+            tasks.push(Self::wait_for_snooze(snooze.until));
+        }
 
         // Auto-discover package managers on startup if none is configured
-        if app.config.package_manager.is_none() {
+        if app.config.package_manager().is_none() {
             tasks.push(Task::done(cosmic::Action::App(Message::DiscoverPackageManagers)));
         }
 
         // Check for updates on startup if enabled and package manager is available
         if app.config.auto_check_on_startup {
-            if app.config.package_manager.is_some() {
-                // Add a delay to allow system to stabilize
-                tasks.push(Task::perform(
-                    async move {
-                        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-                    },
-                    |_| cosmic::Action::App(Message::CheckForUpdates),
-                ));
+            if app.config.package_manager().is_some() {
+                tasks.push(app.startup_check());
             } else {
                 // Delay the update check until after package manager discovery
                 tasks.push(Task::done(cosmic::Action::App(Message::DelayedStartupCheck)));
@@ -124,28 +571,20 @@ impl cosmic::Application for CosmicAppletPackageUpdater {
     }
 
     fn view(&self) -> Element<'_, Self::Message> {
-        if self.config.show_update_count {
-            // Always show custom button with icon and count (empty string when 0)
-            let count_text = if self.update_info.total_updates > 0 {
-                format!("{}", self.update_info.total_updates)
-            } else {
-                String::new()
-            };
+        if self.read_only {
+            return self.view_read_only();
+        }
 
-            let custom_button = button::custom(
-                row()
-                    .align_y(cosmic::iced::Alignment::Center)
-                    .spacing(2)
-                    .push(cosmic::widget::icon::from_name(self.get_icon_name()).size(16))
-                    .push(text(count_text).size(12))
-            )
+        if self.config.show_update_count || self.config.high_contrast_indicators {
+            // Always show custom button with icon and count (empty string when 0)
+            let custom_button = button::custom(self.panel_badge())
             .padding([8, 4])
             .class(cosmic::theme::Button::AppletIcon)
             .on_press(Message::TogglePopup);
 
             let limits = Limits::NONE.min_width(1.0).min_height(1.0);
 
-            let content: Element<_> = if self.update_info.has_updates() {
+            let content: Element<_> = if self.update_info.has_updates() && !self.report_only() && !self.update_running {
                 cosmic::widget::mouse_area(custom_button)
                     .on_middle_press(Message::LaunchTerminalUpdate)
                     .into()
@@ -159,10 +598,10 @@ impl cosmic::Application for CosmicAppletPackageUpdater {
         } else {
             let icon_button = self.core
                 .applet
-                .icon_button(&self.get_icon_name())
+                .icon_button_from_handle(icons::handle(self.icon_role(), self.config.icon_source))
                 .on_press(Message::TogglePopup);
 
-            if self.update_info.has_updates() {
+            if self.update_info.has_updates() && !self.report_only() && !self.update_running {
                 cosmic::widget::mouse_area(icon_button)
                     .on_middle_press(Message::LaunchTerminalUpdate)
                     .into()
@@ -183,6 +622,13 @@ impl cosmic::Application for CosmicAppletPackageUpdater {
         })
         .on_press(Message::SwitchTab(PopupTab::Updates));
 
+        let history_button = button::text(if self.active_tab == PopupTab::History {
+            "● History"
+        } else {
+            "○ History"
+        })
+        .on_press(Message::SwitchTab(PopupTab::History));
+
         let settings_button = button::text(if self.active_tab == PopupTab::Settings {
             "● Settings"
         } else {
@@ -190,34 +636,47 @@ impl cosmic::Application for CosmicAppletPackageUpdater {
         })
         .on_press(Message::SwitchTab(PopupTab::Settings));
 
+        // Right-to-left languages read the tabs and the layout mirrored
+        let rtl = locale::is_rtl();
+        let (first_tab, last_tab) = if rtl { (settings_button, updates_button) } else { (updates_button, settings_button) };
         let tabs = row()
             .width(cosmic::iced::Length::Fill)
-            .push(updates_button)
+            .push(first_tab)
+            .push(
+                cosmic::widget::container(horizontal_space())
+                    .width(cosmic::iced::Length::Fill)
+            )
+            .push(history_button)
             .push(
                 cosmic::widget::container(horizontal_space())
                     .width(cosmic::iced::Length::Fill)
             )
-            .push(settings_button);
+            .push(last_tab);
 
         // Tab content
         let tab_content = match self.active_tab {
             PopupTab::Updates => self.view_updates_tab(),
+            PopupTab::History => self.view_history_tab(),
             PopupTab::Settings => self.view_settings_tab(),
         };
 
-        // Package illustration - dynamic based on update status
-        let (icon_name, emoji) = if self.checking_updates {
-            ("view-refresh-symbolic", "⏳")
+        // Package illustration - dynamic based on update status. High
+        // contrast shows the panel's state and glyph instead of the emoji
+        let (icon_role, emoji) = if self.config.high_contrast_indicators {
+            let role = self.icon_role();
+            (role, role.glyph())
+        } else if self.checking_updates {
+            (IconRole::Checking, "⏳")
         } else if self.update_info.has_updates() {
-            ("software-update-available-symbolic", "🎁")
+            (IconRole::UpdatesAvailable, "🎁")
         } else {
-            ("package-x-generic", "✅")
+            (IconRole::UpToDate, "✅")
         };
 
         let status_text = if self.checking_updates {
             text("Checking...").size(11).align_x(cosmic::iced::Alignment::Center)
         } else if self.update_info.has_updates() {
-            text(format!("{} Updates", self.update_info.total_updates)).size(11).align_x(cosmic::iced::Alignment::Center)
+            text(format!("{} Updates", locale::format_count(self.update_info.total_updates))).size(11).align_x(cosmic::iced::Alignment::Center)
         } else {
             text("Up to Date").size(11).align_x(cosmic::iced::Alignment::Center)
         };
@@ -226,7 +685,7 @@ impl cosmic::Application for CosmicAppletPackageUpdater {
             column()
                 .align_x(cosmic::iced::Alignment::Center)
                 .spacing(12)
-                .push(cosmic::widget::icon::from_name(icon_name).size(48))
+                .push(cosmic::widget::icon::icon(icons::handle(icon_role, self.config.icon_source)).size(48))
                 .push(text(emoji).size(28))
                 .push(status_text)
         )
@@ -241,15 +700,16 @@ impl cosmic::Application for CosmicAppletPackageUpdater {
         .padding(12);
 
         // Main content area with illustration
-        let main_content = row()
-            .spacing(space_m)
-            .push(
-                column()
-                    .spacing(space_s)
-                    .width(cosmic::iced::Length::Fill)
-                    .push(tab_content)
-            )
-            .push(package_illustration);
+        let tab_column = column()
+            .spacing(space_s)
+            .width(cosmic::iced::Length::Fill)
+            .align_x(if rtl { cosmic::iced::Alignment::End } else { cosmic::iced::Alignment::Start })
+            .push(tab_content);
+        let main_content = if rtl {
+            row().spacing(space_m).push(package_illustration).push(tab_column)
+        } else {
+            row().spacing(space_m).push(tab_column).push(package_illustration)
+        };
 
         let content = column()
             .spacing(space_s)
@@ -272,601 +732,4322 @@ impl cosmic::Application for CosmicAppletPackageUpdater {
     }
 
     fn update(&mut self, message: Self::Message) -> Task<Self::Message> {
+        self.message_log.record(&message, chrono::Local::now().timestamp());
         match message {
+            // Read-only surfaces never open the popup or launch updates
+            Message::TogglePopup
+            | Message::LaunchTerminalUpdate
+            | Message::UpdateEverything
+            | Message::ResumeUpdateEverything
+            | Message::UpdateAppImages
+            | Message::UpdateContainer(_)
+            | Message::UpdateAlternateRoot
+            | Message::UpdateCustomSource(_) if self.read_only => Task::none(),
+            // The popup comes back by itself once the surfaces are recreated
+            Message::TogglePopup if self.surface_recovery.in_progress() => Task::none(),
+            // The update would stop at the first password prompt
+            Message::LaunchTerminalUpdate
+            | Message::UpdateGroup(_)
+            | Message::UpdateSelected
+            | Message::UpdateEverything
+            | Message::ResumeUpdateEverything if self.report_only() => Task::none(),
+            // A second package manager run would fail on the database lock, and
+            // a check would read the database while it changes
+            Message::LaunchTerminalUpdate
+            | Message::UpdateGroup(_)
+            | Message::UpdateSelected
+            | Message::UpdateEverything
+            | Message::ResumeUpdateEverything
+            | Message::UpdateAppImages
+            | Message::UpdateContainer(_)
+            | Message::UpdateAlternateRoot
+            | Message::UpdateCustomSource(_)
+            | Message::ConfirmUpdate
+            | Message::CheckForUpdates(_) if self.update_running => Task::none(),
             Message::TogglePopup => self.handle_toggle_popup(),
             Message::PopupClosed(id) => self.handle_popup_closed(id),
             Message::SwitchTab(tab) => self.handle_switch_tab(tab),
-            Message::CheckForUpdates => {
-                if let Some(pm) = self.config.package_manager {
-                    self.checking_updates = true;
-                    self.error_message = None;
-                    let checker = UpdateChecker::new(pm);
-                    let include_aur = self.config.include_aur_updates;
-                    return Task::perform(
-                        async move {
-                            checker.check_updates(include_aur).await
-                        },
-                        |result| cosmic::Action::App(Message::UpdatesChecked(result.map_err(|e| e.to_string()))),
-                    );
-                }
-                Task::none()
-            }
+            Message::CheckForUpdates(mode) => self.start_check(mode, None),
             Message::UpdatesChecked(result) => {
                 self.checking_updates = false;
+                self.replaced_binary = self_update::replaced_binary();
+                if let Some(started) = self.check_started.take() {
+                    self.record_diagnostics(started.elapsed(), result.as_ref().map_err(String::as_str));
+                }
                 match result {
                     Ok(update_info) => {
+                        let after_update = self.pre_update_packages.is_some();
+                        if let Some(before) = self.pre_update_packages.take() {
+                            let mut outcome = RunOutcome::compare(&before, &update_info.packages);
+                            // The upgrade only staged a deployment
+                            outcome.reboot_recommended |= !outcome.installed.is_empty()
+                                && self.config.package_manager().is_some_and(|pm| pm.capabilities().applies_on_reboot);
+                            self.record_installed(&outcome);
+                            self.last_run_outcome = Some(outcome);
+                            self.run_outcome_dismissed = false;
+                        } else {
+                            // Failures stay marked until installed some other way
+                            if let Some(outcome) = &mut self.last_run_outcome {
+                                outcome.retain_pending(&update_info.packages);
+                            }
+                            self.record_installed_outside(&update_info);
+                        }
+                        self.selection.retain_pending(&update_info.packages);
+                        if !self.read_only {
+                            let now = chrono::Local::now().timestamp();
+                            health::record_all(&mut self.state.source_health, &update_info.sources, now);
+                            // A check that failed for another reason says
+                            // nothing about the repositories it didn't name
+                            let check_succeeded = update_info.sources.iter().all(|source| source.error.is_none());
+                            health::record_repositories(&mut self.state.repository_health, &update_info.failing_repositories, check_succeeded, now);
+                            if self.last_check.is_some() {
+                                if let Some(change) = CountChange::compare(&self.update_info.packages, &update_info.packages, after_update, now) {
+                                    self.state.count_changes.push(change);
+                                    let excess = self.state.count_changes.len().saturating_sub(history::MAX_COUNT_CHANGES);
+                                    self.state.count_changes.drain(..excess);
+                                }
+                            }
+                            self.state.save();
+                        }
                         self.update_info = update_info;
                         self.last_check = Some(Instant::now());
                         self.error_message = None;
+                        self.trust_failure = None;
+                        self.preflight = None;
                     }
                     Err(error) => {
-                        // Handle specific Wayland errors that might occur after system updates
-                        if error.contains("Protocol error") || error.contains("wl_surface") {
-                            self.error_message = Some("Display system updated. Please restart the applet if issues persist.".to_string());
-                        } else {
-                            self.error_message = Some(error);
+                        self.trust_failure = TrustFailure::detect(&error);
+                        self.error_message = Some(error);
+                    }
+                }
+                if !self.read_only {
+                    self.publish_status();
+                    self.write_metrics();
+                }
+                Task::batch(vec![
+                    self.check_staged_firmware(),
+                    self.check_appimages(),
+                    self.check_containers(),
+                    self.check_remote_hosts(),
+                    self.check_topgrade(),
+                    self.check_alternate_root(),
+                    self.maybe_send_weekly_summary(),
+                    self.maybe_notify_updates(),
+                    self.maybe_post_webhook(),
+                    self.publish_mqtt(),
+                    self.fetch_kernel_highlights(),
+                    self.probe_system(),
+                    self.check_clock(),
+                ])
+            }
+            Message::FirmwareStagedChecked(result) => {
+                match result {
+                    Ok(staged) => {
+                        if staged != self.state.staged_firmware {
+                            self.state.staged_firmware = staged;
+                            self.state.save();
                         }
                     }
+                    // fwupd not installed or not running: keep the last known state
+                    Err(e) => eprintln!("Failed to query staged firmware: {}", e),
                 }
                 Task::none()
             }
-            Message::LaunchTerminalUpdate => {
-                if let Some(pm) = self.config.package_manager {
-                    let terminal = self.config.preferred_terminal.clone();
-                    let command = pm.system_update_command();
-
-                    return Task::perform(
-                        async move {
-                            // Create a unique marker file to track when the terminal closes
-                            let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
-                                .unwrap_or_else(|_| "/tmp".to_string());
-                            let marker_file = format!("{}/cosmic-package-updater-terminal-{}.marker", runtime_dir, std::process::id());
-
-                            // Create the marker file
-                            let _ = std::fs::File::create(&marker_file);
-
-                            // Build command that removes marker file when done
-                            let wrapped_command = format!(
-                                "{} && echo \"Update completed. Press Enter to exit...\" && read; rm -f \"{}\"",
-                                command.replace("\"", "\\\""),
-                                marker_file
-                            );
-
-                            // Spawn the terminal (it will return immediately due to daemonization)
-                            match tokio::process::Command::new(&terminal)
-                                .arg("-e")
-                                .arg("sh")
-                                .arg("-c")
-                                .arg(&wrapped_command)
-                                .spawn()
-                            {
-                                Ok(_) => {
-                                    // Poll for marker file deletion (terminal closed)
-                                    loop {
-                                        if !std::path::Path::new(&marker_file).exists() {
-                                            break;
-                                        }
-                                        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-                                    }
-
-                                    // Add a delay to allow system to stabilize after update
-                                    tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
-                                }
-                                Err(_) => {
-                                    // Clean up marker file on error
-                                    let _ = std::fs::remove_file(&marker_file);
-                                }
-                            }
-                        },
-                        |()| cosmic::Action::App(Message::TerminalFinished),
-                    );
+            Message::AppImagesChecked(result) => {
+                match result {
+                    Ok(updates) => self.appimage_updates = updates,
+                    Err(e) => eprintln!("Failed to check AppImages: {}", e),
                 }
                 Task::none()
             }
-            Message::TerminalFinished => {
-                // Terminal has finished, trigger update check immediately
-                Task::done(cosmic::Action::App(Message::CheckForUpdates))
+            Message::SetAppImageDirectory(directory) => {
+                let mut config = self.config.clone();
+                config.appimage_directory = directory.trim().to_string();
+                if config.appimage_directory.is_empty() {
+                    self.appimage_updates.clear();
+                }
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
             }
-            Message::ConfigChanged(config) => {
-                let old_package_manager = self.config.package_manager;
-                self.config = config;
-                PackageUpdaterConfig::set_entry(&self.config_handler, &self.config);
-
-                // If package manager was just auto-configured and startup check is enabled,
-                // trigger the delayed startup check
-                if old_package_manager.is_none() && self.config.package_manager.is_some() && self.config.auto_check_on_startup {
-                    Task::done(cosmic::Action::App(Message::DelayedStartupCheck))
-                } else {
-                    Task::none()
+            Message::UpdateAppImages => {
+                if self.appimage_updates.is_empty() {
+                    return Task::none();
                 }
+                // Not packages: nothing to record in the history
+                self.update_targets = Some(Vec::new());
+                let launch = self.launch_terminal(appimage::update_command(&self.appimage_updates));
+                Task::batch([launch, self.close_popup_after_update()])
             }
-            Message::Timer => {
-                // Automatically check for updates if a package manager is configured
-                // and we're not already checking
-                if !self.checking_updates && self.config.package_manager.is_some() {
-                    Task::done(cosmic::Action::App(Message::CheckForUpdates))
-                } else {
-                    Task::none()
+            Message::UpdateFlatpak(installation) => {
+                let targets: Vec<PackageUpdate> = self.update_info.packages.iter()
+                    .filter(|p| p.source == Source::Flatpak(installation))
+                    .cloned()
+                    .collect();
+                if targets.is_empty() {
+                    return Task::none();
                 }
+                self.update_targets = Some(targets);
+                let launch = self.launch_terminal(installation.update_command());
+                Task::batch([launch, self.close_popup_after_update()])
             }
-            Message::DiscoverPackageManagers => {
-                self.available_package_managers = PackageManagerDetector::detect_available();
-                if self.config.package_manager.is_none() {
-                    if let Some(preferred) = PackageManagerDetector::get_preferred() {
-                        let mut config = self.config.clone();
-                        config.package_manager = Some(preferred);
-                        return Task::done(cosmic::Action::App(Message::ConfigChanged(config)));
-                    }
+            Message::UpdateCustomSource(name) => {
+                let Some(command) = self.custom_sources.iter()
+                    .find(|source| source.name() == name)
+                    .and_then(|source| source.update_command(self.config.privilege_escalation))
+                else {
+                    return Task::none();
+                };
+                let targets: Vec<PackageUpdate> = self.update_info.packages.iter()
+                    .filter(|p| matches!(&p.source, Source::Custom(source) if *source == name))
+                    .cloned()
+                    .collect();
+                self.update_targets = Some(targets);
+                let launch = self.launch_terminal(command);
+                Task::batch([launch, self.close_popup_after_update()])
+            }
+            Message::ContainersChecked(result) => {
+                match result {
+                    Ok(updates) => self.container_updates = updates,
+                    Err(e) => eprintln!("Failed to check containers: {}", e),
                 }
                 Task::none()
             }
-            Message::DelayedStartupCheck => {
-                // Triggered after package manager discovery to perform startup update check
-                if self.config.auto_check_on_startup && self.config.package_manager.is_some() {
-                    // Add a delay to allow system to stabilize
-                    Task::perform(
-                        async move {
-                            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-                        },
-                        |_| cosmic::Action::App(Message::CheckForUpdates),
-                    )
-                } else {
-                    Task::none()
-                }
+            Message::RemoteHostsChecked(updates) => {
+                self.remote_updates = updates;
+                Task::none()
             }
-            Message::SelectPackageManager(pm) => {
-                let mut config = self.config.clone();
-                config.package_manager = Some(pm);
-                Task::done(cosmic::Action::App(Message::ConfigChanged(config)))
+            Message::RemoteHostDraftChanged(host) => {
+                self.remote_host_draft = host;
+                Task::none()
             }
-            Message::SetCheckInterval(interval) => {
+            Message::AddRemoteHost => {
+                let host = self.remote_host_draft.trim().to_string();
+                if host.is_empty() || self.config.remote_hosts.iter().any(|remote| remote.host == host) {
+                    return Task::none();
+                }
+                self.remote_host_draft.clear();
                 let mut config = self.config.clone();
-                config.check_interval_minutes = interval;
-                Task::done(cosmic::Action::App(Message::ConfigChanged(config)))
+                config.remote_hosts.push(RemoteHost { host, package_manager: None });
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
             }
-            Message::ToggleAutoCheck(enabled) => {
+            Message::RemoveRemoteHost(host) => {
+                self.remote_updates.retain(|updates| updates.host != host);
                 let mut config = self.config.clone();
-                config.auto_check_on_startup = enabled;
-                Task::done(cosmic::Action::App(Message::ConfigChanged(config)))
+                config.remote_hosts.retain(|remote| remote.host != host);
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
             }
-            Message::ToggleIncludeAur(enabled) => {
+            Message::UpdateRemoteHost(host) => {
+                let Some(command) = self.remote_updates.iter()
+                    .find(|updates| updates.host == host)
+                    .and_then(HostUpdates::update_command)
+                else {
+                    return Task::none();
+                };
+                // This machine's packages are unaffected: nothing to compare
+                self.update_targets = Some(Vec::new());
+                let launch = self.launch_terminal(command);
+                Task::batch([launch, self.close_popup_after_update()])
+            }
+            Message::ToggleCheckContainers(enabled) => {
                 let mut config = self.config.clone();
-                config.include_aur_updates = enabled;
-                Task::done(cosmic::Action::App(Message::ConfigChanged(config)))
+                config.check_containers = enabled;
+                if !enabled {
+                    self.container_updates.clear();
+                }
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
             }
-            Message::ToggleShowNotifications(enabled) => {
+            Message::ToggleShowAllPackageManagers(enabled) => {
                 let mut config = self.config.clone();
-                config.show_notifications = enabled;
-                Task::done(cosmic::Action::App(Message::ConfigChanged(config)))
+                config.show_all_package_managers = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
             }
-            Message::ToggleShowUpdateCount(enabled) => {
+            Message::ToggleUseTopgrade(enabled) => {
                 let mut config = self.config.clone();
-                config.show_update_count = enabled;
-                Task::done(cosmic::Action::App(Message::ConfigChanged(config)))
+                config.use_topgrade = enabled;
+                self.topgrade_steps = None;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
             }
-            Message::SetPreferredTerminal(terminal) => {
+            Message::TopgradeChecked(steps) => {
+                self.topgrade_steps = Some(steps);
+                Task::none()
+            }
+            Message::ToggleStartStoppedContainers(enabled) => {
                 let mut config = self.config.clone();
-                config.preferred_terminal = terminal;
-                Task::done(cosmic::Action::App(Message::ConfigChanged(config)))
+                config.start_stopped_containers = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
             }
-            Message::SyncFileChanged => {
-                // Ignore the first sync event on startup (file creation triggers watcher)
-                if self.ignore_next_sync {
-                    self.ignore_next_sync = false;
+            Message::UpdateContainer(name) => {
+                let Some(command) = self.container_updates.iter()
+                    .find(|updates| updates.container.name == name)
+                    .and_then(ContainerUpdates::update_command)
+                else {
                     return Task::none();
+                };
+                // The host's packages are unaffected: nothing to compare
+                self.update_targets = Some(Vec::new());
+                let launch = self.launch_terminal(command);
+                Task::batch([launch, self.close_popup_after_update()])
+            }
+            Message::AlternateRootChecked(updates) => {
+                // The setting changed while the check ran
+                if updates.root == self.config.alternate_root {
+                    self.root_updates = Some(updates);
                 }
-
-                // Another instance completed an update check, sync our state
-                // Only sync if we're not already checking and haven't checked very recently
-                if !self.checking_updates && self.config.package_manager.is_some() {
-                    let should_sync = self.last_check.map_or(true, |last| {
-                        last.elapsed().as_secs() > 3 // Only sync if our last check was more than 3 seconds ago
-                    });
-
-                    if should_sync {
-                        Task::done(cosmic::Action::App(Message::CheckForUpdates))
-                    } else {
-                        Task::none()
-                    }
+                Task::none()
+            }
+            Message::CustomSourceDraftChanged(draft) => {
+                self.custom_source_draft = draft;
+                self.custom_source_draft_error = None;
+                Task::none()
+            }
+            Message::AddCustomSource => {
+                let draft = CustomSource {
+                    name: self.custom_source_draft.name.trim().to_string(),
+                    ..self.custom_source_draft.clone()
+                };
+                if let Err(e) = Parser::new(&draft) {
+                    self.custom_source_draft_error = Some(e.to_string());
+                    return Task::none();
+                }
+                if self.custom_sources.iter().any(|source| source.name() == draft.name) {
+                    self.custom_source_draft_error = Some(format!("A source named '{}' already exists", draft.name));
+                    return Task::none();
+                }
+                self.custom_source_draft = CustomSource::default();
+                let mut config = self.config.clone();
+                config.custom_sources.push(draft);
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::RemoveCustomSource(name) => {
+                let mut config = self.config.clone();
+                config.custom_sources.retain(|source| source.name != name);
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::TogglePlugin(name, enabled) => {
+                let mut config = self.config.clone();
+                if enabled {
+                    config.enabled_plugins.insert(name);
                 } else {
-                    Task::none()
+                    config.enabled_plugins.remove(&name);
                 }
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
             }
-        }
-    }
-
-    fn subscription(&self) -> Subscription<Self::Message> {
-        let mut subscriptions = vec![];
-
-        // Timer subscription for periodic checks
-        if self.config.package_manager.is_some() {
-            let timer_subscription = time::every(Duration::from_secs(self.config.check_interval_minutes as u64 * 60))
-                .map(|_| Message::Timer);
-            subscriptions.push(timer_subscription);
-
-            // File watcher subscription to sync with other instances
-            let sync_subscription = Subscription::run_with_id(
-                "sync_watcher",
-                Self::watch_sync_file()
-            );
-            subscriptions.push(sync_subscription);
-        }
-
-        if subscriptions.is_empty() {
-            Subscription::none()
-        } else {
-            Subscription::batch(subscriptions)
-        }
-    }
-}
-
-impl CosmicAppletPackageUpdater {
-    fn get_sync_path() -> PathBuf {
-        let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
-            .unwrap_or_else(|_| "/tmp".to_string());
-        PathBuf::from(runtime_dir).join("cosmic-package-updater.sync")
-    }
-
-    fn watch_sync_file() -> impl futures::Stream<Item = Message> {
-        use notify::{Watcher, RecursiveMode, Event};
-        use futures::channel::mpsc;
-        use futures::StreamExt;
-
-        async_stream::stream! {
-            let sync_path = Self::get_sync_path();
-
-            // Ensure the parent directory exists
-            if let Some(parent) = sync_path.parent() {
-                let _ = std::fs::create_dir_all(parent);
+            Message::SetAlternateRoot(root) => {
+                let mut config = self.config.clone();
+                config.alternate_root = root.trim().to_string();
+                self.root_updates = None;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
             }
-
-            // Create the sync file if it doesn't exist
-            if !sync_path.exists() {
-                let _ = std::fs::File::create(&sync_path);
+            Message::UpdateAlternateRoot => {
+                let Some(command) = self.root_updates.as_ref().and_then(|updates| updates.update_command(self.config.privilege_escalation)) else {
+                    return Task::none();
+                };
+                // The running system's packages are unaffected: nothing to compare
+                self.update_targets = Some(Vec::new());
+                let launch = self.launch_terminal(command);
+                Task::batch([launch, self.close_popup_after_update()])
             }
-
-            let (tx, mut rx) = mpsc::unbounded();
-
-            let mut watcher = match notify::recommended_watcher(move |res: Result<Event, _>| {
-                if let Ok(event) = res {
-                    if event.kind.is_modify() || event.kind.is_create() {
-                        let _ = tx.unbounded_send(());
-                    }
+            Message::SelectDefaultTab(tab) => {
+                let mut config = self.config.clone();
+                config.default_tab = tab;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleRememberLastTab(enabled) => {
+                let mut config = self.config.clone();
+                config.remember_last_tab = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleCloseOnUpdate(enabled) => {
+                let mut config = self.config.clone();
+                config.close_popup_on_update = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::TogglePinDuringUpdate(enabled) => {
+                let mut config = self.config.clone();
+                config.pin_popup_during_update = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SetReservedCountDigits(digits) => {
+                let mut config = self.config.clone();
+                config.reserved_count_digits = digits;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SetPopupIdleTimeout(seconds) => {
+                let mut config = self.config.clone();
+                config.popup_idle_timeout_secs = seconds;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::PopupActivity => {
+                self.popup_last_activity = Instant::now();
+                Task::none()
+            }
+            Message::PopupFocused(id) => {
+                if self.popup == Some(id) {
+                    self.popup_last_activity = Instant::now();
                 }
-            }) {
-                Ok(w) => w,
-                Err(e) => {
-                    eprintln!("Failed to create file watcher: {}", e);
-                    return;
+                Task::none()
+            }
+            Message::Shutdown => {
+                if !self.read_only && !self.demo {
+                    self.save_session();
+                    self.state.save();
                 }
-            };
-
-            if let Err(e) = watcher.watch(&sync_path, RecursiveMode::NonRecursive) {
-                eprintln!("Failed to watch sync file: {}", e);
-                return;
+                // Ends the runtime, so the application is dropped and
+                // gives up its instance registration
+                cosmic::iced::exit()
             }
-
-            while let Some(_) = rx.next().await {
-                // Small delay to avoid rapid fire events
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                yield Message::SyncFileChanged;
+            Message::PopupIdleTick => {
+                let timeout = Duration::from_secs(self.config.popup_idle_timeout_secs as u64);
+                if self.popup.is_none() || timeout.is_zero() {
+                    return Task::none();
+                }
+                // Count the idle time from the end of the update
+                if self.popup_pinned() {
+                    self.popup_last_activity = Instant::now();
+                    return Task::none();
+                }
+                if self.popup_last_activity.elapsed() >= timeout {
+                    return self.close_popup();
+                }
+                Task::none()
             }
-        }
-    }
+            Message::PackageListScrolled(viewport) => {
+                self.package_list_offset = viewport.relative_offset();
+                Task::none()
+            }
+            Message::ToggleWeeklySummary(enabled) => {
+                let mut config = self.config.clone();
+                config.weekly_summary_notification = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::NotificationSent(result) => {
+                if let Err(e) = result {
+                    eprintln!("Failed to send notification: {}", e);
+                }
+                Task::none()
+            }
+            Message::UpdateNotificationSent(result) => {
+                match result {
+                    Ok(id) => self.update_notification_id = Some(id),
+                    Err(e) => eprintln!("Failed to send notification: {}", e),
+                }
+                Task::none()
+            }
+            Message::NotificationAction(id, action) => {
+                if self.update_notification_id != Some(id) {
+                    return Task::none();
+                }
+                self.update_notification_id = None;
+                match Reminder::from_action_key(&action) {
+                    Some(reminder) => Task::done(cosmic::Action::App(Message::Snooze(reminder))),
+                    None => Task::none(),
+                }
+            }
+            Message::Snooze(reminder) => {
+                if self.read_only {
+                    return Task::none();
+                }
+                let until = reminder.deadline(chrono::Local::now()).timestamp();
+                self.state.snooze = Some(Snooze {
+                    until,
+                    updates: state::update_keys(&self.update_info.packages),
+                });
+                self.state.save();
+                Self::wait_for_snooze(until)
+            }
+            Message::CancelSnooze => {
+                self.state.snooze = None;
+                self.state.save();
+                Task::none()
+            }
+            Message::KernelHighlightsFetched(version, result) => {
+                if let Err(e) = &result {
+                    eprintln!("{}", e);
+                }
+                self.kernel_highlights = Some((version, result));
+                Task::none()
+            }
+            Message::ClockChecked(status) => {
+                self.clock_status = Some(status);
+                Task::none()
+            }
+            Message::SystemProbed(summary) => {
+                self.system_summary = Some(summary);
+                Task::none()
+            }
+            Message::OpenUrl(url) => {
+                if let Err(e) = std::process::Command::new("xdg-open").arg(&url).spawn() {
+                    eprintln!("Failed to open {}: {}", url, e);
+                }
+                Task::none()
+            }
+            Message::SetProxy(proxy) => {
+                let mut config = self.config.clone();
+                config.proxy = proxy.trim().to_string();
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SetWebhookUrl(url) => {
+                let mut config = self.config.clone();
+                config.webhook_url = url.trim().to_string();
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleDiagnostics(enabled) => {
+                let mut config = self.config.clone();
+                config.diagnostics_enabled = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleRepositoryCounted(repository, counted) => {
+                let mut config = self.config.clone();
+                config.excluded_repositories.retain(|excluded| *excluded != repository);
+                if !counted {
+                    config.excluded_repositories.push(repository);
+                }
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleStrictParsing(enabled) => {
+                let mut config = self.config.clone();
+                config.strict_parsing = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SaveDiagnosticsReport => {
+                match diagnostics::save_report(&self.diagnostics_report()) {
+                    Ok(path) => {
+                        self.error_message = None;
+                        return Task::done(cosmic::Action::App(Message::OpenUrl(path.display().to_string())));
+                    }
+                    Err(e) => self.error_message = Some(format!("Failed to save diagnostics: {}", e)),
+                }
+                Task::none()
+            }
+            Message::CopyDiagnosticsReport => {
+                cosmic::iced::clipboard::write(self.diagnostics_report())
+            }
+            Message::ResetDiagnostics => {
+                self.state.diagnostics = Default::default();
+                self.state.save();
+                Task::none()
+            }
+            Message::SetMetricsTextfile(path) => {
+                let mut config = self.config.clone();
+                config.metrics_textfile = path.trim().to_string();
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SetMqttBroker(broker) => {
+                let mut config = self.config.clone();
+                config.mqtt_broker = broker.trim().to_string();
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SetMqttUsername(username) => {
+                let mut config = self.config.clone();
+                config.mqtt_username = username;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SetMqttPassword(password) => {
+                let mut config = self.config.clone();
+                config.mqtt_password = password;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::MqttPublished(result) => {
+                if let Err(e) = result {
+                    eprintln!("{}", e);
+                }
+                Task::none()
+            }
+            Message::SelectWebhookFormat(format) => {
+                let mut config = self.config.clone();
+                config.webhook_format = format;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::WebhookPosted(result) => {
+                if let Err(e) = result {
+                    eprintln!("{}", e);
+                }
+                Task::none()
+            }
+            Message::PrivilegesChecked(can_elevate) => {
+                self.admin_rights = Some(can_elevate);
+                Task::none()
+            }
+            Message::DismissReportOnlyIntro => {
+                self.state.report_only_intro_dismissed = true;
+                self.state.save();
+                Task::none()
+            }
+            Message::ExportReport => {
+                match export::save_report(&self.update_report()) {
+                    Ok(path) => {
+                        self.error_message = None;
+                        return Task::done(cosmic::Action::App(Message::OpenUrl(path.display().to_string())));
+                    }
+                    Err(e) => self.error_message = Some(format!("Failed to save report: {}", e)),
+                }
+                Task::none()
+            }
+            Message::EmailReport => {
+                let subject = format!("{} pending updates", self.update_info.total_updates);
+                if let Err(e) = std::process::Command::new("xdg-email")
+                    .args(["--subject", &subject, "--body", &self.update_report()])
+                    .spawn()
+                {
+                    self.error_message = Some(format!("Failed to open the mail client: {}", e));
+                }
+                Task::none()
+            }
+            Message::SnoozeExpired => {
+                if self.read_only {
+                    return Task::none();
+                }
+
+                // Another instance may have re-raised or cancelled it already
+                self.state.snooze = PersistentState::load().snooze;
+                match &self.state.snooze {
+                    Some(snooze) if chrono::Local::now().timestamp() >= snooze.until => {
+                        self.state.snooze = None;
+                        self.state.save();
+                        if self.update_info.has_updates() {
+                            self.send_updates_notification()
+                        } else {
+                            Task::none()
+                        }
+                    }
+                    _ => Task::none(),
+                }
+            }
+            Message::RebootToApplyFirmware => {
+                if !self.read_only && !self.demo {
+                    if let Err(e) = std::process::Command::new("systemctl").arg("reboot").spawn() {
+                        self.error_message = Some(format!("Failed to reboot: {}", e));
+                    }
+                }
+                Task::none()
+            }
+            Message::LaunchTerminalUpdate if self.needs_preflight() => {
+                if self.preflight_running {
+                    return Task::none();
+                }
+                self.preflight_running = true;
+                self.preflight = None;
+                Task::perform(
+                    async move { preflight::check().await.map_err(|e| e.to_string()) },
+                    |result| cosmic::Action::App(Message::PreflightChecked(result)),
+                )
+            }
+            Message::LaunchTerminalUpdate => {
+                self.preflight_confirmed = false;
+                self.preflight = None;
+                // topgrade updates everything it finds on its own
+                if self.config.use_topgrade {
+                    self.update_targets = Some(self.update_info.packages.clone());
+                    let launch = self.launch_terminal(topgrade::UPDATE_COMMAND.to_string());
+                    return Task::batch([launch, self.close_popup_after_update()]);
+                }
+                let mut commands: Vec<String> = self.config.package_managers().into_iter()
+                    .map(|pm| pm.system_update_command(self.config.privilege_escalation))
+                    .collect();
+                // Custom sources with pending updates
+                commands.extend(self.custom_sources.iter()
+                    .filter(|source| self.update_info.packages.iter().any(|p| matches!(&p.source, Source::Custom(name) if name == source.name())))
+                    .filter_map(|source| source.update_command(self.config.privilege_escalation)));
+                if !commands.is_empty() {
+                    self.update_targets = Some(self.update_info.packages.clone());
+                    let launch = self.launch_terminal(commands.join(" && "));
+                    return Task::batch([launch, self.close_popup_after_update()]);
+                }
+                Task::none()
+            }
+            // topgrade can't pick packages, it runs all its steps
+            Message::UpdateEverything | Message::UpdateSelected | Message::UpdateSecurity | Message::UpdateGroup(_) if self.config.use_topgrade => {
+                Task::done(cosmic::Action::App(Message::LaunchTerminalUpdate))
+            }
+            Message::UpdateEverything => {
+                self.state.update_all_progress = None;
+                self.launch_update_all()
+            }
+            Message::ResumeUpdateEverything => self.launch_update_all(),
+            Message::DismissRunOutcome => {
+                self.run_outcome_dismissed = true;
+                Task::none()
+            }
+            Message::UpdateGroup(group) => {
+                if let Some(pm) = self.config.package_manager() {
+                    let targets: Vec<PackageUpdate> = self.update_info.packages.iter()
+                        .filter(|p| p.group.as_deref() == Some(group.as_str()))
+                        .cloned()
+                        .collect();
+                    let packages: Vec<String> = targets.iter().map(|p| p.name.clone()).collect();
+
+                    if let Some(command) = pm.backend().packages_update_command(self.config.privilege_escalation, &packages) {
+                        self.update_targets = Some(targets);
+                        let launch = self.launch_terminal(command);
+                        return Task::batch([launch, self.close_popup_after_update()]);
+                    }
+                }
+                Task::none()
+            }
+            Message::TogglePackageSelected(name) => {
+                // The order on screen is read from the whole app
+                let mut selection = std::mem::take(&mut self.selection);
+                selection.click(&self.displayed_packages(), &name, self.shift_held);
+                self.selection = selection;
+                Task::none()
+            }
+            Message::SelectOfficial => {
+                self.selection.select_where(&self.update_info.packages, |p| !matches!(p.source, Source::Aur | Source::Firmware | Source::Custom(_)));
+                Task::none()
+            }
+            Message::SelectSecurity => {
+                self.selection.select_where(&self.update_info.packages, |p| p.is_security && !matches!(p.source, Source::Firmware | Source::Custom(_)));
+                Task::none()
+            }
+            Message::DeselectAur => {
+                self.selection.deselect_where(&self.update_info.packages, |p| p.source == Source::Aur);
+                Task::none()
+            }
+            Message::ClearSelection => {
+                self.selection.clear();
+                Task::none()
+            }
+            Message::UpdateSecurity => {
+                if let Some(command) = self.security_update_command() {
+                    self.update_targets = Some(self.security_updates().into_iter().cloned().collect());
+                    let launch = self.launch_terminal(command);
+                    return Task::batch([launch, self.close_popup_after_update()]);
+                }
+                Task::none()
+            }
+            Message::UpdateSelected => {
+                if let Some(pm) = self.config.package_manager() {
+                    let targets: Vec<PackageUpdate> = self.selection.packages(&self.update_info.packages).into_iter().cloned().collect();
+                    let packages: Vec<String> = targets.iter().map(|p| p.name.clone()).collect();
+
+                    if let Some(command) = pm.backend().packages_update_command(self.config.privilege_escalation, &packages) {
+                        self.update_targets = Some(targets);
+                        self.selection.clear();
+                        let launch = self.launch_terminal(command);
+                        return Task::batch([launch, self.close_popup_after_update()]);
+                    }
+                }
+                Task::none()
+            }
+            Message::ShiftHeld(held) => {
+                self.shift_held = held;
+                Task::none()
+            }
+            Message::EditNote(name) => {
+                let note = self.state.package_notes.get(&name).cloned().unwrap_or_default();
+                self.note_editor = Some((name, note));
+                Task::none()
+            }
+            Message::NoteChanged(note) => {
+                if let Some((_, draft)) = &mut self.note_editor {
+                    *draft = note.chars().take(MAX_NOTE_CHARS).collect();
+                }
+                Task::none()
+            }
+            Message::SaveNote => {
+                if let Some((name, note)) = self.note_editor.take() {
+                    let note = note.trim().to_string();
+                    self.update_package_notes(|notes| {
+                        if note.is_empty() {
+                            notes.remove(&name);
+                        } else {
+                            notes.insert(name, note);
+                        }
+                    });
+                }
+                Task::none()
+            }
+            Message::RemoveNote => {
+                if let Some((name, _)) = self.note_editor.take() {
+                    self.update_package_notes(|notes| {
+                        notes.remove(&name);
+                    });
+                }
+                Task::none()
+            }
+            Message::CancelNote => {
+                self.note_editor = None;
+                Task::none()
+            }
+            Message::MarkChangesReviewed => {
+                self.state.changes_reviewed_at = Some(chrono::Local::now().timestamp());
+                self.state.save();
+                Task::none()
+            }
+            Message::ToggleGroup(group) => {
+                if !self.expanded_groups.remove(&group) {
+                    self.expanded_groups.insert(group);
+                }
+                Task::none()
+            }
+            Message::ToggleSource(source) => {
+                if !self.collapsed_sources.remove(&source) {
+                    self.collapsed_sources.insert(source);
+                }
+                Task::none()
+            }
+            Message::ToggleRiskDetails => {
+                self.risk_details_expanded = !self.risk_details_expanded;
+                Task::none()
+            }
+            Message::PreflightChecked(result) => {
+                self.preflight_running = false;
+                match result {
+                    Ok(preflight) if !preflight.is_clean() => {
+                        self.preflight = Some(preflight);
+                        // Launched with a middle click, the warning needs the popup
+                        if self.popup.is_none() {
+                            let open = self.handle_toggle_popup();
+                            self.active_tab = PopupTab::Updates;
+                            return open;
+                        }
+                        Task::none()
+                    }
+                    Ok(preflight) => {
+                        if !preflight.files_checked {
+                            eprintln!("File conflicts not checked: no pacman files database (pacman -Fy)");
+                        }
+                        Task::done(cosmic::Action::App(Message::ConfirmUpdate))
+                    }
+                    // A failed dry run must not block updating
+                    Err(e) => {
+                        eprintln!("Failed to check the update transaction: {}", e);
+                        Task::done(cosmic::Action::App(Message::ConfirmUpdate))
+                    }
+                }
+            }
+            Message::ConfirmUpdate => {
+                self.preflight_confirmed = true;
+                Task::done(cosmic::Action::App(Message::LaunchTerminalUpdate))
+            }
+            Message::RestartApplet => {
+                if let Some(binary) = self.replaced_binary.take() {
+                    let error = self_update::restart(binary);
+                    self.error_message = Some(format!("Failed to restart the applet: {}", error));
+                }
+                Task::none()
+            }
+            Message::SurfaceLost(loss) => {
+                let ours = match loss {
+                    SurfaceLoss::Closed(id) => self.popup == Some(id) || self.core.main_window_id() == Some(id),
+                    // Only the open popup has to move to a remaining output
+                    SurfaceLoss::OutputRemoved => self.popup.is_some(),
+                };
+                if !ours {
+                    return Task::none();
+                }
+                self.recover_surfaces(loss.describe())
+            }
+            Message::RestoreView => {
+                let Some(view) = self.surface_recovery.finish() else {
+                    return Task::none();
+                };
+                self.popup_last_activity = Instant::now();
+                if !view.popup_open {
+                    return Task::none();
+                }
+                self.active_tab = view.tab;
+                self.package_list_offset = view.package_list_offset;
+                self.open_popup()
+            }
+            Message::DismissPreflight => {
+                self.preflight = None;
+                Task::none()
+            }
+            Message::TogglePreflightCheck(enabled) => {
+                let mut config = self.config.clone();
+                config.preflight_check = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ShowAllPackages => {
+                self.package_list_expanded = true;
+                Task::none()
+            }
+            Message::ToggleHeldBack => {
+                self.held_back_expanded = !self.held_back_expanded;
+                // apt's resolver is asked why only once someone wants to know
+                let kept_back: Vec<String> = self.update_info.held_back.iter()
+                    .filter(|held| held.reason == held_back::RESOLVER_REASON)
+                    .map(|held| held.name.clone())
+                    .collect();
+                if !self.held_back_expanded || kept_back.is_empty() || self.demo {
+                    return Task::none();
+                }
+                let Some(pm) = self.config.package_manager() else {
+                    return Task::none();
+                };
+                Task::perform(
+                    async move { UpdateChecker::new(pm).explain_kept_back(&kept_back).await },
+                    |explained| cosmic::Action::App(Message::HeldBackExplained(explained)),
+                )
+            }
+            Message::HeldBackExplained(explained) => {
+                for held in &mut self.update_info.held_back {
+                    if let Some(explanation) = explained.iter().find(|explanation| explanation.name == held.name) {
+                        held.reason = explanation.reason.clone();
+                    }
+                }
+                Task::none()
+            }
+            Message::VersionClicked => {
+                let now = Instant::now();
+                self.version_clicks.retain(|click| now.duration_since(*click) < MULTI_CLICK_INTERVAL);
+                self.version_clicks.push(now);
+                if self.version_clicks.len() == 3 {
+                    self.version_clicks.clear();
+                    self.scheduler_view = match self.scheduler_view {
+                        Some(_) => None,
+                        None => Some(self.scheduler_snapshot()),
+                    };
+                }
+                Task::none()
+            }
+            Message::RefreshSchedulerView => {
+                if self.scheduler_view.is_some() {
+                    self.scheduler_view = Some(self.scheduler_snapshot());
+                }
+                Task::none()
+            }
+            Message::ToggleCountChanges => {
+                self.count_changes_expanded = !self.count_changes_expanded;
+                Task::none()
+            }
+            Message::TerminalLaunched(result) => {
+                match result {
+                    Ok(name) => {
+                        self.active_terminal = Some(name);
+                        self.error_message = None;
+                        Task::perform(
+                            async move {
+                                let started = Instant::now();
+
+                                // Poll for marker file deletion (terminal closed)
+                                terminal::wait_for_marker_removal(&terminal::marker_path()).await;
+                                let duration = started.elapsed();
+
+                                // Add a delay to allow system to stabilize after update
+                                tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+                                duration
+                            },
+                            |duration| cosmic::Action::App(Message::TerminalFinished(duration)),
+                        )
+                    }
+                    Err(error) => {
+                        self.error_message = Some(error);
+                        self.update_all_steps = None;
+                        self.update_targets = None;
+                        self.update_running = false;
+                        Task::none()
+                    }
+                }
+            }
+            Message::TerminalFinished(duration) => {
+                self.active_terminal = None;
+                self.update_running = false;
+                self.pre_update_packages = Some(self.update_targets.take().unwrap_or_else(|| self.update_info.packages.clone()));
+                self.state.last_update_duration = Some(duration.as_secs());
+                let summary = self.finish_update_all();
+                self.state.save();
+                let sound = if self.config.sound_on_update_finished {
+                    self.play_sound(Cue::UpdateFinished)
+                } else {
+                    Task::none()
+                };
+                // Terminal has finished, trigger update check immediately
+                let check = Task::done(cosmic::Action::App(Message::CheckForUpdates(CheckMode::Standard)));
+                Task::batch([check, summary, sound])
+            }
+            Message::ConfigChanged(config) => {
+                let config = *config;
+                let old_package_manager = self.config.package_manager();
+                if config.custom_sources != self.config.custom_sources || config.enabled_plugins != self.config.enabled_plugins {
+                    (self.custom_sources, self.custom_source_errors, self.disabled_plugins) = backend::user_backends(&config.custom_sources, &config.enabled_plugins);
+                }
+                self.config = config;
+                // The demo's settings last for the session
+                if !self.read_only && !self.demo {
+                    PackageUpdaterConfig::set_entry(&self.config_handler, &self.config);
+                }
+
+                // If package manager was just auto-configured and startup check is enabled,
+                // trigger the delayed startup check
+                if old_package_manager.is_none() && self.config.package_manager().is_some() && self.config.auto_check_on_startup {
+                    Task::done(cosmic::Action::App(Message::DelayedStartupCheck))
+                } else {
+                    Task::none()
+                }
+            }
+            Message::Timer => {
+                self.last_timer_tick = Some(Instant::now());
+                // Automatically check for updates if a package manager is configured
+                // and we're not already checking
+                if self.checking_updates || self.config.package_manager().is_none() {
+                    return Task::none();
+                }
+
+                // Only the leader runs scheduled checks, the others take over its
+                // results through the sync file. Until then, they read the cache
+                if !self.membership.as_ref().map_or(true, Membership::is_leader) {
+                    return match self.last_check {
+                        None => self.start_check(CheckMode::Quick, None),
+                        Some(_) => Task::none(),
+                    };
+                }
+
+                if schedule::automatic_check_allowed(&self.config) {
+                    if !self.config.defer_when_busy {
+                        self.session_busy = None;
+                        return Task::done(cosmic::Action::App(Message::CheckForUpdates(CheckMode::Standard)));
+                    }
+                    Task::perform(busy::session_busy(), |busy| cosmic::Action::App(Message::SessionBusyChecked(busy)))
+                } else if !self.deferred_check_pending {
+                    // Outside the allowed hours: run the missed check once the window opens
+                    self.deferred_check_pending = true;
+                    let wait = self.config.check_window.duration_until_open(chrono::Local::now());
+                    Task::perform(
+                        async move {
+                            tokio::time::sleep(wait).await;
+                        },
+                        |_| cosmic::Action::App(Message::DeferredCheckDue),
+                    )
+                } else {
+                    Task::none()
+                }
+            }
+            Message::DeferredCheckDue => {
+                self.deferred_check_pending = false;
+                Task::done(cosmic::Action::App(Message::Timer))
+            }
+            Message::SessionBusyChecked(busy) => {
+                self.session_busy = busy;
+                if busy.is_none() {
+                    return Task::done(cosmic::Action::App(Message::CheckForUpdates(CheckMode::Standard)));
+                }
+                if self.deferred_check_pending {
+                    return Task::none();
+                }
+                // Look again shortly rather than waiting a whole interval
+                self.deferred_check_pending = true;
+                Task::perform(
+                    async {
+                        tokio::time::sleep(BUSY_RECHECK_INTERVAL).await;
+                    },
+                    |_| cosmic::Action::App(Message::DeferredCheckDue),
+                )
+            }
+            Message::DiscoverPackageManagers => {
+                self.available_package_managers = PackageManagerDetector::detect_available();
+                // Only ever picks one while none is selected, a choice is not overridden
+                if self.config.package_manager().is_none() {
+                    let order = self.config.preference_order();
+                    if let Some(preferred) = PackageManagerDetector::preferred(&self.listed_package_managers(), &order) {
+                        let mut config = self.config.clone();
+                        config.enable_package_manager(preferred, true);
+                        return Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))));
+                    }
+                }
+                Task::none()
+            }
+            Message::RedetectPackageManagers => Task::perform(
+                async { tokio::task::spawn_blocking(PackageManagerDetector::detect_available).await.unwrap_or_default() },
+                |available| cosmic::Action::App(Message::PackageManagersDetected(available)),
+            ),
+            Message::PackageManagersDetected(available) => {
+                if available.is_empty() || available == self.available_package_managers {
+                    return Task::none();
+                }
+                let appeared: Vec<PackageManager> = available.iter()
+                    .copied()
+                    .filter(|pm| !self.available_package_managers.contains(pm))
+                    .collect();
+                self.available_package_managers = available;
+
+                let Some(current) = self.config.package_manager() else {
+                    return Task::done(cosmic::Action::App(Message::DiscoverPackageManagers));
+                };
+                let order = self.config.preference_order();
+                let rank = |pm: PackageManager| order.iter().position(|&p| p == pm);
+                if !self.available_package_managers.contains(&current) {
+                    // Ask rather than switching away from the user's choice
+                    self.suggested_package_manager = PackageManagerDetector::preferred(&self.listed_package_managers(), &order);
+                } else if let Some(&better) = appeared.iter().find(|&&pm| pm.supersedes(current) && rank(pm) < rank(current)) {
+                    self.suggested_package_manager = Some(better);
+                }
+                // Uninstalled again before the user decided
+                if self.suggested_package_manager.is_some_and(|pm| !self.available_package_managers.contains(&pm)) {
+                    self.suggested_package_manager = None;
+                }
+                Task::none()
+            }
+            Message::SwitchToSuggestedPackageManager => match self.suggested_package_manager.take() {
+                Some(pm) => Task::done(cosmic::Action::App(Message::SelectPackageManager(pm))),
+                None => Task::none(),
+            },
+            Message::MovePackageManagerPreference(pm, up) => {
+                let mut config = self.config.clone();
+                config.move_preference(pm, &self.listed_package_managers(), up);
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ResetPackageManagerPreference => {
+                let mut config = self.config.clone();
+                config.package_manager_preference.clear();
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::DismissPackageManagerSuggestion => {
+                self.suggested_package_manager = None;
+                Task::none()
+            }
+            Message::DelayedStartupCheck => {
+                // Triggered after package manager discovery to perform startup update check
+                if self.config.auto_check_on_startup && self.config.package_manager().is_some() {
+                    self.startup_check()
+                } else {
+                    Task::none()
+                }
+            }
+            Message::SelectPackageManager(pm) => {
+                let mut config = self.config.clone();
+                config.switch_package_manager(pm);
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::TogglePackageManager(pm, enabled) => {
+                let mut config = self.config.clone();
+                config.enable_package_manager(pm, enabled);
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SelectBadgeSource(source) => {
+                let mut config = self.config.clone();
+                config.badge_source = source;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SelectHorizontalBadgePosition(position) => {
+                let mut config = self.config.clone();
+                config.badge_position_horizontal = position;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SelectVerticalBadgePosition(position) => {
+                let mut config = self.config.clone();
+                config.badge_position_vertical = position;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SelectIconSource(source) => {
+                let mut config = self.config.clone();
+                config.icon_source = source;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SetCheckInterval(interval) => {
+                let mut config = self.config.clone();
+                config.check_interval_minutes = interval;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleAutoCheck(enabled) => {
+                let mut config = self.config.clone();
+                config.auto_check_on_startup = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SetStartupDelay(secs) => {
+                let mut config = self.config.clone();
+                config.startup_delay_secs = secs;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleWaitForGraphicalSession(enabled) => {
+                let mut config = self.config.clone();
+                config.wait_for_graphical_session = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleClockSkewCheck(enabled) => {
+                let mut config = self.config.clone();
+                config.check_clock_skew = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleIncludeAur(enabled) => {
+                let mut config = self.config.clone();
+                config.include_aur_updates = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleShowNotifications(enabled) => {
+                let mut config = self.config.clone();
+                config.show_notifications = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleShowUpdateCount(enabled) => {
+                let mut config = self.config.clone();
+                config.show_update_count = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleHighContrastIndicators(enabled) => {
+                let mut config = self.config.clone();
+                config.high_contrast_indicators = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SetPreferredTerminal(terminal) => {
+                let mut config = self.config.clone();
+                config.preferred_terminal = terminal;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SetTerminalFallbacks(fallbacks) => {
+                let mut config = self.config.clone();
+                config.terminal_fallbacks = fallbacks
+                    .split(',')
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty())
+                    .collect();
+                // Keep the raw text so separators can be typed
+                self.terminal_fallbacks_input = fallbacks;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleDeferWhenBusy(enabled) => {
+                let mut config = self.config.clone();
+                config.defer_when_busy = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleSoundOnUpdates(enabled) => {
+                let mut config = self.config.clone();
+                config.sound_on_updates = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleSoundOnUpdateFinished(enabled) => {
+                let mut config = self.config.clone();
+                config.sound_on_update_finished = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::ToggleSoundQuietHours(enabled) => {
+                let mut config = self.config.clone();
+                config.sound_quiet_hours_enabled = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SetSoundQuietStart(hour) => {
+                let mut config = self.config.clone();
+                config.sound_quiet_hours.start_hour = hour;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SetSoundQuietEnd(hour) => {
+                let mut config = self.config.clone();
+                config.sound_quiet_hours.end_hour = hour;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SoundPlayed(result) => {
+                if let Err(e) = result {
+                    eprintln!("Failed to play sound: {}", e);
+                }
+                Task::none()
+            }
+            Message::ToggleCheckWindow(enabled) => {
+                let mut config = self.config.clone();
+                config.check_window_enabled = enabled;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SetCheckWindowStart(hour) => {
+                let mut config = self.config.clone();
+                config.check_window.start_hour = hour;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SetCheckWindowEnd(hour) => {
+                let mut config = self.config.clone();
+                config.check_window.end_hour = hour;
+                Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config))))
+            }
+            Message::SelectPrivilegeEscalation(privilege) => {
+                let mut config = self.config.clone();
+                config.privilege_escalation = privilege;
+                Task::batch([
+                    Task::done(cosmic::Action::App(Message::ConfigChanged(Box::new(config)))),
+                    self.check_privileges(privilege),
+                ])
+            }
+            Message::SyncFileChanged => {
+                // Empty on creation, or written by an incompatible version
+                let Some(message) = sync::read() else {
+                    return Task::none();
+                };
+                // The refresh cooldown is shared, and another instance's
+                // refresh ends with a check of its own
+                self.state.last_forced_refresh = PersistentState::load().last_forced_refresh.max(self.state.last_forced_refresh);
+                if self.checking_updates || self.config.package_manager().is_none() {
+                    return Task::none();
+                }
+
+                let last_check = self.status_snapshot().last_check;
+                let own_hash = sync::results_hash(&self.update_info);
+                if !message.is_news(self.last_sync_check_id.as_deref(), last_check, own_hash) {
+                    return Task::none();
+                }
+
+                // Another instance completed an update check. It just fetched,
+                // so the cached metadata is fresh
+                self.last_sync_check_id = Some(message.check_id);
+                self.start_check(CheckMode::Quick, Some(message.origin))
+            }
+        }
+    }
+
+    fn subscription(&self) -> Subscription<Self::Message> {
+        Subscription::batch(self.subscriptions().into_iter().map(|(_, subscription)| subscription))
+    }
+}
+
+impl CosmicAppletPackageUpdater {
+    /// The active subscriptions, each with a name for the scheduler view.
+    fn subscriptions(&self) -> Vec<(&'static str, Subscription<Message>)> {
+        // Once installed, the handlers replace the default action of the
+        // signals, so they stay subscribed through surface recoveries
+        let shutdown = ("shutdown_signals", Subscription::run_with_id("shutdown_signals", Self::watch_shutdown_signals()));
+
+        // Dropped subscriptions are started again once the surfaces are back,
+        // under new IDs so none of them carries over
+        if self.surface_recovery.in_progress() {
+            return vec![shutdown];
+        }
+        let generation = self.surface_recovery.generation();
+
+        let mut subscriptions = vec![
+            shutdown,
+            ("surface_events", event::listen_with(|event, _status, _id| SurfaceLoss::from_event(event).map(Message::SurfaceLost))),
+        ];
+
+        // Timer subscription for periodic checks
+        if self.config.package_manager().is_some() {
+            let timer_subscription = time::every(Duration::from_secs(self.config.check_interval_minutes as u64 * 60))
+                .map(|_| Message::Timer);
+            subscriptions.push(("check_timer", timer_subscription));
+
+            // File watcher subscription to sync with other instances
+            let sync_subscription = Subscription::run_with_id(
+                ("sync_watcher", generation),
+                Self::watch_sync_file()
+            );
+            subscriptions.push(("sync_watcher", sync_subscription));
+        }
+
+        // Package managers installed or removed while the applet runs
+        if !self.read_only {
+            subscriptions.push(("redetect_timer", time::every(REDETECT_INTERVAL).map(|_| Message::RedetectPackageManagers)));
+            subscriptions.push(("package_database_watcher", Subscription::run_with_id(
+                ("package_database_watcher", generation),
+                Self::watch_package_databases(),
+            )));
+        }
+
+        // Idle timeout of the open popup
+        if self.popup.is_some() && self.config.popup_idle_timeout_secs > 0 {
+            subscriptions.push(("popup_idle_timer", time::every(Duration::from_secs(1)).map(|_| Message::PopupIdleTick)));
+            subscriptions.push(("popup_activity", event::listen_with(|event, _status, id| match event {
+                event::Event::Window(window::Event::Focused) => Some(Message::PopupFocused(id)),
+                event::Event::Mouse(_) | event::Event::Keyboard(_) => Some(Message::PopupActivity),
+                _ => None,
+            })));
+        }
+
+        // Shift-click selects a range of packages
+        if self.popup.is_some() && self.can_select_packages() {
+            subscriptions.push(("shift_modifier", event::listen_with(|event, _status, _id| match event {
+                event::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => Some(Message::ShiftHeld(modifiers.shift())),
+                _ => None,
+            })));
+        }
+
+        // Reminder buttons on the last update notification
+        if self.update_notification_id.is_some() {
+            use futures::StreamExt;
+
+            subscriptions.push(("notification_actions", Subscription::run_with_id(
+                ("notification_actions", generation),
+                notifications::action_invocations().map(|(id, action)| Message::NotificationAction(id, action)),
+            )));
+        }
+
+        subscriptions
+    }
+
+    /// Scheduler internals for the view behind the version string: the
+    /// instances, the check lock and when each source is checked next.
+    fn scheduler_snapshot(&self) -> Vec<String> {
+        let clock = |instant: Instant| {
+            let now = Instant::now();
+            let offset = if instant >= now {
+                chrono::Duration::from_std(instant - now).unwrap_or_default()
+            } else {
+                -chrono::Duration::from_std(now - instant).unwrap_or_default()
+            };
+            (chrono::Local::now() + offset).format("%H:%M:%S").to_string()
+        };
+        let mut lines = Vec::new();
+
+        let pid = std::process::id();
+        let role = match &self.membership {
+            Some(membership) if membership.is_leader() => "leader",
+            Some(_) => "follower",
+            None => "not registered, checks on its own",
+        };
+        lines.push(format!("Instance {}: {}", pid, role));
+        let peers: Vec<String> = leader::members().into_iter()
+            .filter(|&member| member != pid)
+            .map(|member| member.to_string())
+            .collect();
+        lines.push(format!("Sync peers: {}", if peers.is_empty() { "none".to_string() } else { peers.join(", ") }));
+        match sync::read() {
+            Some(message) => {
+                let time = chrono::DateTime::from_timestamp(message.timestamp, 0)
+                    .map(|time| time.with_timezone(&chrono::Local).format("%H:%M:%S").to_string())
+                    .unwrap_or_default();
+                let applied = self.last_sync_check_id.as_deref() == Some(message.check_id.as_str());
+                lines.push(format!(
+                    "Sync file: check {} by {} at {}{}",
+                    message.check_id,
+                    message.origin.pid,
+                    time,
+                    if applied { ", applied" } else { "" }
+                ));
+            }
+            None => lines.push("Sync file: none".to_string()),
+        }
+
+        let holder = UpdateChecker::last_lock_holder().map_or("none".to_string(), |holder| holder.to_string());
+        match self.check_started {
+            Some(started) => lines.push(format!("Check lock: checking since {}, last taken by {}", clock(started), holder)),
+            None => lines.push(format!("Check lock: idle, last taken by {}", holder)),
+        }
+
+        // The timer starts counting when its subscription starts, so this
+        // is off by the time between that and the first tick
+        let interval = Duration::from_secs(self.config.check_interval_minutes as u64 * 60);
+        let next_tick = self.last_timer_tick.unwrap_or(self.started) + interval;
+        let held_back = if self.config.package_manager().is_none() {
+            Some("no package manager configured")
+        } else if !self.membership.as_ref().map_or(true, Membership::is_leader) {
+            Some("follows the leader")
+        } else if self.deferred_check_pending {
+            Some("deferred")
+        } else {
+            None
+        };
+        let next = match held_back {
+            Some(reason) => reason.to_string(),
+            None => format!("{} (every {} min)", clock(next_tick), self.config.check_interval_minutes),
+        };
+        for pm in self.config.package_managers() {
+            lines.push(format!("Next check of {}: {}", pm.name(), next));
+        }
+        for source in &self.custom_sources {
+            lines.push(format!("Next check of {}: {}", source.name(), next));
+        }
+        let after_check = match held_back {
+            Some(_) => "after the next check".to_string(),
+            None => format!("after the check at {}", clock(next_tick)),
+        };
+        if !self.config.remote_hosts.is_empty() {
+            lines.push(format!("Next check of remote hosts: {}", after_check));
+        }
+        if self.config.check_containers && !self.container_tools.is_empty() {
+            lines.push(format!("Next check of containers: {}", after_check));
+        }
+        if self.config.use_topgrade {
+            lines.push(format!("Next topgrade dry run: {}", after_check));
+        }
+        if !schedule::automatic_check_allowed(&self.config) {
+            lines.push("Outside the check window".to_string());
+        }
+        if let Some(busy) = self.session_busy {
+            lines.push(format!("Last scheduled check held back: {}", busy.describe()));
+        }
+        if let Some(last_check) = self.last_check {
+            lines.push(format!("Last check: {}", clock(last_check)));
+        }
+
+        let names: Vec<&str> = self.subscriptions().into_iter().map(|(name, _)| name).collect();
+        lines.push(format!("Active subscriptions: {}", names.join(", ")));
+        lines
+    }
+
+    /// Reports the first signal that ends the applet, so the in-memory state
+    /// is written before it exits.
+    fn watch_shutdown_signals() -> impl futures::Stream<Item = Message> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        async_stream::stream! {
+            let (Ok(mut terminate), Ok(mut interrupt), Ok(mut hangup)) =
+                (signal(SignalKind::terminate()), signal(SignalKind::interrupt()), signal(SignalKind::hangup()))
+            else {
+                eprintln!("Failed to install the shutdown signal handlers");
+                return;
+            };
+            tokio::select! {
+                _ = terminate.recv() => {}
+                _ = interrupt.recv() => {}
+                _ = hangup.recv() => {}
+            }
+            yield Message::Shutdown;
+        }
+    }
+
+    fn watch_sync_file() -> impl futures::Stream<Item = Message> {
+        use notify::{Watcher, RecursiveMode, Event};
+        use futures::channel::mpsc;
+        use futures::StreamExt;
+
+        async_stream::stream! {
+            let sync_path = sync::path();
+
+            // Ensure the parent directory exists
+            if let Some(parent) = sync_path.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+
+            // Create the sync file if it doesn't exist
+            if !sync_path.exists() {
+                let _ = std::fs::File::create(&sync_path);
+            }
+
+            let (tx, mut rx) = mpsc::unbounded();
+
+            let mut watcher = match notify::recommended_watcher(move |res: Result<Event, _>| {
+                if let Ok(event) = res {
+                    if event.kind.is_modify() || event.kind.is_create() {
+                        let _ = tx.unbounded_send(());
+                    }
+                }
+            }) {
+                Ok(w) => w,
+                Err(e) => {
+                    eprintln!("Failed to create file watcher: {}", e);
+                    return;
+                }
+            };
+
+            if let Err(e) = watcher.watch(&sync_path, RecursiveMode::NonRecursive) {
+                eprintln!("Failed to watch sync file: {}", e);
+                return;
+            }
+
+            while let Some(_) = rx.next().await {
+                // A write raises several events; wait for the burst to end
+                // and report it once
+                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                while rx.try_recv().is_ok() {}
+                yield Message::SyncFileChanged;
+            }
+        }
+    }
+
+    fn watch_package_databases() -> impl futures::Stream<Item = Message> {
+        use notify::{Watcher, RecursiveMode, Event};
+        use futures::channel::mpsc;
+        use futures::StreamExt;
+
+        async_stream::stream! {
+            let (tx, mut rx) = mpsc::unbounded();
+
+            let mut watcher = match notify::recommended_watcher(move |res: Result<Event, _>| {
+                if res.is_ok_and(|event| !event.kind.is_access()) {
+                    let _ = tx.unbounded_send(());
+                }
+            }) {
+                Ok(w) => w,
+                Err(e) => {
+                    eprintln!("Failed to create package database watcher: {}", e);
+                    return;
+                }
+            };
+
+            let mut watched = false;
+            for database in PACKAGE_DATABASES.iter().map(Path::new).filter(|path| path.is_dir()) {
+                match watcher.watch(database, RecursiveMode::NonRecursive) {
+                    Ok(()) => watched = true,
+                    Err(e) => eprintln!("Failed to watch {}: {}", database.display(), e),
+                }
+            }
+            if !watched {
+                return;
+            }
+
+            while rx.next().await.is_some() {
+                // An update touches the database many times; look once it is done
+                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                while rx.try_recv().is_ok() {}
+                yield Message::RedetectPackageManagers;
+            }
+        }
+    }
+
+    fn record_installed(&mut self, outcome: &RunOutcome) {
+        let installed = outcome.installed.clone();
+        if installed.is_empty() {
+            return;
+        }
+
+        let entry = HistoryEntry {
+            timestamp: chrono::Local::now().timestamp(),
+            package_manager: self.config.package_manager().map(|pm| pm.name().to_string()).unwrap_or_default(),
+            packages: installed,
+            unattended: false,
+        };
+        history::append(&entry);
+        self.history.push(entry);
+    }
+
+    /// Records the updates that disappeared since the previous check
+    /// without an update run from the applet. Only checks that succeeded
+    /// for the same sources are compared, and only the leader records, so
+    /// panel instances don't log the same change twice.
+    fn record_installed_outside(&mut self, update_info: &UpdateInfo) {
+        if self.read_only || self.demo || self.last_check.is_none() || self.error_message.is_some() {
+            return;
+        }
+        if !self.membership.as_ref().map_or(true, Membership::is_leader) {
+            return;
+        }
+        let sources = |info: &UpdateInfo| -> Vec<String> { info.sources.iter().map(|s| s.source.clone()).collect() };
+        let succeeded = |info: &UpdateInfo| info.sources.iter().all(|s| s.error.is_none());
+        if sources(&self.update_info) != sources(update_info) || !succeeded(&self.update_info) || !succeeded(update_info) {
+            return;
+        }
+
+        let installed = history::installed_outside(&self.update_info, update_info);
+        if installed.is_empty() {
+            return;
+        }
+        let entry = HistoryEntry {
+            timestamp: chrono::Local::now().timestamp(),
+            package_manager: sources(update_info).join(", "),
+            packages: installed,
+            unattended: true,
+        };
+        history::append(&entry);
+        self.history.push(entry);
+    }
+
+    /// Notifies about a set of updates once, unless the user snoozed it.
+    fn maybe_notify_updates(&mut self) -> Task<Message> {
+        // Notified after the held back check once the session is free
+        let (notify, sound) = (self.config.show_notifications, self.config.sound_on_updates);
+        if !(notify || sound) || self.read_only || self.session_busy.is_some() {
+            return Task::none();
+        }
+
+        // Another instance may have notified or snoozed already
+        let stored = PersistentState::load();
+        self.state.snooze = stored.snooze;
+        self.state.notified_updates = stored.notified_updates;
+
+        let keys = state::update_keys(&self.update_info.packages);
+        if keys.is_empty() {
+            if self.state.snooze.is_some() || !self.state.notified_updates.is_empty() {
+                self.state.snooze = None;
+                self.state.notified_updates.clear();
+                self.state.save();
+            }
+            return Task::none();
+        }
+
+        if let Some(snooze) = &self.state.snooze {
+            let only_snoozed = keys.iter().all(|key| snooze.updates.contains(key));
+            if only_snoozed && chrono::Local::now().timestamp() < snooze.until {
+                return Task::none();
+            }
+            // New updates arrived or the reminder is due
+            self.state.snooze = None;
+        } else if keys == self.state.notified_updates {
+            return Task::none();
+        }
+
+        self.state.notified_updates = keys;
+        self.state.save();
+        let mut tasks = Vec::new();
+        if notify {
+            tasks.push(self.send_updates_notification());
+        }
+        if sound {
+            tasks.push(self.play_sound(Cue::UpdatesFound));
+        }
+        Task::batch(tasks)
+    }
+
+    fn play_sound(&self, cue: Cue) -> Task<Message> {
+        if !sound::allowed_at(&self.config, chrono::Local::now().hour()) {
+            return Task::none();
+        }
+        Task::perform(
+            async move { sound::play(cue).await.map_err(|e| e.to_string()) },
+            |result| cosmic::Action::App(Message::SoundPlayed(result)),
+        )
+    }
+
+    fn send_updates_notification(&self) -> Task<Message> {
+        let count = self.update_info.total_updates;
+        let summary = format!("{} update{} available", locale::format_count(count), if count == 1 { "" } else { "s" });
+
+        let mut names: Vec<&str> = self.update_info.packages.iter().take(5).map(|p| p.name.as_str()).collect();
+        if count > names.len() {
+            names.push("…");
+        }
+        let body = names.join(", ");
+
+        // Reminders are about installing, which this account cannot do
+        let actions: Vec<(&str, &str)> = if self.report_only() {
+            Vec::new()
+        } else {
+            Reminder::ALL.iter().map(|r| (r.action_key(), r.label())).collect()
+        };
+        Task::perform(
+            async move {
+                notifications::send_with_actions(&summary, &body, "software-update-available-symbolic", &actions)
+                    .await
+                    .map_err(|e| e.to_string())
+            },
+            |result| cosmic::Action::App(Message::UpdateNotificationSent(result)),
+        )
+    }
+
+    fn check_privileges(&self, privilege: PrivilegeEscalation) -> Task<Message> {
+        if self.demo {
+            return Task::none();
+        }
+        Task::perform(
+            async move { privilege.user_can_elevate().await },
+            |can_elevate| cosmic::Action::App(Message::PrivilegesChecked(can_elevate)),
+        )
+    }
+
+    /// Changes the notes as last saved by any instance, so notes written
+    /// in another one are kept.
+    fn update_package_notes(&mut self, change: impl FnOnce(&mut BTreeMap<String, String>)) {
+        self.state.package_notes = PersistentState::load().package_notes;
+        change(&mut self.state.package_notes);
+        self.state.save();
+    }
+
+    /// Packages can be picked for a selective update. The names only
+    /// mean something to the package manager that reported them, so this
+    /// needs a single one.
+    fn can_select_packages(&self) -> bool {
+        !self.report_only()
+            && !self.update_running
+            && !self.config.use_topgrade
+            && self.config.package_managers().len() == 1
+            && self.config.package_manager().is_some_and(|pm| pm.capabilities().selective_update)
+    }
+
+    /// Updates are still checked and counted, but installing them is left
+    /// to whoever administers the machine.
+    fn report_only(&self) -> bool {
+        self.admin_rights == Some(false)
+            && self.config.package_manager().is_some_and(|pm| pm.capabilities().needs_privileges)
+    }
+
+    fn record_diagnostics(&mut self, duration: Duration, result: Result<&UpdateInfo, &str>) {
+        if !self.config.diagnostics_enabled || self.read_only {
+            return;
+        }
+        // Every instance records its own checks into the shared state file
+        self.state.diagnostics = PersistentState::load().diagnostics;
+        self.state.diagnostics.record(duration, result, chrono::Local::now().timestamp());
+        self.state.save();
+    }
+
+    fn diagnostics_report(&self) -> String {
+        let distro = self.system_summary.as_ref().map_or("Linux", |summary| summary.distro.as_str());
+        // The last check's unrecognized lines are worth reporting even when
+        // no statistics are recorded
+        let mut diagnostics = self.state.diagnostics.clone();
+        for line in &self.update_info.unrecognized_lines {
+            if !diagnostics.recent_unrecognized.contains(line) {
+                diagnostics.recent_unrecognized.push(line.clone());
+            }
+        }
+        diagnostics.report(self.config.package_manager().map(|pm| pm.name()), distro)
+    }
+
+    fn update_report(&self) -> String {
+        let distro = self.system_summary.as_ref().map_or("Linux", |summary| summary.distro.as_str());
+        export::text_report(&self.update_info, self.config.package_manager().map(|pm| pm.name()), distro)
+    }
+
+    fn probe_system(&self) -> Task<Message> {
+        if self.demo {
+            return Task::none();
+        }
+        let package_manager = self.config.package_manager();
+        Task::perform(
+            async move { sysinfo::probe(package_manager).await },
+            |summary| cosmic::Action::App(Message::SystemProbed(summary)),
+        )
+    }
+
+    /// A wrong clock is the most common cause of certificate and signature
+    /// failures, so it is checked whenever one occurs. Otherwise it is
+    /// compared with the mirror at most hourly, to warn before checks break.
+    fn check_clock(&mut self) -> Task<Message> {
+        let due = self.config.check_clock_skew
+            && !self.last_clock_check.is_some_and(|checked| checked.elapsed() < CLOCK_CHECK_INTERVAL);
+        if self.read_only || self.demo || (self.trust_failure.is_none() && !due) {
+            return Task::none();
+        }
+        self.last_clock_check = Some(Instant::now());
+
+        let reference_url = trust::reference_url(self.config.package_manager());
+        let proxy = self.config.proxy.clone();
+        Task::perform(
+            async move { trust::check_clock(&reference_url, &proxy).await },
+            |status| cosmic::Action::App(Message::ClockChecked(status)),
+        )
+    }
+
+    /// Fetches the upstream changelog once per pending stable kernel release.
+    fn fetch_kernel_highlights(&self) -> Task<Message> {
+        let Some(kernel) = KernelUpdate::find(&self.update_info.packages) else {
+            return Task::none();
+        };
+
+        let already_fetched = self.kernel_highlights.as_ref()
+            .is_some_and(|(version, result)| *version == kernel.version && result.is_ok());
+        if kernel.kind != ReleaseKind::StablePoint || already_fetched || self.demo {
+            return Task::none();
+        }
+
+        let version = kernel.version;
+        let proxy = self.config.proxy.clone();
+        Task::perform(
+            async move { kernel::fetch_highlights(version, &proxy).await.map_err(|e| e.to_string()) },
+            move |result| cosmic::Action::App(Message::KernelHighlightsFetched(version, result)),
+        )
+    }
+
+    fn preflight_section(&self, preflight: &Preflight) -> Vec<Element<'_, Message>> {
+        const SHOWN_FILE_CONFLICTS: usize = 8;
+        let mut widgets: Vec<Element<'_, Message>> = vec![text("⚠ pacman is likely to stop this update").size(14).into()];
+
+        for problem in &preflight.problems {
+            widgets.push(text(format!("  • {}", problem)).size(10).into());
+        }
+        for conflict in preflight.file_conflicts.iter().take(SHOWN_FILE_CONFLICTS) {
+            widgets.push(text(format!("  • {}", conflict.describe())).size(10).into());
+        }
+        if preflight.file_conflicts.len() > SHOWN_FILE_CONFLICTS {
+            widgets.push(
+                text(format!("  … and {} more conflicting files", preflight.file_conflicts.len() - SHOWN_FILE_CONFLICTS))
+                    .size(10)
+                    .into()
+            );
+        }
+
+        widgets.push(
+            row()
+                .spacing(8)
+                .push(button::text("Update Anyway").on_press(Message::ConfirmUpdate))
+                .push(button::text("Dismiss").on_press(Message::DismissPreflight))
+                .into()
+        );
+        widgets
+    }
+
+    fn kernel_section(&self, kernel: &KernelUpdate) -> Vec<Element<'_, Message>> {
+        let mut widgets: Vec<Element<'_, Message>> = vec![text(kernel.summary()).size(12).into()];
+
+        if let Some((version, Ok(highlights))) = &self.kernel_highlights {
+            if *version == kernel.version && kernel.kind == ReleaseKind::StablePoint {
+                widgets.push(
+                    text(format!("{} upstream commits, {} referencing CVEs", highlights.commits, highlights.cve_fixes))
+                        .size(10)
+                        .into()
+                );
+                for subject in &highlights.subjects {
+                    widgets.push(text(format!("  • {}", subject)).size(10).into());
+                }
+            }
+        }
+
+        let mut links = row()
+            .spacing(8)
+            .push(button::text("Upstream changes").on_press(Message::OpenUrl(kernel.upstream_url())));
+        if let Some(url) = self.config.package_manager().and_then(|pm| kernel.distro_changelog_url(pm)) {
+            links = links.push(button::text("Distribution changelog").on_press(Message::OpenUrl(url)));
+        }
+        widgets.push(links.into());
+
+        widgets
+    }
+
+    /// Quick check and forced refresh, each disabled while cooling down.
+    fn check_buttons(&self) -> Element<'_, Message> {
+        let quick_ready = !self.checking_updates
+            && !self.update_running
+            && !self.last_check.is_some_and(|last| last.elapsed().as_secs() < QUICK_CHECK_COOLDOWN_SECS);
+        let mut buttons = row()
+            .spacing(8)
+            .push(
+                button::text("Quick Check")
+                    .on_press_maybe(quick_ready.then_some(Message::CheckForUpdates(CheckMode::Quick)))
+                    .width(cosmic::iced::Length::Fill)
+            );
+
+        if self.config.package_manager().is_some_and(|pm| pm.capabilities().metadata_refresh) {
+            // Shared by all instances, so a refresh from another panel counts too
+            let since_refresh = self.state.last_forced_refresh
+                .map(|last| chrono::Local::now().timestamp() - last);
+            let wait_minutes = since_refresh
+                .filter(|secs| *secs < FORCED_REFRESH_COOLDOWN_SECS)
+                .map(|secs| (FORCED_REFRESH_COOLDOWN_SECS - secs + 59) / 60);
+
+            let label = match wait_minutes {
+                Some(minutes) => format!("Refresh in {} min", minutes),
+                None => "Refresh & Check".to_string(),
+            };
+            let ready = !self.checking_updates && !self.update_running && wait_minutes.is_none();
+            buttons = buttons.push(
+                button::text(label)
+                    .on_press_maybe(ready.then_some(Message::CheckForUpdates(CheckMode::Refresh)))
+                    .width(cosmic::iced::Length::Fill)
+            );
+        }
+
+        buttons.into()
+    }
+
+    /// Explains report-only mode the first time, then offers the report actions.
+    fn report_only_section(&self) -> Vec<Element<'_, Message>> {
+        let mut widgets: Vec<Element<'_, Message>> = Vec::new();
+
+        if !self.state.report_only_intro_dismissed {
+            widgets.push(text("Report-only mode").size(14).into());
+            widgets.push(
+                text(format!(
+                    "This account can't use {} to install updates. Updates are still checked and counted, \
+                     and the list can be exported or emailed to whoever manages this machine.",
+                    self.config.privilege_escalation
+                ))
+                .size(12)
+                .into(),
+            );
+            widgets.push(button::text("Got it").on_press(Message::DismissReportOnlyIntro).into());
+        }
+
+        if self.update_info.has_updates() {
+            widgets.push(
+                row()
+                    .spacing(8)
+                    .push(button::text("Export Report").on_press(Message::ExportReport))
+                    .push(button::text("Email Report").on_press(Message::EmailReport))
+                    .into(),
+            );
+        }
+
+        widgets
+    }
+
+    fn wait_for_snooze(until: i64) -> Task<Message> {
+        let seconds = (until - chrono::Local::now().timestamp()).max(0) as u64;
+        Task::perform(
+            async move {
+                tokio::time::sleep(Duration::from_secs(seconds)).await;
+            },
+            |_| cosmic::Action::App(Message::SnoozeExpired),
+        )
+    }
+
+    fn maybe_send_weekly_summary(&mut self) -> Task<Message> {
+        if !self.config.weekly_summary_notification || self.read_only || self.session_busy.is_some() {
+            return Task::none();
+        }
+
+        // Another instance may have sent it already
+        self.state.last_weekly_summary = PersistentState::load().last_weekly_summary;
+        let now = chrono::Local::now().timestamp();
+
+        match self.state.last_weekly_summary {
+            None => {
+                // Start counting the first week now
+                self.state.last_weekly_summary = Some(now);
+                self.state.save();
+                Task::none()
+            }
+            Some(last) if now - last >= WEEK_SECS => {
+                self.state.last_weekly_summary = Some(now);
+                self.state.save();
+
+                let body = format!("This week: {}", Summary::since(&self.history, now - WEEK_SECS).describe());
+                Task::perform(
+                    async move {
+                        notifications::send("Weekly update summary", &body, "software-update-available-symbolic")
+                            .await
+                            .map_err(|e| e.to_string())
+                    },
+                    |result| cosmic::Action::App(Message::NotificationSent(result)),
+                )
+            }
+            Some(_) => Task::none(),
+        }
+    }
+
+    fn check_appimages(&self) -> Task<Message> {
+        if self.config.appimage_directory.is_empty() || self.demo {
+            return Task::none();
+        }
+        let directory = appimage::expand_directory(&self.config.appimage_directory);
+        Task::perform(
+            async move {
+                appimage::check(&SystemRunner, &directory).await.map_err(|e| e.to_string())
+            },
+            |result| cosmic::Action::App(Message::AppImagesChecked(result)),
+        )
+    }
+
+    fn check_containers(&self) -> Task<Message> {
+        if !self.config.check_containers || self.container_tools.is_empty() || self.demo {
+            return Task::none();
+        }
+        let tools = self.container_tools.clone();
+        let start_stopped = self.config.start_stopped_containers;
+        Task::perform(
+            async move {
+                containers::check_all(Arc::new(SystemRunner), &tools, start_stopped).await.map_err(|e| e.to_string())
+            },
+            |result| cosmic::Action::App(Message::ContainersChecked(result)),
+        )
+    }
+
+    fn check_topgrade(&self) -> Task<Message> {
+        if !self.config.use_topgrade || self.demo {
+            return Task::none();
+        }
+        Task::perform(
+            async move { topgrade::dry_run(&SystemRunner).await.map_err(|e| e.to_string()) },
+            |steps| cosmic::Action::App(Message::TopgradeChecked(steps)),
+        )
+    }
+
+    fn check_remote_hosts(&self) -> Task<Message> {
+        if self.config.remote_hosts.is_empty() || self.demo {
+            return Task::none();
+        }
+        let hosts = self.config.remote_hosts.clone();
+        Task::perform(
+            async move { remote::check_all(Arc::new(SystemRunner), &hosts).await },
+            |updates| cosmic::Action::App(Message::RemoteHostsChecked(updates)),
+        )
+    }
+
+    fn check_alternate_root(&self) -> Task<Message> {
+        if self.config.alternate_root.is_empty() || self.demo {
+            return Task::none();
+        }
+        let root = self.config.alternate_root.clone();
+        Task::perform(
+            alternate_root::check(Arc::new(SystemRunner), root),
+            |updates| cosmic::Action::App(Message::AlternateRootChecked(updates)),
+        )
+    }
+
+    fn check_staged_firmware(&self) -> Task<Message> {
+        if self.demo {
+            return Task::none();
+        }
+        Task::perform(
+            async move {
+                firmware::detect_staged().await.map_err(|e| e.to_string())
+            },
+            |result| cosmic::Action::App(Message::FirmwareStagedChecked(result)),
+        )
+    }
+
+    fn needs_preflight(&self) -> bool {
+        self.config.preflight_check
+            && !self.demo
+            && !self.preflight_confirmed
+            && self.config.package_manager().is_some_and(|pm| pm.capabilities().preflight)
+    }
+
+    /// Steps "Update Everything" runs for the current configuration.
+    fn update_all_plan(&self) -> Vec<Step> {
+        update_all::plan(&self.config.package_managers(), self.config.include_aur_updates, self.fwupd_available)
+    }
+
+    /// Starts the steps not completed by an unfinished run, all of them
+    /// unless one is being resumed.
+    fn launch_update_all(&mut self) -> Task<Message> {
+        let steps = self.state.update_all_progress.clone()
+            .unwrap_or_default()
+            .remaining(&self.update_all_plan());
+        if steps.is_empty() || self.update_running {
+            return Task::none();
+        }
+
+        let progress = update_all::progress_path();
+        let _ = std::fs::remove_file(&progress);
+        let command = update_all::command(&steps, self.config.privilege_escalation, &progress);
+        self.update_all_steps = Some(steps);
+        let launch = self.launch_terminal(command);
+        Task::batch([launch, self.close_popup_after_update()])
+    }
+
+    /// Records how far an "Update Everything" run got and reports it.
+    fn finish_update_all(&mut self) -> Task<Message> {
+        if self.update_all_steps.take().is_none() {
+            return Task::none();
+        }
+
+        let path = update_all::progress_path();
+        let progress = self.state.update_all_progress.take()
+            .unwrap_or_default()
+            .resumed(RunProgress::read(&path));
+        let _ = std::fs::remove_file(&path);
+
+        let steps = self.update_all_plan();
+        let finished = progress.remaining(&steps).is_empty();
+        let summary = if finished { "Everything is up to date" } else { "Update stopped before the end" };
+        let body = progress.summary(&steps);
+        if !finished {
+            self.state.update_all_progress = Some(progress);
+        }
+        if !self.config.show_notifications {
+            return Task::none();
+        }
+
+        Task::perform(
+            async move {
+                notifications::send(summary, &body, "software-update-available-symbolic")
+                    .await
+                    .map_err(|e| e.to_string())
+            },
+            |result| cosmic::Action::App(Message::NotificationSent(result)),
+        )
+    }
+
+    fn launch_terminal(&mut self, command: String) -> Task<Message> {
+        self.update_running = true;
+        if self.demo {
+            // Nothing runs; the updates it targets are gone at the next check
+            let targets = self.update_targets.as_ref().unwrap_or(&self.update_info.packages);
+            self.demo_installed.extend(targets.iter().map(|p| p.name.clone()));
+            eprintln!("Demo: not running {}", command);
+            return Task::perform(
+                async {
+                    tokio::time::sleep(demo::UPDATE_DURATION).await;
+                    demo::UPDATE_DURATION
+                },
+                |duration| cosmic::Action::App(Message::TerminalFinished(duration)),
+            );
+        }
+        let candidates = terminal::candidates(
+            &self.config.preferred_terminal,
+            &self.config.terminal_fallbacks,
+        );
+
+        Task::perform(
+            async move {
+                // Create a marker file to track when the terminal closes
+                let marker_file = terminal::marker_path();
+                let _ = std::fs::File::create(&marker_file);
+
+                let result = terminal::spawn_first_available(&candidates, &command, &marker_file);
+                if result.is_err() {
+                    // Clean up marker file on error
+                    let _ = std::fs::remove_file(&marker_file);
+                }
+                result
+            },
+            |result| cosmic::Action::App(Message::TerminalLaunched(result)),
+        )
+    }
+
+    fn handle_toggle_popup(&mut self) -> Task<Message> {
+        if self.popup.is_some() {
+            self.close_popup()
+        } else {
+            self.popup_last_activity = Instant::now();
+
+            self.active_tab = match self.last_tab {
+                Some(tab) if self.config.remember_last_tab => tab,
+                _ => self.config.default_tab,
+            };
+
+            let recently_closed = self.popup_closed_at.is_some_and(|closed| closed.elapsed() < SCROLL_MEMORY);
+            if !recently_closed {
+                self.package_list_offset = RelativeOffset::START;
+            }
+
+            self.open_popup()
+        }
+    }
+
+    /// Runs the startup check once the system is ready for it, so it doesn't
+    /// fail on a network or keyring that is still coming up after login.
+    fn startup_check(&self) -> Task<Message> {
+        let delay = Duration::from_secs(self.config.startup_delay_secs as u64);
+        Task::perform(
+            readiness::wait(delay, self.config.wait_for_graphical_session),
+            |_| cosmic::Action::App(Message::Timer),
+        )
+    }
+
+    /// Creates the popup surface on the active tab and scroll position.
+    fn open_popup(&mut self) -> Task<Message> {
+        // Add error handling for popup creation
+        if let Some(main_window_id) = self.core.main_window_id() {
+            let new_id = Id::unique();
+            self.popup.replace(new_id);
+            let mut popup_settings = self.core.applet.get_popup_settings(
+                main_window_id,
+                new_id,
+                None,
+                None,
+                None,
+            );
+            popup_settings.positioner.size_limits = Limits::NONE
+                .max_width(550.0)
+                .min_width(450.0)
+                .min_height(350.0)
+                .max_height(800.0);
+
+            // The list is rebuilt with the popup, so restore its position once it exists
+            let restore_scroll = scrollable::snap_to(PACKAGE_LIST_ID.clone(), self.package_list_offset);
+
+            Task::batch(vec![
+                get_popup(popup_settings).chain(restore_scroll),
+                window::gain_focus(new_id),
+            ])
+        } else {
+            eprintln!("Failed to get main window ID for popup");
+            self.error_message = Some("Unable to open popup window".to_string());
+            Task::none()
+        }
+    }
+
+    /// Tears down the popup and subscriptions after the compositor took a
+    /// surface and recreates them, with the same view, once it has settled.
+    fn recover_surfaces(&mut self, reason: &str) -> Task<Message> {
+        let view = ViewSnapshot {
+            popup_open: self.popup.is_some(),
+            tab: self.active_tab,
+            package_list_offset: self.package_list_offset,
+        };
+        if !self.surface_recovery.begin(view, Instant::now()) {
+            self.error_message = Some(format!("The applet's surfaces could not be recovered, restart the applet: {}", reason));
+            return Task::none();
+        }
+        eprintln!("Recovering the applet's surfaces: {}", reason);
+
+        // Not a close by the user, so the tab and scroll position are not remembered
+        let destroy = match self.popup.take() {
+            Some(popup) => destroy_popup(popup),
+            None => Task::none(),
+        };
+        Task::batch(vec![
+            destroy,
+            Task::perform(tokio::time::sleep(recovery::SETTLE_DELAY), |_| cosmic::Action::App(Message::RestoreView)),
+        ])
+    }
+
+    fn handle_popup_closed(&mut self, id: Id) -> Task<Message> {
+        if self.popup.as_ref() == Some(&id) {
+            self.popup = None;
+            self.remember_popup_state();
+        }
+        Task::none()
+    }
+
+    /// Starts a check. `origin` is the instance whose check this one follows;
+    /// such checks are not announced to the other instances again.
+    fn start_check(&mut self, mode: CheckMode, origin: Option<CheckOrigin>) -> Task<Message> {
+        let package_managers = self.config.package_managers();
+        if package_managers.is_empty() {
+            return Task::none();
+        }
+
+        if mode == CheckMode::Refresh {
+            self.state.last_forced_refresh = Some(chrono::Local::now().timestamp());
+            self.state.save();
+        }
+
+        self.checking_updates = true;
+        self.check_started = Some(Instant::now());
+        self.error_message = None;
+        if self.demo {
+            self.last_check_origin = origin;
+            return Task::perform(demo::check(self.demo_installed.clone()), |result| {
+                cosmic::Action::App(Message::UpdatesChecked(result))
+            });
+        }
+        let checkers: Vec<(PackageManager, UpdateChecker)> = package_managers.into_iter()
+            .map(|pm| {
+                let checker = UpdateChecker::new(pm)
+                    .mode(mode)
+                    .excluded_repositories(&self.config.excluded_repositories)
+                    .proxy(&self.config.proxy);
+                (pm, checker)
+            })
+            .collect();
+        let announce = origin.is_none();
+        self.last_check_origin = origin;
+        let include_aur = self.config.include_aur_updates;
+        let custom_sources = self.custom_sources.clone();
+        Task::perform(
+            async move { merge::check_all(checkers, &custom_sources, include_aur, announce).await },
+            |result| cosmic::Action::App(Message::UpdatesChecked(result.map_err(|e| e.to_string()))),
+        )
+    }
+
+    fn close_popup(&mut self) -> Task<Message> {
+        match self.popup.take() {
+            Some(popup) => {
+                self.remember_popup_state();
+                destroy_popup(popup)
+            }
+            None => Task::none(),
+        }
+    }
+
+    /// While an update runs, a pinned popup stays open to show its progress.
+    fn popup_pinned(&self) -> bool {
+        self.config.pin_popup_during_update && self.active_terminal.is_some()
+    }
+
+    fn close_popup_after_update(&mut self) -> Task<Message> {
+        // The terminal has not reported back yet, so pinning is decided by the setting alone
+        if self.config.close_popup_on_update && !self.config.pin_popup_during_update {
+            self.close_popup()
+        } else {
+            Task::none()
+        }
+    }
+
+    fn remember_popup_state(&mut self) {
+        self.last_tab = Some(self.active_tab);
+        self.popup_closed_at = Some(Instant::now());
+        // The next opening starts with the short list again
+        self.package_list_expanded = false;
+    }
+
+    fn handle_switch_tab(&mut self, tab: PopupTab) -> Task<Message> {
+        self.active_tab = tab;
+        Task::none()
+    }
+
+    fn severity(&self) -> Severity {
+        if self.error_message.is_some() {
+            Severity::Error
+        } else if self.config.strict_parsing && !self.update_info.unrecognized_lines.is_empty() {
+            Severity::Warning
+        } else if self.update_info.has_updates() {
+            Severity::UpdatesAvailable
+        } else {
+            Severity::UpToDate
+        }
+    }
+
+    /// Writes what only lives in memory for the next start.
+    fn save_session(&self) {
+        let session = Session {
+            last_check: self.status_snapshot().last_check,
+            update_info: self.last_check.map(|_| self.update_info.clone()),
+            pre_update_packages: self.pre_update_packages.clone(),
+            last_run_outcome: self.last_run_outcome.clone(),
+        };
+        session.save();
+    }
+
+    /// Takes over the session of the previous run. Its check still shows
+    /// its age; the startup check replaces it as usual.
+    fn restore_session(&mut self, session: Session) {
+        if let (Some(update_info), Some(last_check)) = (session.update_info, session.last_check) {
+            let age = (chrono::Local::now().timestamp() - last_check).max(0) as u64;
+            if let Some(instant) = Instant::now().checked_sub(Duration::from_secs(age)) {
+                self.update_info = update_info;
+                self.last_check = Some(instant);
+            }
+        }
+        self.pre_update_packages = session.pre_update_packages;
+        self.last_run_outcome = session.last_run_outcome;
+    }
+
+    fn status_snapshot(&self) -> StatusSnapshot {
+        let last_check = self.last_check
+            .map(|instant| chrono::Local::now().timestamp() - instant.elapsed().as_secs() as i64);
+
+        StatusSnapshot::new(&self.update_info, self.severity())
+            .last_check(last_check)
+            .package_manager(self.config.package_manager().map(|pm| pm.name()))
+            .error(self.error_message.as_deref())
+    }
+
+    fn publish_status(&self) {
+        export::publish(&self.status_snapshot());
+    }
+
+    /// Retained messages make this idempotent, so every instance may publish.
+    fn publish_mqtt(&self) -> Task<Message> {
+        if self.config.mqtt_broker.is_empty() || self.read_only || self.demo {
+            return Task::none();
+        }
+
+        let broker = Broker {
+            address: self.config.mqtt_broker.clone(),
+            username: self.config.mqtt_username.clone(),
+            password: self.config.mqtt_password.clone(),
+        };
+        let status = MqttStatus {
+            snapshot: self.status_snapshot(),
+            security_updates: self.update_info.packages.iter().filter(|p| p.is_security).count(),
+        };
+        Task::perform(
+            async move {
+                mqtt::publish(&broker, &sysinfo::hostname(), &status)
+                    .await
+                    .map_err(|e| e.to_string())
+            },
+            |result| cosmic::Action::App(Message::MqttPublished(result)),
+        )
+    }
+
+    fn write_metrics(&self) {
+        if self.config.metrics_textfile.is_empty() || self.demo {
+            return;
+        }
+
+        let mut metrics = Metrics::new(&self.update_info);
+        metrics.last_check = self.status_snapshot().last_check;
+        // Another instance may have run the last update
+        metrics.update_duration_secs = PersistentState::load().last_update_duration;
+        metrics::write_textfile(&PathBuf::from(&self.config.metrics_textfile), &metrics);
+    }
+
+    /// Reports the check to the configured webhook. Every panel instance
+    /// re-checks when one of them does, so only the first one reports.
+    fn maybe_post_webhook(&mut self) -> Task<Message> {
+        if self.config.webhook_url.is_empty() || self.read_only || self.demo {
+            return Task::none();
+        }
+
+        let now = chrono::Local::now().timestamp();
+        self.state.last_webhook_report = PersistentState::load().last_webhook_report;
+        if self.state.last_webhook_report.is_some_and(|last| now - last < WEBHOOK_DEDUP_SECS) {
+            return Task::none();
+        }
+        self.state.last_webhook_report = Some(now);
+        self.state.save();
+
+        let url = self.config.webhook_url.clone();
+        let format = self.config.webhook_format;
+        let report = Report::new(sysinfo::hostname(), self.status_snapshot(), &self.update_info);
+        let proxy = self.config.proxy.clone();
+        Task::perform(
+            async move { webhook::post(&url, format, &report, &proxy).await.map_err(|e| e.to_string()) },
+            |result| cosmic::Action::App(Message::WebhookPosted(result)),
+        )
+    }
+
+    fn icon_role(&self) -> IconRole {
+        if self.checking_updates {
+            return IconRole::Checking;
+        }
+
+        match self.severity() {
+            Severity::Error => IconRole::Error,
+            Severity::Warning => IconRole::Warning,
+            Severity::UpdatesAvailable if self.badge_count() > 0 => {
+                if self.update_info.packages.iter().any(|p| p.is_security) {
+                    IconRole::SecurityUpdates
+                } else {
+                    IconRole::UpdatesAvailable
+                }
+            }
+            Severity::UpdatesAvailable | Severity::UpToDate => IconRole::UpToDate,
+        }
+    }
+
+    /// Detected package managers the settings list and auto-selection picks
+    /// from: those of the distribution's family and distribution-independent
+    /// ones, unless all are shown. Selected ones always stay listed, and
+    /// everything is if none would be.
+    fn listed_package_managers(&self) -> Vec<PackageManager> {
+        let Some(os_release) = self.os_release.as_ref().filter(|_| !self.config.show_all_package_managers) else {
+            return self.available_package_managers.clone();
+        };
+        let listed: Vec<PackageManager> = self.available_package_managers.iter()
+            .copied()
+            .filter(|&pm| {
+                os_release.is_relevant(pm) || self.config.enabled_package_managers.contains(&pm)
+            })
+            .collect();
+        if listed.is_empty() {
+            self.available_package_managers.clone()
+        } else {
+            listed
+        }
+    }
+
+    /// Check history of the sources the current configuration checks.
+    fn tracked_sources(&self) -> Vec<(&str, &SourceHealth)> {
+        let mut sources: Vec<&str> = self.config.package_managers().iter().map(|pm| pm.name()).collect();
+        if self.config.include_aur_updates && self.config.package_manager().is_some_and(|pm| pm.capabilities().aur) {
+            sources.push(health::AUR_SOURCE);
+        }
+        sources.extend(self.custom_sources.iter().map(|source| source.name()));
+        sources.into_iter()
+            .filter_map(|source| Some((source, self.state.source_health.get(source)?)))
+            .collect()
+    }
+
+    /// Repositories that failed to download in the last few checks in a
+    /// row, with the last error.
+    fn failing_repositories(&self) -> Vec<(&str, &str)> {
+        self.state.repository_health.iter()
+            .filter(|(_, health)| health.is_failing_repeatedly())
+            .map(|(repository, health)| (repository.as_str(), health.last_error.as_deref().unwrap_or_default()))
+            .collect()
+    }
+
+    /// Repositories of the pending updates and those left out of the count,
+    /// with the names of their updates.
+    fn repositories(&self) -> BTreeMap<String, Vec<&str>> {
+        let mut repositories: BTreeMap<String, Vec<&str>> = self.config.excluded_repositories.iter()
+            .map(|repository| (repository.clone(), Vec::new()))
+            .collect();
+        for package in self.update_info.packages.iter().chain(&self.update_info.filtered) {
+            if let Some(repository) = &package.repository {
+                repositories.entry(repository.clone()).or_default().push(&package.name);
+            }
+        }
+        repositories
+    }
+
+    /// Updates the panel reports, as selected by the badge source.
+    fn badge_count(&self) -> usize {
+        // topgrade mode counts the steps of its dry run that have work left
+        if self.config.use_topgrade {
+            if let Some(Ok(steps)) = &self.topgrade_steps {
+                return topgrade::pending_count(steps, &self.update_info.packages);
+            }
+        }
+        self.config.badge_source.count(&self.update_info)
+    }
+
+    /// Width of the panel count: room for the reserved digits (and the
+    /// glyph), so neighbouring applets don't shift as the count changes,
+    /// growing only past them.
+    fn count_width(&self, count_text: &str, size: f32) -> cosmic::iced::Length {
+        let reserved = self.config.reserved_count_digits as usize;
+        if reserved == 0 {
+            return cosmic::iced::Length::Shrink;
+        }
+        let reserved = reserved + usize::from(self.config.high_contrast_indicators);
+        // Digits of the panel font are about 0.6 em wide
+        cosmic::iced::Length::Fixed(count_text.chars().count().max(reserved) as f32 * size * 0.6)
+    }
+
+    /// Panel icon with the update count placed as configured for the
+    /// panel's orientation.
+    fn panel_badge(&self) -> Element<'_, Message> {
+        let role = self.icon_role();
+        let high_contrast = self.config.high_contrast_indicators;
+        let icon = cosmic::widget::icon::icon(icons::handle(role, self.config.icon_source))
+            .size(16)
+            .class(if high_contrast { cosmic::theme::Svg::Default } else { role.class() });
+        let count = self.badge_count();
+        let count_text = if count > 0 && self.config.show_update_count {
+            locale::format_count(count)
+        } else {
+            String::new()
+        };
+        let count_text = if high_contrast {
+            format!("{}{}", role.glyph(), count_text)
+        } else {
+            count_text
+        };
+
+        let position = if self.core.applet.is_horizontal() {
+            self.config.badge_position_horizontal
+        } else {
+            self.config.badge_position_vertical
+        };
+        match position {
+            // The count follows the icon in reading direction
+            BadgePosition::Beside if locale::is_rtl() => row()
+                .align_y(cosmic::iced::Alignment::Center)
+                .spacing(2)
+                .push(text(count_text.clone()).size(12).width(self.count_width(&count_text, 12.0)).align_x(cosmic::iced::Alignment::End))
+                .push(icon)
+                .into(),
+            BadgePosition::Beside => row()
+                .align_y(cosmic::iced::Alignment::Center)
+                .spacing(2)
+                .push(icon)
+                .push(text(count_text.clone()).size(12).width(self.count_width(&count_text, 12.0)))
+                .into(),
+            BadgePosition::Below => column()
+                .align_x(cosmic::iced::Alignment::Center)
+                .push(icon)
+                .push(text(count_text.clone()).size(10).width(self.count_width(&count_text, 10.0)).align_x(cosmic::iced::Alignment::Center))
+                .into(),
+            BadgePosition::Overlay => cosmic::iced::widget::Stack::new()
+                .width(cosmic::iced::Length::Fixed(24.0))
+                .height(cosmic::iced::Length::Fixed(24.0))
+                .push(
+                    cosmic::widget::container(icon)
+                        .width(cosmic::iced::Length::Fill)
+                        .height(cosmic::iced::Length::Fill)
+                        .align_x(cosmic::iced::alignment::Horizontal::Center)
+                        .align_y(cosmic::iced::alignment::Vertical::Center)
+                )
+                .push(
+                    cosmic::widget::container(text(count_text).size(9))
+                        .width(cosmic::iced::Length::Fill)
+                        .height(cosmic::iced::Length::Fill)
+                        .align_x(cosmic::iced::alignment::Horizontal::Right)
+                        .align_y(cosmic::iced::alignment::Vertical::Bottom)
+                )
+                .into(),
+        }
+    }
+
+    fn view_read_only(&self) -> Element<'_, Message> {
+        // No button or mouse area: the indicator must not expose any action
+        autosize::autosize(
+            cosmic::widget::container(self.panel_badge()).padding([8, 4]),
+            cosmic::widget::Id::unique(),
+        )
+        .limits(Limits::NONE.min_width(1.0).min_height(1.0))
+        .into()
+    }
+
+    fn view_updates_tab(&self) -> Element<'_, Message> {
+        let mut widgets = vec![];
+
+        // System summary header
+        if let Some(summary) = &self.system_summary {
+            widgets.push(text(&summary.distro).size(12).into());
+            if let Some(packages) = summary.describe_packages() {
+                widgets.push(text(packages).size(10).into());
+            }
+            widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
+        }
+
+        if self.replaced_binary.is_some() {
+            widgets.push(
+                row()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(text("The applet was updated").size(12).width(cosmic::iced::Length::Fill))
+                    .push(button::text("Restart Applet").on_press(Message::RestartApplet))
+                    .into()
+            );
+        }
+
+        if let Some(pm) = self.suggested_package_manager {
+            let reason = match self.config.package_manager() {
+                Some(current) if !self.available_package_managers.contains(&current) => {
+                    format!("{} is no longer installed", current.name())
+                }
+                _ => format!("{} is now installed and also checks the AUR", pm.name()),
+            };
+            widgets.push(text(reason).size(12).into());
+            widgets.push(
+                row()
+                    .spacing(8)
+                    .push(button::text(format!("Use {}", pm.name())).on_press(Message::SwitchToSuggestedPackageManager))
+                    .push(button::text("Dismiss").on_press(Message::DismissPackageManagerSuggestion))
+                    .into()
+            );
+        }
+
+        if self.severity() == Severity::Warning {
+            widgets.push(
+                text(format!(
+                    "⚠ {} line(s) of the check output were not recognized, the count may be too low. Settings → Diagnostics lists them.",
+                    self.update_info.unrecognized_lines.len()
+                ))
+                .size(12)
+                .into(),
+            );
+        }
+
+        if let Some(busy) = self.session_busy {
+            widgets.push(text(format!("Scheduled check postponed while {}", busy.describe())).size(12).into());
+        }
+
+        let failing_repositories = self.failing_repositories();
+        if !failing_repositories.is_empty() {
+            let names: Vec<&str> = failing_repositories.iter().map(|(repository, _)| *repository).collect();
+            widgets.push(
+                text(format!(
+                    "⚠ Failing repositories: {}. Checks are slower and miss their updates until they work again.",
+                    names.join(", ")
+                ))
+                .size(12)
+                .into(),
+            );
+            for (_, error) in failing_repositories {
+                widgets.push(text(error).size(10).into());
+            }
+        }
+
+        if let Some(outcome) = self.last_run_outcome.as_ref().filter(|_| !self.run_outcome_dismissed) {
+            widgets.push(
+                row()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(text(outcome.describe()).size(12).width(cosmic::iced::Length::Fill))
+                    .push(button::text("Dismiss").on_press(Message::DismissRunOutcome))
+                    .into()
+            );
+        }
+
+        // Status text
+        if self.checking_updates {
+            widgets.push(text("Checking for updates...").size(18).into());
+        } else if let Some(error) = &self.error_message {
+            match self.trust_failure {
+                Some(failure) => {
+                    widgets.push(text(failure.title()).size(18).into());
+                    if let Some(clock) = self.clock_status.as_ref().and_then(ClockStatus::describe) {
+                        widgets.push(text(clock).size(12).into());
+                    }
+                    widgets.push(text(failure.guidance()).size(12).into());
+                    widgets.push(text(format!("Error: {}", error)).size(10).into());
+                }
+                None => widgets.push(text(format!("Error: {}", error)).size(18).into()),
+            }
+        } else if self.update_info.has_updates() {
+            widgets.push(text(format!("{} updates available", locale::format_count(self.update_info.total_updates))).size(18).into());
+
+            // Only show package breakdown if package manager supports AUR
+            if let Some(pm) = self.config.package_manager() {
+                if pm == PackageManager::NixosRebuild {
+                    widgets.push(text("System closure update available (store paths to build or fetch)").into());
+                }
+                if pm == PackageManager::Swupd {
+                    widgets.push(text("New OS version available; updating moves all installed bundles to it").into());
+                }
+                if pm.capabilities().applies_on_reboot {
+                    widgets.push(text("New deployment available; updating stages it, a reboot is required to apply it").into());
+                }
+            }
+
+            let breakdown = self.update_info.source_breakdown();
+            if breakdown.len() > 1 {
+                let counts: Vec<String> = breakdown.iter()
+                    .map(|(source, count)| format!("{}: {}", source.label(), locale::format_count(*count)))
+                    .collect();
+                widgets.push(text(counts.join(", ")).into());
+            }
+        } else {
+            widgets.push(text("System is up to date").size(18).into());
+        }
+
+        // Why the count is what it is: the latest change, the earlier ones
+        // on request
+        if let Some((latest, earlier)) = self.state.count_changes.split_last() {
+            let describe = |change: &CountChange| {
+                format!("{}: {} → {}, {}", history::format_timestamp(change.timestamp), change.from, change.to, change.describe())
+            };
+            widgets.push(text(describe(latest)).size(12).into());
+            if !earlier.is_empty() {
+                widgets.push(
+                    button::text(format!("Earlier changes {}", if self.count_changes_expanded { "▾" } else { "▸" }))
+                        .on_press(Message::ToggleCountChanges)
+                        .into(),
+                );
+                if self.count_changes_expanded {
+                    for change in earlier.iter().rev() {
+                        widgets.push(text(format!("  {}", describe(change))).size(10).into());
+                    }
+                }
+            }
+        }
+
+        if !self.update_info.ignored.is_empty() {
+            widgets.push(text(format!("{} excluded by system policy", self.update_info.ignored.len())).size(12).into());
+        }
+        if !self.update_info.filtered.is_empty() {
+            widgets.push(text(format!("{} from repositories not counted", self.update_info.filtered.len())).size(12).into());
+        }
+
+        if !self.update_info.held_back.is_empty() {
+            widgets.push(
+                button::text(format!(
+                    "{} not upgrading {}",
+                    self.update_info.held_back.len(),
+                    if self.held_back_expanded { "▾" } else { "▸" }
+                ))
+                .on_press(Message::ToggleHeldBack)
+                .into()
+            );
+            if self.held_back_expanded {
+                for held in &self.update_info.held_back {
+                    widgets.push(text(format!("  • {}: {}", held.name, held.reason)).size(10).into());
+                }
+            }
+        }
+
+        // The failure guidance above already covers the clock
+        if self.trust_failure.is_none() && self.config.check_clock_skew {
+            let skewed_clock = self.clock_status.filter(|clock| clock.large_skew().is_some());
+            if let Some(clock) = skewed_clock.as_ref().and_then(ClockStatus::describe) {
+                widgets.push(text(format!("⚠ {}", clock)).size(12).into());
+                widgets.push(
+                    text("Signature checks and secure connections to mirrors may fail. On dual-boot machines, \
+                        Windows often keeps the hardware clock in local time.")
+                        .size(10)
+                        .into()
+                );
+            }
+        }
+
+        for (source, health) in self.tracked_sources() {
+            if health.is_failing_repeatedly() {
+                widgets.push(
+                    text(format!("⚠ {} failed the last {} checks, see Settings", source, health.consecutive_failures))
+                        .size(12)
+                        .into()
+                );
+            }
+        }
+
+        // Last check time
+        if let Some(last_check) = self.last_check {
+            let elapsed = last_check.elapsed();
+            let mut time_text = if elapsed.as_secs() < 60 {
+                "Last checked: just now".to_string()
+            } else if elapsed.as_secs() < 3600 {
+                format!("Last checked: {} minutes ago", elapsed.as_secs() / 60)
+            } else {
+                format!("Last checked: {} hours ago", elapsed.as_secs() / 3600)
+            };
+            if let Some(origin) = &self.last_check_origin {
+                time_text.push_str(&format!(" by {}", origin.describe()));
+            }
+            widgets.push(text(time_text).size(12).into());
+        }
+
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(16.0)).into());
+
+        widgets.push(self.check_buttons());
+
+        if self.report_only() {
+            widgets.extend(self.report_only_section());
+        }
+
+        // Update System button right after Check for Updates if updates available
+        if self.update_info.has_updates() {
+            let assessment = risk::assess(&self.update_info.packages, &self.update_info.removals);
+
+            let update_button: Element<_> = if self.report_only() {
+                text("Installing needs administrator rights")
+                    .size(12)
+                    .width(cosmic::iced::Length::Fill)
+                    .into()
+            } else if self.update_running {
+                button::text("Update System")
+                    .width(cosmic::iced::Length::Fill)
+                    .into()
+            } else if self.preflight_running {
+                text("Checking the update for conflicts...")
+                    .size(12)
+                    .width(cosmic::iced::Length::Fill)
+                    .into()
+            } else {
+                button::text("Update System")
+                    .on_press(Message::LaunchTerminalUpdate)
+                    .width(cosmic::iced::Length::Fill)
+                    .into()
+            };
+
+            if let Some(preflight) = &self.preflight {
+                widgets.extend(self.preflight_section(preflight));
+            }
+
+            widgets.push(
+                row()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(update_button)
+                    .push(
+                        button::text(format!(
+                            "Risk: {} {}",
+                            assessment.level.name(),
+                            if self.risk_details_expanded { "▾" } else { "▸" }
+                        ))
+                        .on_press(Message::ToggleRiskDetails)
+                    )
+                    .into()
+            );
+
+            if self.risk_details_expanded {
+                if assessment.reasons.is_empty() {
+                    widgets.push(text("  No kernel, core library or major version updates").size(10).into());
+                }
+                for reason in assessment.reasons {
+                    widgets.push(text(format!("  • {}", reason)).size(10).into());
+                }
+            }
+
+            if !self.report_only() {
+                let steps = self.update_all_plan();
+                if steps.len() > 1 && self.state.update_all_progress.is_none() && !self.update_running {
+                    let order: Vec<String> = steps.iter().map(Step::label).collect();
+                    widgets.push(
+                        button::text("Update Everything")
+                            .on_press(Message::UpdateEverything)
+                            .width(cosmic::iced::Length::Fill)
+                            .into()
+                    );
+                    widgets.push(text(format!("  {}", order.join(" → "))).size(10).into());
+                }
+
+                widgets.push(text("💡 Tip: Middle-click on the Panel icon").size(10).into());
+
+                let mut remind_row = row()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center);
+                if let Some(snooze) = &self.state.snooze {
+                    remind_row = remind_row
+                        .push(text(format!("Reminder set for {}", history::format_timestamp(snooze.until))).size(12))
+                        .push(Space::with_width(cosmic::iced::Length::Fill))
+                        .push(button::text("Cancel").on_press(Message::CancelSnooze));
+                } else {
+                    remind_row = remind_row.push(text("Remind me:").size(12));
+                    for reminder in Reminder::ALL {
+                        remind_row = remind_row.push(button::text(reminder.label()).on_press(Message::Snooze(reminder)));
+                    }
+                }
+                widgets.push(remind_row.into());
+            }
+        }
+
+        if let Some(progress) = &self.state.update_all_progress {
+            if !self.report_only() && !self.update_running {
+                widgets.push(text("\"Update Everything\" did not finish").size(14).into());
+                widgets.push(text(progress.summary(&self.update_all_plan())).size(12).into());
+                widgets.push(
+                    row()
+                        .spacing(8)
+                        .push(button::text("Resume").on_press(Message::ResumeUpdateEverything))
+                        .push(button::text("Start Over").on_press(Message::UpdateEverything))
+                        .into()
+                );
+            }
+        }
+
+        if let Some(kernel) = KernelUpdate::find(&self.update_info.packages) {
+            widgets.extend(self.kernel_section(&kernel));
+        }
+
+        if !self.state.staged_firmware.is_empty() {
+            widgets.push(text("Firmware will install on next reboot:").size(14).into());
+            for staged in &self.state.staged_firmware {
+                widgets.push(text(format!("  {} → {}", staged.device, staged.version)).size(12).into());
+            }
+            widgets.push(
+                button::text("Reboot Now")
+                    .on_press(Message::RebootToApplyFirmware)
+                    .width(cosmic::iced::Length::Fill)
+                    .into()
+            );
+        }
+
+        if !self.appimage_updates.is_empty() {
+            widgets.push(text(format!("AppImage updates ({}):", self.appimage_updates.len())).size(14).into());
+            for update in &self.appimage_updates {
+                widgets.push(text(format!("  {}", update.name)).size(12).into());
+            }
+            widgets.push(
+                button::text("Update AppImages")
+                    .on_press_maybe((!self.update_running).then_some(Message::UpdateAppImages))
+                    .width(cosmic::iced::Length::Fill)
+                    .into()
+            );
+        }
+
+        let containers: Vec<&ContainerUpdates> = self.container_updates.iter()
+            .filter(|updates| !updates.packages.is_empty() || updates.error.is_some())
+            .collect();
+        if !containers.is_empty() {
+            widgets.push(text("Containers:").size(14).into());
+            for updates in containers {
+                let name = &updates.container.name;
+                match &updates.error {
+                    Some(error) => widgets.push(text(format!("  {}: {}", name, error)).size(12).into()),
+                    None => {
+                        widgets.push(
+                            row()
+                                .spacing(8)
+                                .align_y(cosmic::iced::Alignment::Center)
+                                .push(text(format!("  {} ({})", name, updates.packages.len())).size(12).width(cosmic::iced::Length::Fill))
+                                .push(
+                                    button::text("Update")
+                                        .on_press_maybe((!self.update_running).then(|| Message::UpdateContainer(name.clone())))
+                                )
+                                .into()
+                        );
+                        for package in &updates.packages {
+                            widgets.push(text(self.package_label(package)).size(10).into());
+                        }
+                    }
+                }
+            }
+        }
+
+        match &self.topgrade_steps {
+            Some(Ok(steps)) if !steps.is_empty() => {
+                let names = |pending: Option<bool>| steps.iter()
+                    .filter(|step| step.pending(&self.update_info.packages) == pending)
+                    .map(|step| step.name.as_str())
+                    .collect::<Vec<_>>();
+                let (pending, unchecked) = (names(Some(true)), names(None));
+                widgets.push(text(format!("Update runs topgrade, {} of {} steps with pending work: {}", pending.len(), steps.len(), pending.join(", "))).size(12).into());
+                if !unchecked.is_empty() {
+                    widgets.push(text(format!("Not checked here, may have work: {}", unchecked.join(", "))).size(10).into());
+                }
+            }
+            Some(Err(error)) => widgets.push(text(format!("⚠ {}", error)).size(12).into()),
+            _ => {}
+        }
+
+        let hosts: Vec<&HostUpdates> = self.remote_updates.iter()
+            .filter(|updates| !updates.packages.is_empty() || updates.error.is_some())
+            .collect();
+        if !hosts.is_empty() {
+            widgets.push(text("Remote hosts:").size(14).into());
+            for updates in hosts {
+                let host = &updates.host;
+                match &updates.error {
+                    Some(error) => widgets.push(text(format!("  {}: {}", host, error)).size(12).into()),
+                    None => {
+                        let package_manager = updates.package_manager.map_or("", |pm| pm.name());
+                        widgets.push(
+                            row()
+                                .spacing(8)
+                                .align_y(cosmic::iced::Alignment::Center)
+                                .push(text(format!("  {} ({}, {})", host, package_manager, updates.packages.len())).size(12).width(cosmic::iced::Length::Fill))
+                                .push(
+                                    button::text("Update")
+                                        .on_press_maybe((!self.update_running).then(|| Message::UpdateRemoteHost(host.clone())))
+                                )
+                                .into()
+                        );
+                        for package in &updates.packages {
+                            widgets.push(text(self.package_label(package)).size(10).into());
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(updates) = self.root_updates.as_ref().filter(|updates| !updates.packages.is_empty() || updates.error.is_some()) {
+            widgets.push(text(format!("Alternate root {}:", updates.root)).size(14).into());
+            match &updates.error {
+                Some(error) => widgets.push(text(format!("  {}", error)).size(12).into()),
+                None => {
+                    let package_manager = updates.package_manager.map_or("", |pm| pm.name());
+                    widgets.push(
+                        row()
+                            .spacing(8)
+                            .align_y(cosmic::iced::Alignment::Center)
+                            .push(text(format!("  {} ({})", package_manager, updates.packages.len())).size(12).width(cosmic::iced::Length::Fill))
+                            .push(button::text("Update").on_press_maybe((!self.update_running).then_some(Message::UpdateAlternateRoot)))
+                            .into()
+                    );
+                    for package in &updates.packages {
+                        widgets.push(text(self.package_label(package)).size(10).into());
+                    }
+                }
+            }
+        }
+
+        if let Some(terminal) = &self.active_terminal {
+            let status = if *terminal == self.config.preferred_terminal {
+                format!("⏳ Update in progress in {}", terminal)
+            } else {
+                format!("⏳ Update in progress in {} ('{}' was unavailable)", terminal, self.config.preferred_terminal)
+            };
+            widgets.push(text(status).size(12).into());
+            widgets.push(text("Checks and further updates are paused until the terminal is closed").size(10).into());
+        } else if self.update_running {
+            widgets.push(text("⏳ Starting the terminal...").size(12).into());
+        }
+
+        let failed = self.last_run_outcome.as_ref().map_or(0, |outcome| outcome.failed.len());
+        if failed > 0 {
+            widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
+            widgets.push(
+                text(format!("⚠ {} package{} failed to update", failed, if failed == 1 { "" } else { "s" }))
+                    .size(14)
+                    .into()
+            );
+            if let Some(pm) = self.config.package_manager() {
+                widgets.push(text(pm.backend().failed_update_guidance()).size(12).into());
+            }
+        }
+
+        if self.update_info.has_updates() || !self.update_info.ignored.is_empty() || !self.update_info.filtered.is_empty() {
+            widgets.push(Space::with_height(cosmic::iced::Length::Fixed(16.0)).into());
+
+            // Show package list
+            widgets.push(text("Packages to update:").size(14).into());
+            if self.can_select_packages() && self.update_info.has_updates() {
+                widgets.push(self.selection_toolbar());
+            }
+            if let Some(editor) = self.note_editor() {
+                widgets.push(editor);
+            }
+            widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
+
+            // Create scrollable list of packages. A large backlog is cut
+            // short until asked for, building thousands of rows at once
+            // stalls the popup
+            let mut package_list = column().spacing(4);
+            let mut remaining = if self.package_list_expanded { usize::MAX } else { PACKAGE_LIST_PREVIEW };
+
+            package_list = package_list.extend(self.security_section());
+
+            // One section per source. With more than one, each can be
+            // folded under its header
+            let breakdown = self.update_info.source_breakdown();
+            let foldable = breakdown.len() > 1;
+            for (index, (source, count)) in breakdown.iter().enumerate() {
+                let collapsed = foldable && self.collapsed_sources.contains(source);
+                let packages: Vec<_> = if collapsed {
+                    Vec::new()
+                } else {
+                    take_preview(self.update_info.packages.iter().filter(|p| p.source == *source).collect(), &mut remaining)
+                };
+                if !collapsed && packages.is_empty() {
+                    continue;
+                }
+                if index > 0 {
+                    package_list = package_list.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)));
+                }
+
+                let title: Element<'_, Message> = if foldable {
+                    button::text(format!("{} {} ({})", if collapsed { "▸" } else { "▾" }, source.label(), locale::format_count(*count)))
+                        .on_press(Message::ToggleSource(source.clone()))
+                        .into()
+                } else {
+                    text(format!("{}:", source.label())).size(12).into()
+                };
+                let mut header = row()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(title)
+                    .push(Space::with_width(cosmic::iced::Length::Fill));
+                match source {
+                    Source::Flatpak(installation) => {
+                        header = header.push(
+                            button::text("Update")
+                                .on_press_maybe((!self.update_running).then_some(Message::UpdateFlatpak(*installation)))
+                        );
+                    }
+                    Source::Custom(name) => {
+                        let updatable = self.custom_sources.iter()
+                            .any(|backend| backend.name() == name && backend.update_command(self.config.privilege_escalation).is_some());
+                        if updatable {
+                            header = header.push(
+                                button::text("Update")
+                                    .on_press_maybe((!self.update_running).then(|| Message::UpdateCustomSource(name.clone())))
+                            );
+                        }
+                    }
+                    _ => {}
+                }
+                // A package manager's own updates need no header while alone
+                if foldable || *source != Source::System {
+                    package_list = package_list.push(header);
+                }
+
+                match source {
+                    // Neither can be selected or grouped
+                    Source::Custom(_) | Source::Firmware => {
+                        for package in packages {
+                            package_list = package_list.push(text(self.package_label(package)).size(10));
+                        }
+                    }
+                    _ => package_list = package_list.extend(self.package_rows(&packages)),
+                }
+            }
+
+            // Updates the package manager is configured to skip, not part of the count
+            let ignored = take_preview(self.update_info.ignored.iter().collect(), &mut remaining);
+            if !ignored.is_empty() {
+                if self.update_info.has_updates() {
+                    package_list = package_list.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)));
+                }
+                package_list = package_list.push(text("Excluded by system policy:").size(12));
+                for package in ignored {
+                    package_list = package_list.push(text(self.package_label(package)).size(10));
+                }
+            }
+
+            let filtered = take_preview(self.update_info.filtered.iter().collect(), &mut remaining);
+            if !filtered.is_empty() {
+                if self.update_info.has_updates() || !self.update_info.ignored.is_empty() {
+                    package_list = package_list.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)));
+                }
+                package_list = package_list.push(text("From repositories not counted:").size(12));
+                for package in filtered {
+                    package_list = package_list.push(text(self.package_label(package)).size(10));
+                }
+            }
+
+            let listed = self.update_info.packages.len() + self.update_info.ignored.len() + self.update_info.filtered.len();
+            if remaining == 0 && !self.package_list_expanded && listed > PACKAGE_LIST_PREVIEW {
+                package_list = package_list.push(
+                    button::text(format!("Show all {}", locale::format_count(listed))).on_press(Message::ShowAllPackages)
+                );
+            }
+
+            // Add the package list in a scrollable styled container
+            widgets.push(
+                cosmic::widget::container(
+                    scrollable(package_list)
+                        .id(PACKAGE_LIST_ID.clone())
+                        .on_scroll(Message::PackageListScrolled)
+                        .width(cosmic::iced::Length::Fill)
+                        .height(cosmic::iced::Length::Fixed(100.0)) // Reasonable height with more popup space
+                )
+                .style(|_theme| cosmic::widget::container::Style {
+                    background: Some(cosmic::iced_core::Background::Color([0.1, 0.1, 0.1, 0.1].into())),
+                    border: cosmic::iced::Border {
+                        radius: cosmic::iced::border::Radius::from(8.0),
+                        width: 1.0,
+                        color: [0.3, 0.3, 0.3, 0.5].into(),
+                    },
+                    ..Default::default()
+                })
+                .padding(12)
+                .width(cosmic::iced::Length::Fill)
+                .into()
+            );
+
+        }
+
+        column()
+            .spacing(8)
+            .extend(widgets)
+            .into()
+    }
+
+    /// The selectable packages in the order the list shows them: per
+    /// source, the ungrouped ones, then the members of each open group.
+    fn displayed_packages(&self) -> Vec<&PackageUpdate> {
+        let breakdown = self.update_info.source_breakdown();
+        let foldable = breakdown.len() > 1;
+        let mut displayed = Vec::new();
+        for (source, _) in &breakdown {
+            let listed_apart = matches!(source, Source::Custom(_) | Source::Firmware);
+            if listed_apart || (foldable && self.collapsed_sources.contains(source)) {
+                continue;
+            }
+            let mut groups: BTreeMap<&str, Vec<&PackageUpdate>> = BTreeMap::new();
+            for package in self.update_info.packages.iter().filter(|p| p.source == *source) {
+                match package.group.as_deref() {
+                    Some(group) => groups.entry(group).or_default().push(package),
+                    None => displayed.push(package),
+                }
+            }
+            for (group, members) in groups {
+                if self.expanded_groups.contains(group) {
+                    displayed.extend(members);
+                }
+            }
+        }
+        displayed
+    }
+
+    /// Security updates from the package manager's repositories, those
+    /// "Install security updates only" installs.
+    fn security_updates(&self) -> Vec<&PackageUpdate> {
+        self.update_info.packages.iter()
+            .filter(|p| p.is_security && p.source == Source::System)
+            .collect()
+    }
+
+    fn security_update_command(&self) -> Option<String> {
+        let names: Vec<String> = self.security_updates().iter().map(|p| p.name.clone()).collect();
+        if names.is_empty() {
+            return None;
+        }
+        self.config.package_manager()?.backend().security_update_command(self.config.privilege_escalation, &names)
+    }
+
+    /// Security updates of every source, pinned above the sections in a
+    /// compact list.
+    fn security_section(&self) -> Vec<Element<'_, Message>> {
+        let security: Vec<&PackageUpdate> = self.update_info.packages.iter().filter(|p| p.is_security).collect();
+        if security.is_empty() {
+            return Vec::new();
+        }
+        let mut header = row()
+            .spacing(8)
+            .align_y(cosmic::iced::Alignment::Center)
+            .push(text(format!("{} Security updates ({}):", IconRole::SecurityUpdates.glyph(), security.len())).size(12))
+            .push(Space::with_width(cosmic::iced::Length::Fill));
+        // Only those of the package manager are installed; the header
+        // counts every source's
+        if !self.report_only() && !self.config.use_topgrade && self.security_update_command().is_some() {
+            header = header.push(
+                button::text(format!("Install security updates only ({})", self.security_updates().len()))
+                    .on_press_maybe((!self.update_running).then_some(Message::UpdateSecurity))
+            );
+        }
+        let mut widgets: Vec<Element<'_, Message>> = vec![header.into()];
+        for package in security {
+            widgets.push(text(self.package_label(package)).size(10).into());
+        }
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
+        widgets
+    }
+
+    /// Updates that already failed once are marked, so they don't pass for
+    /// newly arrived ones.
+    fn package_label(&self, package: &PackageUpdate) -> String {
+        let marker = if self.last_run_outcome.as_ref().is_some_and(|outcome| outcome.is_failed(package)) { "⚠ " } else { "" };
+        let severity = package.severity.as_deref().map(|severity| format!(" ({})", severity)).unwrap_or_default();
+        let marker = if package.is_security && self.config.high_contrast_indicators {
+            format!("{}{} ", marker, IconRole::SecurityUpdates.glyph())
+        } else {
+            marker.to_string()
+        };
+        if package.current_version != "unknown" {
+            format!("  {}{} {} → {}{}", marker, package.name, package.current_version, package.new_version, severity)
+        } else {
+            format!("  {}{} → {}{}", marker, package.name, package.new_version, severity)
+        }
+    }
+
+    /// Rows for a package list section. Packages without a group are listed
+    /// individually; grouped packages are folded under a collapsible header.
+    fn package_rows<'a>(&'a self, packages: &[&'a PackageUpdate]) -> Vec<Element<'a, Message>> {
+        let mut rows: Vec<Element<'a, Message>> = Vec::new();
+        let mut groups: BTreeMap<&str, Vec<&PackageUpdate>> = BTreeMap::new();
+
+        for package in packages {
+            match package.group.as_deref() {
+                Some(group) => groups.entry(group).or_default().push(package),
+                None => rows.push(self.package_row(package)),
+            }
+        }
+
+        let can_update_groups = !self.report_only() && !self.update_running && !self.config.use_topgrade && self.config.package_manager()
+            .map(|pm| pm.capabilities().selective_update)
+            .unwrap_or(false);
+
+        for (group, members) in groups {
+            let expanded = self.expanded_groups.contains(group);
+            let header = format!("{} {} ({})", if expanded { "▾" } else { "▸" }, group, members.len());
+
+            let mut header_row = row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(
+                    button::text(header)
+                        .on_press(Message::ToggleGroup(group.to_string()))
+                )
+                .push(Space::with_width(cosmic::iced::Length::Fill));
+
+            if can_update_groups {
+                header_row = header_row.push(
+                    button::text("Update group")
+                        .on_press(Message::UpdateGroup(group.to_string()))
+                );
+            }
+            rows.push(header_row.into());
+
+            if expanded {
+                for package in members {
+                    rows.push(self.package_row(package));
+                }
+            }
+        }
+
+        rows
+    }
+
+    /// A package's label, clickable to select it while selective updates
+    /// are possible, with the user's note on it.
+    fn package_row<'a>(&'a self, package: &'a PackageUpdate) -> Element<'a, Message> {
+        let label: Element<'a, Message> = if self.can_select_packages() {
+            let marker = if self.selection.contains(&package.name) { "☑" } else { "☐" };
+            button::text(format!("{}{}", marker, self.package_label(package)))
+                .on_press(Message::TogglePackageSelected(package.name.clone()))
+                .into()
+        } else {
+            text(self.package_label(package)).size(10).into()
+        };
+        let mut entry = column()
+            .push(
+                row()
+                    .spacing(4)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(label)
+                    .push(Space::with_width(cosmic::iced::Length::Fill))
+                    .push(button::text("✎").on_press(Message::EditNote(package.name.clone())))
+            );
+        if let Some(note) = self.state.package_notes.get(&package.name) {
+            entry = entry.push(text(format!("    “{}”", note)).size(10));
+        }
+        entry.into()
+    }
+
+    /// Fields defining a new custom source: the check command, the pattern
+    /// reading each line of its output and the update command.
+    fn custom_source_editor(&self) -> Vec<Element<'_, Message>> {
+        let draft = &self.custom_source_draft;
+        let mut widgets: Vec<Element<'_, Message>> = vec![
+            text("New source").size(12).into(),
+            text_input("Name, e.g. opkg", &draft.name)
+                .on_input(|name| Message::CustomSourceDraftChanged(CustomSource { name, ..draft.clone() }))
+                .width(cosmic::iced::Length::Fill)
+                .into(),
+            text_input("Check command, e.g. opkg list-upgradable", &draft.check_command)
+                .on_input(|check_command| Message::CustomSourceDraftChanged(CustomSource { check_command, ..draft.clone() }))
+                .width(cosmic::iced::Length::Fill)
+                .into(),
+            text_input(r"Pattern, e.g. ^(?P<name>\S+) - (?P<old>\S+) - (?P<new>\S+)$", &draft.pattern)
+                .on_input(|pattern| Message::CustomSourceDraftChanged(CustomSource { pattern, ..draft.clone() }))
+                .width(cosmic::iced::Length::Fill)
+                .into(),
+            text_input("Update command (empty to only check)", &draft.update_command)
+                .on_input(|update_command| Message::CustomSourceDraftChanged(CustomSource { update_command, ..draft.clone() }))
+                .width(cosmic::iced::Length::Fill)
+                .into(),
+            text("The pattern needs the groups name and new; old (or current) is optional").size(10).into(),
+        ];
+        if let Some(error) = &self.custom_source_draft_error {
+            widgets.push(text(format!("⚠ {}", error)).size(10).into());
+        }
+        widgets.push(
+            button::text("Add source")
+                .on_press_maybe((!draft.name.trim().is_empty() && !draft.check_command.trim().is_empty()).then_some(Message::AddCustomSource))
+                .into(),
+        );
+        widgets
+    }
+
+    /// Editor for the note on one package, shown above the list.
+    fn note_editor(&self) -> Option<Element<'_, Message>> {
+        let (name, draft) = self.note_editor.as_ref()?;
+        Some(
+            column()
+                .spacing(4)
+                .push(text(format!("Note on {}", name)).size(12))
+                .push(
+                    text_input("e.g. broke audio last time, wait for the .1 release", draft.as_str())
+                        .on_input(Message::NoteChanged)
+                        .on_submit(|_| Message::SaveNote)
+                        .width(cosmic::iced::Length::Fill)
+                )
+                .push(
+                    row()
+                        .spacing(8)
+                        .push(button::text("Save").on_press(Message::SaveNote))
+                        .push(button::text("Remove").on_press(Message::RemoveNote))
+                        .push(button::text("Cancel").on_press(Message::CancelNote))
+                )
+                .into()
+        )
+    }
+
+    /// Bulk selection actions and the update of the selected packages.
+    fn selection_toolbar(&self) -> Element<'_, Message> {
+        let has_aur = self.update_info.packages.iter().any(|p| p.source == Source::Aur);
+        let has_security = self.update_info.packages.iter().any(|p| p.is_security);
+        let mut toolbar = row()
+            .spacing(4)
+            .align_y(cosmic::iced::Alignment::Center)
+            .push(button::text(if has_aur { "All official" } else { "All" }).on_press(Message::SelectOfficial));
+        if has_security {
+            toolbar = toolbar.push(button::text("All security").on_press(Message::SelectSecurity));
+        }
+        if has_aur {
+            toolbar = toolbar.push(button::text("No AUR").on_press(Message::DeselectAur));
+        }
+        if !self.selection.is_empty() {
+            toolbar = toolbar
+                .push(button::text("Clear").on_press(Message::ClearSelection))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(button::text(format!("Update Selected ({})", self.selection.len())).on_press(Message::UpdateSelected));
+        }
+        toolbar.into()
+    }
+
+    fn view_history_tab(&self) -> Element<'_, Message> {
+        let mut widgets = vec![];
+
+        // Weekly recap card
+        let summary = Summary::since(&self.history, chrono::Local::now().timestamp() - WEEK_SECS);
+        widgets.push(
+            cosmic::widget::container(
+                column()
+                    .spacing(4)
+                    .push(text("This week").size(14))
+                    .push(text(summary.describe()).size(12))
+            )
+            .style(|_theme| cosmic::widget::container::Style {
+                background: Some(cosmic::iced_core::Background::Color([0.1, 0.1, 0.1, 0.1].into())),
+                border: cosmic::iced::Border {
+                    radius: cosmic::iced::border::Radius::from(8.0),
+                    width: 1.0,
+                    color: [0.3, 0.3, 0.3, 0.5].into(),
+                },
+                ..Default::default()
+            })
+            .padding(12)
+            .width(cosmic::iced::Length::Fill)
+            .into()
+        );
+
+        let changes = history::changes_since_last_update(&self.history, self.state.changes_reviewed_at);
+        if !changes.is_empty() {
+            let mut list = column().spacing(2);
+            for change in &changes {
+                let security = if change.is_security { " (security)" } else { "" };
+                list = list.push(
+                    text(format!("  {} {} → {}{}", change.name, change.from_version, change.to_version, security)).size(10)
+                );
+            }
+            let mut card = column()
+                .spacing(4)
+                .push(text(format!("Changed since your last update ({})", changes.len())).size(14))
+                .push(text("Installed outside the applet, e.g. by automatic updates").size(10))
+                .push(scrollable(list).height(cosmic::iced::Length::Fixed(100.0)));
+            if !self.read_only {
+                card = card.push(button::text("Mark as Reviewed").on_press(Message::MarkChangesReviewed));
+            }
+            widgets.push(
+                cosmic::widget::container(card)
+                    .style(|_theme| cosmic::widget::container::Style {
+                        background: Some(cosmic::iced_core::Background::Color([0.1, 0.1, 0.1, 0.1].into())),
+                        border: cosmic::iced::Border {
+                            radius: cosmic::iced::border::Radius::from(8.0),
+                            width: 1.0,
+                            color: [0.3, 0.3, 0.3, 0.5].into(),
+                        },
+                        ..Default::default()
+                    })
+                    .padding(12)
+                    .width(cosmic::iced::Length::Fill)
+                    .into()
+            );
+        }
+
+        widgets.push(text("Recent updates").size(14).into());
+
+        if self.history.is_empty() {
+            widgets.push(text("No updates recorded yet").size(12).into());
+        } else {
+            let mut entries = column().spacing(6);
+            for entry in self.history.iter().rev().take(50) {
+                let names: Vec<&str> = entry.packages.iter().map(|p| p.name.as_str()).collect();
+                entries = entries
+                    .push(
+                        text(format!(
+                            "{} — {} package{} ({}{})",
+                            history::format_timestamp(entry.timestamp),
+                            entry.packages.len(),
+                            if entry.packages.len() == 1 { "" } else { "s" },
+                            entry.package_manager,
+                            if entry.unattended { ", outside the applet" } else { "" }
+                        ))
+                        .size(12)
+                    )
+                    .push(text(format!("  {}", names.join(", "))).size(10));
+            }
+
+            widgets.push(
+                scrollable(entries)
+                    .width(cosmic::iced::Length::Fill)
+                    .height(cosmic::iced::Length::Fixed(180.0))
+                    .into()
+            );
+        }
+
+        column()
+            .spacing(8)
+            .extend(widgets)
+            .into()
+    }
+
+    fn view_settings_tab(&self) -> Element<'_, Message> {
+        let mut widgets = vec![];
+
+        widgets.push(text("Package Manager").size(16).into());
+
+        if self.available_package_managers.is_empty() {
+            widgets.push(text("No package managers found").size(14).into());
+            widgets.push(
+                button::text("Discover Package Managers")
+                    .on_press(Message::DiscoverPackageManagers)
+                    .into(),
+            );
+        } else {
+            let listed = self.listed_package_managers();
+            widgets.push(text(format!("Found {} package managers:", listed.len())).size(12).into());
+            widgets.push(
+                text("Check the enabled ones, in this order; the first is the primary (packages reported twice are counted once)")
+                    .size(12)
+                    .into(),
+            );
+            let primary = self.config.package_manager();
+            let order = self.config.preference_order();
+            for pm in order.into_iter().filter(|pm| listed.contains(pm)) {
+                let enabled = self.config.enabled_package_managers.contains(&pm);
+                // One stays on, with none the preferred one would be enabled again
+                let toggle = if enabled && self.config.enabled_package_managers.len() == 1 {
+                    toggler(enabled)
+                } else {
+                    toggler(enabled).on_toggle(move |enabled| Message::TogglePackageManager(pm, enabled))
+                };
+                let label = if primary == Some(pm) { format!("{} (primary)", pm.name()) } else { pm.name().to_string() };
+                let mut entry = row()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(toggle)
+                    .push(text(label))
+                    .push(Space::with_width(cosmic::iced::Length::Fill));
+                if listed.len() > 1 {
+                    entry = entry
+                        .push(button::text("▲").on_press(Message::MovePackageManagerPreference(pm, true)))
+                        .push(button::text("▼").on_press(Message::MovePackageManagerPreference(pm, false)));
+                }
+                widgets.push(entry.into());
+            }
+            if listed.len() > 1 && !self.config.package_manager_preference.is_empty() {
+                widgets.push(button::text("Reset Order").on_press(Message::ResetPackageManagerPreference).into());
+            }
+
+            let hidden = self.available_package_managers.len() - listed.len();
+            if hidden > 0 || self.config.show_all_package_managers {
+                widgets.push(
+                    row()
+                        .spacing(8)
+                        .align_y(cosmic::iced::Alignment::Center)
+                        .push(text("Show all detected package managers (containers, chroots)"))
+                        .push(Space::with_width(cosmic::iced::Length::Fill))
+                        .push(toggler(self.config.show_all_package_managers).on_toggle(Message::ToggleShowAllPackageManagers))
+                        .into(),
+                );
+            }
+            if hidden > 0 {
+                let distro = self.os_release.as_ref().and_then(|os_release| os_release.pretty_name.as_deref()).unwrap_or("this distribution");
+                widgets.push(text(format!("{} not made for {} hidden", hidden, distro)).size(10).into());
+            }
+        }
+
+        let sources = self.tracked_sources();
+        if !sources.is_empty() {
+            widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
+            widgets.push(text("Sources").size(14).into());
+            let durations: Vec<String> = sources.iter()
+                .filter_map(|(source, health)| Some(format!("{}: {}", source, health::format_duration(health.last_duration()?))))
+                .collect();
+            if !durations.is_empty() {
+                widgets.push(text(format!("Last check took {}", durations.join(", "))).size(10).into());
+            }
+            for (source, health) in sources {
+                let marker = if health.is_failing_repeatedly() || health.is_consistently_slow() { "⚠ " } else { "" };
+                widgets.push(text(format!("{}{}", marker, source)).size(12).into());
+                let describe = |timestamp: Option<i64>| timestamp.map_or("never".to_string(), history::format_timestamp);
+                widgets.push(
+                    text(format!(
+                        "Last success: {} · Last failure: {}",
+                        describe(health.last_success),
+                        describe(health.last_failure)
+                    ))
+                    .size(10)
+                    .into()
+                );
+                if health.consecutive_failures > 0 {
+                    if let Some(error) = &health.last_error {
+                        widgets.push(text(format!("Failed {} checks in a row: {}", health.consecutive_failures, error)).size(10).into());
+                    }
+                }
+                if health.is_consistently_slow() {
+                    widgets.push(text("Slow in the last checks; a slow or unreachable mirror is the usual cause").size(10).into());
+                }
+            }
+        }
 
-    fn handle_toggle_popup(&mut self) -> Task<Message> {
-        if let Some(p) = self.popup.take() {
-            destroy_popup(p)
-        } else {
-            // Add error handling for popup creation
-            if let Some(main_window_id) = self.core.main_window_id() {
-                let new_id = Id::unique();
-                self.popup.replace(new_id);
-                let mut popup_settings = self.core.applet.get_popup_settings(
-                    main_window_id,
-                    new_id,
-                    None,
-                    None,
-                    None,
+        let repositories = self.repositories();
+        if !repositories.is_empty() {
+            widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
+            widgets.push(text("Repositories").size(14).into());
+            widgets.push(text("Updates from repositories that are not counted are listed apart").size(10).into());
+            for (repository, packages) in repositories {
+                let counted = !self.config.excluded_repositories.contains(&repository);
+                let source = self.update_info.repository_sources.get(repository.as_str());
+                widgets.push(
+                    row()
+                        .spacing(8)
+                        .align_y(cosmic::iced::Alignment::Center)
+                        .push(text(format!("{} ({})", repository, packages.len())).size(12))
+                        .push(Space::with_width(cosmic::iced::Length::Fill))
+                        .push(toggler(counted).on_toggle(move |counted| Message::ToggleRepositoryCounted(repository.clone(), counted)))
+                        .into(),
                 );
-                popup_settings.positioner.size_limits = Limits::NONE
-                    .max_width(550.0)
-                    .min_width(450.0)
-                    .min_height(350.0)
-                    .max_height(800.0);
+                if let Some(path) = source {
+                    widgets.push(text(format!("Configured in {}", path.display())).size(10).into());
+                }
+                if !packages.is_empty() {
+                    widgets.push(text(packages.join(", ")).size(10).into());
+                }
+            }
+        }
 
-                Task::batch(vec![
-                    get_popup(popup_settings),
-                    window::gain_focus(new_id),
-                ])
-            } else {
-                eprintln!("Failed to get main window ID for popup");
-                self.error_message = Some("Unable to open popup window".to_string());
-                Task::none()
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(16.0)).into());
+
+        // Check interval
+        widgets.push(text("Check Interval (minutes)").size(14).into());
+        let interval_value = self.config.check_interval_minutes.to_string();
+        widgets.push(
+            text_input("60", interval_value)
+                .on_input(|s| Message::SetCheckInterval(s.parse::<u32>().unwrap_or(60).max(1).min(1440)))
+                .width(cosmic::iced::Length::Fill)
+                .into(),
+        );
+
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text("Only check during allowed hours"))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(toggler(self.config.check_window_enabled).on_toggle(Message::ToggleCheckWindow))
+                .into(),
+        );
+
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text("Wait while gaming or in do not disturb"))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(toggler(self.config.defer_when_busy).on_toggle(Message::ToggleDeferWhenBusy))
+                .into(),
+        );
+
+        if self.config.check_window_enabled {
+            let window = self.config.check_window;
+            widgets.push(
+                row()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(text("From"))
+                    .push(
+                        text_input("8", window.start_hour.to_string())
+                            .on_input(move |s| Message::SetCheckWindowStart(s.parse::<u8>().unwrap_or(window.start_hour).min(23)))
+                    )
+                    .push(text("to"))
+                    .push(
+                        text_input("22", window.end_hour.to_string())
+                            .on_input(move |s| Message::SetCheckWindowEnd(s.parse::<u8>().unwrap_or(window.end_hour).min(23)))
+                    )
+                    .push(text("o'clock"))
+                    .into(),
+            );
+        }
+
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
+
+        // Toggles
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text("Auto-check on startup"))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(toggler(self.config.auto_check_on_startup).on_toggle(Message::ToggleAutoCheck))
+                .into(),
+        );
+
+        if self.config.auto_check_on_startup {
+            widgets.push(
+                row()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(text("Delay after network is online (seconds)"))
+                    .push(Space::with_width(cosmic::iced::Length::Fill))
+                    .push(
+                        text_input("2", self.config.startup_delay_secs.to_string())
+                            .on_input(|s| Message::SetStartupDelay(s.parse::<u32>().unwrap_or(2).min(600)))
+                            .width(cosmic::iced::Length::Fixed(64.0))
+                    )
+                    .into(),
+            );
+            widgets.push(
+                row()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(text("Wait for the graphical session"))
+                    .push(Space::with_width(cosmic::iced::Length::Fill))
+                    .push(toggler(self.config.wait_for_graphical_session).on_toggle(Message::ToggleWaitForGraphicalSession))
+                    .into(),
+            );
+        }
+
+        // Only show AUR toggle if package manager supports it
+        if let Some(pm) = self.config.package_manager() {
+            if pm.capabilities().aur {
+                widgets.push(
+                    row()
+                        .spacing(8)
+                        .align_y(cosmic::iced::Alignment::Center)
+                        .push(text("Include AUR updates"))
+                        .push(Space::with_width(cosmic::iced::Length::Fill))
+                        .push(toggler(self.config.include_aur_updates).on_toggle(Message::ToggleIncludeAur))
+                        .into(),
+                );
+            }
+            if pm.capabilities().preflight {
+                widgets.push(
+                    row()
+                        .spacing(8)
+                        .align_y(cosmic::iced::Alignment::Center)
+                        .push(text("Check for conflicts before updating"))
+                        .push(Space::with_width(cosmic::iced::Length::Fill))
+                        .push(toggler(self.config.preflight_check).on_toggle(Message::TogglePreflightCheck))
+                        .into(),
+                );
             }
         }
-    }
 
-    fn handle_popup_closed(&mut self, id: Id) -> Task<Message> {
-        if self.popup.as_ref() == Some(&id) {
-            self.popup = None;
-            self.active_tab = PopupTab::Updates;
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text("Show notifications"))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(toggler(self.config.show_notifications).on_toggle(Message::ToggleShowNotifications))
+                .into(),
+        );
+
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text("Weekly summary notification"))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(toggler(self.config.weekly_summary_notification).on_toggle(Message::ToggleWeeklySummary))
+                .into(),
+        );
+
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text("Play a sound when updates are found"))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(toggler(self.config.sound_on_updates).on_toggle(Message::ToggleSoundOnUpdates))
+                .into(),
+        );
+
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text("Play a sound when an update finishes"))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(toggler(self.config.sound_on_update_finished).on_toggle(Message::ToggleSoundOnUpdateFinished))
+                .into(),
+        );
+
+        if self.config.sound_on_updates || self.config.sound_on_update_finished {
+            widgets.push(
+                row()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(text("No sounds during quiet hours"))
+                    .push(Space::with_width(cosmic::iced::Length::Fill))
+                    .push(toggler(self.config.sound_quiet_hours_enabled).on_toggle(Message::ToggleSoundQuietHours))
+                    .into(),
+            );
+            if self.config.sound_quiet_hours_enabled {
+                let quiet = self.config.sound_quiet_hours;
+                widgets.push(
+                    row()
+                        .spacing(8)
+                        .align_y(cosmic::iced::Alignment::Center)
+                        .push(text("From"))
+                        .push(
+                            text_input("22", quiet.start_hour.to_string())
+                                .on_input(move |s| Message::SetSoundQuietStart(s.parse::<u8>().unwrap_or(quiet.start_hour).min(23)))
+                        )
+                        .push(text("to"))
+                        .push(
+                            text_input("7", quiet.end_hour.to_string())
+                                .on_input(move |s| Message::SetSoundQuietEnd(s.parse::<u8>().unwrap_or(quiet.end_hour).min(23)))
+                        )
+                        .push(text("o'clock"))
+                        .into(),
+                );
+            }
         }
-        Task::none()
-    }
 
-    fn handle_switch_tab(&mut self, tab: PopupTab) -> Task<Message> {
-        self.active_tab = tab;
-        Task::none()
-    }
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text("Warn about a wrong system clock"))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(toggler(self.config.check_clock_skew).on_toggle(Message::ToggleClockSkewCheck))
+                .into(),
+        );
+
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text("Show update count"))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(toggler(self.config.show_update_count).on_toggle(Message::ToggleShowUpdateCount))
+                .into(),
+        );
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text("High contrast indicators"))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(toggler(self.config.high_contrast_indicators).on_toggle(Message::ToggleHighContrastIndicators))
+                .into(),
+        );
+        widgets.push(text("Marks the state with a glyph (🛡 security updates, ↑ updates, ! error) instead of color alone, in the panel and the popup").size(10).into());
+
+        widgets.push(text("Panel Count Shows").size(14).into());
+        for source in BadgeSource::ALL {
+            let marker = if self.config.badge_source == source { "●" } else { "○" };
+            widgets.push(
+                button::text(format!("{} {}", marker, source.name()))
+                    .on_press(Message::SelectBadgeSource(source))
+                    .width(cosmic::iced::Length::Fill)
+                    .into(),
+            );
+        }
+
+        widgets.push(text("Panel Icons").size(14).into());
+        for source in IconSource::ALL {
+            let marker = if self.config.icon_source == source { "●" } else { "○" };
+            widgets.push(
+                button::text(format!("{} {}", marker, source.name()))
+                    .on_press(Message::SelectIconSource(source))
+                    .width(cosmic::iced::Length::Fill)
+                    .into(),
+            );
+        }
+
+        if self.config.show_update_count {
+            widgets.push(text("Update Count Position").size(14).into());
+            widgets.push(text("Horizontal panels").size(12).into());
+            for position in BadgePosition::ALL {
+                let marker = if self.config.badge_position_horizontal == position { "●" } else { "○" };
+                widgets.push(
+                    button::text(format!("{} {}", marker, position.name()))
+                        .on_press(Message::SelectHorizontalBadgePosition(position))
+                        .width(cosmic::iced::Length::Fill)
+                        .into(),
+                );
+            }
+            widgets.push(text("Vertical panels").size(12).into());
+            for position in BadgePosition::ALL {
+                let marker = if self.config.badge_position_vertical == position { "●" } else { "○" };
+                widgets.push(
+                    button::text(format!("{} {}", marker, position.name()))
+                        .on_press(Message::SelectVerticalBadgePosition(position))
+                        .width(cosmic::iced::Length::Fill)
+                        .into(),
+                );
+            }
+            widgets.push(text("Reserve width for digits, so the applet keeps its width as the count changes (0 = fit the count)").size(12).into());
+            widgets.push(
+                text_input("0", self.config.reserved_count_digits.to_string())
+                    .on_input(|s| Message::SetReservedCountDigits(s.parse::<u8>().unwrap_or(0).min(5)))
+                    .width(cosmic::iced::Length::Fill)
+                    .into(),
+            );
+        }
+
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
+
+        // Popup tabs
+        widgets.push(text("Default Tab").size(14).into());
+        for tab in PopupTab::ALL {
+            let marker = if self.config.default_tab == tab { "●" } else { "○" };
+            widgets.push(
+                button::text(format!("{} {}", marker, tab.name()))
+                    .on_press(Message::SelectDefaultTab(tab))
+                    .width(cosmic::iced::Length::Fill)
+                    .into(),
+            );
+        }
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text("Reopen on the last used tab"))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(toggler(self.config.remember_last_tab).on_toggle(Message::ToggleRememberLastTab))
+                .into(),
+        );
+
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
+
+        // Popup behavior
+        widgets.push(text("Popup Behavior").size(14).into());
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text("Keep open while an update runs"))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(toggler(self.config.pin_popup_during_update).on_toggle(Message::TogglePinDuringUpdate))
+                .into(),
+        );
+        if !self.config.pin_popup_during_update {
+            widgets.push(
+                row()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(text("Close when an update starts"))
+                    .push(Space::with_width(cosmic::iced::Length::Fill))
+                    .push(toggler(self.config.close_popup_on_update).on_toggle(Message::ToggleCloseOnUpdate))
+                    .into(),
+            );
+        }
+        widgets.push(text("Close after seconds without input (0 = never)").size(12).into());
+        widgets.push(
+            text_input("0", self.config.popup_idle_timeout_secs.to_string())
+                .on_input(|s| Message::SetPopupIdleTimeout(s.parse::<u32>().unwrap_or(0).min(3600)))
+                .width(cosmic::iced::Length::Fill)
+                .into(),
+        );
+
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
 
-    fn get_icon_name(&self) -> &'static str {
-        if self.checking_updates {
-            "view-refresh-symbolic"
-        } else if self.error_message.is_some() {
-            "dialog-error-symbolic"
-        } else if self.update_info.has_updates() {
-            "software-update-available-symbolic"
+        // Terminal setting
+        widgets.push(text("Preferred Terminal").size(14).into());
+        let terminal_value = if self.config.preferred_terminal.is_empty() {
+            "cosmic-term".to_string()
         } else {
-            "package-x-generic-symbolic"
-        }
-    }
-
-    fn view_updates_tab(&self) -> Element<'_, Message> {
-        let mut widgets = vec![];
+            self.config.preferred_terminal.clone()
+        };
+        widgets.push(
+            text_input("cosmic-term", terminal_value)
+                .on_input(Message::SetPreferredTerminal)
+                .width(cosmic::iced::Length::Fill)
+                .into(),
+        );
 
-        // Status text
-        if self.checking_updates {
-            widgets.push(text("Checking for updates...").size(18).into());
-        } else if let Some(error) = &self.error_message {
-            widgets.push(text(format!("Error: {}", error)).size(18).into());
-        } else if self.update_info.has_updates() {
-            widgets.push(text(format!("{} updates available", self.update_info.total_updates)).size(18).into());
+        widgets.push(text("Fallback Terminals (comma-separated, tried in order)").size(12).into());
+        widgets.push(
+            text_input("cosmic-term, alacritty, foot, xterm", &self.terminal_fallbacks_input)
+                .on_input(Message::SetTerminalFallbacks)
+                .width(cosmic::iced::Length::Fill)
+                .into(),
+        );
 
-            // Only show package breakdown if package manager supports AUR
-            if let Some(pm) = self.config.package_manager {
-                if pm.supports_aur() {
-                    widgets.push(text(format!("Official packages: {}", self.update_info.official_updates)).into());
-                    widgets.push(text(format!("AUR packages: {}", self.update_info.aur_updates)).into());
-                }
-            }
-        } else {
-            widgets.push(text("System is up to date").size(18).into());
-        }
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
 
-        // Last check time
-        if let Some(last_check) = self.last_check {
-            let elapsed = last_check.elapsed();
-            let time_text = if elapsed.as_secs() < 60 {
-                "Last checked: just now".to_string()
-            } else if elapsed.as_secs() < 3600 {
-                format!("Last checked: {} minutes ago", elapsed.as_secs() / 60)
-            } else {
-                format!("Last checked: {} hours ago", elapsed.as_secs() / 3600)
-            };
-            widgets.push(text(time_text).size(12).into());
-        }
+        widgets.push(text("Network Proxy").size(14).into());
+        widgets.push(text("Used for webhooks and kernel changelogs; package managers keep their own proxy settings").size(12).into());
+        let proxy_placeholder = match http::environment_proxy() {
+            Some(proxy) => format!("From environment: {}", proxy),
+            None => "http://proxy.example.com:3128 (empty uses the environment)".to_string(),
+        };
+        widgets.push(
+            text_input(proxy_placeholder, &self.config.proxy)
+                .on_input(Message::SetProxy)
+                .width(cosmic::iced::Length::Fill)
+                .into(),
+        );
 
-        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(16.0)).into());
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
 
-        // Check button
+        // Webhook reporting
+        widgets.push(text("Webhook Reporting").size(14).into());
+        widgets.push(text("Report each check to a URL, e.g. a collector or an ntfy topic (empty disables)").size(12).into());
         widgets.push(
-            button::text("Check for Updates")
-                .on_press(Message::CheckForUpdates)
+            text_input("https://ntfy.sh/my-updates", &self.config.webhook_url)
+                .on_input(Message::SetWebhookUrl)
                 .width(cosmic::iced::Length::Fill)
-                .into()
+                .into(),
         );
+        if !self.config.webhook_url.is_empty() {
+            for format in WebhookFormat::ALL {
+                let marker = if self.config.webhook_format == format { "●" } else { "○" };
+                widgets.push(
+                    button::text(format!("{} {}", marker, format.name()))
+                        .on_press(Message::SelectWebhookFormat(format))
+                        .width(cosmic::iced::Length::Fill)
+                        .into(),
+                );
+            }
+        }
 
-        // Update System button right after Check for Updates if updates available
-        if self.update_info.has_updates() {
+        widgets.push(text("MQTT / Home Assistant").size(14).into());
+        widgets.push(text("Publish the update count and security flag with discovery messages (empty broker disables)").size(12).into());
+        widgets.push(
+            text_input("broker.local:1883", &self.config.mqtt_broker)
+                .on_input(Message::SetMqttBroker)
+                .width(cosmic::iced::Length::Fill)
+                .into(),
+        );
+        if !self.config.mqtt_broker.is_empty() {
             widgets.push(
-                button::text("Update System")
-                    .on_press(Message::LaunchTerminalUpdate)
+                text_input("Username (optional)", &self.config.mqtt_username)
+                    .on_input(Message::SetMqttUsername)
                     .width(cosmic::iced::Length::Fill)
-                    .into()
+                    .into(),
+            );
+            widgets.push(
+                text_input("Password (optional)", &self.config.mqtt_password)
+                    .password()
+                    .on_input(Message::SetMqttPassword)
+                    .width(cosmic::iced::Length::Fill)
+                    .into(),
             );
-            widgets.push(text("💡 Tip: Middle-click on the Panel icon").size(10).into());
         }
 
-        if self.update_info.has_updates() {
-            widgets.push(Space::with_height(cosmic::iced::Length::Fixed(16.0)).into());
-
-            // Show package list
-            widgets.push(text("Packages to update:").size(14).into());
-            widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
-
-            // Create scrollable list of packages
-            let mut package_list = column().spacing(4);
-
-            // Group packages by type - only if package manager supports AUR
-            let supports_aur = self.config.package_manager
-                .map(|pm| pm.supports_aur())
-                .unwrap_or(false);
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
 
-            if supports_aur {
-                let official_packages: Vec<_> = self.update_info.packages.iter()
-                    .filter(|p| !p.is_aur)
-                    .collect();
-                let aur_packages: Vec<_> = self.update_info.packages.iter()
-                    .filter(|p| p.is_aur)
-                    .collect();
+        widgets.push(text("AppImages").size(14).into());
+        widgets.push(text("Check the AppImages in this directory with appimageupdatetool after each check (empty disables)").size(12).into());
+        widgets.push(
+            text_input("~/Applications", &self.config.appimage_directory)
+                .on_input(Message::SetAppImageDirectory)
+                .width(cosmic::iced::Length::Fill)
+                .into(),
+        );
+        if !self.config.appimage_directory.is_empty() && !self.appimageupdatetool_available {
+            widgets.push(text("appimageupdatetool is not installed").size(10).into());
+        }
 
-                if !official_packages.is_empty() {
-                    package_list = package_list.push(text("Official:").size(12));
-                    for package in official_packages.iter() {
-                        let package_text = if package.current_version != "unknown" {
-                            format!("  {} {} → {}", package.name, package.current_version, package.new_version)
-                        } else {
-                            format!("  {} → {}", package.name, package.new_version)
-                        };
-                        package_list = package_list.push(text(package_text).size(10));
-                    }
-                }
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
 
-                if !aur_packages.is_empty() {
-                    if !official_packages.is_empty() {
-                        package_list = package_list.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)));
-                    }
-                    package_list = package_list.push(text("AUR:").size(12));
-                    for package in aur_packages.iter() {
-                        let package_text = if package.current_version != "unknown" {
-                            format!("  {} {} → {}", package.name, package.current_version, package.new_version)
-                        } else {
-                            format!("  {} → {}", package.name, package.new_version)
-                        };
-                        package_list = package_list.push(text(package_text).size(10));
-                    }
-                }
-            } else {
-                // No AUR support - show all packages without grouping
-                for package in self.update_info.packages.iter() {
-                    let package_text = if package.current_version != "unknown" {
-                        format!("  {} {} → {}", package.name, package.current_version, package.new_version)
-                    } else {
-                        format!("  {} → {}", package.name, package.new_version)
-                    };
-                    package_list = package_list.push(text(package_text).size(10));
-                }
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text("Check Distrobox and Toolbox containers"))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(toggler(self.config.check_containers).on_toggle(Message::ToggleCheckContainers))
+                .into(),
+        );
+        if self.config.check_containers {
+            if self.container_tools.is_empty() {
+                widgets.push(text("Neither distrobox nor toolbox is installed").size(10).into());
             }
-
-            // Add the package list in a scrollable styled container
             widgets.push(
-                cosmic::widget::container(
-                    scrollable(package_list)
-                        .width(cosmic::iced::Length::Fill)
-                        .height(cosmic::iced::Length::Fixed(100.0)) // Reasonable height with more popup space
-                )
-                .style(|_theme| cosmic::widget::container::Style {
-                    background: Some(cosmic::iced_core::Background::Color([0.1, 0.1, 0.1, 0.1].into())),
-                    border: cosmic::iced::Border {
-                        radius: cosmic::iced::border::Radius::from(8.0),
-                        width: 1.0,
-                        color: [0.3, 0.3, 0.3, 0.5].into(),
-                    },
-                    ..Default::default()
-                })
-                .padding(12)
-                .width(cosmic::iced::Length::Fill)
-                .into()
+                row()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(text("Also check stopped containers (starts them)"))
+                    .push(Space::with_width(cosmic::iced::Length::Fill))
+                    .push(toggler(self.config.start_stopped_containers).on_toggle(Message::ToggleStartStoppedContainers))
+                    .into(),
             );
-
         }
 
-        column()
-            .spacing(8)
-            .extend(widgets)
-            .into()
-    }
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
 
-    fn view_settings_tab(&self) -> Element<'_, Message> {
-        let mut widgets = vec![];
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text("Update with topgrade"))
+                .push(Space::with_width(cosmic::iced::Length::Fill))
+                .push(toggler(self.config.use_topgrade).on_toggle(Message::ToggleUseTopgrade))
+                .into(),
+        );
+        widgets.push(text("Every update runs topgrade; updating selected packages, groups or security updates only is turned off. Its dry run lists the steps after each check, and the panel counts the steps with pending work as found by the checks above").size(10).into());
 
-        widgets.push(text("Package Manager").size(16).into());
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
 
-        if self.available_package_managers.is_empty() {
-            widgets.push(text("No package managers found").size(14).into());
+        widgets.push(text("Remote Hosts").size(14).into());
+        widgets.push(text("Checked over SSH after each check, with key authentication (hosts asking for a password fail); the package manager is detected on the host").size(12).into());
+        for remote in &self.config.remote_hosts {
+            let package_manager = remote.package_manager.map(|pm| format!(" ({})", pm.name())).unwrap_or_default();
             widgets.push(
-                button::text("Discover Package Managers")
-                    .on_press(Message::DiscoverPackageManagers)
+                row()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(text(format!("{}{}", remote.host, package_manager)).size(12))
+                    .push(Space::with_width(cosmic::iced::Length::Fill))
+                    .push(button::text("Remove").on_press(Message::RemoveRemoteHost(remote.host.clone())))
                     .into(),
             );
-        } else {
-            widgets.push(text(format!("Found {} package managers:", self.available_package_managers.len())).size(12).into());
-            for &pm in &self.available_package_managers {
-                let is_selected = self.config.package_manager == Some(pm);
-                let button_text = if is_selected {
-                    format!("● {}", pm.name())
-                } else {
-                    format!("○ {}", pm.name())
-                };
-                widgets.push(
-                    button::text(button_text)
-                        .on_press(Message::SelectPackageManager(pm))
+        }
+        widgets.push(
+            row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(
+                    text_input("user@host", &self.remote_host_draft)
+                        .on_input(Message::RemoteHostDraftChanged)
+                        .on_submit(|_| Message::AddRemoteHost)
                         .width(cosmic::iced::Length::Fill)
-                        .into(),
-                );
+                )
+                .push(button::text("Add host").on_press_maybe((!self.remote_host_draft.trim().is_empty()).then_some(Message::AddRemoteHost)))
+                .into(),
+        );
+
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
+
+        widgets.push(text("Custom Sources").size(14).into());
+        let dirs: Vec<String> = plugin::plugin_dirs().iter().map(|dir| dir.display().to_string()).collect();
+        widgets.push(text(format!("Defined here or under custom_sources in the config file, and plugins in {}", dirs.join(" and "))).size(12).into());
+        for source in &self.custom_sources {
+            let update = if source.update_command(self.config.privilege_escalation).is_some() { "" } else { " (check only)" };
+            let mut entry = row()
+                .spacing(8)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(text(format!("{}{}", source.name(), update)).size(12))
+                .push(Space::with_width(cosmic::iced::Length::Fill));
+            // Plugins are removed from their directory
+            if self.config.custom_sources.iter().any(|configured| configured.name == source.name()) {
+                entry = entry.push(button::text("Remove").on_press(Message::RemoveCustomSource(source.name().to_string())));
+            } else {
+                entry = entry.push(button::text("Disable").on_press(Message::TogglePlugin(source.name().to_string(), false)));
             }
+            widgets.push(entry.into());
         }
+        for name in &self.disabled_plugins {
+            widgets.push(
+                row()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(text(format!("{} (plugin, not enabled)", name)).size(12))
+                    .push(Space::with_width(cosmic::iced::Length::Fill))
+                    .push(button::text("Enable").on_press(Message::TogglePlugin(name.clone(), true)))
+                    .into(),
+            );
+        }
+        for error in &self.custom_source_errors {
+            widgets.push(text(format!("⚠ {}", error)).size(10).into());
+        }
+        widgets.extend(self.custom_source_editor());
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
 
-        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(16.0)).into());
-
-        // Check interval
-        widgets.push(text("Check Interval (minutes)").size(14).into());
-        let interval_value = self.config.check_interval_minutes.to_string();
+        widgets.push(text("Alternate Root").size(14).into());
+        widgets.push(text("Also check the installation under this directory, e.g. a chroot or a mounted system (empty disables)").size(12).into());
         widgets.push(
-            text_input("60", interval_value)
-                .on_input(|s| Message::SetCheckInterval(s.parse::<u32>().unwrap_or(60).max(1).min(1440)))
+            text_input("/mnt/arch", &self.config.alternate_root)
+                .on_input(Message::SetAlternateRoot)
                 .width(cosmic::iced::Length::Fill)
                 .into(),
         );
 
         widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
 
-        // Toggles
+        widgets.push(text("Prometheus Textfile").size(14).into());
+        widgets.push(text("Write metrics for node_exporter's textfile collector after each check (empty disables)").size(12).into());
         widgets.push(
-            row()
-                .spacing(8)
-                .align_y(cosmic::iced::Alignment::Center)
-                .push(text("Auto-check on startup"))
-                .push(Space::with_width(cosmic::iced::Length::Fill))
-                .push(toggler(self.config.auto_check_on_startup).on_toggle(Message::ToggleAutoCheck))
+            text_input("/var/lib/node_exporter/textfile_collector/package_updater.prom", &self.config.metrics_textfile)
+                .on_input(Message::SetMetricsTextfile)
+                .width(cosmic::iced::Length::Fill)
                 .into(),
         );
 
-        // Only show AUR toggle if package manager supports it
-        if let Some(pm) = self.config.package_manager {
-            if pm.supports_aur() {
-                widgets.push(
-                    row()
-                        .spacing(8)
-                        .align_y(cosmic::iced::Alignment::Center)
-                        .push(text("Include AUR updates"))
-                        .push(Space::with_width(cosmic::iced::Length::Fill))
-                        .push(toggler(self.config.include_aur_updates).on_toggle(Message::ToggleIncludeAur))
-                        .into(),
-                );
-            }
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
+
+        // Privilege escalation
+        widgets.push(text("Privilege Escalation").size(14).into());
+        for privilege in PrivilegeEscalation::ALL {
+            let is_selected = self.config.privilege_escalation == privilege;
+            let marker = if is_selected { "●" } else { "○" };
+            let button_text = if self.available_privilege_tools.contains(&privilege) {
+                format!("{} {}", marker, privilege.name())
+            } else {
+                format!("{} {} (not installed)", marker, privilege.name())
+            };
+            widgets.push(
+                button::text(button_text)
+                    .on_press(Message::SelectPrivilegeEscalation(privilege))
+                    .width(cosmic::iced::Length::Fill)
+                    .into(),
+            );
         }
 
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
+
+        widgets.push(text("Diagnostics").size(14).into());
         widgets.push(
             row()
                 .spacing(8)
                 .align_y(cosmic::iced::Alignment::Center)
-                .push(text("Show notifications"))
+                .push(text("Record check statistics"))
                 .push(Space::with_width(cosmic::iced::Length::Fill))
-                .push(toggler(self.config.show_notifications).on_toggle(Message::ToggleShowNotifications))
+                .push(toggler(self.config.diagnostics_enabled).on_toggle(Message::ToggleDiagnostics))
                 .into(),
         );
-
+        widgets.push(text("Kept in the local state file only, to attach to bug reports").size(10).into());
         widgets.push(
             row()
                 .spacing(8)
                 .align_y(cosmic::iced::Alignment::Center)
-                .push(text("Show update count"))
+                .push(text("Strict parsing"))
                 .push(Space::with_width(cosmic::iced::Length::Fill))
-                .push(toggler(self.config.show_update_count).on_toggle(Message::ToggleShowUpdateCount))
+                .push(toggler(self.config.strict_parsing).on_toggle(Message::ToggleStrictParsing))
                 .into(),
         );
+        widgets.push(text("Warn instead of showing a count when check output was not recognized").size(10).into());
+        if self.config.diagnostics_enabled || self.state.diagnostics.checks > 0 {
+            for line in self.state.diagnostics.lines() {
+                widgets.push(text(line).size(10).into());
+            }
+            widgets.push(
+                row()
+                    .spacing(8)
+                    .push(button::text("Save Report").on_press(Message::SaveDiagnosticsReport))
+                    .push(button::text("Reset").on_press(Message::ResetDiagnostics))
+                    .into()
+            );
+        }
 
-        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
-
-        // Terminal setting
-        widgets.push(text("Preferred Terminal").size(14).into());
-        let terminal_value = if self.config.preferred_terminal.is_empty() {
-            "cosmic-term".to_string()
+        let unrecognized = if self.update_info.unrecognized_lines.is_empty() {
+            &self.state.diagnostics.recent_unrecognized
         } else {
-            self.config.preferred_terminal.clone()
+            &self.update_info.unrecognized_lines
         };
-        widgets.push(
-            text_input("cosmic-term", terminal_value)
-                .on_input(Message::SetPreferredTerminal)
-                .width(cosmic::iced::Length::Fill)
+        if !unrecognized.is_empty() {
+            widgets.push(
+                text(format!(
+                    "{} output line(s) not recognized, some updates may be missing:",
+                    unrecognized.len()
+                ))
+                .size(12)
                 .into(),
-        );
+            );
+            for line in unrecognized {
+                widgets.push(text(line.clone()).size(10).into());
+            }
+            widgets.push(button::text("Copy for Bug Report").on_press(Message::CopyDiagnosticsReport).into());
+        }
+
+        widgets.push(Space::with_height(cosmic::iced::Length::Fixed(8.0)).into());
+        widgets.push(button::text(format!("Version {}", env!("CARGO_PKG_VERSION"))).on_press(Message::VersionClicked).into());
+        if let Some(snapshot) = &self.scheduler_view {
+            widgets.push(text("Scheduler").size(14).into());
+            for line in snapshot {
+                widgets.push(text(line.clone()).size(10).into());
+            }
+            widgets.push(text("Recent messages:").size(12).into());
+            for entry in self.message_log.entries() {
+                let time = chrono::DateTime::from_timestamp(entry.last, 0)
+                    .map(|time| time.with_timezone(&chrono::Local).format("%H:%M:%S").to_string())
+                    .unwrap_or_default();
+                let repeats = if entry.count > 1 { format!(" ×{}", entry.count) } else { String::new() };
+                widgets.push(text(format!("{} {}{}", time, entry.name, repeats)).size(10).into());
+            }
+            widgets.push(button::text("Refresh").on_press(Message::RefreshSchedulerView).into());
+        }
 
         column()
             .spacing(8)
             .extend(widgets)
             .into()
     }
-}
\ No newline at end of file
+}
+
+/// The entries that fit in what is left of the package list preview.
+fn take_preview<'a>(mut entries: Vec<&'a PackageUpdate>, remaining: &mut usize) -> Vec<&'a PackageUpdate> {
+    entries.truncate(*remaining);
+    *remaining -= entries.len();
+    entries
+}
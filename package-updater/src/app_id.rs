@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Application ID of the upstream build.
+const UPSTREAM_APP_ID: &str = "com.github.cosmic_ext.PackageUpdater";
+
+/// Application ID the upstream build had before it took the ID of its
+/// settings namespace.
+const LEGACY_APP_ID: &str = "com.cosmic.PackageUpdater";
+
+/// Application ID and cosmic-config namespace. A rebranded build sets the
+/// `APPID` environment variable at compile time, as the justfile does, so
+/// it keeps its own settings next to an upstream build.
+pub const APP_ID: &str = match option_env!("APPID") {
+    Some(id) if !id.is_empty() => id,
+    _ => UPSTREAM_APP_ID,
+};
+
+/// Set for `--demo`, whose files are kept apart from those of the real
+/// instances.
+static DEMO: AtomicBool = AtomicBool::new(false);
+
+pub fn use_demo_files() {
+    DEMO.store(true, Ordering::Relaxed);
+}
+
+/// Copies the settings libcosmic kept under the legacy application ID to
+/// the current one, leaving keys that already exist there alone. Only the
+/// upstream build had the legacy ID.
+pub fn migrate_legacy_config(version: u64) {
+    if APP_ID != UPSTREAM_APP_ID {
+        return;
+    }
+    let config_home = std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string())).join(".config"));
+    let version = format!("v{}", version);
+    let legacy = config_home.join("cosmic").join(LEGACY_APP_ID).join(&version);
+    let Ok(entries) = std::fs::read_dir(&legacy) else {
+        return;
+    };
+    let current = config_home.join("cosmic").join(APP_ID).join(&version);
+    if let Err(e) = std::fs::create_dir_all(&current) {
+        eprintln!("Failed to create {}: {}", current.display(), e);
+        return;
+    }
+    for entry in entries.flatten() {
+        let target = current.join(entry.file_name());
+        if entry.path().is_file() && !target.exists() {
+            if let Err(e) = std::fs::copy(entry.path(), &target) {
+                eprintln!("Failed to migrate {}: {}", entry.path().display(), e);
+            }
+        }
+    }
+}
+
+/// Name of the state directory and prefix of the runtime files, so builds
+/// with different IDs don't take over each other's sync messages, instance
+/// registrations or state. The upstream build keeps its established names.
+pub fn file_prefix() -> String {
+    let prefix = if APP_ID == UPSTREAM_APP_ID { "cosmic-package-updater" } else { APP_ID };
+    if DEMO.load(Ordering::Relaxed) {
+        format!("{}-demo", prefix)
+    } else {
+        prefix.to_string()
+    }
+}
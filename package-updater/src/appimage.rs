@@ -0,0 +1,124 @@
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+use crate::backend;
+use crate::runner::{shell_quote, CommandRunner};
+
+/// `appimageupdatetool --check-for-update` exit code for "update available";
+/// 0 means up to date, anything else that the check failed.
+const UPDATE_AVAILABLE: i32 = 1;
+
+/// An AppImage in the configured directory with a newer release.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppImageUpdate {
+    /// File name without the .AppImage extension
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Whether AppImageUpdate's command line tool is installed.
+pub fn is_available() -> bool {
+    backend::on_path("appimageupdatetool")
+}
+
+/// The configured directory, with a leading "~/" for the home directory.
+pub fn expand_directory(directory: &str) -> PathBuf {
+    match directory.strip_prefix("~/") {
+        Some(rest) => {
+            let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+            PathBuf::from(home).join(rest)
+        }
+        None => PathBuf::from(directory),
+    }
+}
+
+/// AppImages in the directory whose update information points to a newer
+/// release. AppImages without update information fail their check and are
+/// skipped; the check fails only if none of them could be checked.
+pub async fn check(runner: &dyn CommandRunner, directory: &Path) -> Result<Vec<AppImageUpdate>> {
+    let files = appimages_in(directory)?;
+    let mut updates = Vec::new();
+    let mut last_error = None;
+    let mut checked = 0;
+
+    for path in files {
+        let Some(file) = path.to_str() else {
+            continue;
+        };
+        let output = runner.run("appimageupdatetool", &["--check-for-update", file]).await?;
+        match output.code {
+            Some(0) => checked += 1,
+            Some(UPDATE_AVAILABLE) => {
+                checked += 1;
+                updates.push(AppImageUpdate { name: name_of(&path), path });
+            }
+            _ => last_error = Some(format!("{}: {}", name_of(&path), output.stderr.trim())),
+        }
+    }
+
+    match last_error {
+        Some(error) if checked == 0 => Err(anyhow!("Failed to check AppImages for updates: {}", error)),
+        _ => Ok(updates),
+    }
+}
+
+/// Command that updates the AppImages one after another, replacing each
+/// with the new release.
+pub fn update_command(updates: &[AppImageUpdate]) -> String {
+    updates.iter()
+        .map(|update| format!("appimageupdatetool --remove-old {}", shell_quote(&update.path.to_string_lossy())))
+        .collect::<Vec<_>>()
+        .join(" && ")
+}
+
+fn appimages_in(directory: &Path) -> Result<Vec<PathBuf>> {
+    let entries = std::fs::read_dir(directory)
+        .map_err(|e| anyhow!("Failed to read {}: {}", directory.display(), e))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("appimage")))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn name_of(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::{CommandOutput, ScriptedRunner};
+
+    #[tokio::test]
+    async fn checks_appimages_in_directory() {
+        let directory = std::env::temp_dir().join(format!("package-updater-appimages-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).expect("create directory");
+        for file in ["Obsidian-old.AppImage", "Krita.appimage", "broken.AppImage", "notes.txt"] {
+            std::fs::write(directory.join(file), "").expect("write file");
+        }
+
+        let check_for_update = |file: &str| format!("appimageupdatetool --check-for-update {}", directory.join(file).display());
+        let runner = ScriptedRunner::new([
+            (check_for_update("Obsidian-old.AppImage"), CommandOutput::new(1, "", "")),
+            (check_for_update("Krita.appimage"), CommandOutput::new(0, "", "")),
+            (check_for_update("broken.AppImage"), CommandOutput::new(2, "", "Could not find update information in the AppImage\n")),
+        ]);
+
+        let updates = check(&runner, &directory).await.expect("check");
+        assert_eq!(updates, [AppImageUpdate { name: "Obsidian-old".to_string(), path: directory.join("Obsidian-old.AppImage") }]);
+
+        std::fs::remove_file(directory.join("Obsidian-old.AppImage")).expect("remove file");
+        std::fs::remove_file(directory.join("Krita.appimage")).expect("remove file");
+        assert!(check(&runner, &directory).await.is_err(), "no AppImage could be checked");
+        std::fs::remove_dir_all(&directory).expect("remove directory");
+
+        let update = AppImageUpdate { name: "it's".to_string(), path: PathBuf::from("/home/me/Apps/it's.AppImage") };
+        assert_eq!(update_command(&[update]), r"appimageupdatetool --remove-old '/home/me/Apps/it'\''s.AppImage'");
+    }
+}
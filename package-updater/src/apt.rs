@@ -0,0 +1,170 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+
+use crate::backend::{self, Backend, Capabilities, CheckContext};
+use crate::held_back::{self, HeldBack};
+use crate::package_manager::{CheckMode, PackageUpdate};
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::shell_quote;
+
+/// Debian, Ubuntu and their derivatives.
+pub struct Apt;
+
+impl Backend for Apt {
+    fn name(&self) -> &str {
+        "apt"
+    }
+
+    /// apt-get is the stable interface and present even without the apt CLI.
+    fn detect(&self) -> bool {
+        backend::on_path("apt-get")
+    }
+
+    /// apt keeps its indexes in root-owned caches.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { selective_update: true, needs_privileges: true, ..Capabilities::default() }
+    }
+
+    /// `apt` warns that its output is not stable, simulating with apt-get
+    /// is. In the C locale, the sections of kept back and phased updates
+    /// are read too.
+    fn check_command(&self, _mode: CheckMode) -> Option<(&str, Vec<&str>)> {
+        Some(("env", vec!["LC_ALL=C", "apt-get", "-s", "dist-upgrade"]))
+    }
+
+    /// "Inst package [old-version] (new-version suite, suite [arch])". The
+    /// "[old-version]" part is missing for newly installed packages (e.g. a
+    /// new kernel ABI); these lines are never translated.
+    fn parse_line(&self, line: &str) -> Option<PackageUpdate> {
+        let (name, rest) = line.strip_prefix("Inst ")?.split_once(' ')?;
+        let rest = rest.trim_start();
+
+        let (current_version, rest) = match rest.strip_prefix('[') {
+            Some(bracketed) => {
+                let (current, rest) = bracketed.split_once(']')?;
+                (current, rest.trim_start())
+            }
+            None => ("unknown", rest),
+        };
+
+        let details = rest.strip_prefix('(')?;
+        let new_version = details.split_whitespace().next()?;
+        // "Origin:Version/suite", the first of the ones offering it
+        let repository = details.split_whitespace().nth(1)
+            .map(|origin| origin.trim_end_matches(',').to_string())
+            .filter(|origin| origin.contains('/'));
+
+        Some(PackageUpdate {
+            is_security: details.contains("-security"),
+            repository,
+            ..PackageUpdate::new(name, current_version, new_version)
+        })
+    }
+
+    /// Only "Inst name [old] (new ...)" is an upgrade; the rest of the
+    /// simulation is translated text, new installs, removals and configure
+    /// steps.
+    fn is_noise(&self, line: &str) -> bool {
+        !(line.starts_with("Inst ") && line.contains('['))
+    }
+
+    /// "Remv name [version]"
+    fn parse_removal(&self, line: &str) -> Option<String> {
+        line.strip_prefix("Remv ")?.split_whitespace().next().map(str::to_string)
+    }
+
+    fn parse_held_back(&self, output: &str) -> Vec<HeldBack> {
+        let (kept_back, phased) = held_back::parse_apt_kept_back(output);
+        phased.iter().map(|name| HeldBack::new(name, held_back::PHASED_REASON))
+            .chain(kept_back.iter().map(|name| HeldBack::new(name, held_back::RESOLVER_REASON)))
+            .collect()
+    }
+
+    fn root_check_command(&self, root: &str) -> Result<(&str, Vec<String>)> {
+        Ok(("apt-get", vec!["-o".to_string(), format!("Dir={}", root), "-s".to_string(), "dist-upgrade".to_string()]))
+    }
+
+    /// The updates the check's simulation kept back or deferred, with
+    /// `apt-mark hold` told apart from the resolver. Why the resolver keeps
+    /// them back takes a simulation per package, see
+    /// [`Backend::explain_kept_back`].
+    fn explain_held_back<'a>(&'a self, context: &'a CheckContext<'a>, _packages: &'a [PackageUpdate]) -> BoxFuture<'a, Vec<HeldBack>> {
+        Box::pin(async move {
+            let found = context.notes.held_back.lock().map(|mut found| std::mem::take(&mut *found)).unwrap_or_default();
+            if !found.iter().any(|held| held.reason == held_back::RESOLVER_REASON) {
+                return found;
+            }
+
+            let holds = match context.runner.run("apt-mark", &["showhold"]).await {
+                Ok(output) if output.success() => output.stdout,
+                _ => String::new(),
+            };
+            let mut found: Vec<HeldBack> = found.into_iter()
+                .map(|held| match holds.split_whitespace().any(|hold| hold == held.name) {
+                    true => HeldBack::new(&held.name, held_back::HOLD_REASON),
+                    false => held,
+                })
+                .collect();
+            // Deferred first, then holds, then the resolver's
+            found.sort_by_key(|held| match held.reason.as_str() {
+                held_back::PHASED_REASON => 0,
+                held_back::HOLD_REASON => 1,
+                _ => 2,
+            });
+            found
+        })
+    }
+
+    /// Their unmet dependencies, or what installing them would remove.
+    fn explain_kept_back<'a>(&'a self, context: &'a CheckContext<'a>, packages: &'a [String]) -> BoxFuture<'a, Vec<HeldBack>> {
+        Box::pin(async move {
+            if packages.is_empty() {
+                return Vec::new();
+            }
+            let mut args = vec!["LC_ALL=C", "apt-get", "-s", "install"];
+            args.extend(packages.iter().map(String::as_str));
+            match context.runner.run("env", &args).await {
+                Ok(output) => held_back::parse_apt_install(&output.stdout, packages),
+                Err(e) => {
+                    eprintln!("Failed to explain held back packages: {}", e);
+                    Vec::new()
+                }
+            }
+        })
+    }
+
+    /// Packages pinned below the candidate version.
+    fn excluded<'a>(&'a self, context: &'a CheckContext<'a>, packages: &'a [PackageUpdate]) -> BoxFuture<'a, Vec<String>> {
+        Box::pin(async move {
+            let mut args = vec!["LC_ALL=C", "apt-cache", "policy"];
+            args.extend(packages.iter().map(|p| p.name.as_str()));
+            match context.runner.run("env", &args).await {
+                Ok(output) if output.success() => held_back::parse_apt_pinned(&output.stdout),
+                _ => Vec::new(),
+            }
+        })
+    }
+
+    fn update_command(&self, privilege: PrivilegeEscalation) -> Option<String> {
+        Some(format!("{} && {}", privilege.wrap("apt-get update"), privilege.wrap("apt-get dist-upgrade")))
+    }
+
+    fn packages_update_command(&self, privilege: PrivilegeEscalation, packages: &[String]) -> Option<String> {
+        if packages.is_empty() {
+            return None;
+        }
+        Some(privilege.wrap(&format!("apt-get install --only-upgrade {}", packages.join(" "))))
+    }
+
+    /// apt hands dpkg the archives by their paths under the root, so dpkg
+    /// is given the root rather than chrooted into it, and runs the root's
+    /// own configuration and install scripts.
+    fn root_update_command(&self, privilege: PrivilegeEscalation, root: &str) -> Option<String> {
+        let apt = format!("apt-get -o Dir={0} -o DPkg::Options::=--root={0}", shell_quote(root));
+        Some(format!("{} && {}", privilege.wrap(&format!("{} update", apt)), privilege.wrap(&format!("{} dist-upgrade", apt))))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Kept-back packages need new dependencies; 'sudo apt --fix-broken install' repairs an interrupted run."
+    }
+}
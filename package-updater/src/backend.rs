@@ -0,0 +1,351 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use std::collections::BTreeSet;
+use std::process::Command;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Instant;
+
+use crate::custom_source::{self, CustomSource};
+use crate::health::{self, SourceCheck};
+use crate::held_back::HeldBack;
+use crate::package_manager::{CheckMode, PackageUpdate, UpdateInfo};
+use crate::plugin;
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::{CommandOutput, CommandRunner};
+use crate::sanitize::{strip_ansi, strip_ansi_lines};
+use crate::{apk, apt, brew, cargo, dnf, emerge, eopkg, firmware, flatpak, nixos, npm, packagekit, pacman, pipx, rpm_ostree, slackpkg, swupd, sysupdate, xbps, zypper};
+
+/// The built-in backends, one per package manager, in the order of
+/// [`PackageManager::PREFERENCE`](crate::package_manager::PackageManager::PREFERENCE).
+pub static BUILTIN: LazyLock<Vec<Box<dyn Backend>>> = LazyLock::new(|| {
+    vec![
+        Box::new(pacman::Pacman { helper: Some("paru") }),
+        Box::new(pacman::Pacman { helper: Some("yay") }),
+        Box::new(pacman::Pacman { helper: None }),
+        Box::new(apt::Apt),
+        Box::new(rpm_ostree::RpmOstree),
+        Box::new(dnf::Dnf),
+        Box::new(zypper::Zypper),
+        Box::new(apk::Apk),
+        Box::new(xbps::Xbps),
+        Box::new(emerge::Emerge),
+        Box::new(eopkg::Eopkg),
+        Box::new(slackpkg::Slackpkg),
+        Box::new(swupd::Swupd),
+        Box::new(nixos::Nixos),
+        Box::new(sysupdate::Sysupdate),
+        Box::new(packagekit::PackageKit),
+        Box::new(flatpak::Flatpak),
+        Box::new(brew::Brew),
+        Box::new(cargo::Cargo),
+        Box::new(pipx::Pipx),
+        Box::new(npm::Npm),
+        Box::new(firmware::Fwupd),
+    ]
+});
+
+/// The built-in backend of that name.
+pub fn builtin(name: &str) -> Option<&'static dyn Backend> {
+    BUILTIN.iter().find(|backend| backend.name() == name).map(Box::as_ref)
+}
+
+/// What a backend can do beyond checking and updating everything. The
+/// sources the user added have none of these.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Checks and installs AUR updates too
+    pub aur: bool,
+    /// The pending upgrade can be dry-run before launching it
+    pub preflight: bool,
+    /// Individual packages can be updated by the names the check reports
+    pub selective_update: bool,
+    /// A check can refresh the repository metadata without root
+    pub metadata_refresh: bool,
+    /// Updates are installed into a new deployment that only takes effect
+    /// after a reboot
+    pub applies_on_reboot: bool,
+    /// Installing updates needs administrator rights
+    pub needs_privileges: bool,
+}
+
+/// A source of updates: a package manager, or a custom source or plugin
+/// the user added. How to find it, check it and install what it found.
+pub trait Backend: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Whether the backend can be used on this system. The sources the
+    /// user added always can.
+    fn detect(&self) -> bool {
+        true
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    /// Program and arguments that list the updates, for backends checked
+    /// with a single listing.
+    fn check_command(&self, _mode: CheckMode) -> Option<(&str, Vec<&str>)> {
+        None
+    }
+
+    /// Reads the updates from the output of the check command, line by
+    /// line with [`Self::parse_line`].
+    fn parse(&self, output: &CommandOutput) -> Result<Vec<PackageUpdate>> {
+        Ok(output.stdout.lines().filter_map(|line| read_line(self, line)).collect())
+    }
+
+    /// The update a line of the check output describes, if any. Escape
+    /// sequences and blank lines are already removed.
+    fn parse_line(&self, _line: &str) -> Option<PackageUpdate> {
+        None
+    }
+
+    /// Whether a line that is not an update is expected in the check
+    /// output. Other lines are kept as unrecognized.
+    fn is_noise(&self, _line: &str) -> bool {
+        false
+    }
+
+    /// Name of a package a line of the check output says the upgrade removes.
+    fn parse_removal(&self, _line: &str) -> Option<String> {
+        None
+    }
+
+    /// Updates the check output says won't be installed.
+    fn parse_held_back(&self, _output: &str) -> Vec<HeldBack> {
+        Vec::new()
+    }
+
+    /// Exit code of `program` for "no updates available".
+    fn no_updates_code(&self, _program: &str) -> Option<i32> {
+        None
+    }
+
+    /// Runs the check command and reads its output with
+    /// [`CheckContext::listing`]. The sources the user added and the
+    /// backends that aren't a single listing override this.
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(async move {
+            let (program, args) = self.check_command(context.mode)
+                .ok_or_else(|| anyhow!("{} has no check command", self.name()))?;
+            context.listing(self, program, &args).await
+        })
+    }
+
+    /// Program and arguments that list the AUR updates.
+    fn aur_check_command(&self) -> Option<(&str, Vec<&str>)> {
+        None
+    }
+
+    /// Program and arguments that list the updates of the installation
+    /// under `root`, without writing to it.
+    fn root_check_command(&self, _root: &str) -> Result<(&str, Vec<String>)> {
+        Err(anyhow!("{} can't check an alternate root", self.name()))
+    }
+
+    /// Available updates that won't be installed, and why, after a check
+    /// found `packages`.
+    fn explain_held_back<'a>(&'a self, _context: &'a CheckContext<'a>, _packages: &'a [PackageUpdate]) -> BoxFuture<'a, Vec<HeldBack>> {
+        Box::pin(async { Vec::new() })
+    }
+
+    /// Why the resolver keeps the `packages` back, when the user asks.
+    fn explain_kept_back<'a>(&'a self, _context: &'a CheckContext<'a>, _packages: &'a [String]) -> BoxFuture<'a, Vec<HeldBack>> {
+        Box::pin(async { Vec::new() })
+    }
+
+    /// Names of the `packages` the configuration excludes from upgrades,
+    /// for listings that don't apply it themselves.
+    fn excluded<'a>(&'a self, _context: &'a CheckContext<'a>, _packages: &'a [PackageUpdate]) -> BoxFuture<'a, Vec<String>> {
+        Box::pin(async { Vec::new() })
+    }
+
+    /// Adds what the listing leaves out, e.g. repositories and groups.
+    fn annotate<'a>(&'a self, _context: &'a CheckContext<'a>, _packages: &'a mut [PackageUpdate]) -> BoxFuture<'a, ()> {
+        Box::pin(async {})
+    }
+
+    /// Command that installs all updates, None for sources that are only checked.
+    fn update_command(&self, privilege: PrivilegeEscalation) -> Option<String>;
+
+    /// Command that upgrades only the given packages.
+    fn packages_update_command(&self, _privilege: PrivilegeEscalation, _packages: &[String]) -> Option<String> {
+        None
+    }
+
+    /// Command that installs only the security updates: the backend's own
+    /// security filter where it has one, or else the packages flagged as
+    /// security fixes, by name.
+    fn security_update_command(&self, privilege: PrivilegeEscalation, security_packages: &[String]) -> Option<String> {
+        self.packages_update_command(privilege, security_packages)
+    }
+
+    /// Command that upgrades the installation under `root` from the running system.
+    fn root_update_command(&self, _privilege: PrivilegeEscalation, _root: &str) -> Option<String> {
+        None
+    }
+
+    /// What to try when packages are still pending after an update run.
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error."
+    }
+}
+
+/// Found in the output of a check besides the updates, kept across retries.
+#[derive(Default)]
+pub struct CheckNotes {
+    pub unrecognized_lines: Mutex<Vec<String>>,
+    pub failing_repositories: Mutex<Vec<SourceCheck>>,
+    pub held_back: Mutex<Vec<HeldBack>>,
+    pub removals: Mutex<Vec<String>>,
+}
+
+/// What a backend checks with.
+pub struct CheckContext<'a> {
+    pub runner: &'a dyn CommandRunner,
+    pub mode: CheckMode,
+    /// Proxy for requests to package indexes, empty for curl's default
+    pub proxy: &'a str,
+    pub notes: &'a CheckNotes,
+}
+
+impl CheckContext<'_> {
+    /// Runs a listing of `backend`'s updates and reads it line by line,
+    /// keeping the failing repositories, held back updates, removals and
+    /// unrecognized lines it shows along the way.
+    pub async fn listing<B: Backend + ?Sized>(&self, backend: &B, program: &str, args: &[&str]) -> Result<Vec<PackageUpdate>> {
+        let output = self.runner.run(program, args).await?;
+        self.note_failing_repositories(&output.stderr);
+
+        if !output.success() {
+            let exit_code = output.code.unwrap_or(-1);
+            if backend.no_updates_code(program) == Some(exit_code) {
+                return Ok(Vec::new());
+            }
+            // Other exit codes may still come with a listing to read, e.g.
+            // dnf's 100 for "updates available"
+            if output.stdout.trim().is_empty() {
+                eprintln!("Update check failed with exit code {}: {}", exit_code, output.stderr);
+                return Err(anyhow!("Failed to check for updates (exit {}): {}", exit_code, output.stderr));
+            }
+        }
+
+        let stdout = &output.stdout;
+        if let Ok(mut found) = self.notes.held_back.lock() {
+            found.extend(backend.parse_held_back(stdout));
+        }
+        if let Ok(mut found) = self.notes.removals.lock() {
+            found.extend(stdout.lines().filter_map(|line| backend.parse_removal(line)));
+        }
+
+        let mut packages = Vec::new();
+        for line in stdout.lines() {
+            match read_line(backend, line) {
+                Some(package) => packages.push(package),
+                None => self.note_unrecognized(backend, line),
+            }
+        }
+        Ok(packages)
+    }
+
+    /// Keeps a line that was not read as an update, unless it is known
+    /// not to describe one. Such lines point at an output format change
+    /// that would otherwise silently undercount updates.
+    fn note_unrecognized<B: Backend + ?Sized>(&self, backend: &B, line: &str) {
+        let line = strip_ansi(line);
+        if is_header_line(&line) || backend.is_noise(&line) {
+            return;
+        }
+        if let Ok(mut lines) = self.notes.unrecognized_lines.lock() {
+            lines.push(format!("{}: {}", backend.name(), line.trim()));
+        }
+    }
+
+    /// Keeps the repositories a command's error output names as failing,
+    /// once each across retries.
+    pub fn note_failing_repositories(&self, stderr: &str) {
+        if let Ok(mut failing) = self.notes.failing_repositories.lock() {
+            for check in health::failing_repositories(&strip_ansi_lines(stderr)) {
+                if !failing.iter().any(|known| known.source == check.source) {
+                    failing.push(check);
+                }
+            }
+        }
+    }
+}
+
+/// A line of a check output read with `backend`'s
+/// [`Backend::parse_line`]. Color codes and progress bar redraws would
+/// end up in names and versions.
+pub fn read_line<B: Backend + ?Sized>(backend: &B, line: &str) -> Option<PackageUpdate> {
+    let line = strip_ansi(line);
+    if is_header_line(&line) {
+        return None;
+    }
+    backend.parse_line(&line)
+}
+
+fn is_header_line(line: &str) -> bool {
+    line.starts_with("Listing...") || line.starts_with("Done") ||
+    line.starts_with("WARNING:") || line.starts_with("S |") ||
+    line.starts_with("--+") || line.trim().is_empty()
+}
+
+/// The backends the user added: the custom sources of the config, then
+/// the plugins in `enabled_plugins`. Invalid custom sources are left out,
+/// with the reason for each, and the names of the plugins that aren't
+/// enabled are returned last.
+pub fn user_backends(custom_sources: &[CustomSource], enabled_plugins: &BTreeSet<String>) -> (Vec<Arc<dyn Backend>>, Vec<String>, Vec<String>) {
+    let (parsers, errors) = custom_source::register(custom_sources);
+    let (plugins, disabled): (Vec<_>, Vec<_>) = plugin::plugin_dirs().iter()
+        .flat_map(|dir| plugin::discover(dir))
+        .partition(|plugin| enabled_plugins.contains(plugin.name()));
+    let backends = parsers.into_iter()
+        .map(|parser| Arc::new(parser) as Arc<dyn Backend>)
+        .chain(plugins.into_iter().map(|plugin| Arc::new(plugin) as Arc<dyn Backend>))
+        .fold(Vec::new(), |mut backends: Vec<Arc<dyn Backend>>, backend| {
+            // The first source of a name wins, e.g. over a plugin of the same name
+            if backends.iter().any(|known| known.name() == backend.name()) {
+                eprintln!("Skipping {}: a source of that name is already registered", backend.name());
+            } else {
+                backends.push(backend);
+            }
+            backends
+        });
+    let disabled = disabled.iter().map(|plugin| plugin.name().to_string()).collect();
+    (backends, errors, disabled)
+}
+
+/// Checks the backends the user added one after another and adds their
+/// updates to `update_info`. A failing one is recorded like a failing
+/// package manager and doesn't fail the check.
+pub async fn check_all(backends: &[Arc<dyn Backend>], runner: &dyn CommandRunner, update_info: &mut UpdateInfo) {
+    let notes = CheckNotes::default();
+    let context = CheckContext { runner, mode: CheckMode::default(), proxy: "", notes: &notes };
+    for backend in backends {
+        let started = Instant::now();
+        let result = match backend.check(&context).await {
+            Ok(packages) => {
+                update_info.packages.extend(packages);
+                update_info.count();
+                Ok(())
+            }
+            Err(e) => {
+                eprintln!("Failed to check {} updates: {}", backend.name(), e);
+                Err(e.to_string())
+            }
+        };
+        update_info.sources.push(SourceCheck::new(backend.name(), result).took(started.elapsed()));
+    }
+}
+
+/// Whether `binary` is on PATH.
+pub fn on_path(binary: &str) -> bool {
+    Command::new("which")
+        .arg(binary)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
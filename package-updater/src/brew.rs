@@ -0,0 +1,64 @@
+use crate::backend::{self, Backend, Capabilities};
+use crate::merge::Source;
+use crate::package_manager::{CheckMode, PackageUpdate};
+use crate::privilege::PrivilegeEscalation;
+
+/// Homebrew on Linux (Linuxbrew).
+pub struct Brew;
+
+impl Backend for Brew {
+    fn name(&self) -> &str {
+        "brew"
+    }
+
+    fn detect(&self) -> bool {
+        backend::on_path("brew")
+    }
+
+    /// Homebrew refuses to run as root.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { selective_update: true, ..Capabilities::default() }
+    }
+
+    /// Against the taps as last updated, `brew upgrade` updates them first.
+    fn check_command(&self, _mode: CheckMode) -> Option<(&str, Vec<&str>)> {
+        Some(("brew", vec!["outdated", "--verbose"]))
+    }
+
+    /// "name (installed[, installed...]) < latest", "!=" instead of "<" for
+    /// casks, " [pinned at version]" after pinned formulae, which
+    /// `brew upgrade` skips.
+    fn parse_line(&self, line: &str) -> Option<PackageUpdate> {
+        let (name, rest) = line.trim().split_once(" (")?;
+        let (installed, rest) = rest.split_once(") ")?;
+        let rest = rest.strip_prefix("< ").or_else(|| rest.strip_prefix("!= "))?;
+        let new_version = rest.split_whitespace().next()?;
+        // Older versions still installed come first
+        let current_version = installed.rsplit(", ").next()?;
+        Some(PackageUpdate {
+            is_ignored: rest.contains("[pinned at "),
+            source: Source::Homebrew,
+            ..PackageUpdate::new(name, current_version, new_version)
+        })
+    }
+
+    /// Auto-update progress.
+    fn is_noise(&self, line: &str) -> bool {
+        line.starts_with("==> ")
+    }
+
+    fn update_command(&self, _privilege: PrivilegeEscalation) -> Option<String> {
+        Some("brew upgrade".to_string())
+    }
+
+    fn packages_update_command(&self, _privilege: PrivilegeEscalation, packages: &[String]) -> Option<String> {
+        if packages.is_empty() {
+            return None;
+        }
+        Some(format!("brew upgrade {}", packages.join(" ")))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; 'brew doctor' lists problems with the installation."
+    }
+}
@@ -0,0 +1,71 @@
+use cosmic_config::{Config, ConfigGet};
+
+const GAMEMODE_SERVICE: &str = "com.feralinteractive.GameMode";
+
+#[zbus::proxy(
+    interface = "com.feralinteractive.GameMode",
+    default_service = "com.feralinteractive.GameMode",
+    default_path = "/com/feralinteractive/GameMode"
+)]
+trait GameMode {
+    #[zbus(property)]
+    fn client_count(&self) -> zbus::Result<i32>;
+}
+
+/// Settings of the COSMIC notification daemon, which holds the
+/// do-not-disturb switch.
+const NOTIFICATIONS_CONFIG_ID: &str = "com.system76.CosmicNotifications";
+const NOTIFICATIONS_CONFIG_VERSION: u64 = 1;
+
+/// Why background work is held back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusyReason {
+    /// A game asked GameMode for performance settings
+    Game,
+    /// Do not disturb is on, which presenters and players turn on for
+    /// fullscreen sessions
+    DoNotDisturb,
+}
+
+impl BusyReason {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            BusyReason::Game => "a game is running",
+            BusyReason::DoNotDisturb => "do not disturb is on",
+        }
+    }
+}
+
+/// Whether the session is in a game or asked not to be disturbed. Either
+/// hint missing (no GameMode, another desktop) counts as not busy.
+pub async fn session_busy() -> Option<BusyReason> {
+    if game_running().await {
+        Some(BusyReason::Game)
+    } else if do_not_disturb() {
+        Some(BusyReason::DoNotDisturb)
+    } else {
+        None
+    }
+}
+
+async fn game_running() -> bool {
+    let clients = async {
+        let connection = zbus::Connection::session().await?;
+        // Asking the service would start it; games that use it do so
+        let running = zbus::fdo::DBusProxy::new(&connection).await?
+            .name_has_owner(GAMEMODE_SERVICE.try_into()?)
+            .await?;
+        if !running {
+            return Ok(0);
+        }
+        GameModeProxy::new(&connection).await?.client_count().await
+    };
+    clients.await.is_ok_and(|count: i32| count > 0)
+}
+
+fn do_not_disturb() -> bool {
+    Config::new(NOTIFICATIONS_CONFIG_ID, NOTIFICATIONS_CONFIG_VERSION)
+        .ok()
+        .and_then(|config| config.get::<bool>("do_not_disturb").ok())
+        .unwrap_or(false)
+}
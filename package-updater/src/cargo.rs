@@ -0,0 +1,69 @@
+use crate::backend::{self, Backend, Capabilities};
+use crate::merge::Source;
+use crate::package_manager::{CheckMode, PackageUpdate};
+use crate::privilege::PrivilegeEscalation;
+
+/// Binaries installed with `cargo install`, through cargo-update.
+pub struct Cargo;
+
+impl Backend for Cargo {
+    fn name(&self) -> &str {
+        "cargo"
+    }
+
+    /// The cargo-update subcommand, cargo alone can't tell what is outdated.
+    fn detect(&self) -> bool {
+        backend::on_path("cargo-install-update")
+    }
+
+    /// Installs into the user's home.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { selective_update: true, ..Capabilities::default() }
+    }
+
+    /// Every installed crate against crates.io (or its git repository),
+    /// with a "Needs update" column.
+    fn check_command(&self, _mode: CheckMode) -> Option<(&str, Vec<&str>)> {
+        Some(("cargo", vec!["install-update", "--list"]))
+    }
+
+    /// "name  v1.0.0  v1.1.0  Yes"; crates installed from git show commit
+    /// hashes instead of versions.
+    fn parse_line(&self, line: &str) -> Option<PackageUpdate> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() != 4 || parts[3] != "Yes" {
+            return None;
+        }
+        let version = |v: &str| v.strip_prefix('v').unwrap_or(v).to_string();
+        Some(PackageUpdate {
+            name: parts[0].to_string(),
+            current_version: version(parts[1]),
+            new_version: version(parts[2]),
+            source: Source::Cargo,
+            ..Default::default()
+        })
+    }
+
+    /// Registry polling, table headers and crates that are up to date.
+    fn is_noise(&self, line: &str) -> bool {
+        line.starts_with(char::is_whitespace)
+            || line.starts_with("Package ")
+            || line.trim_end().ends_with(" No")
+            || line.starts_with("No packages need updating")
+    }
+
+    fn update_command(&self, _privilege: PrivilegeEscalation) -> Option<String> {
+        Some("cargo install-update -a".to_string())
+    }
+
+    fn packages_update_command(&self, _privilege: PrivilegeEscalation, packages: &[String]) -> Option<String> {
+        if packages.is_empty() {
+            return None;
+        }
+        Some(format!("cargo install-update {}", packages.join(" ")))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; a crate may need a newer Rust toolchain ('rustup update')."
+    }
+}
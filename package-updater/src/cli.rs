@@ -0,0 +1,169 @@
+use crate::config::PackageUpdaterConfig;
+use crate::backend;
+use crate::export::{self, Severity, StatusSnapshot};
+use crate::merge;
+use crate::package_manager::{CheckMode, UpdateChecker};
+
+pub const EXIT_UP_TO_DATE: i32 = 0;
+pub const EXIT_ERROR: i32 = 1;
+pub const EXIT_UPDATES: i32 = 10;
+pub const EXIT_SECURITY_UPDATES: i32 = 11;
+/// Strict parsing found unrecognized check output
+pub const EXIT_INCOMPLETE: i32 = 12;
+
+/// Options of the headless `--check-only` invocation.
+pub struct CheckOnly {
+    /// Encode the result in the exit code instead of always exiting with 0
+    pub exit_code: bool,
+    /// Check even if a running applet published a recent status
+    pub no_cache: bool,
+}
+
+impl CheckOnly {
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        if !args.iter().any(|arg| arg == "--check-only") {
+            return None;
+        }
+        Some(Self {
+            exit_code: args.iter().any(|arg| arg == "--exit-code"),
+            no_cache: args.iter().any(|arg| arg == "--no-cache"),
+        })
+    }
+
+    /// Prints a one-line summary and returns the process exit code.
+    pub fn run(&self) -> i32 {
+        let (_, config) = PackageUpdaterConfig::load();
+        let now = chrono::Local::now().timestamp();
+
+        let cached = export::read().filter(|snapshot| !self.no_cache && is_fresh(snapshot, &config, now));
+        let snapshot = match cached {
+            Some(snapshot) => snapshot,
+            None => match check(&config) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    eprintln!("Failed to check for updates: {}", e);
+                    return EXIT_ERROR;
+                }
+            },
+        };
+
+        println!("{}", summary(&snapshot));
+        if self.exit_code {
+            exit_code(&snapshot)
+        } else {
+            EXIT_UP_TO_DATE
+        }
+    }
+}
+
+/// A snapshot is reused while it is younger than the check interval and
+/// was taken with the configured package manager.
+fn is_fresh(snapshot: &StatusSnapshot, config: &PackageUpdaterConfig, now: i64) -> bool {
+    let max_age = i64::from(config.check_interval_minutes) * 60;
+    snapshot.severity != Severity::Error
+        && snapshot.package_manager.as_deref() == config.package_manager().map(|pm| pm.name())
+        && snapshot.last_check.is_some_and(|last_check| now - last_check < max_age)
+}
+
+/// Runs a quick check of all configured package managers and publishes the
+/// result, so the next invocation can reuse it.
+fn check(config: &PackageUpdaterConfig) -> anyhow::Result<StatusSnapshot> {
+    let package_managers = config.package_managers();
+    let Some(primary) = package_managers.first().copied() else {
+        anyhow::bail!("No package manager configured");
+    };
+
+    // Applets don't need to follow a check nobody sees
+    let checkers = package_managers.into_iter()
+        .map(|pm| {
+            let checker = UpdateChecker::new(pm)
+                .mode(CheckMode::Quick)
+                .excluded_repositories(&config.excluded_repositories)
+                .proxy(&config.proxy);
+            (pm, checker)
+        })
+        .collect();
+    let runtime = tokio::runtime::Runtime::new()?;
+    let (user_backends, _, _) = backend::user_backends(&config.custom_sources, &config.enabled_plugins);
+    let update_info = runtime.block_on(merge::check_all(checkers, &user_backends, config.include_aur_updates, false))?;
+
+    let severity = if config.strict_parsing && !update_info.unrecognized_lines.is_empty() {
+        Severity::Warning
+    } else if update_info.total_updates > 0 {
+        Severity::UpdatesAvailable
+    } else {
+        Severity::UpToDate
+    };
+    let snapshot = StatusSnapshot::new(&update_info, severity)
+        .last_check(Some(chrono::Local::now().timestamp()))
+        .package_manager(Some(primary.name()));
+    export::publish(&snapshot);
+    Ok(snapshot)
+}
+
+fn summary(snapshot: &StatusSnapshot) -> String {
+    let summary = match (snapshot.update_count, snapshot.security_updates) {
+        (0, _) => "System is up to date".to_string(),
+        (1, 0) => "1 update available".to_string(),
+        (count, 0) => format!("{} updates available", count),
+        (count, security) => format!("{} updates available ({} security)", count, security),
+    };
+    if snapshot.severity == Severity::Warning {
+        format!("{}, but some check output was not recognized", summary)
+    } else {
+        summary
+    }
+}
+
+/// An incomplete count wins, a compliance check can't rely on it.
+fn exit_code(snapshot: &StatusSnapshot) -> i32 {
+    if snapshot.severity == Severity::Warning {
+        EXIT_INCOMPLETE
+    } else if snapshot.security_updates > 0 {
+        EXIT_SECURITY_UPDATES
+    } else if snapshot.update_count > 0 {
+        EXIT_UPDATES
+    } else {
+        EXIT_UP_TO_DATE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_manager::{PackageManager, PackageUpdate, UpdateInfo};
+
+    fn snapshot(packages: &[(&str, bool)]) -> StatusSnapshot {
+        let mut update_info = UpdateInfo::new();
+        update_info.packages = packages.iter()
+            .map(|&(name, is_security)| PackageUpdate { is_security, ..PackageUpdate::new(name, "1.0", "1.1") })
+            .collect();
+        update_info.total_updates = update_info.packages.len();
+        StatusSnapshot::new(&update_info, Severity::UpdatesAvailable)
+    }
+
+    #[test]
+    fn exit_code_reflects_pending_updates() {
+        assert_eq!(exit_code(&snapshot(&[])), EXIT_UP_TO_DATE);
+        assert_eq!(exit_code(&snapshot(&[("firefox", false)])), EXIT_UPDATES);
+        assert_eq!(exit_code(&snapshot(&[("firefox", false), ("openssl", true)])), EXIT_SECURITY_UPDATES);
+        assert_eq!(summary(&snapshot(&[("firefox", false), ("openssl", true)])), "2 updates available (1 security)");
+
+        let incomplete = StatusSnapshot { severity: Severity::Warning, ..snapshot(&[("firefox", false), ("openssl", true)]) };
+        assert_eq!(exit_code(&incomplete), EXIT_INCOMPLETE);
+        assert_eq!(summary(&incomplete), "2 updates available (1 security), but some check output was not recognized");
+    }
+
+    #[test]
+    fn cached_status_expires_with_check_interval() {
+        let config = PackageUpdaterConfig {
+            enabled_package_managers: [PackageManager::Pacman].into(),
+            ..Default::default()
+        };
+        let cached = snapshot(&[]).last_check(Some(1_000)).package_manager(Some(PackageManager::Pacman.name()));
+
+        assert!(is_fresh(&cached, &config, 1_000 + 59 * 60));
+        assert!(!is_fresh(&cached, &config, 1_000 + 60 * 60));
+        assert!(!is_fresh(&cached.clone().package_manager(Some(PackageManager::Dnf.name())), &config, 1_000));
+    }
+}
@@ -1,42 +1,282 @@
 use cosmic_config::{Config, ConfigGet, ConfigSet};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
+use crate::app::PopupTab;
+use crate::app_id;
+use crate::custom_source::CustomSource;
+use crate::icons::{BadgePosition, BadgeSource, IconSource};
 use crate::package_manager::PackageManager;
+use crate::privilege::PrivilegeEscalation;
+use crate::remote::RemoteHost;
+use crate::schedule::CheckWindow;
+use crate::terminal;
+use crate::webhook::WebhookFormat;
 
 pub const CONFIG_VERSION: u64 = 1;
 
+/// The selection of releases before package managers were enabled as a
+/// set: one package manager and the ones checked along with it.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct LegacySelection {
+    package_manager: Option<PackageManager>,
+    additional_package_managers: Vec<PackageManager>,
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
 pub struct PackageUpdaterConfig {
-    pub package_manager: Option<PackageManager>,
+    /// Package managers that are checked and updated, e.g. pacman and Flatpak
+    pub enabled_package_managers: BTreeSet<PackageManager>,
+    /// Order of the package managers, most preferred first; missing ones
+    /// follow in the built-in order. The first enabled one is the primary,
+    /// and one is picked in this order while none is enabled
+    pub package_manager_preference: Vec<PackageManager>,
+    /// List package managers of other distributions in the settings too,
+    /// e.g. ones installed for a container or chroot
+    pub show_all_package_managers: bool,
     pub check_interval_minutes: u32,
     pub auto_check_on_startup: bool,
+    /// Extra wait after the network is online before the startup check
+    pub startup_delay_secs: u32,
+    /// Also wait for systemd's graphical-session.target before the startup check
+    pub wait_for_graphical_session: bool,
     pub include_aur_updates: bool,
     pub show_notifications: bool,
     pub show_update_count: bool,
+    /// Updates the panel count and icon state are based on
+    pub badge_source: BadgeSource,
+    pub icon_source: IconSource,
+    /// Mark the state with a glyph next to the icons and draw them in the
+    /// text color, for users who can't tell the state colors apart
+    pub high_contrast_indicators: bool,
+    pub badge_position_horizontal: BadgePosition,
+    /// Vertical panels are narrow, so the count goes below the icon by default
+    pub badge_position_vertical: BadgePosition,
+    /// Keep room for this many digits of the count, so the applet doesn't
+    /// change width as the count does; 0 fits the count
+    pub reserved_count_digits: u8,
     pub preferred_terminal: String,
+    pub terminal_fallbacks: Vec<String>,
+    pub privilege_escalation: PrivilegeEscalation,
+    pub check_window_enabled: bool,
+    pub check_window: CheckWindow,
+    /// Hold back scheduled checks and notifications while a game runs or
+    /// do not disturb is on
+    pub defer_when_busy: bool,
+    pub weekly_summary_notification: bool,
+    /// Play the sound theme's cue when new updates are found
+    pub sound_on_updates: bool,
+    /// Play the sound theme's cue when an update run's terminal closes
+    pub sound_on_update_finished: bool,
+    pub sound_quiet_hours_enabled: bool,
+    /// Hours without sounds, wrapping around midnight like the check window
+    pub sound_quiet_hours: CheckWindow,
+    /// Compare the clock with the mirror's after checks, at most hourly
+    pub check_clock_skew: bool,
+    pub default_tab: PopupTab,
+    /// Reopen the popup on the tab it was closed on, for the current session
+    pub remember_last_tab: bool,
+    /// Close the popup when an update is started from it
+    pub close_popup_on_update: bool,
+    /// Keep the popup open while an update runs, overriding the options around it
+    pub pin_popup_during_update: bool,
+    /// Dry-run the upgrade for conflicts before launching it (Arch)
+    pub preflight_check: bool,
+    /// Close the popup after this many seconds without input, 0 to disable
+    pub popup_idle_timeout_secs: u32,
+    /// Proxy for the applet's own requests; empty uses the proxy environment variables
+    pub proxy: String,
+    /// Receives a report after each check, empty to disable
+    pub webhook_url: String,
+    pub webhook_format: WebhookFormat,
+    /// Prometheus textfile written after each check, empty to disable
+    pub metrics_textfile: String,
+    /// Record check statistics in the state file for bug reports
+    pub diagnostics_enabled: bool,
+    /// Report a warning instead of the plain count when a check's output
+    /// had lines that were not recognized
+    pub strict_parsing: bool,
+    /// Repositories whose updates are listed apart and not counted
+    pub excluded_repositories: Vec<String>,
+    /// Directory of AppImages checked with appimageupdatetool, empty to disable
+    pub appimage_directory: String,
+    /// Check the Distrobox and Toolbox containers after each check
+    pub check_containers: bool,
+    /// Also check stopped containers, which starts them
+    pub start_stopped_containers: bool,
+    /// Installation to check after each check, e.g. a chroot or a mounted
+    /// system, empty to disable
+    pub alternate_root: String,
+    /// Machines checked over SSH after each check
+    pub remote_hosts: Vec<RemoteHost>,
+    /// Update through topgrade, whose dry run lists its steps after each check
+    pub use_topgrade: bool,
+    /// Package managers without a backend, checked with a command and a
+    /// pattern
+    pub custom_sources: Vec<CustomSource>,
+    /// Plugins allowed to run, by name; the others in the plugin
+    /// directories are only listed
+    pub enabled_plugins: BTreeSet<String>,
+    /// MQTT broker as "host" or "host:port", empty to disable
+    pub mqtt_broker: String,
+    pub mqtt_username: String,
+    pub mqtt_password: String,
 }
 
 impl Default for PackageUpdaterConfig {
     fn default() -> Self {
         Self {
-            package_manager: None,
+            enabled_package_managers: BTreeSet::new(),
+            package_manager_preference: Vec::new(),
+            show_all_package_managers: false,
             check_interval_minutes: 60,
             auto_check_on_startup: true,
+            startup_delay_secs: 2,
+            wait_for_graphical_session: false,
             include_aur_updates: true,
             show_notifications: true,
             show_update_count: true,
+            badge_source: BadgeSource::All,
+            icon_source: IconSource::default(),
+            high_contrast_indicators: false,
+            badge_position_horizontal: BadgePosition::Beside,
+            badge_position_vertical: BadgePosition::Below,
+            reserved_count_digits: 0,
             preferred_terminal: "cosmic-term".to_string(),
+            terminal_fallbacks: terminal::default_fallbacks(),
+            privilege_escalation: PrivilegeEscalation::default(),
+            check_window_enabled: false,
+            defer_when_busy: true,
+            check_window: CheckWindow::default(),
+            weekly_summary_notification: false,
+            sound_on_updates: false,
+            sound_on_update_finished: false,
+            sound_quiet_hours_enabled: false,
+            sound_quiet_hours: CheckWindow { start_hour: 22, end_hour: 7 },
+            check_clock_skew: false,
+            default_tab: PopupTab::Updates,
+            remember_last_tab: true,
+            close_popup_on_update: false,
+            pin_popup_during_update: true,
+            preflight_check: true,
+            popup_idle_timeout_secs: 0,
+            proxy: String::new(),
+            webhook_url: String::new(),
+            webhook_format: WebhookFormat::default(),
+            metrics_textfile: String::new(),
+            diagnostics_enabled: false,
+            strict_parsing: false,
+            excluded_repositories: Vec::new(),
+            appimage_directory: String::new(),
+            check_containers: false,
+            start_stopped_containers: false,
+            alternate_root: String::new(),
+            remote_hosts: Vec::new(),
+            use_topgrade: false,
+            custom_sources: Vec::new(),
+            enabled_plugins: BTreeSet::new(),
+            mqtt_broker: String::new(),
+            mqtt_username: String::new(),
+            mqtt_password: String::new(),
         }
     }
 }
 
 impl PackageUpdaterConfig {
     pub fn load() -> (Config, Self) {
-        let config = Config::new("com.github.cosmic_ext.PackageUpdater", CONFIG_VERSION).unwrap();
-        let config_helper = Self::get_entry(&config).unwrap_or_default();
+        app_id::migrate_legacy_config(CONFIG_VERSION);
+        let config = Config::new(app_id::APP_ID, CONFIG_VERSION).unwrap();
+        let mut config_helper = Self::get_entry(&config).unwrap_or_default();
+        if config_helper.enabled_package_managers.is_empty() {
+            if let Ok(legacy) = config.get::<LegacySelection>("config") {
+                config_helper.migrate(legacy);
+            }
+        }
         (config, config_helper)
     }
 
+    /// Enables the package managers selected before they were a set, the
+    /// one that was selected staying the primary.
+    fn migrate(&mut self, legacy: LegacySelection) {
+        let Some(primary) = legacy.package_manager else {
+            return;
+        };
+        self.enabled_package_managers = std::iter::once(primary).chain(legacy.additional_package_managers).collect();
+        if self.package_manager() != Some(primary) {
+            self.package_manager_preference = std::iter::once(primary)
+                .chain(self.preference_order().into_iter().filter(|&pm| pm != primary))
+                .collect();
+        }
+    }
+
+    /// The primary package manager: the first enabled one in preference
+    /// order. Its check decides whether a check failed, and its commands
+    /// are used for single packages and groups.
+    pub fn package_manager(&self) -> Option<PackageManager> {
+        self.package_managers().into_iter().next()
+    }
+
+    /// The enabled package managers, the primary first.
+    pub fn package_managers(&self) -> Vec<PackageManager> {
+        self.preference_order().into_iter()
+            .filter(|pm| self.enabled_package_managers.contains(pm))
+            .collect()
+    }
+
+    /// Turns `pm` on or off.
+    pub fn enable_package_manager(&mut self, pm: PackageManager, enabled: bool) {
+        if enabled {
+            self.enabled_package_managers.insert(pm);
+        } else {
+            self.enabled_package_managers.remove(&pm);
+        }
+    }
+
+    /// Makes `pm` the primary in place of the current one, e.g. an AUR
+    /// helper in place of pacman; the other enabled ones stay enabled.
+    pub fn switch_package_manager(&mut self, pm: PackageManager) {
+        if let Some(current) = self.package_manager() {
+            self.enabled_package_managers.remove(&current);
+        }
+        self.enabled_package_managers.insert(pm);
+        if self.package_manager() != Some(pm) {
+            self.package_manager_preference = std::iter::once(pm)
+                .chain(self.preference_order().into_iter().filter(|&other| other != pm))
+                .collect();
+        }
+    }
+
+    /// Every package manager, most preferred first.
+    pub fn preference_order(&self) -> Vec<PackageManager> {
+        let mut order: Vec<PackageManager> = Vec::new();
+        for &pm in self.package_manager_preference.iter().chain(PackageManager::PREFERENCE.iter()) {
+            if !order.contains(&pm) {
+                order.push(pm);
+            }
+        }
+        order
+    }
+
+    /// Moves `pm` one place up or down among the `available` managers, the
+    /// only ones the settings list.
+    pub fn move_preference(&mut self, pm: PackageManager, available: &[PackageManager], up: bool) {
+        let order = self.preference_order();
+        let mut shown: Vec<PackageManager> = order.iter().copied().filter(|p| available.contains(p)).collect();
+        let Some(i) = shown.iter().position(|&p| p == pm) else {
+            return;
+        };
+        let j = if up { i.checked_sub(1) } else { Some(i + 1).filter(|&j| j < shown.len()) };
+        let Some(j) = j else {
+            return;
+        };
+        shown.swap(i, j);
+        self.package_manager_preference = shown.into_iter()
+            .chain(order.into_iter().filter(|p| !available.contains(p)))
+            .collect();
+    }
+
     pub fn get_entry(config: &Config) -> Option<Self> {
         config.get("config").ok()
     }
@@ -44,4 +284,46 @@ impl PackageUpdaterConfig {
     pub fn set_entry(config: &Config, config_helper: &Self) {
         let _ = config.set("config", config_helper);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_manager::PackageManagerDetector;
+
+    #[test]
+    fn reorders_package_manager_preference() {
+        let available = [PackageManager::Paru, PackageManager::Pacman, PackageManager::Flatpak];
+        let mut config = PackageUpdaterConfig::default();
+        assert_eq!(PackageManagerDetector::preferred(&available, &config.preference_order()), Some(PackageManager::Paru));
+
+        config.move_preference(PackageManager::Pacman, &available, true);
+        let order = config.preference_order();
+        assert_eq!(&order[..3], &[PackageManager::Pacman, PackageManager::Paru, PackageManager::Flatpak]);
+        assert_eq!(order.len(), PackageManager::PREFERENCE.len());
+        assert_eq!(PackageManagerDetector::preferred(&available, &order), Some(PackageManager::Pacman));
+
+        // Already first
+        config.move_preference(PackageManager::Pacman, &available, true);
+        assert_eq!(config.preference_order(), order);
+    }
+
+    #[test]
+    fn enables_package_managers_as_a_set() {
+        let mut config = PackageUpdaterConfig::default();
+        config.migrate(LegacySelection {
+            package_manager: Some(PackageManager::Pacman),
+            additional_package_managers: vec![PackageManager::Flatpak, PackageManager::Paru],
+        });
+        // The selected one stays the primary, ahead of the AUR helper
+        assert_eq!(config.package_managers(), [PackageManager::Pacman, PackageManager::Paru, PackageManager::Flatpak]);
+
+        config.enable_package_manager(PackageManager::Pacman, false);
+        assert_eq!(config.package_manager(), Some(PackageManager::Paru));
+        config.enable_package_manager(PackageManager::Paru, false);
+        assert_eq!(config.package_managers(), [PackageManager::Flatpak]);
+
+        config.switch_package_manager(PackageManager::Yay);
+        assert_eq!(config.package_managers(), [PackageManager::Yay]);
+    }
+}
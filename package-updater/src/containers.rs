@@ -0,0 +1,267 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use std::io;
+use std::sync::Arc;
+
+use crate::backend;
+use crate::package_manager::{PackageManager, PackageUpdate, UpdateChecker};
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::{shell_quote, CommandOutput, CommandRunner};
+
+/// Package managers a container's distribution can have, with the binary
+/// that shows it is there. Containers are checked with the plain system
+/// package manager; AUR helpers and universal formats stay on the host.
+const PACKAGE_MANAGERS: [(PackageManager, &str); 8] = [
+    (PackageManager::Pacman, "pacman"),
+    (PackageManager::Apt, "apt-get"),
+    (PackageManager::Dnf, "dnf"),
+    (PackageManager::Zypper, "zypper"),
+    (PackageManager::Apk, "apk"),
+    (PackageManager::Xbps, "xbps-install"),
+    (PackageManager::Emerge, "emerge"),
+    (PackageManager::Eopkg, "eopkg"),
+];
+
+/// Tool that manages development containers on the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Distrobox,
+    Toolbox,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 2] = [Tool::Distrobox, Tool::Toolbox];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::Distrobox => "distrobox",
+            Tool::Toolbox => "toolbox",
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        backend::on_path(self.name())
+    }
+
+    fn list_args(&self) -> &'static [&'static str] {
+        match self {
+            Tool::Distrobox => &["list", "--no-color"],
+            Tool::Toolbox => &["list", "--containers"],
+        }
+    }
+
+    /// Arguments that run the command following them inside the container.
+    fn run_args<'a>(&self, container: &'a str) -> Vec<&'a str> {
+        match self {
+            Tool::Distrobox => vec!["enter", "--name", container, "--"],
+            Tool::Toolbox => vec!["run", "--container", container],
+        }
+    }
+
+    fn parse_list(&self, output: &str) -> Vec<Container> {
+        match self {
+            Tool::Distrobox => parse_distrobox_list(output),
+            Tool::Toolbox => parse_toolbox_list(output),
+        }
+    }
+}
+
+/// A container as listed by its tool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Container {
+    pub tool: Tool,
+    pub name: String,
+    pub running: bool,
+}
+
+/// Result of checking one container.
+#[derive(Debug, Clone)]
+pub struct ContainerUpdates {
+    pub container: Container,
+    pub package_manager: Option<PackageManager>,
+    pub packages: Vec<PackageUpdate>,
+    /// Why the container wasn't checked, or its check failed
+    pub error: Option<String>,
+}
+
+impl ContainerUpdates {
+    /// Command that updates the container's packages from the host,
+    /// through sudo, which the container images set up without a password.
+    pub fn update_command(&self) -> Option<String> {
+        let command = shell_quote(&self.package_manager?.system_update_command(PrivilegeEscalation::Sudo));
+        let name = shell_quote(&self.container.name);
+        let mut args = vec![self.container.tool.name()];
+        args.extend(self.container.tool.run_args(&name));
+        args.extend(["sh", "-c", &command]);
+        Some(args.join(" "))
+    }
+}
+
+/// Runs commands inside a container through its tool.
+pub struct ContainerRunner {
+    container: Container,
+    host: Arc<dyn CommandRunner>,
+}
+
+impl CommandRunner for ContainerRunner {
+    fn run<'a>(&'a self, program: &'a str, args: &'a [&'a str]) -> BoxFuture<'a, io::Result<CommandOutput>> {
+        Box::pin(async move {
+            let mut wrapped = self.container.tool.run_args(&self.container.name);
+            wrapped.push(program);
+            wrapped.extend_from_slice(args);
+            self.host.run(self.container.tool.name(), &wrapped).await
+        })
+    }
+}
+
+/// Checks every container of the given tools with the package manager
+/// found inside it. Stopped containers are skipped unless `start_stopped`,
+/// since entering one starts it.
+pub async fn check_all(host: Arc<dyn CommandRunner>, tools: &[Tool], start_stopped: bool) -> Result<Vec<ContainerUpdates>> {
+    let mut containers = Vec::new();
+    for tool in tools {
+        let output = host.run(tool.name(), tool.list_args()).await?;
+        if !output.success() {
+            return Err(anyhow!("{} list failed: {}", tool.name(), output.stderr.trim()));
+        }
+        containers.extend(tool.parse_list(&output.stdout));
+    }
+
+    let mut results = Vec::new();
+    for container in containers {
+        if !container.running && !start_stopped {
+            results.push(ContainerUpdates {
+                container,
+                package_manager: None,
+                packages: Vec::new(),
+                error: Some("stopped, not checked".to_string()),
+            });
+            continue;
+        }
+        results.push(check(host.clone(), container).await);
+    }
+    Ok(results)
+}
+
+async fn check(host: Arc<dyn CommandRunner>, container: Container) -> ContainerUpdates {
+    let runner = Arc::new(ContainerRunner { container: container.clone(), host });
+    let mut result = ContainerUpdates { container, package_manager: None, packages: Vec::new(), error: None };
+
+    let package_manager = match detect_package_manager(runner.as_ref()).await {
+        Ok(Some(package_manager)) => package_manager,
+        Ok(None) => {
+            result.error = Some("no supported package manager".to_string());
+            return result;
+        }
+        Err(e) => {
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    result.package_manager = Some(package_manager);
+
+    match UpdateChecker::new(package_manager).runner(runner).check_updates(false).await {
+        Ok(update_info) => {
+            result.error = update_info.sources.into_iter().find_map(|source| source.error);
+            result.packages = update_info.packages;
+        }
+        Err(e) => result.error = Some(e.to_string()),
+    }
+    result
+}
+
+/// The first supported package manager found through `runner`, e.g. in a
+/// container or on a remote host.
+pub async fn detect_package_manager(runner: &dyn CommandRunner) -> Result<Option<PackageManager>> {
+    let binaries: Vec<&str> = PACKAGE_MANAGERS.iter().map(|(_, binary)| *binary).collect();
+    let script = format!("command -v {}", binaries.join(" "));
+    let output = runner.run("sh", &["-c", &script]).await?;
+    // Exits with 1 when any of them is missing; only a missing shell or
+    // container leaves stdout empty
+    if output.stdout.trim().is_empty() && !output.stderr.trim().is_empty() {
+        return Err(anyhow!("{}", output.stderr.trim()));
+    }
+
+    let found: Vec<&str> = output.stdout.lines().filter_map(|path| path.trim().rsplit('/').next()).collect();
+    Ok(PACKAGE_MANAGERS.iter()
+        .find(|(_, binary)| found.contains(binary))
+        .map(|(package_manager, _)| *package_manager))
+}
+
+/// `distrobox list` prints a table separated by " | ":
+/// "ID | NAME | STATUS | IMAGE", with "Up ..." for running containers.
+fn parse_distrobox_list(output: &str) -> Vec<Container> {
+    output.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split('|').map(str::trim).collect();
+            let (name, status) = (columns.get(1)?, columns.get(2)?);
+            Some(Container {
+                tool: Tool::Distrobox,
+                name: name.to_string(),
+                running: status.starts_with("Up"),
+            })
+        })
+        .filter(|container| !container.name.is_empty())
+        .collect()
+}
+
+/// `toolbox list --containers` prints columns separated by runs of
+/// spaces, where "CREATED" holds spaces itself: "CONTAINER ID  CONTAINER
+/// NAME  CREATED  STATUS  IMAGE NAME".
+fn parse_toolbox_list(output: &str) -> Vec<Container> {
+    output.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split("  ").map(str::trim).filter(|column| !column.is_empty()).collect();
+            let (name, status) = (columns.get(1)?, columns.get(3)?);
+            Some(Container {
+                tool: Tool::Toolbox,
+                name: name.to_string(),
+                running: status.eq_ignore_ascii_case("running") || status.starts_with("Up"),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::ScriptedRunner;
+
+    #[tokio::test]
+    async fn checks_containers_with_their_package_manager() {
+        let detect = "sh -c command -v pacman apt-get dnf zypper apk xbps-install emerge eopkg";
+        let host = ScriptedRunner::new([
+            ("distrobox list --no-color".to_string(), CommandOutput::new(0, include_str!("../tests/fixtures/distrobox/list.txt"), "")),
+            (format!("distrobox enter --name arch-dev -- {}", detect), CommandOutput::new(1, "/usr/bin/pacman\n", "")),
+            ("distrobox enter --name arch-dev -- checkupdates".to_string(), CommandOutput::new(0, "ripgrep 14.0.3-1 -> 14.1.0-1\n", "")),
+            (format!("distrobox enter --name minimal -- {}", detect), CommandOutput::new(127, "", "")),
+        ]);
+
+        let results = check_all(Arc::new(host), &[Tool::Distrobox], false).await.expect("check");
+        let names: Vec<&str> = results.iter().map(|r| r.container.name.as_str()).collect();
+        assert_eq!(names, ["arch-dev", "minimal", "fedora-39"]);
+
+        assert_eq!(results[0].package_manager, Some(PackageManager::Pacman));
+        assert_eq!(results[0].packages.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), ["ripgrep"]);
+        assert_eq!(
+            results[0].update_command().as_deref(),
+            Some("distrobox enter --name 'arch-dev' -- sh -c 'sudo pacman -Syu'")
+        );
+        assert_eq!(results[1].error.as_deref(), Some("no supported package manager"));
+        assert_eq!(results[2].error.as_deref(), Some("stopped, not checked"));
+    }
+
+    #[test]
+    fn parses_toolbox_list() {
+        let containers = parse_toolbox_list(include_str!("../tests/fixtures/toolbox/list_containers.txt"));
+        assert_eq!(
+            containers,
+            [
+                Container { tool: Tool::Toolbox, name: "fedora-toolbox-39".to_string(), running: true },
+                Container { tool: Tool::Toolbox, name: "rhel-work".to_string(), running: false },
+            ]
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{Backend, CheckContext};
+use crate::merge::Source;
+use crate::package_manager::{CheckMode, PackageUpdate};
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::CommandOutput;
+
+/// Groups a pattern must have; `current` (or `old`) is optional.
+const REQUIRED_GROUPS: [&str; 2] = ["name", "new"];
+
+/// A package manager without a backend of its own, defined in the config:
+/// a command that lists the updates and a pattern that reads each line.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct CustomSource {
+    pub name: String,
+    /// Run with `sh -c`; must exit with 0, updates or not
+    pub check_command: String,
+    /// Regex with the named groups `name` and `new`, and optionally
+    /// `current` or `old`; lines it doesn't match are skipped
+    pub pattern: String,
+    /// Run in the terminal with the other update commands, empty to only check
+    pub update_command: String,
+}
+
+/// A custom source with its pattern compiled, registered at startup and
+/// whenever the sources in the config change. Checked like a built-in
+/// [`Backend`], after the package managers.
+#[derive(Clone, Debug)]
+pub struct Parser {
+    source: CustomSource,
+    regex: Regex,
+}
+
+impl Parser {
+    pub fn new(source: &CustomSource) -> Result<Self> {
+        if source.name.trim().is_empty() {
+            return Err(anyhow!("A custom source has no name"));
+        }
+        let regex = Regex::new(&source.pattern)
+            .map_err(|e| anyhow!("Invalid pattern of custom source '{}': {}", source.name, e))?;
+        let groups: Vec<&str> = regex.capture_names().flatten().collect();
+        if let Some(missing) = REQUIRED_GROUPS.iter().find(|group| !groups.contains(group)) {
+            return Err(anyhow!("The pattern of custom source '{}' has no '{}' group", source.name, missing));
+        }
+        Ok(Self { source: source.clone(), regex })
+    }
+
+    fn parse_line(&self, line: &str) -> Option<PackageUpdate> {
+        let captures = self.regex.captures(line.trim_end())?;
+        let group = |name: &str| captures.name(name).map(|m| m.as_str().to_string()).filter(|value| !value.is_empty());
+        Some(PackageUpdate {
+            name: group("name")?,
+            current_version: group("current").or_else(|| group("old")).unwrap_or_else(|| "unknown".to_string()),
+            new_version: group("new")?,
+            source: Source::Custom(self.source.name.clone()),
+            ..Default::default()
+        })
+    }
+}
+
+impl Backend for Parser {
+    fn name(&self) -> &str {
+        &self.source.name
+    }
+
+    fn check_command(&self, _mode: CheckMode) -> Option<(&str, Vec<&str>)> {
+        Some(("sh", vec!["-c", &self.source.check_command]))
+    }
+
+    /// Lines the pattern doesn't match are skipped, not unrecognized.
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(async move {
+            let output = context.runner.run("sh", &["-c", &self.source.check_command]).await?;
+            self.parse(&output)
+        })
+    }
+
+    fn parse(&self, output: &CommandOutput) -> Result<Vec<PackageUpdate>> {
+        if !output.success() {
+            return Err(anyhow!("Failed to check for updates (exit {}): {}", output.code.unwrap_or(-1), output.stderr));
+        }
+        Ok(output.stdout.lines().filter_map(|line| self.parse_line(line)).collect())
+    }
+
+    /// Run as configured, it elevates itself if it needs to.
+    fn update_command(&self, _privilege: PrivilegeEscalation) -> Option<String> {
+        Some(self.source.update_command.clone()).filter(|command| !command.trim().is_empty())
+    }
+}
+
+/// Compiles the configured sources. Invalid ones are left out, with the
+/// reason for each.
+pub fn register(sources: &[CustomSource]) -> (Vec<Parser>, Vec<String>) {
+    let mut parsers = Vec::new();
+    let mut errors = Vec::new();
+    for source in sources {
+        match Parser::new(source) {
+            Ok(parser) => parsers.push(parser),
+            Err(e) => {
+                eprintln!("{}", e);
+                errors.push(e.to_string());
+            }
+        }
+    }
+    (parsers, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend;
+    use crate::package_manager::UpdateInfo;
+    use crate::runner::ScriptedRunner;
+    use std::sync::Arc;
+
+    fn source(name: &str, check_command: &str, pattern: &str) -> CustomSource {
+        CustomSource { name: name.to_string(), check_command: check_command.to_string(), pattern: pattern.to_string(), update_command: String::new() }
+    }
+
+    #[tokio::test]
+    async fn checks_sources_with_their_pattern() {
+        let (parsers, errors) = register(&[
+            source("opkg", "opkg list-upgradable", r"^(?P<name>\S+) - (?P<current>\S+) - (?P<new>\S+)$"),
+            source("broken", "missing-tool", r"^(?P<name>\S+) (?P<new>\S+)$"),
+            source("no-version", "true", r"^(?P<name>\S+)$"),
+            source("invalid", "true", r"(?P<name>"),
+        ]);
+        assert_eq!(parsers.len(), 2);
+        assert_eq!(errors[0], "The pattern of custom source 'no-version' has no 'new' group");
+        assert!(errors[1].starts_with("Invalid pattern of custom source 'invalid'"));
+
+        let sources: Vec<Arc<dyn Backend>> = parsers.into_iter().map(|parser| Arc::new(parser) as Arc<dyn Backend>).collect();
+        let mut update_info = UpdateInfo::new();
+        let runner = ScriptedRunner::new([(
+            "sh -c opkg list-upgradable",
+            CommandOutput::new(0, "busybox - 1.36.1-1 - 1.36.1-2\nlibc - 1.2.4-4 - 1.2.5-1\nCollected errors:\n", ""),
+        )]);
+        backend::check_all(&sources, &runner, &mut update_info).await;
+        let found: Vec<(&str, &str, &str)> = update_info.packages.iter()
+            .map(|p| (p.name.as_str(), p.current_version.as_str(), p.new_version.as_str()))
+            .collect();
+        assert_eq!(found, [("busybox", "1.36.1-1", "1.36.1-2"), ("libc", "1.2.4-4", "1.2.5-1")]);
+        assert_eq!(update_info.total_updates, 2);
+        assert!(update_info.sources[0].error.is_none());
+        assert!(update_info.sources[1].error.is_some());
+    }
+
+    #[test]
+    fn reads_the_old_version_from_either_group() {
+        let parser = Parser::new(&source("opkg", "opkg list-upgradable", r"^(?P<name>\S+) - (?P<old>\S+) - (?P<new>\S+)$")).expect("valid source");
+        let update = parser.parse_line("busybox - 1.36.1-1 - 1.36.1-2").expect("matching line");
+        assert_eq!((update.current_version.as_str(), update.new_version.as_str()), ("1.36.1-1", "1.36.1-2"));
+    }
+}
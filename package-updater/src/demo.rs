@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::config::PackageUpdaterConfig;
+use crate::health::SourceCheck;
+use crate::merge::Source;
+use crate::package_manager::{FlatpakInstallation, PackageManager, PackageUpdate, UpdateInfo};
+
+/// How long a demo check takes, long enough to see the checking state.
+const CHECK_DURATION: Duration = Duration::from_millis(1500);
+
+/// How long a demo update "runs" in place of a terminal session.
+pub const UPDATE_DURATION: Duration = Duration::from_secs(3);
+
+/// Package managers the demo pretends are installed.
+pub const PACKAGE_MANAGERS: [PackageManager; 3] = [PackageManager::Paru, PackageManager::Pacman, PackageManager::Flatpak];
+
+/// Settings of a demo session: an Arch system with an AUR helper and
+/// Flatpak, without anything that would run real commands.
+pub fn config() -> PackageUpdaterConfig {
+    PackageUpdaterConfig {
+        enabled_package_managers: [PackageManager::Paru, PackageManager::Flatpak].into(),
+        preflight_check: false,
+        ..PackageUpdaterConfig::default()
+    }
+}
+
+/// A check of the fake backend: a fixed set of updates, without those a
+/// demo update already "installed".
+pub async fn check(installed: HashSet<String>) -> Result<UpdateInfo, String> {
+    tokio::time::sleep(CHECK_DURATION).await;
+    Ok(update_info(&installed))
+}
+
+fn update_info(installed: &HashSet<String>) -> UpdateInfo {
+    let mut info = UpdateInfo::new();
+    info.packages = packages().into_iter().filter(|p| !installed.contains(&p.name)).collect();
+    info.ignored = vec![PackageUpdate { is_ignored: true, ..PackageUpdate::new("nvidia", "545.29.06-18", "550.54.14-1") }];
+    info.sources = vec![
+        SourceCheck::new(PackageManager::Paru.name(), Ok(())),
+        SourceCheck::new(PackageManager::Flatpak.name(), Ok(())),
+    ];
+    info.count();
+    info
+}
+
+fn packages() -> Vec<PackageUpdate> {
+    let security = |p: PackageUpdate| PackageUpdate { is_security: true, ..p };
+    let aur = |p: PackageUpdate| PackageUpdate { source: Source::Aur, ..p };
+    let xorg = |p: PackageUpdate| PackageUpdate { group: Some("xorg".to_string()), ..p };
+    let flathub = |installation, p: PackageUpdate| PackageUpdate {
+        repository: Some("flathub".to_string()),
+        source: Source::Flatpak(installation),
+        ..p
+    };
+    vec![
+        PackageUpdate::new("linux", "6.7.4.arch1-1", "6.7.5.arch1-1"),
+        security(PackageUpdate::new("firefox", "122.0.1-1", "123.0-1")),
+        security(PackageUpdate::new("openssl", "3.2.0-1", "3.2.1-1")),
+        PackageUpdate::new("mesa", "1:23.3.5-1", "1:24.0.1-1"),
+        PackageUpdate::new("systemd", "255.3-1", "255.3-2"),
+        PackageUpdate::new("python", "3.11.7-1", "3.11.8-1"),
+        xorg(PackageUpdate::new("xorg-server", "21.1.11-1", "21.1.11-2")),
+        xorg(PackageUpdate::new("xorg-xwayland", "23.2.4-1", "23.2.4-2")),
+        aur(PackageUpdate::new("visual-studio-code-bin", "1.86.0-1", "1.86.2-1")),
+        aur(PackageUpdate::new("spotify", "1:1.2.26.1187-1", "1:1.2.31.1205-1")),
+        flathub(FlatpakInstallation::User, PackageUpdate::new("org.gimp.GIMP", "2.10.36", "2.10.38")),
+        flathub(FlatpakInstallation::System, PackageUpdate::new("org.libreoffice.LibreOffice", "24.2.0.3", "24.2.1.2")),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn covers_every_kind_of_update() {
+        let info = update_info(&HashSet::new());
+        assert!(info.aur_updates > 0 && info.official_updates > info.aur_updates);
+        assert!(info.packages.iter().any(|p| p.is_security));
+        assert!(info.packages.iter().any(|p| p.repository.as_deref() == Some("flathub")));
+        assert!(info.packages.iter().any(|p| p.group.is_some()));
+
+        let installed: HashSet<String> = ["firefox", "spotify"].map(String::from).into();
+        let info = update_info(&installed);
+        assert_eq!(info.total_updates, packages().len() - 2);
+        assert!(!info.packages.iter().any(|p| installed.contains(&p.name)));
+    }
+}
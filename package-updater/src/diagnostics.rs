@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::{self, Debug, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::package_manager::UpdateInfo;
+
+/// Check durations kept for the statistics, the most recent ones.
+const KEPT_DURATIONS: usize = 50;
+
+/// Distinct unrecognized output lines kept, the most recent ones.
+const KEPT_UNRECOGNIZED_LINES: usize = 20;
+
+/// Messages kept for the scheduler view, the most recent ones.
+const KEPT_MESSAGES: usize = 20;
+
+/// How often a source was checked and how often that failed.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default)]
+pub struct SourceCounts {
+    pub checks: u32,
+    pub failures: u32,
+}
+
+/// Statistics about this machine's checks, recorded only when enabled and
+/// only ever written to the state file, to attach to bug reports.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default)]
+pub struct Diagnostics {
+    /// Unix timestamp of the first recorded check
+    pub since: Option<i64>,
+    pub checks: u32,
+    pub failed_checks: u32,
+    /// Milliseconds, oldest first
+    pub check_durations_ms: Vec<u64>,
+    /// Output lines of the checks that were neither an update nor known
+    /// noise, each one a possibly missed update
+    pub unrecognized_lines: u64,
+    /// The most recent of those lines, oldest first
+    pub recent_unrecognized: Vec<String>,
+    /// By source name
+    pub sources: BTreeMap<String, SourceCounts>,
+}
+
+impl Diagnostics {
+    pub fn record(&mut self, duration: Duration, result: Result<&UpdateInfo, &str>, now: i64) {
+        self.since.get_or_insert(now);
+        self.checks += 1;
+        self.check_durations_ms.push(duration.as_millis() as u64);
+        if self.check_durations_ms.len() > KEPT_DURATIONS {
+            self.check_durations_ms.remove(0);
+        }
+
+        match result {
+            Ok(update_info) => {
+                self.unrecognized_lines += update_info.unrecognized_lines.len() as u64;
+                for line in &update_info.unrecognized_lines {
+                    self.recent_unrecognized.retain(|kept| kept != line);
+                    self.recent_unrecognized.push(line.clone());
+                }
+                let excess = self.recent_unrecognized.len().saturating_sub(KEPT_UNRECOGNIZED_LINES);
+                self.recent_unrecognized.drain(..excess);
+                for source in &update_info.sources {
+                    let counts = self.sources.entry(source.source.clone()).or_default();
+                    counts.checks += 1;
+                    if source.error.is_some() {
+                        counts.failures += 1;
+                    }
+                }
+            }
+            Err(_) => self.failed_checks += 1,
+        }
+    }
+
+    pub fn median_duration(&self) -> Option<Duration> {
+        let mut durations = self.check_durations_ms.clone();
+        durations.sort_unstable();
+        durations.get(durations.len() / 2).map(|&ms| Duration::from_millis(ms))
+    }
+
+    pub fn slowest_duration(&self) -> Option<Duration> {
+        self.check_durations_ms.iter().max().map(|&ms| Duration::from_millis(ms))
+    }
+
+    /// One line per statistic, for the settings view and bug reports.
+    pub fn lines(&self) -> Vec<String> {
+        let seconds = |duration: Option<Duration>| duration.map_or("-".to_string(), |d| format!("{:.1} s", d.as_secs_f64()));
+        let mut lines = vec![
+            format!("Checks: {} ({} failed)", self.checks, self.failed_checks),
+            format!(
+                "Check duration: median {}, slowest {} (last {})",
+                seconds(self.median_duration()),
+                seconds(self.slowest_duration()),
+                self.check_durations_ms.len()
+            ),
+            format!("Unrecognized output lines: {}", self.unrecognized_lines),
+        ];
+        for (source, counts) in &self.sources {
+            lines.push(format!(
+                "{}: {} of {} checks failed ({}%)",
+                source,
+                counts.failures,
+                counts.checks,
+                counts.failures * 100 / counts.checks.max(1)
+            ));
+        }
+        lines
+    }
+
+    pub fn report(&self, package_manager: Option<&str>, distro: &str) -> String {
+        let mut report = format!(
+            "Package Updater diagnostics\nSystem: {}\nPackage manager: {}\n",
+            distro,
+            package_manager.unwrap_or("none")
+        );
+        if let Some(since) = self.since {
+            report.push_str(&format!("Recorded since: {}\n", crate::history::format_timestamp(since)));
+        }
+        report.push('\n');
+        for line in self.lines() {
+            report.push_str(&line);
+            report.push('\n');
+        }
+        if !self.recent_unrecognized.is_empty() {
+            report.push_str("\nUnrecognized output lines:\n");
+            for line in &self.recent_unrecognized {
+                report.push_str(&format!("  {}\n", line));
+            }
+        }
+        report
+    }
+}
+
+/// A message the applet processed, with how often it came in a row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoggedMessage {
+    pub name: String,
+    pub count: u32,
+    /// Unix timestamp of the last one
+    pub last: i64,
+}
+
+/// The applet's recent messages by name, kept in memory for the scheduler
+/// view. Repeats, like timer ticks, are folded into one entry.
+#[derive(Debug, Default)]
+pub struct MessageLog {
+    entries: VecDeque<LoggedMessage>,
+}
+
+impl MessageLog {
+    pub fn record(&mut self, message: &impl Debug, now: i64) {
+        let name = variant_name(message);
+        if let Some(last) = self.entries.back_mut().filter(|last| last.name == name) {
+            last.count += 1;
+            last.last = now;
+            return;
+        }
+        if self.entries.len() == KEPT_MESSAGES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LoggedMessage { name, count: 1, last: now });
+    }
+
+    /// Newest first.
+    pub fn entries(&self) -> impl Iterator<Item = &LoggedMessage> {
+        self.entries.iter().rev()
+    }
+}
+
+/// Name of an enum variant from its `Debug` output, e.g. "UpdatesChecked".
+/// Formatting stops after the name, so large payloads cost nothing.
+pub fn variant_name(value: &impl Debug) -> String {
+    struct Name(String);
+
+    impl Write for Name {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            match s.find(|c: char| !(c.is_alphanumeric() || c == '_')) {
+                Some(end) => {
+                    self.0.push_str(&s[..end]);
+                    Err(fmt::Error)
+                }
+                None => {
+                    self.0.push_str(s);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    let mut name = Name(String::new());
+    let _ = write!(name, "{:?}", value);
+    name.0
+}
+
+pub fn save_report(report: &str) -> std::io::Result<PathBuf> {
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    let path = PathBuf::from(home).join(format!(
+        "package-updater-diagnostics-{}.txt",
+        chrono::Local::now().format("%Y-%m-%d")
+    ));
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::health::SourceCheck;
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    enum Message {
+        Timer,
+        Checked(Result<Vec<u32>, String>),
+    }
+
+    #[test]
+    fn logs_messages_by_name_with_repeats_folded() {
+        let mut log = MessageLog::default();
+        log.record(&Message::Checked(Ok(vec![1, 2])), 10);
+        log.record(&Message::Timer, 20);
+        log.record(&Message::Timer, 30);
+        let entries: Vec<(&str, u32, i64)> = log.entries().map(|entry| (entry.name.as_str(), entry.count, entry.last)).collect();
+        assert_eq!(entries, [("Timer", 2, 30), ("Checked", 1, 10)]);
+
+        for n in 0..KEPT_MESSAGES {
+            log.record(&Message::Checked(Err(n.to_string())), 40);
+            log.record(&Message::Timer, 40);
+        }
+        assert_eq!(log.entries().count(), KEPT_MESSAGES);
+    }
+
+    #[test]
+    fn records_durations_and_failure_rates() {
+        let mut diagnostics = Diagnostics::default();
+        let mut update_info = UpdateInfo::new();
+        update_info.unrecognized_lines = vec!["paru: foo 1.0 => 1.1".to_string(), "paru: bar".to_string()];
+        update_info.sources = vec![SourceCheck::new("paru", Ok(())), SourceCheck::new("AUR", Err("timeout".to_string()))];
+
+        for ms in [300, 100, 200] {
+            diagnostics.record(Duration::from_millis(ms), Ok(&update_info), 1000);
+        }
+        diagnostics.record(Duration::from_millis(5000), Err("lock held"), 2000);
+
+        assert_eq!((diagnostics.checks, diagnostics.failed_checks), (4, 1));
+        assert_eq!(diagnostics.since, Some(1000));
+        assert_eq!(diagnostics.median_duration(), Some(Duration::from_millis(300)));
+        assert_eq!(diagnostics.slowest_duration(), Some(Duration::from_millis(5000)));
+        assert_eq!(diagnostics.unrecognized_lines, 6);
+        assert_eq!(diagnostics.recent_unrecognized.len(), 2);
+        assert!(diagnostics.report(Some("paru"), "Arch Linux").contains("  paru: foo 1.0 => 1.1\n"));
+        assert_eq!(diagnostics.sources["AUR"], SourceCounts { checks: 3, failures: 3 });
+        assert!(diagnostics.lines().contains(&"AUR: 3 of 3 checks failed (100%)".to_string()));
+
+        for _ in 0..KEPT_DURATIONS {
+            diagnostics.record(Duration::from_millis(1), Err("lock held"), 3000);
+        }
+        assert_eq!(diagnostics.check_durations_ms.len(), KEPT_DURATIONS);
+    }
+}
@@ -0,0 +1,183 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+
+use crate::backend::{self, Backend, Capabilities, CheckContext};
+use crate::held_back::{self, HeldBack};
+use crate::package_manager::{CheckMode, PackageUpdate};
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::CommandRunner;
+
+/// Fedora, RHEL and their derivatives, with dnf 4 or dnf5.
+pub struct Dnf;
+
+impl Backend for Dnf {
+    fn name(&self) -> &str {
+        "dnf"
+    }
+
+    fn detect(&self) -> bool {
+        backend::on_path("dnf")
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { selective_update: true, metadata_refresh: true, needs_privileges: true, ..Capabilities::default() }
+    }
+
+    /// dnf5 lists the updates as JSON, dnf 4 only as a table.
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(async move {
+            if !is_dnf5(context.runner).await {
+                let mut args = vec!["check-update", "-q", "--color=never"];
+                args.extend(mode_args(context.mode));
+                return context.listing(self, "dnf", &args).await;
+            }
+
+            let mut args = vec!["repoquery", "--upgrades", "--latest-limit=1", "--json"];
+            args.extend(mode_args(context.mode));
+            let output = context.runner.run("dnf", &args).await?;
+            context.note_failing_repositories(&output.stderr);
+            if !output.success() {
+                return Err(anyhow!("Failed to check for updates (exit {}): {}", output.code.unwrap_or(-1), output.stderr));
+            }
+            parse_json(&output.stdout)
+        })
+    }
+
+    /// "package.arch version repo"
+    fn parse_line(&self, line: &str) -> Option<PackageUpdate> {
+        // Indented lines list the packages replaced in the "Obsoleting
+        // Packages" section
+        if line.starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 3 {
+            return None;
+        }
+        // Names may contain dots too
+        let (name, _arch) = parts[0].rsplit_once('.')?;
+        Some(PackageUpdate {
+            repository: Some(parts[2].to_string()),
+            ..PackageUpdate::new(name, "unknown", parts[1])
+        })
+    }
+
+    fn is_noise(&self, line: &str) -> bool {
+        line.starts_with(char::is_whitespace)
+            || line.starts_with("Obsoleting Packages")
+            || line.starts_with("Last metadata expiration check")
+    }
+
+    /// dnf and zypper refresh their caches under the root, which needs
+    /// root and changes the root, even when only checking.
+    fn root_check_command(&self, _root: &str) -> Result<(&str, Vec<String>)> {
+        Err(anyhow!("dnf roots aren't supported: checking one writes its caches under the root"))
+    }
+
+    /// dnf lists candidates it can't install among the updates. Its
+    /// resolver pass costs as much as the check, so quick checks skip it.
+    /// Runs in the C locale, the explanations are parsed.
+    fn explain_held_back<'a>(&'a self, context: &'a CheckContext<'a>, packages: &'a [PackageUpdate]) -> BoxFuture<'a, Vec<HeldBack>> {
+        Box::pin(async move {
+            if packages.is_empty() || context.mode == CheckMode::Quick {
+                return Vec::new();
+            }
+            let names: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+            let mut args = vec!["LC_ALL=C", "dnf", "upgrade", "--best", "--assumeno"];
+            args.extend(mode_args(context.mode));
+            // Fails without problems to report too, e.g. when dnf 4 insists on root
+            match context.runner.run("env", &args).await {
+                Ok(output) => held_back::parse_dnf_problems(&format!("{}\n{}", output.stdout, output.stderr), &names),
+                Err(e) => {
+                    eprintln!("Failed to explain held back packages: {}", e);
+                    Vec::new()
+                }
+            }
+        })
+    }
+
+    /// The `excludepkgs` of dnf's main configuration, which the check
+    /// lists anyway.
+    fn excluded<'a>(&'a self, context: &'a CheckContext<'a>, packages: &'a [PackageUpdate]) -> BoxFuture<'a, Vec<String>> {
+        Box::pin(async move {
+            // dnf 4 needs dnf-plugins-core for this
+            let args: &[&str] = if is_dnf5(context.runner).await { &["--dump-main-config"] } else { &["config-manager", "--dump"] };
+            let patterns = match context.runner.run("dnf", args).await {
+                Ok(output) if output.success() => held_back::parse_dnf_excludes(&output.stdout),
+                _ => return Vec::new(),
+            };
+            packages.iter()
+                .filter(|p| patterns.iter().any(|pattern| held_back::glob_matches(pattern, &p.name)))
+                .map(|p| p.name.clone())
+                .collect()
+        })
+    }
+
+    fn update_command(&self, privilege: PrivilegeEscalation) -> Option<String> {
+        Some(privilege.wrap("dnf upgrade"))
+    }
+
+    fn packages_update_command(&self, privilege: PrivilegeEscalation, packages: &[String]) -> Option<String> {
+        if packages.is_empty() {
+            return None;
+        }
+        Some(privilege.wrap(&format!("dnf upgrade {}", packages.join(" "))))
+    }
+
+    fn security_update_command(&self, privilege: PrivilegeEscalation, _security_packages: &[String]) -> Option<String> {
+        Some(privilege.wrap("dnf upgrade --security"))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; 'dnf check' lists broken dependencies."
+    }
+}
+
+fn mode_args(mode: CheckMode) -> Vec<&'static str> {
+    match mode {
+        CheckMode::Quick => vec!["--cacheonly"],
+        CheckMode::Refresh => vec!["--refresh"],
+        CheckMode::Standard => Vec::new(),
+    }
+}
+
+async fn is_dnf5(runner: &dyn CommandRunner) -> bool {
+    // dnf5 prints "dnf5 version 5.x"; dnf 4 prints just its version
+    runner.run("dnf", &["--version"]).await
+        .map(|output| output.success() && output.stdout.contains("dnf5"))
+        .unwrap_or(false)
+}
+
+/// Parses `dnf5 repoquery --json` output, a list of package objects.
+fn parse_json(json: &str) -> Result<Vec<PackageUpdate>> {
+    let packages: Vec<serde_json::Value> = serde_json::from_str(json)?;
+    let field = |package: &serde_json::Value, key: &str| {
+        package.get(key).and_then(|v| v.as_str()).map(str::to_string)
+    };
+
+    let updates = packages
+        .iter()
+        .filter_map(|package| {
+            let name = field(package, "name")?;
+            let new_version = field(package, "evr").or_else(|| {
+                let version = field(package, "version")?;
+                let release = field(package, "release")?;
+                Some(match field(package, "epoch").filter(|epoch| epoch != "0") {
+                    Some(epoch) => format!("{}:{}-{}", epoch, version, release),
+                    None => format!("{}-{}", version, release),
+                })
+            })?;
+
+            Some(PackageUpdate {
+                name,
+                current_version: "unknown".to_string(),
+                new_version,
+                repository: field(package, "repo_id"),
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    Ok(updates)
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+
+use crate::backend::{self, Backend, Capabilities, CheckContext};
+use crate::package_manager::PackageUpdate;
+use crate::privilege::PrivilegeEscalation;
+
+/// Gentoo's Portage.
+pub struct Emerge;
+
+impl Backend for Emerge {
+    fn name(&self) -> &str {
+        "emerge"
+    }
+
+    fn detect(&self) -> bool {
+        backend::on_path("emerge")
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { selective_update: true, needs_privileges: true, ..Capabilities::default() }
+    }
+
+    /// Resolving @world takes minutes, eix only compares the installed
+    /// versions with the repository's.
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(async move {
+            let has_eix = context.runner.run("which", &["eix"]).await
+                .map(|output| output.success())
+                .unwrap_or(false);
+            if has_eix {
+                context.listing(self, "env", &["EIX_LIMIT_COMPACT=0", "eix", "--nocolor", "--compact", "--upgrade"]).await
+            } else {
+                context.listing(self, "emerge", &["--pretend", "--update", "--deep", "--newuse", "--color=n", "--nospinner", "@world"]).await
+            }
+        })
+    }
+
+    /// Portage: "[ebuild     U  ] category/name-version[:slot]::repo [old-version[:slot]::repo]"
+    /// eix: "[U] category/name (old-version[(slot)]@date -> new-version[(slot)]): description"
+    fn parse_line(&self, line: &str) -> Option<PackageUpdate> {
+        let (name, current_version, new_version) = match line.strip_prefix("[U] ") {
+            Some(rest) => parse_eix_upgrade(rest)?,
+            None => {
+                let (atom, current) = upgrade(line)?;
+                let (name, new_version) = split_atom(atom)?;
+                (name, current.unwrap_or("unknown"), new_version)
+            }
+        };
+        Some(PackageUpdate::new(name, current_version, new_version))
+    }
+
+    /// Only upgrades are read; new dependencies, reinstalls and downgrades
+    /// are listed too.
+    fn is_noise(&self, line: &str) -> bool {
+        !line.starts_with("[U] ") && upgrade(line).is_none()
+    }
+
+    fn update_command(&self, privilege: PrivilegeEscalation) -> Option<String> {
+        Some(privilege.wrap("emerge -avuDN @world"))
+    }
+
+    /// --oneshot keeps the packages out of the world set.
+    fn packages_update_command(&self, privilege: PrivilegeEscalation, packages: &[String]) -> Option<String> {
+        if packages.is_empty() {
+            return None;
+        }
+        Some(privilege.wrap(&format!("emerge -avu1 {}", packages.join(" "))))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error: blocked packages and needed USE or keyword changes are listed at the end."
+    }
+}
+
+/// The atom and installed version of a Portage merge list entry that
+/// upgrades a package, without slots and repositories.
+fn upgrade(line: &str) -> Option<(&str, Option<&str>)> {
+    let (flags, rest) = line.strip_prefix('[')?.split_once(']')?;
+    let (kind, flags) = flags.split_once(' ')?;
+    if !matches!(kind, "ebuild" | "binary") || !flags.contains('U') {
+        return None;
+    }
+    let mut parts = rest.split_whitespace();
+    let atom = parts.next()?.split(':').next()?;
+    let current = parts.next()
+        .and_then(|part| part.strip_prefix('['))
+        .and_then(|part| part.split([':', ']']).next());
+    Some((atom, current))
+}
+
+/// "category/name-1.2.3-r1" into name and version. Names may contain
+/// "-<digit>" too, but never end with something that looks like a version.
+fn split_atom(atom: &str) -> Option<(&str, &str)> {
+    let index = atom.rmatch_indices('-')
+        .map(|(index, _)| index)
+        .find(|&index| atom[index + 1..].starts_with(|c: char| c.is_ascii_digit()))?;
+    Some((&atom[..index], &atom[index + 1..]))
+}
+
+/// Name, newest installed and best available version of an eix upgrade
+/// line after the "[U] ". With several slots installed, eix lists each.
+fn parse_eix_upgrade(rest: &str) -> Option<(&str, &str, &str)> {
+    // Strips keyword markers like "(~)", the slot and the install date
+    fn version(field: &str) -> Option<&str> {
+        field.trim_start_matches(|c: char| !c.is_ascii_digit()).split(['(', '@']).next()
+    }
+
+    let (name, rest) = rest.split_once(" (")?;
+    let versions = rest.split_once("): ").map(|(versions, _)| versions).or_else(|| rest.strip_suffix(')'))?;
+    let (installed, best) = versions.split_once(" -> ")?;
+    Some((name, version(installed.rsplit(", ").next()?)?, version(best)?))
+}
@@ -0,0 +1,55 @@
+use crate::backend::{self, Backend, Capabilities};
+use crate::package_manager::{CheckMode, PackageUpdate};
+use crate::privilege::PrivilegeEscalation;
+
+/// Solus.
+pub struct Eopkg;
+
+impl Backend for Eopkg {
+    fn name(&self) -> &str {
+        "eopkg"
+    }
+
+    fn detect(&self) -> bool {
+        backend::on_path("eopkg")
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { selective_update: true, needs_privileges: true, ..Capabilities::default() }
+    }
+
+    /// Against the repository index on disk, `eopkg upgrade` refreshes it
+    /// first.
+    fn check_command(&self, _mode: CheckMode) -> Option<(&str, Vec<&str>)> {
+        Some(("eopkg", vec!["list-upgrades", "--no-color"]))
+    }
+
+    /// "name   - summary", without versions
+    fn parse_line(&self, line: &str) -> Option<PackageUpdate> {
+        let (name, _) = line.split_once(" - ")?;
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return None;
+        }
+        Some(PackageUpdate::new(name, "unknown", "unknown"))
+    }
+
+    fn is_noise(&self, line: &str) -> bool {
+        line.starts_with("No packages to upgrade")
+    }
+
+    fn update_command(&self, privilege: PrivilegeEscalation) -> Option<String> {
+        Some(privilege.wrap("eopkg upgrade"))
+    }
+
+    fn packages_update_command(&self, privilege: PrivilegeEscalation, packages: &[String]) -> Option<String> {
+        if packages.is_empty() {
+            return None;
+        }
+        Some(privilege.wrap(&format!("eopkg upgrade {}", packages.join(" "))))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; 'eopkg check' lists broken packages."
+    }
+}
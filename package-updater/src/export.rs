@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::app_id;
+use crate::package_manager::{PackageUpdate, UpdateInfo};
+use crate::sysinfo;
+
+/// Bumped whenever a field of [`StatusSnapshot`] changes meaning or is removed.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Overall update status, in increasing order of urgency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    UpToDate,
+    UpdatesAvailable,
+    /// Strict parsing found check output it didn't recognize, so the count
+    /// may be too low
+    Warning,
+    Error,
+}
+
+/// Applet status in a form other surfaces (dashboards, desktop widgets) can
+/// consume without depending on the applet UI. Fields with a default are
+/// missing from files written before they were added.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusSnapshot {
+    pub schema_version: u32,
+    pub update_count: usize,
+    pub official_updates: usize,
+    pub aur_updates: usize,
+    /// Updates of custom sources and plugins, not part of `official_updates`
+    #[serde(default)]
+    pub custom_updates: usize,
+    /// Updates flagged as security fixes, from any source
+    #[serde(default)]
+    pub security_updates: usize,
+    pub severity: Severity,
+    /// Unix timestamp of the last completed check
+    pub last_check: Option<i64>,
+    pub package_manager: Option<String>,
+    pub error: Option<String>,
+}
+
+impl StatusSnapshot {
+    pub fn new(update_info: &UpdateInfo, severity: Severity) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            update_count: update_info.total_updates,
+            official_updates: update_info.official_updates,
+            aur_updates: update_info.aur_updates,
+            custom_updates: update_info.custom_updates,
+            security_updates: update_info.packages.iter().filter(|p| p.is_security).count(),
+            severity,
+            last_check: None,
+            package_manager: None,
+            error: None,
+        }
+    }
+
+    pub fn last_check(mut self, timestamp: Option<i64>) -> Self {
+        self.last_check = timestamp;
+        self
+    }
+
+    pub fn package_manager(mut self, name: Option<&str>) -> Self {
+        self.package_manager = name.map(str::to_string);
+        self
+    }
+
+    pub fn error(mut self, error: Option<&str>) -> Self {
+        self.error = error.map(str::to_string);
+        self
+    }
+}
+
+fn get_status_path() -> PathBuf {
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+        .unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(runtime_dir).join(format!("{}-status.json", app_id::file_prefix()))
+}
+
+/// The last published snapshot, if there is one and it can be parsed.
+pub fn read() -> Option<StatusSnapshot> {
+    let contents = std::fs::read_to_string(get_status_path()).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Writes the snapshot as JSON, replacing the previous one atomically so
+/// readers never see a partial file.
+pub fn publish(snapshot: &StatusSnapshot) {
+    let path = get_status_path();
+    let tmp_path = path.with_extension("json.tmp");
+
+    let contents = match serde_json::to_string_pretty(snapshot) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("Failed to serialize status snapshot: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = std::fs::write(&tmp_path, contents).and_then(|_| std::fs::rename(&tmp_path, &path)) {
+        eprintln!("Failed to publish status to {}: {}", path.display(), e);
+    }
+}
+
+/// Plain-text list of pending updates for someone who can install them,
+/// e.g. the administrator of a managed machine.
+pub fn text_report(update_info: &UpdateInfo, package_manager: Option<&str>, distro: &str) -> String {
+    let mut report = format!("Pending updates on {} ({})\n", sysinfo::hostname(), distro);
+    report.push_str(&format!("Checked: {}", chrono::Local::now().format("%Y-%m-%d %H:%M")));
+    if let Some(name) = package_manager {
+        report.push_str(&format!(" with {}", name));
+    }
+    report.push_str("\n\n");
+
+    if update_info.packages.is_empty() {
+        report.push_str("The system is up to date.\n");
+    } else {
+        report.push_str(&format!("{} updates available:\n", update_info.total_updates));
+        for package in &update_info.packages {
+            report.push_str(&report_line(package));
+        }
+    }
+
+    if !update_info.ignored.is_empty() {
+        report.push_str("\nExcluded by system policy:\n");
+        for package in &update_info.ignored {
+            report.push_str(&report_line(package));
+        }
+    }
+
+    if !update_info.filtered.is_empty() {
+        report.push_str("\nFrom repositories not counted:\n");
+        for package in &update_info.filtered {
+            report.push_str(&report_line(package));
+        }
+    }
+
+    report
+}
+
+fn report_line(package: &PackageUpdate) -> String {
+    let security = if package.is_security { " (security)" } else { "" };
+    let repository = package.repository.as_ref().map_or(String::new(), |repository| format!(" [{}]", repository));
+    if package.current_version != "unknown" {
+        format!("  {} {} -> {}{}{}\n", package.name, package.current_version, package.new_version, security, repository)
+    } else {
+        format!("  {} -> {}{}{}\n", package.name, package.new_version, security, repository)
+    }
+}
+
+/// Writes the report to the home directory, where it is easy to find and
+/// attach, and returns its path.
+pub fn save_report(report: &str) -> std::io::Result<PathBuf> {
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    let path = PathBuf::from(home).join(format!(
+        "pending-updates-{}.txt",
+        chrono::Local::now().format("%Y-%m-%d")
+    ));
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
@@ -0,0 +1,161 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command as TokioCommand;
+
+use crate::backend::{on_path, Backend, Capabilities, CheckContext};
+use crate::merge::Source;
+use crate::package_manager::PackageUpdate;
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::CommandRunner;
+
+/// A firmware update that fwupd has staged and will install on the next
+/// reboot (e.g. a UEFI capsule).
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct StagedFirmware {
+    pub device: String,
+    pub version: String,
+}
+
+/// Whether fwupd's client is installed, so firmware can be updated.
+pub fn is_available() -> bool {
+    on_path("fwupdmgr")
+}
+
+/// Device firmware, through fwupd.
+pub struct Fwupd;
+
+impl Backend for Fwupd {
+    fn name(&self) -> &str {
+        "fwupd"
+    }
+
+    fn detect(&self) -> bool {
+        is_available()
+    }
+
+    /// fwupd reports display names, not the device IDs it needs to update
+    /// single devices, and authorizes through polkit.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(get_updates(context.runner))
+    }
+
+    fn update_command(&self, _privilege: PrivilegeEscalation) -> Option<String> {
+        Some("fwupdmgr update".to_string())
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; 'fwupdmgr get-devices' shows why a device can't be updated."
+    }
+}
+
+/// `fwupdmgr` exit code for "no updatable devices" and "no updates".
+const NOTHING_TO_DO: i32 = 2;
+
+// FwupdUpdateState::NeedsReboot, serialized as a number by older fwupd releases
+const UPDATE_STATE_NEEDS_REBOOT: u64 = 4;
+
+/// Devices whose firmware update is waiting for a reboot to be applied.
+pub async fn detect_staged() -> Result<Vec<StagedFirmware>> {
+    let output = TokioCommand::new("fwupdmgr")
+        .args(["get-devices", "--json"])
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "fwupdmgr get-devices failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    parse_staged(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_staged(json: &str) -> Result<Vec<StagedFirmware>> {
+    let root: serde_json::Value = serde_json::from_str(json)?;
+    let devices = root
+        .get("Devices")
+        .and_then(|d| d.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    let staged = devices
+        .iter()
+        .filter(|device| {
+            let flagged = device
+                .get("Flags")
+                .and_then(|f| f.as_array())
+                .is_some_and(|flags| flags.iter().any(|f| f.as_str() == Some("needs-reboot")));
+
+            let state = device.get("UpdateState");
+            let pending = state.and_then(|s| s.as_str()) == Some("needs-reboot")
+                || state.and_then(|s| s.as_u64()) == Some(UPDATE_STATE_NEEDS_REBOOT);
+
+            flagged || pending
+        })
+        .map(|device| StagedFirmware {
+            device: device
+                .get("Name")
+                .and_then(|n| n.as_str())
+                .unwrap_or("Unknown device")
+                .to_string(),
+            version: device
+                .get("UpdateVersion")
+                .or_else(|| device.get("Version"))
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown")
+                .to_string(),
+        })
+        .collect();
+
+    Ok(staged)
+}
+
+/// Devices with a newer firmware release from an enabled remote.
+pub async fn get_updates(runner: &dyn CommandRunner) -> Result<Vec<PackageUpdate>> {
+    let output = runner.run("fwupdmgr", &["get-updates", "--json"]).await?;
+    match output.code {
+        Some(0) => parse_updates(&output.stdout),
+        Some(NOTHING_TO_DO) => Ok(Vec::new()),
+        code => Err(anyhow!("Failed to check for updates (exit {}): {}", code.unwrap_or(-1), output.stderr)),
+    }
+}
+
+/// Devices in `fwupdmgr get-updates --json` output, with the newest release
+/// offered for each (listed first). Releases that fix known issues (CVEs)
+/// count as security updates.
+fn parse_updates(json: &str) -> Result<Vec<PackageUpdate>> {
+    let root: serde_json::Value = serde_json::from_str(json)?;
+    let devices = root
+        .get("Devices")
+        .and_then(|d| d.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    let updates = devices
+        .iter()
+        .filter_map(|device| {
+            let release = device.get("Releases")?.as_array()?.first()?;
+            let text = |value: &serde_json::Value, key: &str| value.get(key).and_then(|v| v.as_str()).map(str::to_string);
+            Some(PackageUpdate {
+                name: text(device, "Name").unwrap_or_else(|| "Unknown device".to_string()),
+                current_version: text(device, "Version").unwrap_or_else(|| "unknown".to_string()),
+                new_version: text(release, "Version")?,
+                is_security: release
+                    .get("Issues")
+                    .and_then(|issues| issues.as_array())
+                    .is_some_and(|issues| !issues.is_empty()),
+                repository: text(release, "RemoteId"),
+                source: Source::Firmware,
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    Ok(updates)
+}
@@ -0,0 +1,83 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+
+use crate::backend::{self, Backend, Capabilities, CheckContext};
+use crate::merge::Source;
+use crate::package_manager::{CheckMode, FlatpakInstallation, PackageUpdate};
+use crate::privilege::PrivilegeEscalation;
+
+/// Flatpak applications and runtimes, of both installations.
+pub struct Flatpak;
+
+impl Backend for Flatpak {
+    fn name(&self) -> &str {
+        "flatpak"
+    }
+
+    fn detect(&self) -> bool {
+        backend::on_path("flatpak")
+    }
+
+    /// Flatpak reports display names, not the application IDs it needs to
+    /// update single ones, and updates user installations without
+    /// administrator rights.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { metadata_refresh: true, ..Capabilities::default() }
+    }
+
+    /// Both installations, each with its own remotes. A failing one is
+    /// skipped; the check fails only if neither could be checked.
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(async move {
+            let checks = FlatpakInstallation::ALL.map(|installation| async move {
+                // Pin the columns, the default set differs between versions
+                let mut args = vec!["remote-ls", "--updates", installation.flag(), "--columns=name,application,version,branch"];
+                if context.mode == CheckMode::Quick {
+                    args.push("--cached");
+                }
+                (installation, context.listing(self, "flatpak", &args).await)
+            });
+
+            let mut updates = Vec::new();
+            let mut failed = Vec::new();
+            for (installation, result) in futures::future::join_all(checks).await {
+                match result {
+                    Ok(packages) => updates.extend(packages.into_iter().map(|package| PackageUpdate {
+                        source: Source::Flatpak(installation),
+                        ..package
+                    })),
+                    Err(e) => failed.push(format!("{} installation: {}", installation.name(), e)),
+                }
+            }
+
+            if failed.len() == FlatpakInstallation::ALL.len() {
+                return Err(anyhow!("{}", failed.join("; ")));
+            }
+            for failure in &failed {
+                eprintln!("Failed to check the Flatpak {}", failure);
+            }
+            Ok(updates)
+        })
+    }
+
+    /// "name\tapp-id\tversion\tbranch"
+    fn parse_line(&self, line: &str) -> Option<PackageUpdate> {
+        let parts: Vec<&str> = line.split('\t').collect();
+        if parts.len() < 3 {
+            return None;
+        }
+        Some(PackageUpdate::new(parts[0], "unknown", parts[2]))
+    }
+
+    /// One installation after the other, so a failure names its scope.
+    fn update_command(&self, _privilege: PrivilegeEscalation) -> Option<String> {
+        Some(FlatpakInstallation::ALL.iter()
+            .map(|installation| installation.update_command())
+            .collect::<Vec<_>>()
+            .join(" && "))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; 'flatpak repair --user' or 'flatpak repair --system' fixes a damaged installation."
+    }
+}
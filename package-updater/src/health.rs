@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Consecutive failed checks after which a source is marked as broken.
+const REPEATED_FAILURES: u32 = 3;
+
+/// Check durations kept per source.
+const KEPT_DURATIONS: usize = 5;
+
+/// How long a source's check may take before it counts as slow, and in how
+/// many checks in a row before the settings warn about it.
+const SLOW_CHECK: Duration = Duration::from_secs(20);
+const REPEATED_SLOW_CHECKS: usize = 3;
+
+/// Source name of the AUR, which is checked through the package manager's
+/// helper but fails on its own (RPC blocked, rate limited).
+pub const AUR_SOURCE: &str = "AUR";
+
+/// Outcome of one source in one check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceCheck {
+    /// Package manager name, or [`AUR_SOURCE`]
+    pub source: String,
+    pub error: Option<String>,
+    /// How long the source's check took, retry included
+    pub duration: Option<Duration>,
+}
+
+impl SourceCheck {
+    pub fn new(source: &str, result: Result<(), String>) -> Self {
+        Self { source: source.to_string(), error: result.err(), duration: None }
+    }
+
+    pub fn took(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+}
+
+/// When a source last worked and last failed, kept across restarts.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default)]
+pub struct SourceHealth {
+    /// Unix timestamps
+    pub last_success: Option<i64>,
+    pub last_failure: Option<i64>,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    /// Durations of the last checks in milliseconds, oldest first
+    pub recent_durations_ms: Vec<u64>,
+}
+
+impl SourceHealth {
+    pub fn record(&mut self, error: Option<&str>, now: i64) {
+        match error {
+            None => {
+                self.last_success = Some(now);
+                self.consecutive_failures = 0;
+            }
+            Some(error) => {
+                self.last_failure = Some(now);
+                self.consecutive_failures += 1;
+                self.last_error = Some(error.to_string());
+            }
+        }
+    }
+
+    pub fn is_failing_repeatedly(&self) -> bool {
+        self.consecutive_failures >= REPEATED_FAILURES
+    }
+
+    pub fn record_duration(&mut self, duration: Duration) {
+        self.recent_durations_ms.push(duration.as_millis().try_into().unwrap_or(u64::MAX));
+        let excess = self.recent_durations_ms.len().saturating_sub(KEPT_DURATIONS);
+        self.recent_durations_ms.drain(..excess);
+    }
+
+    pub fn last_duration(&self) -> Option<Duration> {
+        self.recent_durations_ms.last().map(|&ms| Duration::from_millis(ms))
+    }
+
+    /// Whether the last few checks all took long, which points at a slow
+    /// mirror rather than a one-off.
+    pub fn is_consistently_slow(&self) -> bool {
+        self.recent_durations_ms.len() >= REPEATED_SLOW_CHECKS
+            && self.recent_durations_ms.iter()
+                .rev()
+                .take(REPEATED_SLOW_CHECKS)
+                .all(|&ms| Duration::from_millis(ms) >= SLOW_CHECK)
+    }
+}
+
+/// "1.2 s", or "850 ms" below a second.
+pub fn format_duration(duration: Duration) -> String {
+    if duration < Duration::from_secs(1) {
+        format!("{} ms", duration.as_millis())
+    } else {
+        format!("{:.1} s", duration.as_secs_f64())
+    }
+}
+
+pub fn record_all(health: &mut BTreeMap<String, SourceHealth>, checks: &[SourceCheck], now: i64) {
+    for check in checks {
+        let entry = health.entry(check.source.clone()).or_default();
+        entry.record(check.error.as_deref(), now);
+        if let Some(duration) = check.duration {
+            entry.record_duration(duration);
+        }
+    }
+}
+
+/// Records a check's failing repositories, and a success for the tracked
+/// repositories it did not name when the check got through.
+pub fn record_repositories(health: &mut BTreeMap<String, SourceHealth>, failing: &[SourceCheck], check_succeeded: bool, now: i64) {
+    record_all(health, failing, now);
+    if check_succeeded {
+        for (repository, entry) in health.iter_mut() {
+            if !failing.iter().any(|check| &check.source == repository) {
+                entry.record(None, now);
+            }
+        }
+    }
+}
+
+/// Repositories the error output of a check names as failing to download,
+/// with the first line naming each. Covers the English messages of pacman,
+/// dnf, zypper and flatpak; apt's simulation downloads nothing.
+pub fn failing_repositories(output: &str) -> Vec<SourceCheck> {
+    let mut failing: Vec<SourceCheck> = Vec::new();
+    for line in output.lines().map(str::trim) {
+        let Some(repository) = failing_repository(line) else {
+            continue;
+        };
+        if !failing.iter().any(|check| check.source == repository) {
+            failing.push(SourceCheck::new(repository, Err(line.to_string())));
+        }
+    }
+    failing
+}
+
+fn failing_repository(line: &str) -> Option<&str> {
+    // pacman: "error: failed retrieving file 'chaotic-aur.db' from <mirror> : ..."
+    if let Some(file) = quoted_after(line, "failed retrieving file ") {
+        return file.strip_suffix(".db").or_else(|| file.strip_suffix(".files"));
+    }
+    // pacman: "error: failed to update chaotic-aur (download library error)"
+    if let Some(rest) = line.strip_prefix("error: failed to update ") {
+        return rest.split_whitespace().next();
+    }
+    // flatpak: "error: Unable to load summary from remote flathub: ..."
+    for marker in ["Unable to load summary from remote ", "Unable to load metadata from remote "] {
+        if let Some(rest) = line.split_once(marker).map(|(_, rest)| rest) {
+            return rest.split([':', ' ']).next().filter(|name| !name.is_empty());
+        }
+    }
+    // dnf: "Errors during downloading metadata for repository 'X':",
+    // "Failed to download metadata for repo 'X': ...", dnf5's
+    // "Failed to download metadata (metalink: ...) for repository \"X\""
+    if line.contains("metadata") || line.contains("repomd.xml") {
+        if let Some(name) = quoted_after(line, "repository ").or_else(|| quoted_after(line, "repo ")) {
+            return Some(name);
+        }
+    }
+    // zypper: "Repository 'X' is invalid.", "Problem retrieving files from 'X'."
+    if line.contains("is invalid") {
+        return quoted_after(line, "Repository ");
+    }
+    quoted_after(line, "Problem retrieving files from ")
+}
+
+/// The single- or double-quoted name right after the marker.
+fn quoted_after<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = line.split_once(marker)?.1;
+    let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let rest = &rest[1..];
+    rest.split_once(quote).map(|(name, _)| name).filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_sources_failing_repeatedly() {
+        let mut health = BTreeMap::new();
+        let failing = [SourceCheck::new("pacman", Ok(())), SourceCheck::new(AUR_SOURCE, Err("HTTP 403".to_string()))];
+        for now in 1..=REPEATED_FAILURES as i64 {
+            record_all(&mut health, &failing, now);
+        }
+
+        let aur = &health[AUR_SOURCE];
+        assert!(aur.is_failing_repeatedly());
+        assert_eq!((aur.last_success, aur.last_failure), (None, Some(3)));
+        assert_eq!(aur.last_error.as_deref(), Some("HTTP 403"));
+        assert_eq!(health["pacman"].last_success, Some(3));
+
+        record_all(&mut health, &[SourceCheck::new(AUR_SOURCE, Ok(()))], 4);
+        let aur = &health[AUR_SOURCE];
+        assert!(!aur.is_failing_repeatedly());
+        // The last failure stays visible after recovering
+        assert_eq!((aur.last_success, aur.last_failure), (Some(4), Some(3)));
+    }
+
+    #[test]
+    fn warns_about_sources_slow_in_a_row() {
+        let mut health = BTreeMap::new();
+        let check = |secs| [SourceCheck::new("flatpak", Ok(())).took(Duration::from_secs(secs))];
+        for (now, secs) in [(1, 2), (2, 25), (3, 31)] {
+            record_all(&mut health, &check(secs), now);
+        }
+        assert!(!health["flatpak"].is_consistently_slow());
+
+        for now in 4..=8 {
+            record_all(&mut health, &check(40), now);
+        }
+        let flatpak = &health["flatpak"];
+        assert!(flatpak.is_consistently_slow());
+        assert_eq!(flatpak.recent_durations_ms.len(), KEPT_DURATIONS);
+        assert_eq!(format_duration(flatpak.last_duration().unwrap_or_default()), "40.0 s");
+        assert_eq!(format_duration(Duration::from_millis(850)), "850 ms");
+    }
+
+    #[test]
+    fn finds_failing_repositories() {
+        let sources = |output: &str| -> Vec<String> {
+            failing_repositories(output).into_iter().map(|check| check.source).collect()
+        };
+        let pacman = "error: failed retrieving file 'chaotic-aur.db' from cdn-mirror.chaotic.cx : The requested URL returned error: 404\n\
+                      error: failed retrieving file 'chaotic-aur.db' from geo-mirror.chaotic.cx : Could not resolve host\n\
+                      error: failed to update chaotic-aur (download library error)\n\
+                      error: failed to synchronize all databases (failed to retrieve some files)\n";
+        assert_eq!(sources(pacman), ["chaotic-aur"]);
+        let dnf = "Errors during downloading metadata for repository 'copr:copr.fedorainfracloud.org:user:gone':\n\
+                   \x20 - Status code: 404 for https://download.copr.fedorainfracloud.org/results/user/gone/repodata/repomd.xml\n\
+                   Error: Failed to download metadata for repo 'copr:copr.fedorainfracloud.org:user:gone': Cannot download repomd.xml\n\
+                   Failed to download metadata (metalink: \"https://mirrors.rpmfusion.org/metalink\") for repository \"rpmfusion-free\"\n";
+        assert_eq!(sources(dnf), ["copr:copr.fedorainfracloud.org:user:gone", "rpmfusion-free"]);
+        let zypper = "Problem retrieving files from 'Packman Repository'.\nRepository 'Packman Repository' is invalid.\n\
+                      Skipping repository 'Packman Repository' because of the above error.\nRepository 'OBS' is invalid.\n";
+        assert_eq!(sources(zypper), ["Packman Repository", "OBS"]);
+        assert_eq!(sources("error: Unable to load summary from remote flathub-beta: Server returned status 503\n"), ["flathub-beta"]);
+        assert!(sources("E: Unable to locate package foo\nerror: target not found: bar\n").is_empty());
+
+        let mut health = BTreeMap::new();
+        record_repositories(&mut health, &failing_repositories(pacman), false, 1);
+        record_repositories(&mut health, &[], false, 2);
+        assert_eq!(health["chaotic-aur"].consecutive_failures, 1);
+        record_repositories(&mut health, &[], true, 3);
+        assert_eq!(health["chaotic-aur"].last_success, Some(3));
+    }
+}
@@ -0,0 +1,247 @@
+use serde::{Deserialize, Serialize};
+
+/// An available update the package manager won't install, and why.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeldBack {
+    pub name: String,
+    pub reason: String,
+}
+
+impl HeldBack {
+    pub fn new(name: &str, reason: impl Into<String>) -> Self {
+        Self { name: name.to_string(), reason: reason.into() }
+    }
+}
+
+pub const PHASED_REASON: &str = "phased update, not offered to this machine yet";
+pub const HOLD_REASON: &str = "held with apt-mark hold";
+pub const RESOLVER_REASON: &str = "kept back by the resolver";
+
+/// Packages apt's `dist-upgrade` simulation keeps back, split into those
+/// deferred by phased updates (Ubuntu) and the rest. Needs `LC_ALL=C`
+/// output, the section headers are translated.
+pub fn parse_apt_kept_back(output: &str) -> (Vec<String>, Vec<String>) {
+    let kept_back = section_words(output, "The following packages have been kept back:");
+    let phased = section_words(output, "The following upgrades have been deferred due to phasing:");
+    (kept_back, phased)
+}
+
+/// Explains why the packages of an `apt-get -s install <packages>`
+/// simulation can't be installed: their unmet dependencies, or the
+/// packages installing them would remove.
+pub fn parse_apt_install(output: &str, packages: &[String]) -> Vec<HeldBack> {
+    let mut reasons: Vec<(String, Vec<String>)> = Vec::new();
+    let mut in_unmet = false;
+
+    for line in output.lines() {
+        if line.starts_with("The following packages have unmet dependencies:") {
+            in_unmet = true;
+            continue;
+        }
+        if !in_unmet {
+            continue;
+        }
+        if !line.starts_with(' ') {
+            break;
+        }
+
+        // " foo : Depends: libfoo2 (>= 2.0) but it is not going to be installed"
+        // with further relations of the same package indented below
+        let relation = match line.split_once(" : ") {
+            Some((name, relation)) => {
+                reasons.push((name.trim().to_string(), Vec::new()));
+                relation
+            }
+            None => line,
+        };
+        if let Some((_, relations)) = reasons.last_mut() {
+            relations.push(describe_relation(relation.trim()));
+        }
+    }
+
+    let removed = section_words(output, "The following packages will be REMOVED:");
+    packages.iter()
+        .map(|name| match reasons.iter().find(|(package, _)| package == name) {
+            Some((_, relations)) => HeldBack::new(name, relations.join("; ")),
+            None if !removed.is_empty() => HeldBack::new(name, format!("would remove {}", removed.join(", "))),
+            None => HeldBack::new(name, RESOLVER_REASON),
+        })
+        .collect()
+}
+
+fn describe_relation(relation: &str) -> String {
+    let Some((kind, rest)) = relation.split_once(": ") else {
+        return relation.to_string();
+    };
+    let verb = match kind {
+        "Depends" | "PreDepends" => "requires",
+        "Breaks" => "breaks",
+        "Conflicts" => "conflicts with",
+        _ => return relation.to_string(),
+    };
+    format!("{} {}", verb, rest)
+}
+
+/// Problems `dnf upgrade --best --assumeno` reports for update candidates
+/// it can't install, matched to the names of the pending `packages`.
+pub fn parse_dnf_problems(output: &str, packages: &[&str]) -> Vec<HeldBack> {
+    const CANDIDATE: &str = "cannot install the best update candidate for package ";
+    let mut held_back = Vec::new();
+    let mut current: Option<&str> = None;
+
+    for line in output.lines().map(str::trim) {
+        if line.starts_with("Problem") {
+            // "Problem 1: cannot install the best update candidate for package foo-1.0-1.fc40.x86_64"
+            current = line.split_once(CANDIDATE).and_then(|(_, nevra)| package_name(nevra, packages));
+        } else if let (Some(name), Some(reason)) = (current, line.strip_prefix("- ")) {
+            if !reason.starts_with("cannot install") {
+                held_back.push(HeldBack::new(name, reason));
+                current = None;
+            }
+        }
+    }
+
+    held_back
+}
+
+/// The longest package name `nevra` ("name-version-release.arch") starts with.
+fn package_name<'a>(nevra: &str, packages: &[&'a str]) -> Option<&'a str> {
+    packages.iter()
+        .copied()
+        .filter(|name| nevra.strip_prefix(name).is_some_and(|rest| rest.starts_with('-')))
+        .max_by_key(|name| name.len())
+}
+
+/// The `excludepkgs` patterns of a dumped dnf main configuration
+/// ("excludepkgs = kernel*, firefox"); `exclude` is the older name.
+pub fn parse_dnf_excludes(output: &str) -> Vec<String> {
+    output.lines()
+        .filter_map(|line| line.split_once('='))
+        .filter(|(key, _)| matches!(key.trim(), "excludepkgs" | "exclude"))
+        .flat_map(|(_, value)| value.split([',', ' ']))
+        .filter(|pattern| !pattern.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Shell-style match with `*` and `?`, as dnf matches its exclude patterns.
+pub fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position after the last '*' and the name position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, tried)) => {
+                    p = star;
+                    n = tried + 1;
+                    backtrack = Some((star, tried + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Packages of an `apt-cache policy <packages>` listing whose candidate is
+/// the installed version: a pin keeps them from being upgraded.
+pub fn parse_apt_pinned(output: &str) -> Vec<String> {
+    let mut pinned = Vec::new();
+    let mut package: Option<&str> = None;
+    let mut installed: Option<&str> = None;
+
+    for line in output.lines() {
+        if let Some(name) = line.strip_suffix(':').filter(|_| !line.starts_with(' ')) {
+            package = Some(name);
+            installed = None;
+        } else if let Some(version) = line.trim().strip_prefix("Installed: ") {
+            installed = Some(version).filter(|version| *version != "(none)");
+        } else if let Some(candidate) = line.trim().strip_prefix("Candidate: ") {
+            if let (Some(name), Some(installed)) = (package, installed) {
+                if installed == candidate {
+                    pinned.push(name.to_string());
+                }
+            }
+        }
+    }
+
+    pinned
+}
+
+/// Package names listed in the indented lines below `header`.
+fn section_words(output: &str, header: &str) -> Vec<String> {
+    output.lines()
+        .skip_while(|line| !line.starts_with(header))
+        .skip(1)
+        .take_while(|line| line.starts_with(' '))
+        .flat_map(str::split_whitespace)
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_apt_kept_back_and_phased_packages() {
+        let (kept_back, phased) = parse_apt_kept_back(include_str!("../tests/fixtures/apt/simulate_dist_upgrade_kept_back.txt"));
+        assert_eq!(kept_back, vec!["libfoo-tools", "mesa-vulkan-drivers"]);
+        assert_eq!(phased, vec!["gnome-shell"]);
+    }
+
+    #[test]
+    fn explains_apt_unmet_dependencies() {
+        let packages = vec!["libfoo-tools".to_string(), "mesa-vulkan-drivers".to_string()];
+        let held_back = parse_apt_install(include_str!("../tests/fixtures/apt/simulate_install_unmet.txt"), &packages);
+        assert_eq!(held_back, vec![
+            HeldBack::new("libfoo-tools", "requires libfoo2 (>= 2.0) but it is not going to be installed"),
+            HeldBack::new(
+                "mesa-vulkan-drivers",
+                "requires libdrm2 (>= 2.4.120) but 2.4.113-2 is to be installed; breaks mesa-va-drivers (< 24.0)",
+            ),
+        ]);
+
+        let removing = "The following packages will be REMOVED:\n  libfoo1\nThe following packages will be upgraded:\n  libfoo-tools\n";
+        assert_eq!(parse_apt_install(removing, &packages[..1]), vec![HeldBack::new("libfoo-tools", "would remove libfoo1")]);
+    }
+
+    #[test]
+    fn finds_excluded_packages() {
+        let excludes = parse_dnf_excludes("exclude = \nexcludepkgs = kernel*, firefox\ninstallonly_limit = 3\n");
+        assert_eq!(excludes, vec!["kernel*", "firefox"]);
+        assert!(glob_matches("kernel*", "kernel-core"));
+        assert!(glob_matches("*-devel", "glibc-devel"));
+        assert!(glob_matches("python3.1?", "python3.12"));
+        assert!(!glob_matches("kernel*", "akmod-nvidia"));
+        assert!(!glob_matches("firefox", "firefox-langpacks"));
+
+        let policy = "firefox:\n  Installed: 119.0\n  Candidate: 119.0\n  Version table:\n     120.0 -1\n\
+                      gimp:\n  Installed: 2.10.34-1\n  Candidate: 2.10.36-1\n";
+        assert_eq!(parse_apt_pinned(policy), vec!["firefox"]);
+    }
+
+    #[test]
+    fn explains_dnf_problems() {
+        let held_back = parse_dnf_problems(
+            include_str!("../tests/fixtures/dnf/upgrade_best_problems.txt"),
+            &["foo", "foo-libs", "bar"],
+        );
+        assert_eq!(held_back, vec![
+            HeldBack::new("foo-libs", "nothing provides libbar.so.3()(64bit) needed by foo-libs-2.0-1.fc40.x86_64 from updates"),
+            HeldBack::new("bar", "package bar-3.1-1.fc40.x86_64 from updates requires baz >= 2, but none of the providers can be installed"),
+        ]);
+    }
+}
@@ -0,0 +1,353 @@
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::package_manager::{is_kernel_package, PackageUpdate, UpdateInfo};
+use crate::risk::is_core_package;
+use crate::state;
+
+pub const WEEK_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// A package that was upgraded, by an update run started from the applet
+/// or outside of it.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct InstalledPackage {
+    pub name: String,
+    pub from_version: String,
+    pub to_version: String,
+    #[serde(default)]
+    pub is_security: bool,
+}
+
+impl From<&PackageUpdate> for InstalledPackage {
+    fn from(package: &PackageUpdate) -> Self {
+        Self {
+            name: package.name.clone(),
+            from_version: package.current_version.clone(),
+            to_version: package.new_version.clone(),
+            is_security: package.is_security,
+        }
+    }
+}
+
+/// One completed update run.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct HistoryEntry {
+    /// Unix timestamp (seconds) of when the run finished
+    pub timestamp: i64,
+    pub package_manager: String,
+    pub packages: Vec<InstalledPackage>,
+    /// Installed outside the applet (unattended upgrades, Flatpak's
+    /// automatic updates, a terminal), noticed when a check no longer
+    /// listed the updates
+    #[serde(default)]
+    pub unattended: bool,
+}
+
+/// What an update run did, found by comparing the updates it was started
+/// for with the ones still pending afterwards.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct RunOutcome {
+    pub installed: Vec<InstalledPackage>,
+    /// Updates still pending at the same version
+    pub failed: Vec<PackageUpdate>,
+    /// A kernel or core component was upgraded
+    pub reboot_recommended: bool,
+}
+
+impl RunOutcome {
+    pub fn compare(before: &[PackageUpdate], after: &[PackageUpdate]) -> Self {
+        let mut outcome = Self::default();
+        for old in before {
+            // Anything no longer pending at the same version was installed
+            if after.iter().any(|new| new.name == old.name && new.new_version == old.new_version) {
+                outcome.failed.push(old.clone());
+            } else {
+                outcome.reboot_recommended |= is_kernel_package(&old.name) || is_core_package(&old.name);
+                outcome.installed.push(InstalledPackage::from(old));
+            }
+        }
+        outcome
+    }
+
+    /// Drops failed updates that a later check no longer lists at the
+    /// same version.
+    pub fn retain_pending(&mut self, pending: &[PackageUpdate]) {
+        self.failed.retain(|old| pending.iter().any(|new| new.name == old.name && new.new_version == old.new_version));
+    }
+
+    pub fn is_failed(&self, package: &PackageUpdate) -> bool {
+        self.failed.iter().any(|old| old.name == package.name && old.new_version == package.new_version)
+    }
+
+    pub fn describe(&self) -> String {
+        let mut parts = vec![format!(
+            "Upgraded {} package{}",
+            self.installed.len(),
+            if self.installed.len() == 1 { "" } else { "s" },
+        )];
+        if !self.failed.is_empty() {
+            parts.push(format!("{} failed", self.failed.len()));
+        }
+        if self.reboot_recommended {
+            parts.push("reboot recommended".to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+/// Count changes kept in the state file.
+pub const MAX_COUNT_CHANGES: usize = 10;
+
+/// How the pending updates changed between two checks, kept to explain the
+/// count in the panel.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default)]
+pub struct CountChange {
+    /// Unix timestamp of the later check
+    pub timestamp: i64,
+    pub from: usize,
+    pub to: usize,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// The later check followed an update run of the applet, which
+    /// installed the removed ones
+    pub after_update: bool,
+}
+
+impl CountChange {
+    /// The change between two checks, None when they list the same updates.
+    pub fn compare(before: &[PackageUpdate], after: &[PackageUpdate], after_update: bool, timestamp: i64) -> Option<Self> {
+        let names = |packages: &[PackageUpdate], others: &[PackageUpdate]| -> Vec<String> {
+            packages.iter()
+                .filter(|package| !others.iter().any(|other| other.name == package.name))
+                .map(|package| package.name.clone())
+                .collect()
+        };
+        let (added, removed) = (names(after, before), names(before, after));
+        if added.is_empty() && removed.is_empty() {
+            return None;
+        }
+        Some(Self { timestamp, from: before.len(), to: after.len(), added, removed, after_update })
+    }
+
+    /// E.g. "+3 new: mesa, vulkan-radeon, linux-firmware; −1 updated elsewhere: vim"
+    pub fn describe(&self) -> String {
+        let list = |names: &[String]| {
+            let mut shown = names.iter().take(5).cloned().collect::<Vec<_>>().join(", ");
+            if names.len() > 5 {
+                shown.push_str(", …");
+            }
+            shown
+        };
+        let mut parts = Vec::new();
+        if !self.added.is_empty() {
+            parts.push(format!("+{} new: {}", self.added.len(), list(&self.added)));
+        }
+        if !self.removed.is_empty() {
+            let reason = if self.after_update { "installed" } else { "updated elsewhere" };
+            parts.push(format!("−{} {}: {}", self.removed.len(), reason, list(&self.removed)));
+        }
+        parts.join("; ")
+    }
+}
+
+/// Updates of the previous check that the new one doesn't list at all, not
+/// even as ignored, filtered or held back: installed by something other
+/// than the applet. An update listed at another version is still pending.
+pub fn installed_outside(before: &UpdateInfo, after: &UpdateInfo) -> Vec<InstalledPackage> {
+    let listed = |name: &str| {
+        after.packages.iter().chain(&after.ignored).chain(&after.filtered).any(|p| p.name == name)
+            || after.held_back.iter().any(|held| held.name == name)
+    };
+    before.packages.iter()
+        .filter(|package| !listed(&package.name))
+        .map(InstalledPackage::from)
+        .collect()
+}
+
+/// Packages installed outside the applet since its last update run, or
+/// since the user reviewed them, once per name from the oldest version to
+/// the newest.
+pub fn changes_since_last_update(entries: &[HistoryEntry], reviewed_at: Option<i64>) -> Vec<InstalledPackage> {
+    let last_update = entries.iter().rev().find(|entry| !entry.unattended).map(|entry| entry.timestamp);
+    let since = last_update.max(reviewed_at);
+
+    let mut changes: Vec<InstalledPackage> = Vec::new();
+    for entry in entries.iter().filter(|entry| entry.unattended && since.map_or(true, |since| entry.timestamp > since)) {
+        for package in &entry.packages {
+            match changes.iter_mut().find(|change| change.name == package.name) {
+                Some(change) => {
+                    change.to_version = package.to_version.clone();
+                    change.is_security |= package.is_security;
+                }
+                None => changes.push(package.clone()),
+            }
+        }
+    }
+    changes
+}
+
+/// Aggregated counts over a range of history entries.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub packages: usize,
+    pub security: usize,
+    pub kernels: usize,
+}
+
+impl Summary {
+    pub fn since(entries: &[HistoryEntry], since_timestamp: i64) -> Self {
+        let mut summary = Self::default();
+
+        for package in entries
+            .iter()
+            .filter(|entry| entry.timestamp >= since_timestamp)
+            .flat_map(|entry| entry.packages.iter())
+        {
+            summary.packages += 1;
+            if package.is_security {
+                summary.security += 1;
+            }
+            if is_kernel_package(&package.name) {
+                summary.kernels += 1;
+            }
+        }
+
+        summary
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{} package{} updated, {} security fix{}, {} kernel{}",
+            self.packages,
+            if self.packages == 1 { "" } else { "s" },
+            self.security,
+            if self.security == 1 { "" } else { "es" },
+            self.kernels,
+            if self.kernels == 1 { "" } else { "s" },
+        )
+    }
+}
+
+fn get_history_path() -> PathBuf {
+    state::state_dir().join("history.jsonl")
+}
+
+pub fn format_timestamp(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|dt| dt.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "unknown time".to_string())
+}
+
+/// Reads the history log, oldest entry first. Unreadable lines are skipped.
+pub fn load() -> Vec<HistoryEntry> {
+    std::fs::read_to_string(get_history_path())
+        .map(|contents| {
+            contents
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn append(entry: &HistoryEntry) {
+    let path = get_history_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+
+    let line = match serde_json::to_string(entry) {
+        Ok(line) => line,
+        Err(e) => {
+            eprintln!("Failed to serialize history entry: {}", e);
+            return;
+        }
+    };
+
+    match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", line) {
+                eprintln!("Failed to write history log {}: {}", path.display(), e);
+            }
+        }
+        Err(e) => eprintln!("Failed to open history log {}: {}", path.display(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, new_version: &str) -> PackageUpdate {
+        PackageUpdate::new(name, "1.0", new_version)
+    }
+
+    #[test]
+    fn explains_count_changes_between_checks() {
+        let before = [package("vim", "9.1"), package("mesa", "24.1")];
+        let after = [package("mesa", "24.2"), package("vulkan-radeon", "24.2"), package("linux-firmware", "20240909")];
+
+        let change = CountChange::compare(&before, &after, false, 0).expect("changed");
+        assert_eq!((change.from, change.to), (2, 3));
+        assert_eq!(change.describe(), "+2 new: vulkan-radeon, linux-firmware; −1 updated elsewhere: vim");
+        assert!(CountChange::compare(&after, &after, false, 0).is_none());
+    }
+
+    #[test]
+    fn compares_pending_updates_before_and_after_run() {
+        let before = [package("linux", "6.9"), package("firefox", "2.0"), package("vim", "9.1")];
+        // vim failed, firefox got a newer version during the run
+        let after = [package("vim", "9.1"), package("firefox", "2.1")];
+
+        let outcome = RunOutcome::compare(&before, &after);
+        assert_eq!(outcome.installed.len(), 2);
+        assert_eq!(outcome.failed, vec![package("vim", "9.1")]);
+        assert!(outcome.reboot_recommended);
+        assert_eq!(outcome.describe(), "Upgraded 2 packages, 1 failed, reboot recommended");
+
+        assert_eq!(RunOutcome::compare(&before[1..2], &[]).describe(), "Upgraded 1 package");
+
+        let mut outcome = outcome;
+        assert!(outcome.is_failed(&package("vim", "9.1")));
+        // A newer version arrived: it is a new update, not the failed one
+        outcome.retain_pending(&[package("vim", "9.2")]);
+        assert!(outcome.failed.is_empty());
+    }
+
+    #[test]
+    fn collects_changes_made_outside_the_applet() {
+        let mut before = UpdateInfo::new();
+        before.packages = vec![package("firefox", "2.0"), package("org.gimp.GIMP", "2.10.38"), package("vim", "9.1")];
+        let mut after = UpdateInfo::new();
+        // vim moved to an excluded repository, firefox has a newer update
+        after.packages = vec![package("firefox", "2.1")];
+        after.filtered = vec![package("vim", "9.1")];
+        let installed = installed_outside(&before, &after);
+        assert_eq!(installed.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), ["org.gimp.GIMP"]);
+
+        let entry = |timestamp: i64, unattended: bool, packages: Vec<InstalledPackage>| HistoryEntry {
+            timestamp,
+            package_manager: "flatpak".to_string(),
+            packages,
+            unattended,
+        };
+        let gimp = |from: &str, to: &str| InstalledPackage {
+            name: "org.gimp.GIMP".to_string(),
+            from_version: from.to_string(),
+            to_version: to.to_string(),
+            is_security: false,
+        };
+        let entries = [
+            entry(100, true, vec![gimp("2.10.34", "2.10.36")]),
+            entry(200, false, installed.clone()),
+            entry(300, true, vec![gimp("2.10.36", "2.10.38")]),
+            entry(400, true, vec![gimp("2.10.38", "3.0.0")]),
+        ];
+        assert_eq!(changes_since_last_update(&entries, None), [gimp("2.10.36", "3.0.0")]);
+        assert!(changes_since_last_update(&entries, Some(400)).is_empty());
+        assert_eq!(changes_since_last_update(&entries[..1], None).len(), 1);
+    }
+}
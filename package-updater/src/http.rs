@@ -0,0 +1,98 @@
+use std::fmt;
+
+use tokio::process::Command as TokioCommand;
+
+/// Variables curl reads for HTTPS requests, in the order it prefers them.
+const PROXY_VARIABLES: [&str; 4] = ["https_proxy", "HTTPS_PROXY", "all_proxy", "ALL_PROXY"];
+
+/// curl exit codes that can only come from the proxy: it could not be
+/// resolved (5) or the handshake with it failed (97).
+const PROXY_EXIT_CODES: [i32; 2] = [5, 97];
+
+/// A failed request, with proxy problems kept apart from other network
+/// errors since they are fixed in a different place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpError {
+    Proxy(String),
+    Network(String),
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::Proxy(message) => write!(f, "proxy error: {}", message),
+            HttpError::Network(message) => write!(f, "network error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+/// A curl invocation for the applet's own requests. An empty `proxy` leaves
+/// curl to the proxy environment variables (`https_proxy`, `no_proxy`, ...).
+pub fn curl(proxy: &str) -> TokioCommand {
+    let mut command = base_command(proxy);
+    command.arg("-f");
+    command
+}
+
+/// Response headers of a HEAD request, whatever its status code. The
+/// certificate is not verified, so only use this for harmless headers.
+pub async fn head_insecure(url: &str, proxy: &str) -> Result<String, HttpError> {
+    let headers = send(base_command(proxy).args(["-I", "--insecure", url])).await?;
+    Ok(String::from_utf8_lossy(&headers).into_owned())
+}
+
+fn base_command(proxy: &str) -> TokioCommand {
+    let mut command = TokioCommand::new("curl");
+    command.args(["-sS", "--max-time", "15"]);
+    if !proxy.is_empty() {
+        command.args(["--proxy", proxy]);
+    }
+    command
+}
+
+/// Runs the request and returns the response body.
+pub async fn send(command: &mut TokioCommand) -> Result<Vec<u8>, HttpError> {
+    let output = command.output().await
+        .map_err(|e| HttpError::Network(format!("failed to run curl: {}", e)))?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(classify(output.status.code(), &String::from_utf8_lossy(&output.stderr)))
+    }
+}
+
+fn classify(exit_code: Option<i32>, stderr: &str) -> HttpError {
+    let message = stderr.trim().to_string();
+    // e.g. "Received HTTP code 407 from proxy after CONNECT"
+    if exit_code.is_some_and(|code| PROXY_EXIT_CODES.contains(&code)) || message.to_lowercase().contains("proxy") {
+        HttpError::Proxy(message)
+    } else {
+        HttpError::Network(message)
+    }
+}
+
+/// The proxy curl picks up from the environment for HTTPS requests.
+pub fn environment_proxy() -> Option<String> {
+    PROXY_VARIABLES.iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proxy_failures_are_told_apart() {
+        assert!(matches!(classify(Some(5), "curl: (5) Could not resolve proxy: proxy.corp"), HttpError::Proxy(_)));
+        assert!(matches!(
+            classify(Some(56), "curl: (56) CONNECT tunnel failed, response 407 from proxy"),
+            HttpError::Proxy(_)
+        ));
+        assert!(matches!(classify(Some(6), "curl: (6) Could not resolve host: ntfy.sh"), HttpError::Network(_)));
+        assert!(matches!(classify(Some(22), "curl: (22) The requested URL returned error: 404"), HttpError::Network(_)));
+    }
+}
@@ -0,0 +1,183 @@
+use cosmic::iced::widget::svg;
+use cosmic::theme;
+use cosmic::widget::icon;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use crate::package_manager::UpdateInfo;
+
+/// Where the applet takes its icons from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IconSource {
+    /// The icon theme, falling back to the bundled icons
+    #[default]
+    System,
+    /// Always the icons embedded in the binary
+    Bundled,
+}
+
+impl IconSource {
+    pub const ALL: [IconSource; 2] = [IconSource::System, IconSource::Bundled];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IconSource::System => "System icon theme",
+            IconSource::Bundled => "Bundled icons",
+        }
+    }
+}
+
+/// Where the update count is drawn relative to the panel icon.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BadgePosition {
+    #[default]
+    Beside,
+    Below,
+    /// Small, in the bottom corner of the icon
+    Overlay,
+}
+
+impl BadgePosition {
+    pub const ALL: [BadgePosition; 3] = [BadgePosition::Beside, BadgePosition::Below, BadgePosition::Overlay];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BadgePosition::Beside => "Beside icon",
+            BadgePosition::Below => "Below icon",
+            BadgePosition::Overlay => "Overlay corner",
+        }
+    }
+}
+
+/// Which updates the panel count and icon state report. The popup always
+/// lists all of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BadgeSource {
+    #[default]
+    All,
+    SecurityOnly,
+    /// Without AUR packages
+    OfficialOnly,
+}
+
+impl BadgeSource {
+    pub const ALL: [BadgeSource; 3] = [BadgeSource::All, BadgeSource::SecurityOnly, BadgeSource::OfficialOnly];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BadgeSource::All => "All updates",
+            BadgeSource::SecurityOnly => "Security updates only",
+            BadgeSource::OfficialOnly => "Official repositories only",
+        }
+    }
+
+    pub fn count(&self, update_info: &UpdateInfo) -> usize {
+        match self {
+            BadgeSource::All => update_info.total_updates,
+            BadgeSource::SecurityOnly => update_info.packages.iter().filter(|p| p.is_security).count(),
+            BadgeSource::OfficialOnly => update_info.official_updates,
+        }
+    }
+}
+
+/// What an applet icon stands for. Each role has a chain of theme icon
+/// names, since icon themes differ in what they ship, and a bundled SVG used
+/// when none of them resolves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IconRole {
+    Checking,
+    UpdatesAvailable,
+    /// Updates available, some of them security fixes
+    SecurityUpdates,
+    UpToDate,
+    Warning,
+    Error,
+}
+
+impl IconRole {
+    fn theme_names(&self) -> &'static [&'static str] {
+        match self {
+            IconRole::Checking => &["view-refresh-symbolic", "emblem-synchronizing-symbolic", "process-working-symbolic"],
+            IconRole::UpdatesAvailable => &[
+                "software-update-available-symbolic",
+                "system-software-update-symbolic",
+                "software-update-urgent-symbolic",
+            ],
+            IconRole::SecurityUpdates => &[
+                "software-update-urgent-symbolic",
+                "software-update-available-symbolic",
+                "system-software-update-symbolic",
+            ],
+            IconRole::UpToDate => &["package-x-generic-symbolic", "emblem-ok-symbolic", "object-select-symbolic"],
+            IconRole::Warning => &["dialog-warning-symbolic", "emblem-important-symbolic"],
+            IconRole::Error => &["dialog-error-symbolic", "dialog-warning-symbolic", "emblem-important-symbolic"],
+        }
+    }
+
+    /// Embedded so the applet renders on minimal icon themes without any
+    /// installed assets.
+    fn bundled_svg(&self) -> &'static [u8] {
+        match self {
+            IconRole::Checking => include_bytes!("../icons/checking-symbolic.svg"),
+            IconRole::UpdatesAvailable => include_bytes!("../icons/update-available-symbolic.svg"),
+            IconRole::SecurityUpdates => include_bytes!("../icons/security-update-symbolic.svg"),
+            IconRole::UpToDate => include_bytes!("../icons/up-to-date-symbolic.svg"),
+            IconRole::Warning => include_bytes!("../icons/warning-symbolic.svg"),
+            IconRole::Error => include_bytes!("../icons/error-symbolic.svg"),
+        }
+    }
+
+    /// Tints the symbolic icon with the theme's accent, warning or
+    /// destructive color, so the state doesn't depend on telling icon
+    /// shapes apart. Checking and up to date keep the panel's color.
+    pub fn class(&self) -> theme::Svg {
+        let color: fn(&theme::Theme) -> cosmic::iced::Color = match self {
+            IconRole::UpdatesAvailable => |theme| theme.cosmic().accent_color().into(),
+            IconRole::SecurityUpdates | IconRole::Warning => |theme| theme.cosmic().warning_color().into(),
+            IconRole::Error => |theme| theme.cosmic().destructive_color().into(),
+            IconRole::Checking | IconRole::UpToDate => return theme::Svg::Default,
+        };
+        theme::Svg::custom(move |theme| svg::Style { color: Some(color(theme)) })
+    }
+
+    /// Text mark of the state for the high contrast indicators, different
+    /// for each role so none is told apart by color alone.
+    pub fn glyph(&self) -> &'static str {
+        match self {
+            IconRole::Checking => "⟳",
+            IconRole::UpdatesAvailable => "↑",
+            IconRole::SecurityUpdates => "🛡",
+            IconRole::UpToDate => "✓",
+            IconRole::Warning => "⚠",
+            IconRole::Error => "!",
+        }
+    }
+}
+
+/// Resolved handles; looking icons up in the theme reads from disk, and the
+/// panel icon is redrawn often.
+static RESOLVED: LazyLock<Mutex<HashMap<IconRole, icon::Handle>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The first theme icon of the role's chain that exists, or the bundled SVG.
+pub fn handle(role: IconRole, source: IconSource) -> icon::Handle {
+    if source == IconSource::Bundled {
+        return bundled(role);
+    }
+
+    let mut resolved = RESOLVED.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    resolved
+        .entry(role)
+        .or_insert_with(|| {
+            role.theme_names()
+                .iter()
+                .find(|name| icon::from_name(**name).path().is_some())
+                .map(|name| icon::from_name(*name).handle())
+                .unwrap_or_else(|| bundled(role))
+        })
+        .clone()
+}
+
+fn bundled(role: IconRole) -> icon::Handle {
+    icon::from_svg_bytes(role.bundled_svg()).symbolic(true)
+}
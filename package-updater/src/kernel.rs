@@ -0,0 +1,209 @@
+use anyhow::{anyhow, Result};
+
+use crate::http;
+use crate::package_manager::{is_kernel_package, PackageManager, PackageUpdate};
+
+/// Number of commit subjects shown from an upstream changelog.
+const MAX_HIGHLIGHTS: usize = 5;
+
+/// Upstream kernel release, parsed from the leading "x.y.z" of a package
+/// version ("6.5.7.arch1-1", "6.5.7-200.fc38", "6.5.0.10.12").
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KernelVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl KernelVersion {
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.split_once(':').map_or(version, |(_, v)| v);
+        let release = version.split(|c: char| !(c.is_ascii_digit() || c == '.')).next()?;
+        let mut numbers = release.split('.').take(3).map(|n| n.parse::<u32>().ok());
+
+        Some(Self {
+            major: numbers.next()??,
+            minor: numbers.next()??,
+            patch: numbers.next().flatten().unwrap_or(0),
+        })
+    }
+}
+
+impl std::fmt::Display for KernelVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Upstream names the first release of a series "6.6", not "6.6.0"
+        if self.patch == 0 {
+            write!(f, "{}.{}", self.major, self.minor)
+        } else {
+            write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReleaseKind {
+    /// New major/minor series with new features and drivers
+    NewSeries,
+    /// Stable point release: bug and security fixes only
+    StablePoint,
+    /// Same upstream version, distribution rebuild or patch
+    Rebuild,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelUpdate {
+    pub package: String,
+    pub version: KernelVersion,
+    pub kind: ReleaseKind,
+}
+
+impl KernelUpdate {
+    /// The first pending kernel update, if any.
+    pub fn find(packages: &[PackageUpdate]) -> Option<Self> {
+        packages.iter().filter(|p| is_kernel_package(&p.name)).find_map(|p| {
+            let version = KernelVersion::parse(&p.new_version)?;
+            let kind = match KernelVersion::parse(&p.current_version) {
+                Some(current) if (current.major, current.minor) != (version.major, version.minor) => ReleaseKind::NewSeries,
+                Some(current) if current.patch != version.patch => ReleaseKind::StablePoint,
+                Some(_) => ReleaseKind::Rebuild,
+                // Backends that don't report the installed version
+                None if version.patch == 0 => ReleaseKind::NewSeries,
+                None => ReleaseKind::StablePoint,
+            };
+
+            Some(Self {
+                package: p.name.clone(),
+                version,
+                kind,
+            })
+        })
+    }
+
+    pub fn summary(&self) -> String {
+        match self.kind {
+            ReleaseKind::NewSeries => format!("Linux {}: new kernel series with new features and drivers", self.version),
+            ReleaseKind::StablePoint => format!("Linux {}: stable point release (bug and security fixes)", self.version),
+            ReleaseKind::Rebuild => format!("Linux {}: distribution rebuild of the same kernel", self.version),
+        }
+    }
+
+    /// kernelnewbies overview for a new series, the kernel.org changelog otherwise.
+    pub fn upstream_url(&self) -> String {
+        match self.kind {
+            ReleaseKind::NewSeries => format!("https://kernelnewbies.org/Linux_{}.{}", self.version.major, self.version.minor),
+            ReleaseKind::StablePoint | ReleaseKind::Rebuild => changelog_url(self.version),
+        }
+    }
+
+    pub fn distro_changelog_url(&self, package_manager: PackageManager) -> Option<String> {
+        match package_manager {
+            PackageManager::Pacman | PackageManager::Paru | PackageManager::Yay => Some(format!(
+                "https://gitlab.archlinux.org/archlinux/packaging/packages/{}/-/commits/main",
+                self.package
+            )),
+            PackageManager::Dnf | PackageManager::RpmOstree => Some(format!("https://bodhi.fedoraproject.org/updates/?packages={}", self.package)),
+            PackageManager::Apt => Some(format!("https://changelogs.ubuntu.com/changelogs/binary/{}", self.package)),
+            PackageManager::Emerge => Some(format!("https://packages.gentoo.org/packages/{}/changelog", self.package)),
+            _ => None,
+        }
+    }
+}
+
+fn changelog_url(version: KernelVersion) -> String {
+    format!("https://cdn.kernel.org/pub/linux/kernel/v{}.x/ChangeLog-{}", version.major, version)
+}
+
+/// Short summary of an upstream stable release changelog.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Highlights {
+    pub commits: usize,
+    /// Commits that reference a CVE
+    pub cve_fixes: usize,
+    pub subjects: Vec<String>,
+}
+
+pub async fn fetch_highlights(version: KernelVersion, proxy: &str) -> Result<Highlights> {
+    let changelog = http::send(http::curl(proxy).arg("-L").arg(changelog_url(version)))
+        .await
+        .map_err(|e| anyhow!("Failed to fetch kernel changelog: {}", e))?;
+
+    Ok(parse_changelog(&String::from_utf8_lossy(&changelog)))
+}
+
+/// Parses `git log` style changelogs as published on kernel.org.
+fn parse_changelog(changelog: &str) -> Highlights {
+    let mut highlights = Highlights::default();
+    let mut awaiting_subject = false;
+    let mut commit_mentions_cve = false;
+
+    for line in changelog.lines() {
+        if line.starts_with("commit ") {
+            highlights.commits += 1;
+            awaiting_subject = true;
+            commit_mentions_cve = false;
+            continue;
+        }
+
+        if line.contains("CVE-") && !commit_mentions_cve {
+            commit_mentions_cve = true;
+            highlights.cve_fixes += 1;
+        }
+
+        if awaiting_subject && line.starts_with("    ") && !line.trim().is_empty() {
+            awaiting_subject = false;
+            let subject = line.trim();
+            // The release commit itself ("Linux 6.5.7") says nothing new
+            if !subject.starts_with("Linux ") && highlights.subjects.len() < MAX_HIGHLIGHTS {
+                highlights.subjects.push(subject.to_string());
+            }
+        }
+    }
+
+    highlights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_kernel_versions() {
+        let parse = |v| KernelVersion::parse(v).map(|v| v.to_string());
+        assert_eq!(parse("6.5.7.arch1-1").as_deref(), Some("6.5.7"));
+        assert_eq!(parse("6.5.7-200.fc38").as_deref(), Some("6.5.7"));
+        assert_eq!(parse("6.6-1").as_deref(), Some("6.6"));
+        assert_eq!(parse("unknown"), None);
+    }
+
+    #[test]
+    fn summarizes_changelog() {
+        let changelog = "\
+commit 1111
+Author: Greg Kroah-Hartman <gregkh@linuxfoundation.org>
+Date:   Tue Oct 10 22:00:00 2023 +0200
+
+    Linux 6.5.7
+
+commit 2222
+Author: Someone <someone@example.org>
+Date:   Mon Oct 9 10:00:00 2023 +0200
+
+    netfilter: nf_tables: fix use-after-free
+
+    This fixes CVE-2023-0000. See CVE-2023-0000 for details.
+
+commit 3333
+Author: Someone <someone@example.org>
+Date:   Mon Oct 9 09:00:00 2023 +0200
+
+    drm/amd: fix display flicker
+";
+        let highlights = parse_changelog(changelog);
+        assert_eq!(highlights.commits, 3);
+        assert_eq!(highlights.cve_fixes, 1);
+        assert_eq!(
+            highlights.subjects,
+            vec!["netfilter: nf_tables: fix use-after-free", "drm/amd: fix display flicker"]
+        );
+    }
+}
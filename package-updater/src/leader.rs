@@ -0,0 +1,135 @@
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+use crate::app_id;
+
+/// Directory with one file per running instance, each locked by its
+/// instance for as long as it runs.
+pub fn dir() -> PathBuf {
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+        .unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(runtime_dir).join(format!("{}.instances", app_id::file_prefix()))
+}
+
+/// This instance's registration. The kernel drops the lock when the process
+/// exits, however it exits, which hands leadership to the next instance.
+#[derive(Debug)]
+pub struct Membership {
+    dir: PathBuf,
+    pid: u32,
+    _file: File,
+}
+
+impl Membership {
+    pub fn join() -> io::Result<Self> {
+        Self::join_in(&dir(), std::process::id())
+    }
+
+    fn join_in(dir: &Path, pid: u32) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(pid.to_string());
+
+        // Another instance may take the file for stale and remove it between
+        // creating and locking it; register again then
+        for _ in 0..3 {
+            let file = OpenOptions::new().write(true).create(true).truncate(false).open(&path)?;
+            if !try_lock(&file)? {
+                continue;
+            }
+            let registered = fs::metadata(&path).is_ok_and(|on_disk| on_disk.ino() == file.metadata().map_or(0, |held| held.ino()));
+            if registered {
+                return Ok(Self { dir: dir.to_path_buf(), pid, _file: file });
+            }
+        }
+        Err(io::Error::other("registration kept being removed"))
+    }
+
+    /// The leader runs the scheduled checks, the others follow its results.
+    /// It is the running instance with the lowest process id.
+    pub fn is_leader(&self) -> bool {
+        live_members(&self.dir).first().map_or(true, |&leader| leader == self.pid)
+    }
+}
+
+impl Drop for Membership {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(self.dir.join(self.pid.to_string()));
+    }
+}
+
+/// Process ids of the running instances, the leader first.
+pub fn members() -> Vec<u32> {
+    live_members(&dir())
+}
+
+/// Process ids of the registered instances that are still running, lowest
+/// first. Registrations nobody holds a lock on anymore are removed.
+fn live_members(dir: &Path) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut pids: Vec<u32> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            let file = File::open(entry.path()).ok()?;
+            match try_lock(&file) {
+                // Locks are per open file, so even our own registration is busy
+                Ok(false) => Some(pid),
+                Ok(true) => {
+                    let _ = fs::remove_file(entry.path());
+                    None
+                }
+                Err(_) => None,
+            }
+        })
+        .collect();
+    pids.sort_unstable();
+    pids
+}
+
+/// Takes an exclusive lock without waiting. `Ok(false)` if it is held elsewhere.
+fn try_lock(file: &File) -> io::Result<bool> {
+    // SAFETY: flock only operates on the descriptor, which `file` keeps open
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let error = io::Error::last_os_error();
+    if error.kind() == io::ErrorKind::WouldBlock {
+        Ok(false)
+    } else {
+        Err(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lowest_running_instance_leads() {
+        let dir = std::env::temp_dir().join(format!("package-updater-leader-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let second = Membership::join_in(&dir, 200).expect("join");
+        // Left behind by an instance that crashed
+        fs::write(dir.join("50"), "").expect("write stale registration");
+        assert!(second.is_leader());
+        assert!(!dir.join("50").exists());
+
+        let first = Membership::join_in(&dir, 100).expect("join");
+        assert!(first.is_leader());
+        assert!(!second.is_leader());
+
+        // Failover once the leader is gone
+        drop(first);
+        assert!(second.is_leader());
+        assert_eq!(live_members(&dir), vec![200]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
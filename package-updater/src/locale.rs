@@ -0,0 +1,105 @@
+use std::sync::LazyLock;
+
+/// Digit group separator of the locale numbers are formatted for.
+static GROUPING: LazyLock<Grouping> = LazyLock::new(|| Grouping::for_locale(&locale_from_env("LC_NUMERIC")));
+
+/// Whether the UI language is written right to left.
+static RTL: LazyLock<bool> = LazyLock::new(|| is_rtl_locale(&locale_from_env("LC_MESSAGES")));
+
+/// Languages written right to left: Arabic, Persian, Hebrew, Urdu, Pashto,
+/// Sindhi, Uyghur, Yiddish, Dhivehi and Central Kurdish.
+const RTL_LANGUAGES: [&str; 10] = ["ar", "fa", "he", "ur", "ps", "sd", "ug", "yi", "dv", "ckb"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Grouping {
+    separator: &'static str,
+    /// Some locales (es, pl) leave four-digit numbers ungrouped
+    min_digits: usize,
+}
+
+impl Grouping {
+    fn for_locale(locale: &str) -> Self {
+        let (language, territory) = split_locale(locale);
+        let separator = match (language, territory) {
+            ("C" | "POSIX", _) => "",
+            ("de" | "it" | "fr", "CH") => "’",
+            ("fr", _) => "\u{202f}",
+            ("pt", "PT") => "\u{a0}",
+            ("de" | "nl" | "it" | "es" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl" | "sr" | "vi" | "is", _) => ".",
+            ("ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "nn" | "no" | "uk" | "bg" | "hu" | "lt" | "lv" | "et" | "be" | "kk", _) => "\u{a0}",
+            _ => ",",
+        };
+        let min_digits = if matches!(language, "es" | "pl") { 5 } else { 4 };
+        Self { separator, min_digits }
+    }
+
+    fn format(&self, count: usize) -> String {
+        let digits = count.to_string();
+        if self.separator.is_empty() || digits.len() < self.min_digits {
+            return digits;
+        }
+
+        let mut formatted = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                formatted.push_str(self.separator);
+            }
+            formatted.push(digit);
+        }
+        formatted
+    }
+}
+
+/// A count with the digit grouping of the user's locale ("12,345", "12.345").
+pub fn format_count(count: usize) -> String {
+    GROUPING.format(count)
+}
+
+pub fn is_rtl() -> bool {
+    *RTL
+}
+
+/// The locale for a category, with the usual precedence: `LC_ALL`, the
+/// category variable, then `LANG`.
+fn locale_from_env(category: &str) -> String {
+    ["LC_ALL", category, "LANG"].iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_else(|| "C".to_string())
+}
+
+/// "de_CH.UTF-8@euro" -> ("de", "CH")
+fn split_locale(locale: &str) -> (&str, &str) {
+    let locale = locale.split(['.', '@']).next().unwrap_or(locale);
+    locale.split_once('_').unwrap_or((locale, ""))
+}
+
+fn is_rtl_locale(locale: &str) -> bool {
+    RTL_LANGUAGES.contains(&split_locale(locale).0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_digits_per_locale() {
+        let format = |locale: &str, count: usize| Grouping::for_locale(locale).format(count);
+        assert_eq!(format("en_US.UTF-8", 1234567), "1,234,567");
+        assert_eq!(format("de_DE.UTF-8", 12345), "12.345");
+        assert_eq!(format("de_CH.UTF-8", 12345), "12’345");
+        assert_eq!(format("fr_FR.UTF-8", 1234), "1\u{202f}234");
+        assert_eq!(format("es_ES.UTF-8", 1234), "1234");
+        assert_eq!(format("es_ES.UTF-8", 12345), "12.345");
+        assert_eq!(format("C", 12345), "12345");
+        assert_eq!(format("en_US.UTF-8", 999), "999");
+    }
+
+    #[test]
+    fn detects_right_to_left_languages() {
+        assert!(is_rtl_locale("ar_EG.UTF-8"));
+        assert!(is_rtl_locale("he_IL"));
+        assert!(!is_rtl_locale("en_US.UTF-8"));
+        assert!(!is_rtl_locale("C"));
+    }
+}
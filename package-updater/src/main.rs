@@ -1,9 +1,89 @@
+mod alternate_root;
+mod apk;
 mod app;
+mod app_id;
+mod appimage;
+mod apt;
+mod backend;
+mod brew;
+mod busy;
+mod cargo;
+mod cli;
 mod config;
+mod containers;
+mod custom_source;
+mod demo;
+mod diagnostics;
+mod dnf;
+mod emerge;
+mod eopkg;
+mod export;
+mod firmware;
+mod flatpak;
+mod health;
+mod held_back;
+mod history;
+mod http;
+mod icons;
+mod kernel;
+mod leader;
+mod locale;
+mod merge;
+mod metrics;
+mod mqtt;
+mod nixos;
+mod notifications;
+mod npm;
+mod os_release;
 mod package_manager;
+mod packagekit;
+mod pacman;
+mod plugin;
+mod pipx;
+mod preflight;
+mod privilege;
+mod readiness;
+mod recovery;
+mod remote;
+mod repos;
+mod risk;
+mod rpm_ostree;
+mod runner;
+mod sanitize;
+mod schedule;
+mod selection;
+mod self_update;
+mod slackpkg;
+mod sound;
+mod state;
+mod swupd;
+mod sync;
+mod sysinfo;
+mod sysupdate;
+mod terminal;
+mod topgrade;
+mod trust;
+mod update_all;
+mod webhook;
+mod xbps;
+mod zypper;
 
-use app::CosmicAppletPackageUpdater;
+use app::{CosmicAppletPackageUpdater, Flags};
 
 fn main() -> cosmic::iced::Result {
-    cosmic::applet::run::<CosmicAppletPackageUpdater>(())
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(check_only) = cli::CheckOnly::from_args(&args) {
+        std::process::exit(check_only.run());
+    }
+
+    let flags = Flags {
+        // Restricted surfaces (lock screen, idle overlays) only get count and severity
+        read_only: args.iter().any(|arg| arg == "--read-only"),
+        demo: args.iter().any(|arg| arg == "--demo"),
+    };
+    if flags.demo {
+        app_id::use_demo_files();
+    }
+
+    cosmic::applet::run::<CosmicAppletPackageUpdater>(flags)
 }
\ No newline at end of file
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{self, Backend};
+use crate::health::SourceCheck;
+use crate::package_manager::{FlatpakInstallation, PackageManager, PackageUpdate, UpdateChecker, UpdateInfo};
+use crate::runner::SystemRunner;
+use crate::sync;
+
+/// Where a package comes from, independent of the tool that reported it,
+/// and the section of the package list it is listed in. pacman, paru and
+/// yay all read the same sync databases, so a repository package is the
+/// same update whichever of them lists it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Source {
+    /// The repositories of the package manager
+    #[default]
+    System,
+    Aur,
+    /// The same application can be installed per user and system-wide
+    Flatpak(FlatpakInstallation),
+    Homebrew,
+    /// A custom source or plugin, by name, listed and updated apart from
+    /// packages
+    Custom(String),
+    Cargo,
+    Pipx,
+    Npm,
+    /// Device firmware from fwupd
+    Firmware,
+}
+
+impl Source {
+    pub fn label(&self) -> String {
+        match self {
+            Source::System => "Official".to_string(),
+            Source::Aur => "AUR".to_string(),
+            Source::Flatpak(installation) => format!("Flatpak ({})", installation.name()),
+            Source::Homebrew => "Homebrew".to_string(),
+            Source::Custom(name) => name.clone(),
+            Source::Cargo => "cargo".to_string(),
+            Source::Pipx => "pipx".to_string(),
+            Source::Npm => "npm".to_string(),
+            Source::Firmware => "Firmware".to_string(),
+        }
+    }
+}
+
+/// Combines the results of several package managers into one, listing each
+/// package once per source. Results are taken in order, so the primary
+/// package manager's entry wins; later duplicates only fill in details the
+/// first entry lacks.
+pub fn merge(results: &[(PackageManager, UpdateInfo)]) -> UpdateInfo {
+    let mut merged = UpdateInfo::new();
+    merged.packages = merge_packages(results.iter().flat_map(|(_, info)| &info.packages));
+    merged.ignored = merge_packages(results.iter().flat_map(|(_, info)| &info.ignored));
+    merged.filtered = merge_packages(results.iter().flat_map(|(_, info)| &info.filtered));
+    merged.held_back = results.iter().flat_map(|(_, info)| info.held_back.iter().cloned()).collect();
+    merged.removals = results.iter().flat_map(|(_, info)| info.removals.iter().cloned()).collect();
+    merged.sources = results.iter().flat_map(|(_, info)| info.sources.iter().cloned()).collect();
+    merged.failing_repositories = results.iter().flat_map(|(_, info)| info.failing_repositories.iter().cloned()).collect();
+    merged.unrecognized_lines = results.iter().flat_map(|(_, info)| info.unrecognized_lines.iter().cloned()).collect();
+    merged.repository_sources = results.iter().flat_map(|(_, info)| info.repository_sources.clone()).collect();
+
+    merged.count();
+    merged
+}
+
+/// Runs the checkers one after another and merges their results, then adds
+/// those of the custom sources and plugins. The first checker belongs to
+/// the primary package manager and decides whether the check failed; errors
+/// from the others are only logged. With `announce`, the other instances
+/// are told about the merged result.
+pub async fn check_all(
+    checkers: Vec<(PackageManager, UpdateChecker)>,
+    user_backends: &[Arc<dyn Backend>],
+    include_aur: bool,
+    announce: bool,
+) -> Result<UpdateInfo> {
+    let mut results = Vec::new();
+    let mut failed = Vec::new();
+    for (i, (pm, checker)) in checkers.into_iter().enumerate() {
+        match checker.check_updates(include_aur).await {
+            Ok(update_info) => results.push((pm, update_info)),
+            Err(e) if i == 0 => return Err(e),
+            Err(e) => {
+                eprintln!("Failed to check {} updates: {}", pm.name(), e);
+                failed.push(SourceCheck::new(pm.name(), Err(e.to_string())));
+            }
+        }
+    }
+    let mut merged = merge(&results);
+    merged.sources.extend(failed);
+    backend::check_all(user_backends, &SystemRunner, &mut merged).await;
+    if announce {
+        sync::publish(&merged);
+    }
+    Ok(merged)
+}
+
+fn merge_packages<'a>(packages: impl Iterator<Item = &'a PackageUpdate>) -> Vec<PackageUpdate> {
+    let mut merged: Vec<PackageUpdate> = Vec::new();
+    let mut seen: HashMap<(Source, String), usize> = HashMap::new();
+
+    for package in packages {
+        let key = (package.source.clone(), package.name.clone());
+        match seen.get(&key) {
+            Some(&index) => {
+                let existing = &mut merged[index];
+                if existing.current_version == "unknown" {
+                    existing.current_version = package.current_version.clone();
+                }
+                if existing.group.is_none() {
+                    existing.group = package.group.clone();
+                }
+                if existing.repository.is_none() {
+                    existing.repository = package.repository.clone();
+                }
+                existing.is_security |= package.is_security;
+            }
+            None => {
+                seen.insert(key, merged.len());
+                merged.push(package.clone());
+            }
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pkg(name: &str, new_version: &str, is_aur: bool) -> PackageUpdate {
+        let source = if is_aur { Source::Aur } else { Source::System };
+        PackageUpdate { source, ..PackageUpdate::new(name, "unknown", new_version) }
+    }
+
+    fn info(packages: Vec<PackageUpdate>) -> UpdateInfo {
+        let mut info = UpdateInfo::new();
+        info.total_updates = packages.len();
+        info.packages = packages;
+        info
+    }
+
+    #[test]
+    fn deduplicates_overlapping_backends() {
+        let mut grouped = pkg("gnome-shell", "45.1-1", false);
+        grouped.current_version = "45.0-1".to_string();
+        grouped.group = Some("gnome".to_string());
+
+        let merged = merge(&[
+            (PackageManager::Pacman, info(vec![pkg("gnome-shell", "45.1-1", false), pkg("linux", "6.5.7.arch1-1", false)])),
+            (PackageManager::Paru, info(vec![grouped, pkg("paru-bin", "2.0.1-1", true)])),
+            (PackageManager::Yay, info(vec![pkg("paru-bin", "2.0.1-1", true)])),
+        ]);
+
+        assert_eq!(merged.total_updates, 3);
+        assert_eq!(merged.official_updates, 2);
+        assert_eq!(merged.aur_updates, 1);
+        // The first entry is kept and completed from the duplicate
+        assert_eq!(merged.packages[0].current_version, "45.0-1");
+        assert_eq!(merged.packages[0].group.as_deref(), Some("gnome"));
+    }
+
+    #[test]
+    fn keeps_sources_apart() {
+        // A flatpak can share its name with a distribution package
+        let merged = merge(&[
+            (PackageManager::Pacman, info(vec![pkg("firefox", "118.0.2-1", false)])),
+            (PackageManager::Flatpak, info(vec![PackageUpdate { source: Source::Flatpak(FlatpakInstallation::System), ..pkg("firefox", "118.0.2", false) }])),
+        ]);
+
+        assert_eq!(merged.total_updates, 2);
+    }
+}
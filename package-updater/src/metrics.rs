@@ -0,0 +1,84 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::package_manager::UpdateInfo;
+
+/// Values exported in the Prometheus text format, for node_exporter's
+/// textfile collector.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metrics {
+    pub official_updates: usize,
+    pub aur_updates: usize,
+    pub custom_updates: usize,
+    /// Unix timestamp of the last completed check
+    pub last_check: Option<i64>,
+    /// Wall time of the last update run started from the applet
+    pub update_duration_secs: Option<u64>,
+}
+
+impl Metrics {
+    pub fn new(update_info: &UpdateInfo) -> Self {
+        Self {
+            official_updates: update_info.official_updates,
+            aur_updates: update_info.aur_updates,
+            custom_updates: update_info.custom_updates,
+            ..Default::default()
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# HELP pending_updates_total Updates waiting to be installed.");
+        let _ = writeln!(out, "# TYPE pending_updates_total gauge");
+        let _ = writeln!(out, "pending_updates_total{{source=\"official\"}} {}", self.official_updates);
+        let _ = writeln!(out, "pending_updates_total{{source=\"aur\"}} {}", self.aur_updates);
+        let _ = writeln!(out, "pending_updates_total{{source=\"custom\"}} {}", self.custom_updates);
+
+        // Absent rather than zero until known, so alerts on staleness don't fire on 1970
+        if let Some(timestamp) = self.last_check {
+            let _ = writeln!(out, "# HELP last_check_timestamp Unix time of the last completed update check.");
+            let _ = writeln!(out, "# TYPE last_check_timestamp gauge");
+            let _ = writeln!(out, "last_check_timestamp {}", timestamp);
+        }
+
+        if let Some(duration) = self.update_duration_secs {
+            let _ = writeln!(out, "# HELP update_duration_seconds Duration of the last update run.");
+            let _ = writeln!(out, "# TYPE update_duration_seconds gauge");
+            let _ = writeln!(out, "update_duration_seconds {}", duration);
+        }
+
+        out
+    }
+}
+
+/// Replaces the textfile atomically; node_exporter may read it at any time.
+pub fn write_textfile(path: &Path, metrics: &Metrics) {
+    let tmp_path = path.with_extension("prom.tmp");
+    if let Err(e) = std::fs::write(&tmp_path, metrics.render()).and_then(|_| std::fs::rename(&tmp_path, path)) {
+        eprintln!("Failed to write metrics to {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_text_format() {
+        let metrics = Metrics {
+            official_updates: 12,
+            aur_updates: 3,
+            custom_updates: 2,
+            last_check: Some(1_700_000_000),
+            update_duration_secs: None,
+        };
+        let rendered = metrics.render();
+
+        assert!(rendered.contains("pending_updates_total{source=\"official\"} 12\n"));
+        assert!(rendered.contains("pending_updates_total{source=\"aur\"} 3\n"));
+        assert!(rendered.contains("pending_updates_total{source=\"custom\"} 2\n"));
+        assert!(rendered.contains("last_check_timestamp 1700000000\n"));
+        assert!(!rendered.contains("update_duration_seconds"));
+    }
+}
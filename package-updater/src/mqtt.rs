@@ -0,0 +1,162 @@
+use anyhow::{anyhow, Result};
+use serde_json::json;
+use std::io::Write;
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::PathBuf;
+use tokio::process::Command as TokioCommand;
+
+use crate::export::StatusSnapshot;
+
+/// Prefix Home Assistant listens on for discovery messages.
+const DISCOVERY_PREFIX: &str = "homeassistant";
+
+/// Broker connection, from the settings.
+#[derive(Debug, Clone)]
+pub struct Broker {
+    /// "host" or "host:port"
+    pub address: String,
+    pub username: String,
+    pub password: String,
+}
+
+/// Status published to the state topic, which the discovered entities read.
+pub struct MqttStatus {
+    pub snapshot: StatusSnapshot,
+    pub security_updates: usize,
+}
+
+/// Home Assistant object ids may only contain [a-zA-Z0-9_-].
+fn node_id(host: &str) -> String {
+    host.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect()
+}
+
+fn state_topic(node: &str) -> String {
+    format!("cosmic-package-updater/{}/state", node)
+}
+
+/// Retained discovery messages for an update count sensor and a security
+/// update binary sensor, both grouped under a device named after the host.
+fn discovery_messages(host: &str) -> Vec<(String, String)> {
+    let node = node_id(host);
+    let device = json!({
+        "identifiers": [format!("cosmic_package_updater_{}", node)],
+        "name": host,
+        "model": "COSMIC Package Updater",
+    });
+
+    let pending = json!({
+        "name": "Pending updates",
+        "unique_id": format!("{}_pending_updates", node),
+        "state_topic": state_topic(&node),
+        "value_template": "{{ value_json.update_count }}",
+        "unit_of_measurement": "updates",
+        "icon": "mdi:package-up",
+        "device": device,
+    });
+
+    let security = json!({
+        "name": "Security updates",
+        "unique_id": format!("{}_security_updates", node),
+        "state_topic": state_topic(&node),
+        "value_template": "{{ 'ON' if value_json.security_updates > 0 else 'OFF' }}",
+        "icon": "mdi:shield-alert",
+        "device": device,
+    });
+
+    vec![
+        (format!("{}/sensor/{}/pending_updates/config", DISCOVERY_PREFIX, node), pending.to_string()),
+        (format!("{}/binary_sensor/{}/security_updates/config", DISCOVERY_PREFIX, node), security.to_string()),
+    ]
+}
+
+/// Publishes discovery and state with mosquitto_pub. All messages are
+/// retained, so Home Assistant picks them up after restarting too.
+pub async fn publish(broker: &Broker, host: &str, status: &MqttStatus) -> Result<()> {
+    let state = json!({
+        "update_count": status.snapshot.update_count,
+        "security_updates": status.security_updates,
+        "severity": status.snapshot.severity,
+        "last_check": status.snapshot.last_check,
+    });
+
+    let mut messages = discovery_messages(host);
+    messages.push((state_topic(&node_id(host)), state.to_string()));
+
+    let credentials = write_credentials(broker)?;
+    let result = async {
+        for (topic, payload) in messages {
+            publish_message(broker, credentials.as_ref(), &topic, &payload).await?;
+        }
+        Ok(())
+    }
+    .await;
+    if let Some(dir) = credentials {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+    result
+}
+
+/// Writes the login to a config file only the user can read, in a
+/// directory mosquitto_pub reads as its `XDG_CONFIG_HOME`. On the command
+/// line, any local user could read the password from /proc.
+fn write_credentials(broker: &Broker) -> Result<Option<PathBuf>> {
+    if broker.username.is_empty() {
+        return Ok(None);
+    }
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+        .unwrap_or_else(|_| "/tmp".to_string());
+    let dir = PathBuf::from(runtime_dir).join(format!("cosmic-package-updater-mqtt-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::DirBuilder::new().mode(0o700).create(&dir)?;
+
+    let mut options = format!("-u {}\n", broker.username);
+    if !broker.password.is_empty() {
+        options.push_str(&format!("-P {}\n", broker.password));
+    }
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(dir.join("mosquitto_pub"))?
+        .write_all(options.as_bytes())?;
+    Ok(Some(dir))
+}
+
+async fn publish_message(broker: &Broker, credentials: Option<&PathBuf>, topic: &str, payload: &str) -> Result<()> {
+    let mut command = TokioCommand::new("mosquitto_pub");
+    match broker.address.rsplit_once(':') {
+        Some((host, port)) => command.args(["-h", host, "-p", port]),
+        None => command.args(["-h", &broker.address]),
+    };
+    if let Some(dir) = credentials {
+        command.env("XDG_CONFIG_HOME", dir);
+    }
+
+    let output = command.args(["-r", "-t", topic, "-m", payload]).output().await?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to publish to MQTT topic {}: {}",
+            topic,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_discovery_topics() {
+        let messages = discovery_messages("my.desktop");
+        assert_eq!(messages[0].0, "homeassistant/sensor/my_desktop/pending_updates/config");
+        assert_eq!(messages[1].0, "homeassistant/binary_sensor/my_desktop/security_updates/config");
+
+        let config: serde_json::Value = serde_json::from_str(&messages[0].1).unwrap();
+        assert_eq!(config["state_topic"], "cosmic-package-updater/my_desktop/state");
+        assert_eq!(config["device"]["name"], "my.desktop");
+    }
+}
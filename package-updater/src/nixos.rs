@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use std::path::{Path, PathBuf};
+
+use crate::backend::{self, Backend, Capabilities, CheckContext};
+use crate::package_manager::PackageUpdate;
+use crate::privilege::PrivilegeEscalation;
+use crate::sysinfo;
+
+/// Location of the system flake used by flake-based NixOS configurations.
+pub const FLAKE_DIR: &str = "/etc/nixos";
+
+/// Name of the single update a changed NixOS system closure is listed as.
+const SYSTEM_NAME: &str = "NixOS system";
+
+/// The running NixOS system, a link into the store.
+const CURRENT_SYSTEM: &str = "/run/current-system";
+
+/// NixOS, configured with a flake. The system changes as a whole: a
+/// changed closure is one update.
+pub struct Nixos;
+
+impl Backend for Nixos {
+    fn name(&self) -> &str {
+        "nixos-rebuild"
+    }
+
+    /// Only flake-based configurations are supported.
+    fn detect(&self) -> bool {
+        Path::new(FLAKE_DIR).join("flake.nix").exists() && backend::on_path("nixos-rebuild")
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { needs_privileges: true, ..Capabilities::default() }
+    }
+
+    /// The flake's inputs are updated into a lock file of the applet's own,
+    /// leaving the configuration's untouched, and a dry run of the system
+    /// build against it lists the store paths it would build or fetch.
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(async move {
+            let lock_file = flake_lock_path().to_string_lossy().into_owned();
+            let output = context.runner
+                .run("nix", &["flake", "update", "--flake", FLAKE_DIR, "--output-lock-file", &lock_file])
+                .await?;
+            if !output.success() {
+                return Err(anyhow!("Failed to update the flake inputs (exit {}): {}", output.code.unwrap_or(-1), output.stderr));
+            }
+
+            let system = format!("{}#nixosConfigurations.\"{}\".config.system.build.toplevel", FLAKE_DIR, sysinfo::hostname());
+            let output = context.runner
+                .run("nix", &["build", "--dry-run", "--no-link", "--no-write-lock-file", "--reference-lock-file", &lock_file, &system])
+                .await?;
+            if !output.success() {
+                return Err(anyhow!("Failed to check for updates (exit {}): {}", output.code.unwrap_or(-1), output.stderr));
+            }
+
+            let current = std::fs::read_link(CURRENT_SYSTEM).ok()
+                .and_then(|path| path.file_name().and_then(|name| system_version(&name.to_string_lossy())));
+            // The paths are listed on stderr
+            Ok(closure_update(&output.stderr, current).into_iter().collect())
+        })
+    }
+
+    /// Updates the flake's inputs in its lock file, then builds and
+    /// switches to the system against them.
+    fn update_command(&self, privilege: PrivilegeEscalation) -> Option<String> {
+        Some(format!(
+            "{} && {}",
+            privilege.wrap(&format!("nix flake update --flake {}", FLAKE_DIR)),
+            privilege.wrap(&format!("nixos-rebuild switch --upgrade --flake {}", FLAKE_DIR))
+        ))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the rebuild again and read the error; the flake's inputs may not build."
+    }
+}
+
+/// Lock file with the updated flake inputs, written by each check.
+fn flake_lock_path() -> PathBuf {
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+        .unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(runtime_dir).join("cosmic-package-updater-flake.lock")
+}
+
+/// The single update of a NixOS system closure when `nix build --dry-run`
+/// lists store paths ("  /nix/store/<hash>-name-version[.drv]") to build
+/// or fetch, versioned like the system's own derivation if it is rebuilt.
+fn closure_update(output: &str, current_version: Option<String>) -> Option<PackageUpdate> {
+    let paths: Vec<&str> = output.lines()
+        .filter_map(|line| line.trim().strip_prefix("/nix/store/"))
+        .collect();
+    if paths.is_empty() {
+        return None;
+    }
+    let new_version = paths.iter().find_map(|path| system_version(path.trim_end_matches(".drv")));
+    Some(PackageUpdate::new(
+        SYSTEM_NAME,
+        current_version.as_deref().unwrap_or("unknown"),
+        new_version.as_deref().unwrap_or("unknown"),
+    ))
+}
+
+/// Version of a system derivation's store path,
+/// "<hash>-nixos-system-<hostname>-<version>"; host names may contain
+/// dashes, the version starts at the first one followed by a digit.
+fn system_version(path: &str) -> Option<String> {
+    let (_, rest) = path.split_once("-nixos-system-")?;
+    let split = rest.match_indices('-')
+        .map(|(idx, _)| idx)
+        .find(|&idx| rest[idx + 1..].starts_with(|c: char| c.is_ascii_digit()))?;
+    Some(rest[split + 1..].to_string())
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use futures::{Stream, StreamExt};
+use std::collections::HashMap;
+use zbus::zvariant::Value;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    #[zbus(signal)]
+    fn action_invoked(&self, id: u32, action_key: &str) -> zbus::Result<()>;
+}
+
+const APP_NAME: &str = "Package Updater";
+
+/// Sends a desktop notification through the freedesktop notification service.
+pub async fn send(summary: &str, body: &str, icon: &str) -> Result<u32> {
+    send_with_actions(summary, body, icon, &[]).await
+}
+
+/// Like [`send`], with `(key, label)` pairs shown as buttons. Clicks are
+/// reported by [`action_invocations`].
+pub async fn send_with_actions(summary: &str, body: &str, icon: &str, actions: &[(&str, &str)]) -> Result<u32> {
+    let connection = zbus::Connection::session().await?;
+    let proxy = NotificationsProxy::new(&connection).await?;
+
+    let actions: Vec<&str> = actions.iter().flat_map(|(key, label)| [*key, *label]).collect();
+    let id = proxy
+        .notify(APP_NAME, 0, icon, summary, body, &actions, HashMap::new(), -1)
+        .await?;
+
+    Ok(id)
+}
+
+/// Notification id and action key for every notification button clicked.
+/// The service broadcasts these for all applications, so callers match the
+/// id against the notifications they sent.
+pub fn action_invocations() -> impl Stream<Item = (u32, String)> {
+    async_stream::stream! {
+        let signals = async {
+            let connection = zbus::Connection::session().await?;
+            let proxy = NotificationsProxy::new(&connection).await?;
+            proxy.receive_action_invoked().await
+        };
+
+        let mut signals = match signals.await {
+            Ok(signals) => signals,
+            Err(e) => {
+                eprintln!("Failed to listen for notification actions: {}", e);
+                return;
+            }
+        };
+
+        while let Some(signal) = signals.next().await {
+            if let Ok(args) = signal.args() {
+                yield (args.id, args.action_key.to_string());
+            }
+        }
+    }
+}
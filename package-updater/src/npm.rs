@@ -0,0 +1,93 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+
+use crate::backend::{self, Backend, Capabilities, CheckContext};
+use crate::merge::Source;
+use crate::package_manager::PackageUpdate;
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::CommandRunner;
+
+/// `npm outdated` exit code for "some packages are outdated".
+const OUTDATED: i32 = 1;
+
+/// Node.js tools installed with `npm install -g`.
+pub struct Npm;
+
+impl Backend for Npm {
+    fn name(&self) -> &str {
+        "npm"
+    }
+
+    fn detect(&self) -> bool {
+        backend::on_path("npm")
+    }
+
+    /// Installs into the user's global prefix.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { selective_update: true, ..Capabilities::default() }
+    }
+
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(get_updates(context.runner))
+    }
+
+    fn update_command(&self, _privilege: PrivilegeEscalation) -> Option<String> {
+        Some("npm update -g".to_string())
+    }
+
+    fn packages_update_command(&self, _privilege: PrivilegeEscalation, packages: &[String]) -> Option<String> {
+        if packages.is_empty() {
+            return None;
+        }
+        Some(format!("npm update -g {}", packages.join(" ")))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; EACCES means the global prefix is system-wide, 'npm config set prefix ~/.local' moves it to your home."
+    }
+}
+
+/// Global packages whose installed version differs from the one
+/// `npm update -g` would install.
+pub async fn get_updates(runner: &dyn CommandRunner) -> Result<Vec<PackageUpdate>> {
+    let output = runner.run("npm", &["outdated", "--global", "--json"]).await?;
+    if !matches!(output.code, Some(0) | Some(OUTDATED)) {
+        return Err(anyhow!("Failed to check for updates (exit {}): {}", output.code.unwrap_or(-1), output.stderr));
+    }
+
+    parse_outdated(&output.stdout)
+}
+
+/// Parses `npm outdated --global --json` output, an object keyed by package
+/// name. Packages that aren't installed (no "current") and linked ones are
+/// left out; older npm releases print nothing when all is current.
+fn parse_outdated(json: &str) -> Result<Vec<PackageUpdate>> {
+    if json.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let root: serde_json::Value = serde_json::from_str(json)?;
+    let packages = root.as_object().ok_or_else(|| anyhow!("npm outdated output is not an object"))?;
+    if let Some(error) = packages.get("error") {
+        let summary = error.get("summary").and_then(|s| s.as_str()).unwrap_or_default();
+        return Err(anyhow!("Failed to check for updates: {}", summary));
+    }
+
+    let updates = packages
+        .iter()
+        .filter_map(|(name, package)| {
+            let field = |key: &str| package.get(key).and_then(|v| v.as_str());
+            let current = field("current")?;
+            let wanted = field("wanted").filter(|&wanted| wanted != current && wanted != "linked")?;
+
+            Some(PackageUpdate {
+                name: name.clone(),
+                current_version: current.to_string(),
+                new_version: wanted.to_string(),
+                source: Source::Npm,
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    Ok(updates)
+}
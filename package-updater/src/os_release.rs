@@ -0,0 +1,118 @@
+use crate::package_manager::PackageManager;
+
+/// Distribution families by os-release ID, with the package managers that
+/// manage their system packages.
+const FAMILIES: [(&str, &[PackageManager]); 14] = [
+    ("arch", &[PackageManager::Paru, PackageManager::Yay, PackageManager::Pacman]),
+    ("debian", &[PackageManager::Apt]),
+    ("ubuntu", &[PackageManager::Apt]),
+    ("fedora", &[PackageManager::RpmOstree, PackageManager::Dnf]),
+    ("rhel", &[PackageManager::Dnf]),
+    ("suse", &[PackageManager::Zypper]),
+    ("opensuse", &[PackageManager::Zypper]),
+    ("alpine", &[PackageManager::Apk]),
+    ("void", &[PackageManager::Xbps]),
+    ("gentoo", &[PackageManager::Emerge]),
+    ("solus", &[PackageManager::Eopkg]),
+    ("slackware", &[PackageManager::Slackpkg]),
+    ("clear-linux-os", &[PackageManager::Swupd]),
+    ("nixos", &[PackageManager::NixosRebuild]),
+];
+
+/// The fields of os-release the applet uses.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OsRelease {
+    pub id: String,
+    /// Distributions this one derives from, closest first
+    pub id_like: Vec<String>,
+    /// PRETTY_NAME, or NAME without it
+    pub pretty_name: Option<String>,
+}
+
+impl OsRelease {
+    /// Reads /etc/os-release, or /usr/lib/os-release where /etc has none.
+    pub fn load() -> Option<Self> {
+        let contents = std::fs::read_to_string("/etc/os-release")
+            .or_else(|_| std::fs::read_to_string("/usr/lib/os-release"))
+            .ok()?;
+        Some(Self::parse(&contents))
+    }
+
+    fn parse(contents: &str) -> Self {
+        let field = |key: &str| {
+            contents.lines().find_map(|line| {
+                let value = line.strip_prefix(key)?.strip_prefix('=')?;
+                Some(value.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+            })
+        };
+
+        Self {
+            id: field("ID").unwrap_or_default(),
+            id_like: field("ID_LIKE").map(|like| like.split_whitespace().map(str::to_string).collect()).unwrap_or_default(),
+            pretty_name: field("PRETTY_NAME").or_else(|| field("NAME")),
+        }
+    }
+
+    /// Package managers of the distribution's family: its own ID first,
+    /// then the ones it is like. None for distributions not known here.
+    pub fn native_package_managers(&self) -> Option<&'static [PackageManager]> {
+        std::iter::once(&self.id)
+            .chain(&self.id_like)
+            .find_map(|id| {
+                // "opensuse-tumbleweed", "opensuse-leap"
+                let family = if id.starts_with("opensuse") { "opensuse" } else { id.as_str() };
+                FAMILIES.iter().find(|(name, _)| *name == family).map(|(_, managers)| *managers)
+            })
+    }
+
+    /// Whether `pm` belongs on this distribution: one of its family's
+    /// package managers, or one that works on any distribution.
+    pub fn is_relevant(&self, pm: PackageManager) -> bool {
+        match self.native_package_managers() {
+            Some(native) => native.contains(&pm) || is_distribution_independent(pm),
+            None => true,
+        }
+    }
+}
+
+/// Package managers that don't manage a distribution's own packages.
+fn is_distribution_independent(pm: PackageManager) -> bool {
+    matches!(
+        pm,
+        PackageManager::PackageKit
+            | PackageManager::Flatpak
+            | PackageManager::Brew
+            | PackageManager::Cargo
+            | PackageManager::Pipx
+            | PackageManager::Npm
+            | PackageManager::Fwupd
+            // Detected only with transfer definitions, which image-based
+            // distributions of any ID ship
+            | PackageManager::Sysupdate
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_family_of_derivatives() {
+        let endeavour = OsRelease::parse("NAME=\"EndeavourOS\"\nID=\"endeavouros\"\nID_LIKE=\"arch\"\nPRETTY_NAME=\"EndeavourOS\"\n");
+        assert_eq!(endeavour.id_like, ["arch"]);
+        assert!(endeavour.is_relevant(PackageManager::Paru));
+        assert!(endeavour.is_relevant(PackageManager::Flatpak));
+        assert!(!endeavour.is_relevant(PackageManager::Zypper));
+
+        let tumbleweed = OsRelease::parse("NAME=\"openSUSE Tumbleweed\"\nID=\"opensuse-tumbleweed\"\nID_LIKE=\"opensuse suse\"\n");
+        assert_eq!(tumbleweed.native_package_managers(), Some(&[PackageManager::Zypper][..]));
+        assert_eq!(tumbleweed.pretty_name.as_deref(), Some("openSUSE Tumbleweed"));
+
+        let pop = OsRelease::parse("ID=pop\nID_LIKE=\"ubuntu debian\"\n");
+        assert_eq!(pop.native_package_managers(), Some(&[PackageManager::Apt][..]));
+
+        // Unknown distributions hide nothing
+        let unknown = OsRelease::parse("ID=somethingnew\n");
+        assert!(unknown.is_relevant(PackageManager::Zypper));
+    }
+}
@@ -1,12 +1,22 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
-use tokio::process::Command as TokioCommand;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::fs::{File, OpenOptions};
 use std::io::{Write, ErrorKind};
+use std::sync::Arc;
+use std::time::Instant;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+use crate::backend::{self, Backend, Capabilities, CheckContext, CheckNotes};
+use crate::health::{self, SourceCheck};
+use crate::held_back::HeldBack;
+use crate::merge::Source;
+use crate::privilege::PrivilegeEscalation;
+use crate::repos;
+use crate::runner::{CommandRunner, SystemRunner};
+use crate::sysinfo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum PackageManager {
     // Arch Linux
     Pacman,
@@ -20,11 +30,101 @@ pub enum PackageManager {
     Zypper,
     // Alpine Linux
     Apk,
+    // Void Linux
+    Xbps,
+    // Gentoo
+    Emerge,
+    // Solus
+    Eopkg,
+    // Slackware
+    Slackpkg,
+    // Clear Linux (versioned as a whole OS)
+    Swupd,
+    // NixOS (flake-based system configuration)
+    NixosRebuild,
+    // Fedora Atomic desktops (Silverblue, Kinoite)
+    RpmOstree,
+    // Image-based systems updated with systemd-sysupdate (GNOME OS, ParticleOS)
+    Sysupdate,
+    // Any distribution, through the PackageKit daemon
+    PackageKit,
     // Universal
     Flatpak,
+    // Homebrew on Linux (Linuxbrew)
+    Brew,
+    // Binaries installed with `cargo install`, through cargo-update
+    Cargo,
+    // Python applications installed with pipx
+    Pipx,
+    // Node.js tools installed with `npm install -g`
+    Npm,
+    // Device firmware
+    Fwupd,
+}
+
+/// Flatpak installation an update belongs to: the user's own in the home
+/// directory, or the system-wide one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum FlatpakInstallation {
+    User,
+    System,
+}
+
+impl FlatpakInstallation {
+    pub const ALL: [FlatpakInstallation; 2] = [FlatpakInstallation::User, FlatpakInstallation::System];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FlatpakInstallation::User => "user",
+            FlatpakInstallation::System => "system",
+        }
+    }
+
+    /// Option selecting the installation in flatpak commands.
+    pub fn flag(&self) -> &'static str {
+        match self {
+            FlatpakInstallation::User => "--user",
+            FlatpakInstallation::System => "--system",
+        }
+    }
+
+    pub fn update_command(&self) -> String {
+        format!("flatpak update {}", self.flag())
+    }
 }
 
 impl PackageManager {
+    /// Order of detection, most preferred first
+    pub const PREFERENCE: [PackageManager; 22] = [
+        // AUR helpers first (most feature-rich for Arch)
+        PackageManager::Paru,
+        PackageManager::Yay,
+        // System package managers
+        PackageManager::Pacman,
+        PackageManager::Apt,
+        // Image-based Fedora ships dnf as well, which can't install there
+        PackageManager::RpmOstree,
+        PackageManager::Dnf,
+        PackageManager::Zypper,
+        PackageManager::Apk,
+        PackageManager::Xbps,
+        PackageManager::Emerge,
+        PackageManager::Eopkg,
+        PackageManager::Slackpkg,
+        PackageManager::Swupd,
+        PackageManager::NixosRebuild,
+        PackageManager::Sysupdate,
+        // Distributions without a backend of their own
+        PackageManager::PackageKit,
+        // Universal package managers
+        PackageManager::Flatpak,
+        PackageManager::Brew,
+        PackageManager::Cargo,
+        PackageManager::Pipx,
+        PackageManager::Npm,
+        PackageManager::Fwupd,
+    ];
+
     pub fn name(&self) -> &'static str {
         match self {
             PackageManager::Pacman => "pacman",
@@ -34,26 +134,43 @@ impl PackageManager {
             PackageManager::Dnf => "dnf",
             PackageManager::Zypper => "zypper",
             PackageManager::Apk => "apk",
+            PackageManager::Xbps => "xbps",
+            PackageManager::Emerge => "emerge",
+            PackageManager::Eopkg => "eopkg",
+            PackageManager::Slackpkg => "slackpkg",
+            PackageManager::Swupd => "swupd",
+            PackageManager::NixosRebuild => "nixos-rebuild",
+            PackageManager::RpmOstree => "rpm-ostree",
+            PackageManager::Sysupdate => "systemd-sysupdate",
+            PackageManager::PackageKit => "packagekit",
             PackageManager::Flatpak => "flatpak",
+            PackageManager::Brew => "brew",
+            PackageManager::Cargo => "cargo",
+            PackageManager::Pipx => "pipx",
+            PackageManager::Npm => "npm",
+            PackageManager::Fwupd => "fwupd",
         }
     }
 
-    pub fn supports_aur(&self) -> bool {
-        matches!(self, PackageManager::Paru | PackageManager::Yay)
+    /// The built-in backend that detects, checks and updates with this
+    /// package manager, registered under its name in [`backend::BUILTIN`].
+    pub fn backend(&self) -> &'static dyn Backend {
+        backend::builtin(self.name()).expect("every package manager has a built-in backend")
     }
 
+    pub fn capabilities(&self) -> Capabilities {
+        self.backend().capabilities()
+    }
 
-    pub fn system_update_command(&self) -> String {
-        match self {
-            PackageManager::Pacman => "sudo pacman -Syu".to_string(),
-            PackageManager::Paru => "paru -Syu".to_string(),
-            PackageManager::Yay => "yay -Syu".to_string(),
-            PackageManager::Apt => "sudo apt update && sudo apt upgrade".to_string(),
-            PackageManager::Dnf => "sudo dnf upgrade".to_string(),
-            PackageManager::Zypper => "sudo zypper update".to_string(),
-            PackageManager::Apk => "sudo apk upgrade".to_string(),
-            PackageManager::Flatpak => "flatpak update".to_string(),
-        }
+    /// Whether this manager checks everything `other` checks and more, so
+    /// switching to it is worth suggesting: AUR helpers over plain pacman.
+    pub fn supersedes(&self, other: PackageManager) -> bool {
+        self.capabilities().aur && other == PackageManager::Pacman
+    }
+
+    /// Command that installs all updates; every built-in backend has one.
+    pub fn system_update_command(&self, privilege: PrivilegeEscalation) -> String {
+        self.backend().update_command(privilege).unwrap_or_default()
     }
 }
 
@@ -63,20 +180,95 @@ impl std::fmt::Display for PackageManager {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateInfo {
     pub total_updates: usize,
+    /// Updates of the package managers, apart from the AUR
     pub official_updates: usize,
     pub aur_updates: usize,
+    /// Updates of the custom sources and plugins
+    #[serde(default)]
+    pub custom_updates: usize,
     pub packages: Vec<PackageUpdate>,
+    /// Available updates the package manager is configured to skip; not
+    /// included in the counts above
+    pub ignored: Vec<PackageUpdate>,
+    /// Available updates from repositories left out in the settings; not
+    /// included in the counts above
+    pub filtered: Vec<PackageUpdate>,
+    /// Available updates the resolver won't install, with its reason; not
+    /// included in the counts above
+    pub held_back: Vec<HeldBack>,
+    /// Installed packages the upgrade would remove, where the check's
+    /// simulation lists them
+    #[serde(default)]
+    pub removals: Vec<String>,
+    /// Whether each source (repositories, AUR) could be checked
+    pub sources: Vec<SourceCheck>,
+    /// Lines of the check output that were neither an update nor known
+    /// noise, prefixed with the package manager
+    pub unrecognized_lines: Vec<String>,
+    /// Repositories the check's error output named as failing to download
+    pub failing_repositories: Vec<SourceCheck>,
+    /// Configuration file of each repository updates come from, where it
+    /// could be found
+    pub repository_sources: BTreeMap<String, PathBuf>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PackageUpdate {
     pub name: String,
     pub current_version: String,
     pub new_version: String,
-    pub is_aur: bool,
+    /// Where the update comes from, which section of the package list
+    /// it is listed in
+    #[serde(default)]
+    pub source: Source,
+    /// Package group the package belongs to, for backends that expose groups
+    pub group: Option<String>,
+    /// Update is flagged as a security fix by the backend
+    pub is_security: bool,
+    /// Excluded by the system's package manager configuration (pacman
+    /// `IgnorePkg`, dnf `excludepkgs`, apt pinning)
+    pub is_ignored: bool,
+    /// Repository the update comes from, for backends that report it
+    pub repository: Option<String>,
+    /// Severity the backend rates the update with (PackageKit), unless
+    /// it is a normal one
+    pub severity: Option<String>,
+}
+
+impl PackageUpdate {
+    /// An update from the package manager's repositories, without further
+    /// details.
+    pub fn new(name: &str, current_version: &str, new_version: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            current_version: current_version.to_string(),
+            new_version: new_version.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Kernel image packages across distributions (linux, linux-lts,
+/// linux-image-*, kernel, kernel-core, kernel-default, Void's linux6.5,
+/// Gentoo's sys-kernel/gentoo-sources, ...).
+pub fn is_kernel_package(name: &str) -> bool {
+    if name.starts_with("linux-firmware") || name.starts_with("linux-api-headers") || name.contains("headers") {
+        return false;
+    }
+
+    name == "linux"
+        || matches!(name, "linux-lts" | "linux-zen" | "linux-hardened" | "linux-rt" | "linux-virt" | "linux-lts-virt" | "linux-current")
+        || name.starts_with("linux-image-")
+        || name.strip_prefix("linux").is_some_and(|series| {
+            series.starts_with(|c: char| c.is_ascii_digit()) && series.chars().all(|c| c.is_ascii_digit() || c == '.')
+        })
+        || name.strip_prefix("sys-kernel/").is_some_and(|name| name.ends_with("-sources") || name.contains("-kernel"))
+        || name == "kernel"
+        || name == "kernel-core"
+        || name.starts_with("kernel-default")
 }
 
 impl UpdateInfo {
@@ -85,91 +277,183 @@ impl UpdateInfo {
             total_updates: 0,
             official_updates: 0,
             aur_updates: 0,
+            custom_updates: 0,
             packages: Vec::new(),
+            ignored: Vec::new(),
+            filtered: Vec::new(),
+            held_back: Vec::new(),
+            removals: Vec::new(),
+            sources: Vec::new(),
+            unrecognized_lines: Vec::new(),
+            failing_repositories: Vec::new(),
+            repository_sources: BTreeMap::new(),
         }
     }
 
     pub fn has_updates(&self) -> bool {
         self.total_updates > 0
     }
+
+    /// Counts the packages by where they come from.
+    pub fn count(&mut self) {
+        let count = |matches: &dyn Fn(&Source) -> bool| self.packages.iter().filter(|p| matches(&p.source)).count();
+        self.official_updates = count(&|source| !matches!(source, Source::Aur | Source::Custom(_)));
+        self.aur_updates = count(&|source| *source == Source::Aur);
+        self.custom_updates = count(&|source| matches!(source, Source::Custom(_)));
+        self.total_updates = self.packages.len();
+    }
+
+    /// Number of updates from each source that has any, in the order of
+    /// the package list.
+    pub fn source_breakdown(&self) -> Vec<(Source, usize)> {
+        let mut counts: BTreeMap<Source, usize> = BTreeMap::new();
+        for package in &self.packages {
+            *counts.entry(package.source.clone()).or_default() += 1;
+        }
+        counts.into_iter().collect()
+    }
 }
 
 pub struct PackageManagerDetector;
 
 impl PackageManagerDetector {
     pub fn detect_available() -> Vec<PackageManager> {
-        let mut available = Vec::new();
-
-        // Check in order of preference
-        for pm in [
-            // AUR helpers first (most feature-rich for Arch)
-            PackageManager::Paru,
-            PackageManager::Yay,
-            // System package managers
-            PackageManager::Pacman,
-            PackageManager::Apt,
-            PackageManager::Dnf,
-            PackageManager::Zypper,
-            PackageManager::Apk,
-            // Universal package managers
-            PackageManager::Flatpak,
-        ] {
-            if Self::is_available(pm) {
-                available.push(pm);
-            }
-        }
+        PackageManager::PREFERENCE.into_iter()
+            .filter(|pm| pm.backend().detect())
+            .collect()
+    }
+
+    /// The first of `available` in preference `order`.
+    pub fn preferred(available: &[PackageManager], order: &[PackageManager]) -> Option<PackageManager> {
+        order.iter().copied().find(|pm| available.contains(pm))
+    }
+}
+
+/// How much work a check may do before comparing versions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CheckMode {
+    /// The backend's usual behaviour, used for scheduled checks
+    #[default]
+    Standard,
+    /// Only locally cached metadata: fast, and no load on the mirrors
+    Quick,
+    /// Refresh the repository metadata first, where that works without root
+    Refresh,
+}
 
-        available
+/// The instance that completed a check, sent with its sync message so the
+/// other instances can tell where their new state came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckOrigin {
+    pub timestamp: i64,
+    pub pid: u32,
+    pub hostname: String,
+    /// Name of the panel the instance runs in, e.g. "Panel" or "Dock"
+    pub panel: Option<String>,
+    /// Output (monitor) of that panel
+    pub output: Option<String>,
+}
+
+impl CheckOrigin {
+    pub fn current() -> Self {
+        Self {
+            timestamp: chrono::Local::now().timestamp(),
+            pid: std::process::id(),
+            hostname: sysinfo::hostname(),
+            panel: std::env::var("COSMIC_PANEL_NAME").ok(),
+            output: std::env::var("COSMIC_PANEL_OUTPUT").ok(),
+        }
     }
 
-    pub fn get_preferred() -> Option<PackageManager> {
-        Self::detect_available().into_iter().next()
+    pub fn is_this_instance(&self) -> bool {
+        self.pid == std::process::id() && self.hostname == sysinfo::hostname()
     }
 
-    fn is_available(pm: PackageManager) -> bool {
-        Command::new("which")
-            .arg(pm.name())
-            .output()
-            .map(|output| output.status.success())
-            .unwrap_or(false)
+    /// e.g. "the Dock instance on DP-1", with the host added when it is another machine.
+    pub fn describe(&self) -> String {
+        let mut label = match &self.panel {
+            Some(panel) => format!("the {} instance", panel),
+            None => format!("instance {}", self.pid),
+        };
+        if let Some(output) = &self.output {
+            label.push_str(&format!(" on {}", output));
+        }
+        if self.hostname != sysinfo::hostname() {
+            label.push_str(&format!(" ({})", self.hostname));
+        }
+        label
     }
 }
 
 pub struct UpdateChecker {
     package_manager: PackageManager,
+    mode: CheckMode,
+    runner: Arc<dyn CommandRunner>,
+    /// Unrecognized lines, failing repositories, held back updates and
+    /// removals the check's own output showed
+    notes: CheckNotes,
+    excluded_repositories: Vec<String>,
+    /// Proxy for requests to package indexes, empty for curl's default
+    proxy: String,
+    /// Installation to check instead of the running system, e.g. a chroot
+    root: Option<String>,
 }
 
 impl UpdateChecker {
     pub fn new(package_manager: PackageManager) -> Self {
-        Self { package_manager }
+        Self {
+            package_manager,
+            mode: CheckMode::default(),
+            runner: Arc::new(SystemRunner),
+            notes: CheckNotes::default(),
+            excluded_repositories: Vec::new(),
+            proxy: String::new(),
+            root: None,
+        }
     }
 
-    fn get_lock_path() -> PathBuf {
-        let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
-            .unwrap_or_else(|_| "/tmp".to_string());
-        PathBuf::from(runtime_dir).join("cosmic-package-updater.lock")
+    pub fn mode(mut self, mode: CheckMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Repositories whose updates are listed apart and left out of the counts.
+    pub fn excluded_repositories(mut self, repositories: &[String]) -> Self {
+        self.excluded_repositories = repositories.to_vec();
+        self
+    }
+
+    /// Proxy for the package index requests made by the applet itself (pipx).
+    pub fn proxy(mut self, proxy: &str) -> Self {
+        self.proxy = proxy.to_string();
+        self
+    }
+
+    /// Checks the installation under `root` instead of the running system,
+    /// for package managers that support an alternate root.
+    pub fn root(mut self, root: &str) -> Self {
+        self.root = Some(root.to_string());
+        self
     }
 
-    fn get_sync_path() -> PathBuf {
+    /// Runs the check's commands through `runner`, e.g. inside a container.
+    pub fn runner(mut self, runner: Arc<dyn CommandRunner>) -> Self {
+        self.runner = runner;
+        self
+    }
+
+    /// Shared by all builds, whatever their ID: they check the same
+    /// package databases.
+    fn get_lock_path() -> PathBuf {
         let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
             .unwrap_or_else(|_| "/tmp".to_string());
-        PathBuf::from(runtime_dir).join("cosmic-package-updater.sync")
+        PathBuf::from(runtime_dir).join("cosmic-package-updater.lock")
     }
 
-    fn notify_check_completed() {
-        // Touch the sync file to notify other instances
-        let sync_path = Self::get_sync_path();
-        if let Ok(mut file) = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&sync_path)
-        {
-            let _ = writeln!(file, "{}", std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs());
-        }
+    /// Process id of the instance that last took the check lock. The file
+    /// stays after the check, so this is the holder while one runs.
+    pub fn last_lock_holder() -> Option<u32> {
+        std::fs::read_to_string(Self::get_lock_path()).ok()?.trim().parse().ok()
     }
 
     async fn acquire_lock() -> Result<File> {
@@ -210,9 +494,20 @@ impl UpdateChecker {
             }
         };
 
+        let update_info = self.collect_updates(include_aur).await;
+
+        // Lock is automatically released when _lock is dropped
+        Ok(update_info)
+    }
+
+    async fn collect_updates(&self, include_aur: bool) -> UpdateInfo {
         let mut update_info = UpdateInfo::new();
+        let backend = self.package_manager.backend();
+        let context = self.context();
 
         // Step 1: Check official updates first and wait for completion
+        let mut official_result = Ok(());
+        let official_started = Instant::now();
         match self.check_official_updates().await {
             Ok(official_updates) => {
                 let count = official_updates.len();
@@ -232,13 +527,17 @@ impl UpdateChecker {
                     Err(e) => {
                         eprintln!("Retry failed for official updates: {}", e);
                         // Continue with AUR check even if official fails
+                        official_result = Err(e.to_string());
                     }
                 }
             }
         }
+        update_info.sources.push(SourceCheck::new(self.package_manager.name(), official_result).took(official_started.elapsed()));
 
         // Step 2: Only after official check is done, check AUR updates if enabled
-        if include_aur && self.package_manager.supports_aur() {
+        if include_aur && backend.capabilities().aur {
+            let mut aur_result = Ok(());
+            let aur_started = Instant::now();
             match self.check_aur_updates().await {
                 Ok(aur_updates) => {
                     let count = aur_updates.len();
@@ -258,271 +557,903 @@ impl UpdateChecker {
                         Err(e) => {
                             eprintln!("Retry failed for AUR updates: {}", e);
                             // Continue even if AUR check fails
+                            aur_result = Err(e.to_string());
                         }
                     }
                 }
             }
+            update_info.sources.push(SourceCheck::new(health::AUR_SOURCE, aur_result).took(aur_started.elapsed()));
         }
 
-        // Step 3: Calculate final total only after both checks are complete
-        update_info.total_updates = update_info.packages.len();
+        // The explanations, exclusions and repositories below are read from
+        // the running system's configuration
+        if self.root.is_none() {
+            update_info.held_back = backend.explain_held_back(&context, &update_info.packages).await;
+            // dnf lists candidates it can't install among the updates, and
+            // apt before phasing support installs deferred updates anyway
+            update_info.packages.retain(|p| !update_info.held_back.iter().any(|held| held.name == p.name));
 
-        // Notify other instances that we completed a check
-        Self::notify_check_completed();
+            self.mark_excluded(&mut update_info.packages).await;
+            backend.annotate(&context, &mut update_info.packages).await;
+        }
 
-        // Lock is automatically released when _lock is dropped
-        Ok(update_info)
+        // Step 3: Calculate final total only after both checks are complete,
+        // leaving out packages the package manager won't upgrade and those
+        // from repositories left out in the settings
+        let (ignored, packages): (Vec<_>, Vec<_>) = update_info.packages.into_iter().partition(|p| p.is_ignored);
+        let (filtered, packages) = packages.into_iter().partition(|p| {
+            p.repository.as_ref().is_some_and(|repository| self.excluded_repositories.contains(repository))
+        });
+        update_info.packages = packages;
+        update_info.ignored = ignored;
+        update_info.filtered = filtered;
+        if self.root.is_none() {
+            update_info.repository_sources = repos::config_sources(
+                self.package_manager,
+                &[update_info.packages.as_slice(), update_info.filtered.as_slice()].concat(),
+            );
+        }
+        update_info.count();
+        update_info.unrecognized_lines = self.notes.unrecognized_lines.lock().map(|mut lines| std::mem::take(&mut *lines)).unwrap_or_default();
+        update_info.failing_repositories = self.notes.failing_repositories.lock().map(|mut failing| std::mem::take(&mut *failing)).unwrap_or_default();
+        update_info.removals = self.notes.removals.lock().map(|mut removals| std::mem::take(&mut *removals)).unwrap_or_default();
+
+        update_info
+    }
+
+    /// What the package manager's backend checks with.
+    fn context(&self) -> CheckContext<'_> {
+        CheckContext {
+            runner: self.runner.as_ref(),
+            mode: self.mode,
+            proxy: &self.proxy,
+            notes: &self.notes,
+        }
+    }
+
+    /// Marks the packages the configuration excludes from upgrades as
+    /// ignored, for the listings that don't apply it themselves.
+    async fn mark_excluded(&self, packages: &mut [PackageUpdate]) {
+        if packages.is_empty() {
+            return;
+        }
+        let excluded = self.package_manager.backend().excluded(&self.context(), packages).await;
+        for package in packages.iter_mut().filter(|p| excluded.contains(&p.name)) {
+            package.is_ignored = true;
+        }
+    }
+
+    /// Why the resolver keeps the `packages` back: their unmet
+    /// dependencies, or what installing them would remove.
+    pub async fn explain_kept_back(&self, packages: &[String]) -> Vec<HeldBack> {
+        self.package_manager.backend().explain_kept_back(&self.context(), packages).await
     }
 
     async fn check_official_updates(&self) -> Result<Vec<PackageUpdate>> {
-        let (cmd, args) = match self.package_manager {
-            // Arch-based systems
-            PackageManager::Pacman | PackageManager::Paru | PackageManager::Yay => {
-                ("checkupdates", vec![])
-            }
-            // Debian/Ubuntu
-            PackageManager::Apt => {
-                ("apt", vec!["list", "--upgradable"])
-            }
-            // Fedora/RHEL
-            PackageManager::Dnf => {
-                ("dnf", vec!["check-update", "-q"])
-            }
-            // openSUSE/SUSE
-            PackageManager::Zypper => {
-                ("zypper", vec!["list-updates"])
-            }
-            // Alpine Linux
-            PackageManager::Apk => {
-                ("apk", vec!["-u", "list"])
-            }
-            // Flatpak
-            PackageManager::Flatpak => {
-                ("flatpak", vec!["remote-ls", "--updates"])
-            }
-        };
+        match &self.root {
+            Some(root) => self.check_root_updates(root).await,
+            None => self.package_manager.backend().check(&self.context()).await,
+        }
+    }
 
-        self.parse_update_output(cmd, args, false).await
+    /// Updates of the installation under `root`, against the repositories
+    /// its own configuration lists, with the running system's package
+    /// manager. Nothing is synced: that needs root.
+    async fn check_root_updates(&self, root: &str) -> Result<Vec<PackageUpdate>> {
+        let backend = self.package_manager.backend();
+        let (program, args) = backend.root_check_command(root.trim_end_matches('/'))?;
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        self.context().listing(backend, program, &args).await
     }
 
     async fn check_aur_updates(&self) -> Result<Vec<PackageUpdate>> {
-        let (cmd, args) = match self.package_manager {
-            PackageManager::Pacman => return Ok(Vec::new()),
-            PackageManager::Paru => ("paru", vec!["-Qu", "--aur"]),
-            PackageManager::Yay => ("yay", vec!["-Qu", "--aur"]),
-            // Other package managers don't have AUR support
-            _ => return Ok(Vec::new()),
+        let backend = self.package_manager.backend();
+        let Some((program, args)) = backend.aur_check_command() else {
+            return Ok(Vec::new());
         };
+        let packages = self.context().listing(backend, program, &args).await?;
+        Ok(packages.into_iter().map(|package| PackageUpdate { source: Source::Aur, ..package }).collect())
+    }
+}
 
-        self.parse_update_output(cmd, args, true).await
-    }
-
-    async fn parse_update_output(&self, cmd: &str, args: Vec<&str>, is_aur: bool) -> Result<Vec<PackageUpdate>> {
-        let output = TokioCommand::new(cmd)
-            .args(&args)
-            .output()
-            .await?;
-
-        if !output.status.success() {
-            let exit_code = output.status.code().unwrap_or(-1);
-
-            // Handle exit codes more carefully
-            // checkupdates returns 2 when no updates are available
-            // paru/yay return 1 when no updates are available
-            // dnf returns 100 when updates are available, 0 when no updates
-            // apt returns non-zero on error but we check stdout
-            if (cmd == "checkupdates" && exit_code == 2) ||
-               ((cmd == "paru" || cmd == "yay") && exit_code == 1) ||
-               (cmd == "dnf" && exit_code == 100) {
-                // No updates available or special success case
-                if cmd == "dnf" && exit_code == 100 {
-                    // dnf exit code 100 means updates ARE available, continue parsing
-                } else {
-                    return Ok(Vec::new());
-                }
-            } else {
-                // Any other exit code might still have valid output for some package managers
-                // Check if we have stdout output before failing
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                if stdout.trim().is_empty() {
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    eprintln!("Update check failed with exit code {}: {}", exit_code, stderr);
-                    return Err(anyhow!("Failed to check for updates (exit {}): {}", exit_code, stderr));
-                }
-                // Otherwise continue to parse the output
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::held_back;
+    use crate::runner::{CommandOutput, ScriptedRunner};
+    use crate::sanitize::strip_ansi;
+    use proptest::prelude::*;
+
+    fn checker(package_manager: PackageManager, outputs: Vec<(&'static str, CommandOutput)>) -> UpdateChecker {
+        UpdateChecker {
+            package_manager,
+            mode: CheckMode::default(),
+            runner: Arc::new(ScriptedRunner::new(outputs)),
+            notes: CheckNotes::default(),
+            excluded_repositories: Vec::new(),
+            proxy: String::new(),
+            root: None,
         }
+    }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut packages = Vec::new();
+    fn jammy_updates() -> Option<String> {
+        Some("Ubuntu:22.04/jammy-updates".to_string())
+    }
 
-        for line in stdout.lines() {
-            if let Some(package) = self.parse_package_line(line, is_aur) {
-                packages.push(package);
-            }
+    struct Case {
+        name: &'static str,
+        package_manager: PackageManager,
+        include_aur: bool,
+        outputs: Vec<(&'static str, CommandOutput)>,
+        official: usize,
+        aur: usize,
+        packages: Vec<PackageUpdate>,
+    }
+
+    fn cases() -> Vec<Case> {
+        vec![
+            Case {
+                name: "pacman checkupdates with groups",
+                package_manager: PackageManager::Pacman,
+                include_aur: false,
+                outputs: vec![
+                    ("checkupdates", CommandOutput::new(0, include_str!("../tests/fixtures/pacman/checkupdates.txt"), "")),
+                    ("pacman", CommandOutput::new(0, include_str!("../tests/fixtures/pacman/pacman_qg.txt"), "")),
+                ],
+                official: 4,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate::new("firefox", "118.0.1-1", "118.0.2-1"),
+                    PackageUpdate::new("linux", "6.5.6.arch2-1", "6.5.7.arch1-1"),
+                    PackageUpdate::new("mesa", "1:23.2.1-1", "1:23.2.1-2"),
+                    PackageUpdate { group: Some("gnome".to_string()), ..PackageUpdate::new("gnome-shell", "1:45.0-1", "1:45.1-1") },
+                ],
+            },
+            Case {
+                name: "pacman ignored packages and warnings",
+                package_manager: PackageManager::Pacman,
+                include_aur: false,
+                outputs: vec![
+                    ("checkupdates", CommandOutput::new(0, include_str!("../tests/fixtures/pacman/checkupdates_ignored.txt"), "")),
+                    ("pacman", CommandOutput::new(0, "", "")),
+                ],
+                official: 1,
+                aur: 0,
+                packages: vec![PackageUpdate::new("firefox", "118.0.1-1", "118.0.2-1")],
+            },
+            Case {
+                name: "pacman no updates",
+                package_manager: PackageManager::Pacman,
+                include_aur: false,
+                outputs: vec![("checkupdates", CommandOutput::new(2, "", ""))],
+                official: 0,
+                aur: 0,
+                packages: vec![],
+            },
+            Case {
+                name: "paru official and aur",
+                package_manager: PackageManager::Paru,
+                include_aur: true,
+                outputs: vec![
+                    ("checkupdates", CommandOutput::new(0, include_str!("../tests/fixtures/pacman/checkupdates.txt"), "")),
+                    ("paru", CommandOutput::new(0, include_str!("../tests/fixtures/pacman/paru_qu_aur.txt"), "")),
+                ],
+                official: 4,
+                aur: 1,
+                packages: vec![
+                    PackageUpdate::new("firefox", "118.0.1-1", "118.0.2-1"),
+                    PackageUpdate::new("linux", "6.5.6.arch2-1", "6.5.7.arch1-1"),
+                    PackageUpdate::new("mesa", "1:23.2.1-1", "1:23.2.1-2"),
+                    PackageUpdate::new("gnome-shell", "1:45.0-1", "1:45.1-1"),
+                    PackageUpdate { source: Source::Aur, ..PackageUpdate::new("visual-studio-code-bin", "1.83.0-1", "1.83.1-1") },
+                ],
+            },
+            Case {
+                name: "paru colored output",
+                package_manager: PackageManager::Paru,
+                include_aur: true,
+                outputs: vec![
+                    ("checkupdates", CommandOutput::new(2, "", "")),
+                    ("paru", CommandOutput::new(0, include_str!("../tests/fixtures/pacman/paru_qu_aur_color.txt"), "")),
+                ],
+                official: 0,
+                aur: 1,
+                packages: vec![PackageUpdate { source: Source::Aur, ..PackageUpdate::new("visual-studio-code-bin", "1.83.0-1", "1.83.1-1") }],
+            },
+            Case {
+                name: "paru no aur updates",
+                package_manager: PackageManager::Paru,
+                include_aur: true,
+                outputs: vec![("checkupdates", CommandOutput::new(2, "", "")), ("paru", CommandOutput::new(1, "", ""))],
+                official: 0,
+                aur: 0,
+                packages: vec![],
+            },
+            Case {
+                name: "apt-get -s dist-upgrade",
+                package_manager: PackageManager::Apt,
+                include_aur: false,
+                outputs: vec![("env", CommandOutput::new(0, include_str!("../tests/fixtures/apt/simulate_dist_upgrade.txt"), ""))],
+                official: 4,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate {
+                        is_security: true,
+                        repository: jammy_updates(),
+                        ..PackageUpdate::new("firefox", "118.0.1+build1-0ubuntu0.22.04.1", "118.0.2+build2-0ubuntu0.22.04.1")
+                    },
+                    PackageUpdate { repository: jammy_updates(), ..PackageUpdate::new("libssl3", "3.0.2-0ubuntu1.10", "3.0.2-0ubuntu1.12") },
+                    PackageUpdate { repository: jammy_updates(), ..PackageUpdate::new("gir1.2-glib-2.0", "1:1.72.4-0ubuntu0.22.04.1", "1:1.72.4-0ubuntu0.22.04.2") },
+                    PackageUpdate {
+                        is_security: true,
+                        repository: jammy_updates(),
+                        ..PackageUpdate::new("linux-image-6.2.0-36-generic", "unknown", "6.2.0-36.37~22.04.1")
+                    },
+                ],
+            },
+            Case {
+                name: "apt localized (de)",
+                package_manager: PackageManager::Apt,
+                include_aur: false,
+                outputs: vec![("env", CommandOutput::new(0, include_str!("../tests/fixtures/apt/simulate_dist_upgrade_de.txt"), ""))],
+                official: 2,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate {
+                        is_security: true,
+                        repository: jammy_updates(),
+                        ..PackageUpdate::new("firefox", "118.0.1+build1-0ubuntu0.22.04.1", "118.0.2+build2-0ubuntu0.22.04.1")
+                    },
+                    PackageUpdate { repository: jammy_updates(), ..PackageUpdate::new("libssl3", "3.0.2-0ubuntu1.10", "3.0.2-0ubuntu1.12") },
+                ],
+            },
+            Case {
+                name: "apt no updates",
+                package_manager: PackageManager::Apt,
+                include_aur: false,
+                outputs: vec![("env", CommandOutput::new(0, include_str!("../tests/fixtures/apt/simulate_dist_upgrade_empty.txt"), ""))],
+                official: 0,
+                aur: 0,
+                packages: vec![],
+            },
+            Case {
+                name: "dnf check-update",
+                package_manager: PackageManager::Dnf,
+                include_aur: false,
+                outputs: vec![
+                    ("dnf --version", CommandOutput::new(0, "4.18.0\n", "")),
+                    ("dnf", CommandOutput::new(100, include_str!("../tests/fixtures/dnf/check_update.txt"), "")),
+                ],
+                official: 4,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate { repository: Some("updates".to_string()), ..PackageUpdate::new("firefox", "unknown", "118.0.2-1.fc38") },
+                    PackageUpdate { repository: Some("updates".to_string()), ..PackageUpdate::new("kernel-core", "unknown", "6.5.7-200.fc38") },
+                    PackageUpdate { repository: Some("updates".to_string()), ..PackageUpdate::new("python3.11", "unknown", "3.11.6-1.fc38") },
+                    PackageUpdate { repository: Some("updates".to_string()), ..PackageUpdate::new("grub2-tools", "unknown", "1:2.06-100.fc38") },
+                ],
+            },
+            Case {
+                name: "dnf5 repoquery --json",
+                package_manager: PackageManager::Dnf,
+                include_aur: false,
+                outputs: vec![
+                    ("dnf --version", CommandOutput::new(0, "dnf5 version 5.2.6.2\ndnf5 plugin API version 2.0\n", "")),
+                    ("dnf", CommandOutput::new(0, include_str!("../tests/fixtures/dnf/repoquery_upgrades.json"), "")),
+                ],
+                official: 3,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate { repository: Some("updates".to_string()), ..PackageUpdate::new("firefox", "unknown", "118.0.2-1.fc39") },
+                    PackageUpdate { repository: Some("updates".to_string()), ..PackageUpdate::new("grub2-tools", "unknown", "1:2.06-100.fc39") },
+                    PackageUpdate { repository: Some("updates".to_string()), ..PackageUpdate::new("kernel-core", "unknown", "6.5.7-300.fc39") },
+                ],
+            },
+            Case {
+                name: "dnf no updates",
+                package_manager: PackageManager::Dnf,
+                include_aur: false,
+                outputs: vec![("dnf", CommandOutput::new(0, "", ""))],
+                official: 0,
+                aur: 0,
+                packages: vec![],
+            },
+            Case {
+                name: "zypper list-updates",
+                package_manager: PackageManager::Zypper,
+                include_aur: false,
+                outputs: vec![("zypper", CommandOutput::new(0, include_str!("../tests/fixtures/zypper/list_updates.txt"), ""))],
+                official: 2,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate { repository: Some("repo-oss".to_string()), ..PackageUpdate::new("MozillaFirefox", "118.0.1-1.1", "118.0.2-1.1") },
+                    PackageUpdate { repository: Some("repo-update".to_string()), ..PackageUpdate::new("kernel-default", "6.5.6-1.1", "6.5.7-1.1") },
+                ],
+            },
+            Case {
+                name: "zypper localized (de)",
+                package_manager: PackageManager::Zypper,
+                include_aur: false,
+                outputs: vec![("zypper", CommandOutput::new(0, include_str!("../tests/fixtures/zypper/list_updates_de.txt"), ""))],
+                official: 1,
+                aur: 0,
+                packages: vec![PackageUpdate { repository: Some("repo-oss".to_string()), ..PackageUpdate::new("MozillaFirefox", "118.0.1-1.1", "118.0.2-1.1") }],
+            },
+            Case {
+                name: "zypper no updates",
+                package_manager: PackageManager::Zypper,
+                include_aur: false,
+                outputs: vec![("zypper", CommandOutput::new(0, include_str!("../tests/fixtures/zypper/list_updates_empty.txt"), ""))],
+                official: 0,
+                aur: 0,
+                packages: vec![],
+            },
+            Case {
+                name: "apk -u list",
+                package_manager: PackageManager::Apk,
+                include_aur: false,
+                outputs: vec![("apk", CommandOutput::new(0, include_str!("../tests/fixtures/apk/list_upgradable.txt"), ""))],
+                official: 2,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate::new("busybox", "1.36.1-r4", "1.36.1-r5"),
+                    PackageUpdate::new("py3-urllib3", "1.26.17-r0", "1.26.18-r0"),
+                ],
+            },
+            Case {
+                name: "xbps-install -Mun",
+                package_manager: PackageManager::Xbps,
+                include_aur: false,
+                outputs: vec![("xbps-install", CommandOutput::new(0, include_str!("../tests/fixtures/xbps/dry_run.txt"), ""))],
+                official: 3,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate::new("firefox", "unknown", "119.0_1"),
+                    PackageUpdate::new("linux6.5", "unknown", "6.5.10_1"),
+                    PackageUpdate::new("gtk+3", "unknown", "3.24.38_2"),
+                ],
+            },
+            Case {
+                name: "eopkg list-upgrades",
+                package_manager: PackageManager::Eopkg,
+                include_aur: false,
+                outputs: vec![("eopkg", CommandOutput::new(0, include_str!("../tests/fixtures/eopkg/list_upgrades.txt"), ""))],
+                official: 3,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate::new("firefox", "unknown", "unknown"),
+                    PackageUpdate::new("linux-current", "unknown", "unknown"),
+                    PackageUpdate::new("libreoffice-common", "unknown", "unknown"),
+                ],
+            },
+            Case {
+                name: "fwupdmgr get-updates --json",
+                package_manager: PackageManager::Fwupd,
+                include_aur: false,
+                outputs: vec![("fwupdmgr", CommandOutput::new(0, include_str!("../tests/fixtures/fwupd/get_updates.json"), ""))],
+                official: 2,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate {
+                        is_security: true,
+                        repository: Some("lvfs".to_string()),
+                        source: Source::Firmware,
+                        ..PackageUpdate::new("UEFI dbx", "371", "377")
+                    },
+                    PackageUpdate {
+                        repository: Some("lvfs".to_string()),
+                        source: Source::Firmware,
+                        ..PackageUpdate::new("Thunderbolt host controller", "20.00", "21.00")
+                    },
+                ],
+            },
+            Case {
+                name: "fwupdmgr get-updates without updates",
+                package_manager: PackageManager::Fwupd,
+                include_aur: false,
+                outputs: vec![("fwupdmgr", CommandOutput::new(2, "", "No updatable devices\n"))],
+                official: 0,
+                aur: 0,
+                packages: vec![],
+            },
+            Case {
+                name: "brew outdated --verbose",
+                package_manager: PackageManager::Brew,
+                include_aur: false,
+                outputs: vec![("brew", CommandOutput::new(0, include_str!("../tests/fixtures/brew/outdated_verbose.txt"), ""))],
+                // node is pinned
+                official: 3,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate { source: Source::Homebrew, ..PackageUpdate::new("gh", "2.39.1", "2.40.0") },
+                    PackageUpdate { source: Source::Homebrew, ..PackageUpdate::new("python@3.12", "3.12.0_1", "3.12.1") },
+                    PackageUpdate { source: Source::Homebrew, ..PackageUpdate::new("ripgrep", "13.0.0_1", "14.0.3") },
+                ],
+            },
+            Case {
+                name: "cargo install-update --list",
+                package_manager: PackageManager::Cargo,
+                include_aur: false,
+                outputs: vec![("cargo", CommandOutput::new(0, include_str!("../tests/fixtures/cargo/install_update_list.txt"), ""))],
+                official: 3,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate { source: Source::Cargo, ..PackageUpdate::new("cargo-update", "13.3.0", "13.4.0") },
+                    PackageUpdate { source: Source::Cargo, ..PackageUpdate::new("ripgrep", "14.0.3", "14.1.0") },
+                    PackageUpdate { source: Source::Cargo, ..PackageUpdate::new("alacritty", "eb231b3", "4a9ed0e") },
+                ],
+            },
+            Case {
+                name: "pipx list --json against PyPI",
+                package_manager: PackageManager::Pipx,
+                include_aur: false,
+                outputs: vec![
+                    ("pipx", CommandOutput::new(0, include_str!("../tests/fixtures/pipx/list.json"), "")),
+                    ("curl -sSf --max-time 15 https://pypi.org/pypi/black/json", CommandOutput::new(0, r#"{"info": {"version": "24.2.0"}}"#, "")),
+                    ("curl -sSf --max-time 15 https://pypi.org/pypi/httpie/json", CommandOutput::new(0, r#"{"info": {"version": "3.2.2"}}"#, "")),
+                    ("curl -sSf --max-time 15 https://pypi.org/pypi/poetry/json", CommandOutput::new(0, r#"{"info": {"version": "1.7.1"}}"#, "")),
+                ],
+                // httpie is current, the poetry beta is ahead of PyPI's
+                // latest release and mytool was installed from git
+                official: 1,
+                aur: 0,
+                packages: vec![PackageUpdate { source: Source::Pipx, ..PackageUpdate::new("black", "23.12.1", "24.2.0") }],
+            },
+            Case {
+                name: "npm outdated --global --json",
+                package_manager: PackageManager::Npm,
+                include_aur: false,
+                outputs: vec![("npm", CommandOutput::new(1, include_str!("../tests/fixtures/npm/outdated_global.json"), ""))],
+                // my-cli is linked from a checkout, typescript was removed
+                official: 2,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate { source: Source::Npm, ..PackageUpdate::new("@angular/cli", "17.1.2", "17.2.0") },
+                    PackageUpdate { source: Source::Npm, ..PackageUpdate::new("npm", "10.2.4", "10.5.0") },
+                ],
+            },
+            Case {
+                name: "emerge --pretend --update --deep --newuse @world",
+                package_manager: PackageManager::Emerge,
+                include_aur: false,
+                outputs: vec![
+                    ("which", CommandOutput::new(1, "", "")),
+                    ("emerge", CommandOutput::new(0, include_str!("../tests/fixtures/emerge/pretend_world.txt"), "")),
+                ],
+                official: 3,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate::new("sys-kernel/gentoo-sources", "6.1.57", "6.1.60"),
+                    PackageUpdate::new("app-editors/vim", "9.0.1000", "9.0.2000-r1"),
+                    PackageUpdate::new("media-fonts/font-adobe-100dpi", "1.0.3-r2", "1.0.4"),
+                ],
+            },
+            Case {
+                name: "eix --compact --upgrade",
+                package_manager: PackageManager::Emerge,
+                include_aur: false,
+                outputs: vec![
+                    ("which", CommandOutput::new(0, "/usr/bin/eix\n", "")),
+                    ("env", CommandOutput::new(0, include_str!("../tests/fixtures/emerge/eix_upgrade.txt"), "")),
+                ],
+                official: 3,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate::new("app-editors/vim", "9.0.1000", "9.0.2000-r1"),
+                    PackageUpdate::new("sys-kernel/gentoo-sources", "6.1.57", "6.1.60"),
+                    PackageUpdate::new("dev-lang/python", "3.12.0", "3.12.1"),
+                ],
+            },
+            Case {
+                name: "flatpak remote-ls --updates per installation",
+                package_manager: PackageManager::Flatpak,
+                include_aur: false,
+                outputs: vec![
+                    (
+                        "flatpak remote-ls --updates --user --columns=name,application,version,branch",
+                        CommandOutput::new(0, include_str!("../tests/fixtures/flatpak/remote_ls_updates_user.txt"), ""),
+                    ),
+                    (
+                        "flatpak remote-ls --updates --system --columns=name,application,version,branch",
+                        CommandOutput::new(0, include_str!("../tests/fixtures/flatpak/remote_ls_updates.txt"), ""),
+                    ),
+                ],
+                official: 3,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate {
+                        source: Source::Flatpak(FlatpakInstallation::User),
+                        ..PackageUpdate::new("GNU Image Manipulation Program", "unknown", "2.10.38")
+                    },
+                    PackageUpdate { source: Source::Flatpak(FlatpakInstallation::System), ..PackageUpdate::new("Firefox", "unknown", "118.0.2") },
+                    PackageUpdate { source: Source::Flatpak(FlatpakInstallation::System), ..PackageUpdate::new("Mesa", "unknown", "23.1.9") },
+                ],
+            },
+            Case {
+                name: "flatpak no updates",
+                package_manager: PackageManager::Flatpak,
+                include_aur: false,
+                outputs: vec![("flatpak", CommandOutput::new(0, "", ""))],
+                official: 0,
+                aur: 0,
+                packages: vec![],
+            },
+            Case {
+                name: "nix build --dry-run of the system",
+                package_manager: PackageManager::NixosRebuild,
+                include_aur: false,
+                outputs: vec![("nix", CommandOutput::new(0, "", include_str!("../tests/fixtures/nixos/dry_build.txt")))],
+                official: 1,
+                aur: 0,
+                packages: vec![PackageUpdate::new("NixOS system", "unknown", "23.11.20231012")],
+            },
+            Case {
+                name: "rpm-ostree upgrade preview",
+                package_manager: PackageManager::RpmOstree,
+                include_aur: false,
+                outputs: vec![("rpm-ostree", CommandOutput::new(0, include_str!("../tests/fixtures/rpm-ostree/upgrade_preview.txt"), ""))],
+                official: 3,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate::new("firefox", "118.0.2-1.fc39", "119.0-2.fc39"),
+                    PackageUpdate { is_security: true, ..PackageUpdate::new("kernel", "6.5.8-300.fc39", "6.5.10-300.fc39") },
+                    PackageUpdate::new("mesa-dri-drivers", "23.2.1-1.fc39", "23.2.1-2.fc39"),
+                ],
+            },
+            Case {
+                name: "swupd check-update",
+                package_manager: PackageManager::Swupd,
+                include_aur: false,
+                outputs: vec![("swupd", CommandOutput::new(0, include_str!("../tests/fixtures/swupd/check_update.txt"), ""))],
+                official: 1,
+                aur: 0,
+                packages: vec![PackageUpdate::new("Clear Linux OS", "40060", "40090")],
+            },
+            Case {
+                name: "swupd no update",
+                package_manager: PackageManager::Swupd,
+                include_aur: false,
+                outputs: vec![(
+                    "swupd",
+                    CommandOutput::new(1, "Current OS version: 40090\nLatest server version: 40090\nThere are no updates available\n", ""),
+                )],
+                official: 0,
+                aur: 0,
+                packages: vec![],
+            },
+            Case {
+                name: "updatectl check",
+                package_manager: PackageManager::Sysupdate,
+                include_aur: false,
+                outputs: vec![("updatectl", CommandOutput::new(0, include_str!("../tests/fixtures/sysupdate/updatectl_check.txt"), ""))],
+                official: 2,
+                aur: 0,
+                packages: vec![PackageUpdate::new("host", "56", "57"), PackageUpdate::new("component:devel", "3", "4")],
+            },
+            Case {
+                name: "systemd-sysupdate list without updatectl",
+                package_manager: PackageManager::Sysupdate,
+                include_aur: false,
+                outputs: vec![(crate::sysupdate::BINARY, CommandOutput::new(0, include_str!("../tests/fixtures/sysupdate/list.txt"), ""))],
+                official: 1,
+                aur: 0,
+                packages: vec![PackageUpdate::new("host", "56", "57")],
+            },
+            Case {
+                name: "slackpkg package lists",
+                package_manager: PackageManager::Slackpkg,
+                include_aur: false,
+                outputs: vec![
+                    ("ls /var/log/packages", CommandOutput::new(0, include_str!("../tests/fixtures/slackpkg/installed.txt"), "")),
+                    ("cat /var/lib/slackpkg/pkglist", CommandOutput::new(0, include_str!("../tests/fixtures/slackpkg/pkglist"), "")),
+                ],
+                official: 2,
+                aur: 0,
+                packages: vec![
+                    PackageUpdate::new("mozilla-firefox", "115.7.0esr-1_slack15.0", "115.8.0esr-1_slack15.0"),
+                    PackageUpdate::new("openssl", "1.1.1za-1_slack15.0", "1.1.1zb-1_slack15.0"),
+                ],
+            },
+            Case {
+                name: "rpm-ostree no update",
+                package_manager: PackageManager::RpmOstree,
+                include_aur: false,
+                outputs: vec![("rpm-ostree", CommandOutput::new(77, "No updates available.\n", ""))],
+                official: 0,
+                aur: 0,
+                packages: vec![],
+            },
+        ]
+    }
+
+    #[tokio::test]
+    async fn parses_recorded_outputs() {
+        for case in cases() {
+            let info = checker(case.package_manager, case.outputs)
+                .collect_updates(case.include_aur)
+                .await;
+
+            assert_eq!(info.packages, case.packages, "{}", case.name);
+            assert_eq!(info.official_updates, case.official, "{}", case.name);
+            assert_eq!(info.aur_updates, case.aur, "{}", case.name);
+            assert_eq!(info.total_updates, case.official + case.aur, "{}", case.name);
+            assert_eq!(info.unrecognized_lines, Vec::<String>::new(), "{}", case.name);
         }
+    }
+
+    #[tokio::test]
+    async fn keeps_unrecognized_lines() {
+        // dnf wraps names too long for the column onto their own line
+        let info = checker(PackageManager::Dnf, vec![(
+            "dnf",
+            CommandOutput::new(100, "\nkernel.x86_64  6.8.9-300.fc40  updates\ntexlive-collection-fontsrecommended.noarch\n    11:svn54074-73.fc40  updates\n", ""),
+        )])
+        .collect_updates(false)
+        .await;
+
+        assert_eq!(info.total_updates, 1);
+        assert_eq!(info.unrecognized_lines, vec!["dnf: texlive-collection-fontsrecommended.noarch".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn leaves_out_excluded_repositories() {
+        let info = checker(
+            PackageManager::Zypper,
+            vec![("zypper", CommandOutput::new(0, include_str!("../tests/fixtures/zypper/list_updates.txt"), ""))],
+        )
+        .excluded_repositories(&["repo-update".to_string()])
+        .collect_updates(false)
+        .await;
+
+        assert_eq!(info.total_updates, 1);
+        assert_eq!(info.packages[0].name, "MozillaFirefox");
+        assert_eq!(info.filtered, vec![PackageUpdate {
+            repository: Some("repo-update".to_string()),
+            ..PackageUpdate::new("kernel-default", "6.5.6-1.1", "6.5.7-1.1")
+        }]);
+    }
+
+    #[tokio::test]
+    async fn separates_ignored_packages() {
+        let info = checker(
+            PackageManager::Pacman,
+            vec![("checkupdates", CommandOutput::new(0, include_str!("../tests/fixtures/pacman/checkupdates_ignored.txt"), ""))],
+        )
+        .collect_updates(false)
+        .await;
+
+        assert_eq!(
+            info.ignored,
+            vec![PackageUpdate { is_ignored: true, ..PackageUpdate::new("linux", "6.5.6.arch2-1", "6.5.7.arch1-1") }]
+        );
+        assert_eq!(info.total_updates, 1);
+    }
+
+    #[tokio::test]
+    async fn flags_phased_apt_updates_from_the_check() {
+        // Only the check itself answers: no second simulation is needed
+        let info = checker(PackageManager::Apt, vec![
+            ("env LC_ALL=C apt-get -s dist-upgrade", CommandOutput::new(0, include_str!("../tests/fixtures/apt/simulate_dist_upgrade_kept_back.txt"), "")),
+        ])
+        .collect_updates(false)
+        .await;
 
-        Ok(packages)
+        assert_eq!(info.packages.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), ["firefox"]);
+        assert_eq!(info.held_back[0], HeldBack::new("gnome-shell", held_back::PHASED_REASON));
+        assert!(info.unrecognized_lines.is_empty());
     }
 
-    fn parse_package_line(&self, line: &str, is_aur: bool) -> Option<PackageUpdate> {
-        // Skip header lines
-        if line.starts_with("Listing...") || line.starts_with("Done") ||
-           line.starts_with("WARNING:") || line.starts_with("S |") ||
-           line.starts_with("--+") || line.trim().is_empty() {
-            return None;
+    #[tokio::test]
+    async fn lists_packages_the_upgrade_removes() {
+        let info = checker(PackageManager::Apt, vec![
+            ("env LC_ALL=C apt-get -s dist-upgrade", CommandOutput::new(0, include_str!("../tests/fixtures/apt/simulate_dist_upgrade_removals.txt"), "")),
+        ])
+        .collect_updates(false)
+        .await;
+
+        assert!(info.packages.iter().any(|p| p.name == "foo-tools"));
+        assert_eq!(info.removals, ["libfoo1"]);
+    }
+
+    #[tokio::test]
+    async fn explains_held_back_packages() {
+        let apt = checker(PackageManager::Apt, vec![
+            ("env LC_ALL=C apt-get -s dist-upgrade", CommandOutput::new(0, include_str!("../tests/fixtures/apt/simulate_dist_upgrade_kept_back.txt"), "")),
+            ("apt-mark showhold", CommandOutput::new(0, "mesa-vulkan-drivers\n", "")),
+            ("env LC_ALL=C apt-get -s install libfoo-tools", CommandOutput::new(100, include_str!("../tests/fixtures/apt/simulate_install_unmet.txt"), "")),
+        ]);
+        let info = apt.collect_updates(false).await;
+
+        assert_eq!(info.total_updates, 1);
+        assert_eq!(info.held_back, vec![
+            HeldBack::new("gnome-shell", held_back::PHASED_REASON),
+            HeldBack::new("mesa-vulkan-drivers", held_back::HOLD_REASON),
+            HeldBack::new("libfoo-tools", held_back::RESOLVER_REASON),
+        ]);
+        // Explained when the list is opened
+        assert_eq!(apt.explain_kept_back(&["libfoo-tools".to_string()]).await, vec![
+            HeldBack::new("libfoo-tools", "requires libfoo2 (>= 2.0) but it is not going to be installed"),
+        ]);
+
+        let dnf = checker(PackageManager::Dnf, vec![
+            ("dnf --version", CommandOutput::new(0, "4.18.0", "")),
+            ("dnf", CommandOutput::new(100, "foo-libs.x86_64  2.0-1.fc40  updates\nbar.x86_64  3.1-1.fc40  updates\nbaz.x86_64  1.1-1.fc40  updates\n", "")),
+            ("env LC_ALL=C dnf upgrade --best --assumeno", CommandOutput::new(1, "", include_str!("../tests/fixtures/dnf/upgrade_best_problems.txt"))),
+        ])
+        .collect_updates(false)
+        .await;
+
+        assert_eq!(dnf.packages.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), vec!["baz"]);
+        assert_eq!(dnf.held_back.len(), 2);
+
+        // The resolver pass would take as long as the quick check itself
+        let quick = checker(PackageManager::Dnf, vec![
+            ("dnf --version", CommandOutput::new(0, "4.18.0", "")),
+            ("dnf", CommandOutput::new(100, "foo-libs.x86_64  2.0-1.fc40  updates\n", "")),
+        ])
+        .mode(CheckMode::Quick)
+        .collect_updates(false)
+        .await;
+        assert_eq!((quick.packages.len(), quick.held_back.len()), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn quick_checks_use_cached_metadata() {
+        let checkupdates = include_str!("../tests/fixtures/pacman/checkupdates.txt");
+        // Only the cache-only command line is recorded, any other lookup fails
+        let quick = checker(PackageManager::Pacman, vec![("checkupdates --nosync", CommandOutput::new(0, checkupdates, ""))])
+            .mode(CheckMode::Quick)
+            .check_official_updates()
+            .await;
+        assert!(quick.is_ok_and(|packages| !packages.is_empty()));
+
+        let refresh = checker(PackageManager::Dnf, vec![
+            ("dnf --version", CommandOutput::new(0, "4.18.0", "")),
+            ("dnf check-update -q --color=never --refresh", CommandOutput::new(0, "", "")),
+        ])
+        .mode(CheckMode::Refresh)
+        .check_official_updates()
+        .await;
+        assert!(refresh.is_ok());
+    }
+
+    #[tokio::test]
+    async fn reports_failed_checks() {
+        let cases = [
+            (PackageManager::Pacman, "checkupdates", CommandOutput::new(1, "", include_str!("../tests/fixtures/pacman/checkupdates_error_de.txt"))),
+            (PackageManager::Apt, "env", CommandOutput::new(100, "", include_str!("../tests/fixtures/apt/simulate_dist_upgrade_error.txt"))),
+            (PackageManager::Dnf, "dnf", CommandOutput::new(1, "", include_str!("../tests/fixtures/dnf/check_update_error.txt"))),
+        ];
+
+        for (package_manager, program, output) in cases {
+            let result = checker(package_manager, vec![(program, output)])
+                .check_official_updates()
+                .await;
+            assert!(result.is_err(), "{} should fail", program);
         }
 
-        match self.package_manager {
-            // Arch-based: "package 1.0.0-1 -> 1.0.1-1" or "package 1.0.1-1"
-            PackageManager::Pacman | PackageManager::Paru | PackageManager::Yay => {
-                if line.contains(" -> ") {
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 4 && parts[2] == "->" {
-                        return Some(PackageUpdate {
-                            name: parts[0].to_string(),
-                            current_version: parts[1].to_string(),
-                            new_version: parts[3].to_string(),
-                            is_aur,
-                        });
-                    }
-                } else {
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 2 {
-                        return Some(PackageUpdate {
-                            name: parts[0].to_string(),
-                            current_version: "unknown".to_string(),
-                            new_version: parts[1].to_string(),
-                            is_aur,
-                        });
-                    }
-                }
-            }
+        let missing = checker(PackageManager::Flatpak, vec![]).check_official_updates().await;
+        assert!(missing.is_err(), "missing binary should fail");
+    }
 
-            // APT: "package/suite version arch [upgradable from: old-version]"
-            PackageManager::Apt => {
-                if line.contains("[upgradable from:") {
-                    // Split by '/' to get package name
-                    let name = line.split('/').next()?.to_string();
-
-                    // Extract new version (between '/' and architecture)
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    let new_version = if parts.len() >= 2 {
-                        parts[1].to_string()
-                    } else {
-                        "unknown".to_string()
-                    };
-
-                    // Extract old version from [upgradable from: X]
-                    let current_version = if let Some(from_idx) = line.find("[upgradable from: ") {
-                        let start = from_idx + "[upgradable from: ".len();
-                        if let Some(end_idx) = line[start..].find(']') {
-                            line[start..start + end_idx].to_string()
-                        } else {
-                            "unknown".to_string()
-                        }
-                    } else {
-                        "unknown".to_string()
-                    };
-
-                    return Some(PackageUpdate {
-                        name,
-                        current_version,
-                        new_version,
-                        is_aur: false,
-                    });
-                }
-            }
+    const ALL_MANAGERS: [PackageManager; 22] = [
+        PackageManager::Pacman,
+        PackageManager::Paru,
+        PackageManager::Yay,
+        PackageManager::Apt,
+        PackageManager::Dnf,
+        PackageManager::Zypper,
+        PackageManager::Apk,
+        PackageManager::Xbps,
+        PackageManager::Emerge,
+        PackageManager::Eopkg,
+        PackageManager::Slackpkg,
+        PackageManager::Swupd,
+        PackageManager::NixosRebuild,
+        PackageManager::RpmOstree,
+        PackageManager::Sysupdate,
+        PackageManager::PackageKit,
+        PackageManager::Flatpak,
+        PackageManager::Brew,
+        PackageManager::Cargo,
+        PackageManager::Pipx,
+        PackageManager::Npm,
+        PackageManager::Fwupd,
+    ];
 
-            // DNF: "package.arch version repo" (3 columns)
-            PackageManager::Dnf => {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 2 {
-                    // First part is "package.arch"
-                    let name = parts[0].split('.').next()?.to_string();
-                    let new_version = parts[1].to_string();
-
-                    return Some(PackageUpdate {
-                        name,
-                        current_version: "unknown".to_string(),
-                        new_version,
-                        is_aur: false,
-                    });
-                }
-            }
+    /// Random SGR color codes, cursor movement and terminal title sequences.
+    fn escape_sequence() -> impl Strategy<Value = String> {
+        prop_oneof![
+            "\\x1b\\[[0-9;]{0,8}m",
+            "\\x1b\\[[0-9]{0,3}[ABCDGHJK]",
+            "\\x1b\\][0-9];[a-z ]{0,10}\\x07",
+        ]
+    }
 
-            // Zypper: table format with columns
-            // Skip status column and parse name and version
-            PackageManager::Zypper => {
-                let parts: Vec<&str> = line.split('|').collect();
-                if parts.len() >= 4 {
-                    let name = parts[1].trim().to_string();
-                    let new_version = parts[3].trim().to_string();
-
-                    return Some(PackageUpdate {
-                        name,
-                        current_version: "unknown".to_string(),
-                        new_version,
-                        is_aur: false,
-                    });
-                }
-            }
+    #[test]
+    fn registers_a_backend_for_every_package_manager() {
+        for package_manager in ALL_MANAGERS {
+            assert_eq!(package_manager.backend().name(), package_manager.name());
+        }
+        // Detected in preference order
+        let names: Vec<&str> = backend::BUILTIN.iter().map(|backend| backend.name()).collect();
+        assert_eq!(names, PackageManager::PREFERENCE.map(|pm| pm.name()));
+    }
 
-            // APK: "package-version [upgradable from: old-version]"
-            PackageManager::Apk => {
-                if line.contains("[upgradable from:") {
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 1 {
-                        // First part contains package-version, need to extract package name
-                        let pkg_info = parts[0];
-                        let name = if let Some(dash_idx) = pkg_info.rfind('-') {
-                            pkg_info[..dash_idx].to_string()
-                        } else {
-                            pkg_info.to_string()
-                        };
-
-                        // Extract versions
-                        let new_version = parts.get(1).unwrap_or(&"unknown").to_string();
-
-                        let current_version = if let Some(from_idx) = line.find("[upgradable from: ") {
-                            let start = from_idx + "[upgradable from: ".len();
-                            if let Some(end_idx) = line[start..].find(']') {
-                                line[start..start + end_idx].to_string()
-                            } else {
-                                "unknown".to_string()
-                            }
-                        } else {
-                            "unknown".to_string()
-                        };
-
-                        return Some(PackageUpdate {
-                            name,
-                            current_version,
-                            new_version,
-                            is_aur: false,
-                        });
-                    }
-                }
+    #[test]
+    fn installs_security_updates_with_the_backend_filter_or_by_name() {
+        let sudo = PrivilegeEscalation::Sudo;
+        let names = ["openssl".to_string()];
+        let command = |pm: PackageManager| pm.backend().security_update_command(sudo, &names);
+        assert_eq!(command(PackageManager::Dnf).as_deref(), Some("sudo dnf upgrade --security"));
+        assert_eq!(command(PackageManager::Apt).as_deref(), Some("sudo apt-get install --only-upgrade openssl"));
+        assert_eq!(command(PackageManager::Pacman).as_deref(), Some("sudo pacman -Syu --needed openssl"));
+        assert_eq!(command(PackageManager::RpmOstree), None);
+    }
+
+    #[test]
+    fn breaks_updates_down_by_source() {
+        let mut info = UpdateInfo::new();
+        info.packages = vec![
+            PackageUpdate { source: Source::Firmware, ..PackageUpdate::new("UEFI dbx", "220", "371") },
+            PackageUpdate::new("linux", "6.5.6.arch2-1", "6.5.7.arch1-1"),
+            PackageUpdate { source: Source::Aur, ..PackageUpdate::new("yay", "12.1.2-1", "12.1.3-1") },
+            PackageUpdate { source: Source::Flatpak(FlatpakInstallation::User), ..PackageUpdate::new("Firefox", "unknown", "118.0.2") },
+            PackageUpdate::new("mesa", "23.1.8-1", "23.1.9-1"),
+            PackageUpdate { source: Source::Custom("conda".to_string()), ..PackageUpdate::new("numpy", "1.26.4", "2.0.1") },
+        ];
+        assert_eq!(
+            info.source_breakdown(),
+            vec![
+                (Source::System, 2),
+                (Source::Aur, 1),
+                (Source::Flatpak(FlatpakInstallation::User), 1),
+                (Source::Custom("conda".to_string()), 1),
+                (Source::Firmware, 1),
+            ]
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn read_line_never_panics(line in any::<String>()) {
+            for package_manager in ALL_MANAGERS {
+                let _ = backend::read_line(package_manager.backend(), &line);
             }
+        }
 
-            // Flatpak: "name\tapp-id\tversion\tbranch\tremote"
-            PackageManager::Flatpak => {
-                let parts: Vec<&str> = line.split('\t').collect();
-                if parts.len() >= 3 {
-                    let name = parts[0].to_string();
-                    let new_version = parts[2].to_string();
-
-                    return Some(PackageUpdate {
-                        name,
-                        current_version: "unknown".to_string(),
-                        new_version,
-                        is_aur: false,
-                    });
-                }
+        #[test]
+        fn read_line_survives_noisy_bytes(bytes in proptest::collection::vec(any::<u8>(), 0..200)) {
+            // Output is decoded lossily, so truncated UTF-8 shows up as U+FFFD
+            let line = String::from_utf8_lossy(&bytes);
+            for package_manager in ALL_MANAGERS {
+                let _ = backend::read_line(package_manager.backend(), &line);
             }
         }
 
-        None
-    }
+        #[test]
+        fn strip_ansi_removes_all_escapes(
+            parts in proptest::collection::vec(("[ -~]{0,12}", escape_sequence()), 0..8)
+        ) {
+            let line: String = parts.iter().map(|(text, escape)| format!("{}{}", escape, text)).collect();
+            let plain: String = parts.iter().map(|(text, _)| text.as_str()).collect();
+            prop_assert_eq!(strip_ansi(&line), plain);
+        }
+
+        #[test]
+        fn colored_pacman_line_parses_like_plain(
+            name in "[a-z][a-z0-9+_.-]{0,30}",
+            old in "[0-9][0-9a-z.:+]{0,10}-[0-9]",
+            new in "[0-9][0-9a-z.:+]{0,10}-[0-9]",
+            color in escape_sequence(),
+        ) {
+            let plain = format!("{} {} -> {}", name, old, new);
+            let colored = format!("{c}{}\x1b[0m {c}{}\x1b[0m -> {c}{}\x1b[0m\r", name, old, new, c = color);
 
-}
\ No newline at end of file
+            let paru = PackageManager::Paru.backend();
+            let expected = backend::read_line(paru, &plain);
+            prop_assert!(expected.is_some());
+            prop_assert_eq!(backend::read_line(paru, &colored), expected);
+        }
+    }
+}
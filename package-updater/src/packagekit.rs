@@ -0,0 +1,255 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use futures::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use zbus::zvariant::OwnedObjectPath;
+
+use crate::backend::{Backend, Capabilities, CheckContext};
+use crate::package_manager::{CheckMode, PackageUpdate};
+use crate::privilege::PrivilegeEscalation;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.PackageKit",
+    default_service = "org.freedesktop.PackageKit",
+    default_path = "/org/freedesktop/PackageKit"
+)]
+trait PackageKit {
+    fn create_transaction(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(interface = "org.freedesktop.PackageKit.Transaction", default_service = "org.freedesktop.PackageKit")]
+trait Transaction {
+    fn get_updates(&self, filter: u64) -> zbus::Result<()>;
+    fn resolve(&self, filter: u64, packages: &[&str]) -> zbus::Result<()>;
+    fn refresh_cache(&self, force: bool) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn package(&self, info: u32, package_id: &str, summary: &str) -> zbus::Result<()>;
+    #[zbus(signal)]
+    fn error_code(&self, code: u32, details: &str) -> zbus::Result<()>;
+    #[zbus(signal)]
+    fn finished(&self, exit: u32, runtime: u32) -> zbus::Result<()>;
+}
+
+/// D-Bus activation file, present wherever the daemon is installed.
+const SERVICE_FILE: &str = "/usr/share/dbus-1/system-services/org.freedesktop.PackageKit.service";
+
+/// Transactions resolving dependencies of a large backlog take a while,
+/// but one that never finishes must not stall the checks.
+const TRANSACTION_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+// PkFilterEnum bits
+const FILTER_NONE: u64 = 1 << 1;
+const FILTER_INSTALLED: u64 = 1 << 2;
+
+// PkExitEnum::Success
+const EXIT_SUCCESS: u32 = 1;
+
+// PkInfoEnum values of update kinds and severities
+const INFO_LOW: u32 = 3;
+const INFO_ENHANCEMENT: u32 = 4;
+const INFO_NORMAL: u32 = 5;
+const INFO_BUGFIX: u32 = 6;
+const INFO_IMPORTANT: u32 = 7;
+const INFO_SECURITY: u32 = 8;
+const INFO_BLOCKED: u32 = 9;
+const INFO_CRITICAL: u32 = 26;
+
+/// Any distribution, through the PackageKit daemon.
+pub struct PackageKit;
+
+impl Backend for PackageKit {
+    fn name(&self) -> &str {
+        "packagekit"
+    }
+
+    /// A D-Bus service, pkcon is packaged separately.
+    fn detect(&self) -> bool {
+        Path::new(SERVICE_FILE).exists()
+    }
+
+    /// The daemon authorizes through polkit.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { selective_update: true, metadata_refresh: true, ..Capabilities::default() }
+    }
+
+    /// Over D-Bus, not a command.
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(get_updates(context.mode))
+    }
+
+    fn update_command(&self, _privilege: PrivilegeEscalation) -> Option<String> {
+        Some("pkcon update".to_string())
+    }
+
+    fn packages_update_command(&self, _privilege: PrivilegeEscalation, packages: &[String]) -> Option<String> {
+        if packages.is_empty() {
+            return None;
+        }
+        Some(format!("pkcon update {}", packages.join(" ")))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; 'pkcon repair' fixes a broken package database."
+    }
+}
+
+/// Pending updates as the distribution's PackageKit backend reports them,
+/// with the installed versions resolved in a second transaction.
+pub async fn get_updates(mode: CheckMode) -> Result<Vec<PackageUpdate>> {
+    let connection = zbus::Connection::system().await?;
+
+    // Refreshing the metadata is allowed to local users through polkit
+    if mode == CheckMode::Refresh {
+        let (transaction, events) = new_transaction(&connection).await?;
+        transaction.refresh_cache(true).await?;
+        finish(events).await?;
+    }
+
+    let (transaction, events) = new_transaction(&connection).await?;
+    transaction.get_updates(FILTER_NONE).await?;
+    let mut updates: Vec<PackageUpdate> = finish(events).await?
+        .into_iter()
+        .filter_map(|(info, package_id)| update(info, &package_id))
+        .collect();
+    if updates.is_empty() {
+        return Ok(updates);
+    }
+
+    let names: Vec<&str> = updates.iter().map(|update| update.name.as_str()).collect();
+    let (transaction, events) = new_transaction(&connection).await?;
+    transaction.resolve(FILTER_INSTALLED, &names).await?;
+    let installed: HashMap<String, String> = finish(events).await?
+        .into_iter()
+        .filter_map(|(_, package_id)| {
+            let (name, version, _) = split_package_id(&package_id)?;
+            Some((name.to_string(), version.to_string()))
+        })
+        .collect();
+    for update in &mut updates {
+        if let Some(version) = installed.get(&update.name) {
+            update.current_version = version.clone();
+        }
+    }
+    Ok(updates)
+}
+
+enum Event {
+    Package(u32, String),
+    Error(String),
+    Finished(u32),
+}
+
+/// A new transaction, with its signals subscribed to before any method is
+/// called on it.
+async fn new_transaction(connection: &zbus::Connection) -> Result<(TransactionProxy<'static>, impl Stream<Item = Event>)> {
+    let path = PackageKitProxy::new(connection).await?.create_transaction().await?;
+    let proxy = TransactionProxy::builder(connection).path(path)?.build().await?;
+
+    let packages = proxy.receive_package().await?.filter_map(|signal| async move {
+        let args = signal.args().ok()?;
+        Some(Event::Package(args.info, args.package_id.to_string()))
+    });
+    let errors = proxy.receive_error_code().await?.filter_map(|signal| async move {
+        let args = signal.args().ok()?;
+        Some(Event::Error(args.details.to_string()))
+    });
+    let finished = proxy.receive_finished().await?.filter_map(|signal| async move {
+        Some(Event::Finished(signal.args().ok()?.exit))
+    });
+    Ok((proxy, futures::stream::select(packages, futures::stream::select(errors, finished))))
+}
+
+/// Info and ID of the packages a transaction reported until it finished.
+async fn finish(events: impl Stream<Item = Event>) -> Result<Vec<(u32, String)>> {
+    let collect = async {
+        let mut packages = Vec::new();
+        let mut error = None;
+        let mut events = std::pin::pin!(events);
+        while let Some(event) = events.next().await {
+            match event {
+                Event::Package(info, package_id) => packages.push((info, package_id)),
+                Event::Error(details) => error = Some(details),
+                Event::Finished(EXIT_SUCCESS) => return Ok(packages),
+                Event::Finished(_) => break,
+            }
+        }
+        Err(anyhow!("PackageKit transaction failed: {}", error.as_deref().unwrap_or("no details")))
+    };
+    tokio::time::timeout(TRANSACTION_TIMEOUT, collect)
+        .await
+        .map_err(|_| anyhow!("PackageKit transaction did not finish within {} seconds", TRANSACTION_TIMEOUT.as_secs()))?
+}
+
+/// Name, version and data (the repository of an available package) of a
+/// "name;version;arch;data" package ID.
+fn split_package_id(package_id: &str) -> Option<(&str, &str, &str)> {
+    let mut fields = package_id.splitn(4, ';');
+    let name = fields.next().filter(|name| !name.is_empty())?;
+    let version = fields.next()?;
+    let _arch = fields.next()?;
+    Some((name, version, fields.next().unwrap_or_default()))
+}
+
+/// An update from a Package signal. PackageKit 1.2.8 and later send the
+/// update's severity in the upper 16 bits of `info` and its kind in the
+/// lower; earlier releases only the kind, which doubles as severity.
+fn update(info: u32, package_id: &str) -> Option<PackageUpdate> {
+    let (name, version, data) = split_package_id(package_id)?;
+    let kind = info & 0xffff;
+    let severity = match info >> 16 {
+        0 => kind,
+        severity => severity,
+    };
+    Some(PackageUpdate {
+        name: name.to_string(),
+        current_version: "unknown".to_string(),
+        new_version: version.to_string(),
+        is_security: kind == INFO_SECURITY || severity == INFO_CRITICAL,
+        is_ignored: kind == INFO_BLOCKED,
+        repository: Some(data).filter(|data| !data.is_empty()).map(str::to_string),
+        severity: severity_name(severity).map(str::to_string),
+        ..Default::default()
+    })
+}
+
+/// Normal is what most updates are, so it is left unmarked.
+fn severity_name(info: u32) -> Option<&'static str> {
+    match info {
+        INFO_LOW => Some("low"),
+        INFO_ENHANCEMENT => Some("enhancement"),
+        INFO_BUGFIX => Some("bugfix"),
+        INFO_IMPORTANT => Some("important"),
+        INFO_SECURITY => Some("security"),
+        INFO_CRITICAL => Some("critical"),
+        INFO_NORMAL => None,
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_package_signals() {
+        let firefox = update(INFO_SECURITY, "firefox;119.0-1.fc39;x86_64;updates").expect("update");
+        assert_eq!((firefox.name.as_str(), firefox.new_version.as_str()), ("firefox", "119.0-1.fc39"));
+        assert_eq!(firefox.repository.as_deref(), Some("updates"));
+        assert!(firefox.is_security);
+        assert_eq!(firefox.severity.as_deref(), Some("security"));
+
+        // Kind and severity packed by newer releases
+        let kernel = update(INFO_IMPORTANT << 16 | INFO_BUGFIX, "kernel;6.5.12-300.fc39;x86_64;updates").expect("update");
+        assert_eq!(kernel.severity.as_deref(), Some("important"));
+        assert!(!kernel.is_security);
+
+        let blocked = update(INFO_BLOCKED, "mesa-libGL;23.2.1-2.fc39;x86_64;updates").expect("update");
+        assert!(blocked.is_ignored);
+        assert_eq!(update(INFO_NORMAL, "bash;5.2.21-1.fc39;x86_64;").expect("update").repository, None);
+        assert!(update(INFO_NORMAL, ";1.0;x86_64;updates").is_none());
+        assert_eq!(split_package_id("bash;5.2.15-5.fc39;x86_64;installed:fedora"), Some(("bash", "5.2.15-5.fc39", "installed:fedora")));
+    }
+}
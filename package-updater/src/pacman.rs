@@ -0,0 +1,162 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+
+use crate::backend::{self, Backend, Capabilities, CheckContext};
+use crate::merge::Source;
+use crate::package_manager::{CheckMode, PackageUpdate};
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::shell_quote;
+
+/// Arch Linux's pacman, or an AUR helper (paru, yay) that checks and
+/// installs the AUR's updates along with the repositories'.
+pub struct Pacman {
+    pub helper: Option<&'static str>,
+}
+
+impl Backend for Pacman {
+    fn name(&self) -> &str {
+        self.helper.unwrap_or("pacman")
+    }
+
+    fn detect(&self) -> bool {
+        backend::on_path(self.name())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            aur: self.helper.is_some(),
+            preflight: true,
+            selective_update: true,
+            metadata_refresh: true,
+            applies_on_reboot: false,
+            needs_privileges: true,
+        }
+    }
+
+    /// checkupdates always syncs a temporary copy of the databases unless
+    /// told not to.
+    fn check_command(&self, mode: CheckMode) -> Option<(&str, Vec<&str>)> {
+        Some(("checkupdates", if mode == CheckMode::Quick { vec!["--nosync"] } else { vec![] }))
+    }
+
+    /// "package 1.0.0-1 -> 1.0.1-1 [ignored]" or "package 1.0.1-1".
+    fn parse_line(&self, line: &str) -> Option<PackageUpdate> {
+        if is_diagnostic(line) {
+            return None;
+        }
+
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if line.contains(" -> ") {
+            if parts.len() >= 4 && parts[2] == "->" {
+                return Some(PackageUpdate {
+                    is_ignored: parts.get(4) == Some(&"[ignored]"),
+                    ..PackageUpdate::new(parts[0], parts[1], parts[3])
+                });
+            }
+        } else if parts.len() >= 2 {
+            return Some(PackageUpdate::new(parts[0], "unknown", parts[1]));
+        }
+        None
+    }
+
+    fn is_noise(&self, line: &str) -> bool {
+        is_diagnostic(line)
+    }
+
+    /// checkupdates returns 2, paru, yay and `pacman -Qu` return 1.
+    fn no_updates_code(&self, program: &str) -> Option<i32> {
+        Some(if program == "checkupdates" { 2 } else { 1 })
+    }
+
+    fn aur_check_command(&self) -> Option<(&str, Vec<&str>)> {
+        Some((self.helper?, vec!["-Qu", "--aur", "--color", "never"]))
+    }
+
+    /// checkupdates can't be pointed at a root: the root's sync databases
+    /// as of its last sync.
+    fn root_check_command(&self, root: &str) -> Result<(&str, Vec<String>)> {
+        let dbpath = format!("{}/var/lib/pacman", root);
+        let config = format!("{}/etc/pacman.conf", root);
+        let args = ["--root", root, "--dbpath", &dbpath, "--config", &config, "-Qu", "--color", "never"];
+        Ok(("pacman", args.map(str::to_string).to_vec()))
+    }
+
+    /// checkupdates doesn't say which repository an update comes from,
+    /// `pacman -Sl` lists them all as "repository package version", and
+    /// `pacman -Qg` prints "group package" for every installed package in
+    /// a group.
+    fn annotate<'a>(&'a self, context: &'a CheckContext<'a>, packages: &'a mut [PackageUpdate]) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            if let Ok(output) = context.runner.run("pacman", &["-Sl"]).await {
+                if output.success() {
+                    // A package in several repositories comes from the first one
+                    let mut repositories: HashMap<&str, &str> = HashMap::new();
+                    for line in output.stdout.lines() {
+                        let mut parts = line.split_whitespace();
+                        if let (Some(repository), Some(package), Some(_)) = (parts.next(), parts.next(), parts.next()) {
+                            repositories.entry(package).or_insert(repository);
+                        }
+                    }
+                    for package in packages.iter_mut().filter(|p| p.source != Source::Aur) {
+                        package.repository = repositories.get(package.name.as_str()).map(|r| r.to_string());
+                    }
+                }
+            }
+
+            if let Ok(output) = context.runner.run("pacman", &["-Qg"]).await {
+                if output.success() {
+                    let mut groups: HashMap<&str, &str> = HashMap::new();
+                    for line in output.stdout.lines() {
+                        if let Some((group, package)) = line.split_once(' ') {
+                            groups.entry(package.trim()).or_insert(group);
+                        }
+                    }
+                    for package in packages.iter_mut() {
+                        package.group = groups.get(package.name.as_str()).map(|g| g.to_string());
+                    }
+                }
+            }
+        })
+    }
+
+    /// AUR helpers elevate on their own and must not run as root.
+    fn update_command(&self, privilege: PrivilegeEscalation) -> Option<String> {
+        Some(match (self.helper, privilege.aur_helper_flag()) {
+            (None, _) => privilege.wrap("pacman -Syu"),
+            (Some(helper), Some(flag)) => format!("{} {} -Syu", helper, flag),
+            (Some(helper), None) => format!("{} -Syu", helper),
+        })
+    }
+
+    /// Arch doesn't support partial upgrades: refreshing the sync
+    /// databases for the selected packages upgrades the rest too.
+    fn packages_update_command(&self, privilege: PrivilegeEscalation, packages: &[String]) -> Option<String> {
+        if packages.is_empty() {
+            return None;
+        }
+        let names = packages.join(" ");
+        Some(match (self.helper, privilege.aur_helper_flag()) {
+            (None, _) => privilege.wrap(&format!("pacman -Syu --needed {}", names)),
+            (Some(helper), Some(flag)) => format!("{} {} -Syu --needed {}", helper, flag, names),
+            (Some(helper), None) => format!("{} -Syu --needed {}", helper, names),
+        })
+    }
+
+    /// pacman's --sysroot runs the root's own configuration and install
+    /// scripts. Updated like pacman, AUR helpers don't support a root.
+    fn root_update_command(&self, privilege: PrivilegeEscalation, root: &str) -> Option<String> {
+        Some(privilege.wrap(&format!("pacman --sysroot {} -Syu", shell_quote(root))))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error: file conflicts, an outdated archlinux-keyring or a package held by IgnorePkg are the usual causes."
+    }
+}
+
+/// pacman/makepkg diagnostics and AUR helper status lines.
+fn is_diagnostic(line: &str) -> bool {
+    line.starts_with("warning:") || line.starts_with("error:") ||
+    line.starts_with("::") || line.starts_with("==>") ||
+    line.trim_start().starts_with("->")
+}
@@ -0,0 +1,246 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+
+use crate::backend::{self, Backend, Capabilities, CheckContext};
+use crate::merge::Source;
+use crate::package_manager::PackageUpdate;
+use crate::privilege::PrivilegeEscalation;
+
+/// Python applications installed with pipx.
+pub struct Pipx;
+
+impl Backend for Pipx {
+    fn name(&self) -> &str {
+        "pipx"
+    }
+
+    fn detect(&self) -> bool {
+        backend::on_path("pipx")
+    }
+
+    /// Installs into the user's home.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { selective_update: true, ..Capabilities::default() }
+    }
+
+    /// pipx can't tell what is outdated, so each application's version is
+    /// looked up on PyPI. A failed lookup skips that application; the check
+    /// fails only if every lookup failed.
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(async move {
+            let output = context.runner.run("pipx", &["list", "--json"]).await?;
+            if !output.success() {
+                return Err(anyhow!("Failed to check for updates (exit {}): {}", output.code.unwrap_or(-1), output.stderr));
+            }
+            let installed = parse_installed(&output.stdout)?;
+
+            let urls: Vec<String> = installed.iter().map(|(_, package, _)| pypi_url(package)).collect();
+            let requests: Vec<Vec<&str>> = urls.iter()
+                .map(|url| {
+                    let mut args = vec!["-sSf", "--max-time", "15"];
+                    if !context.proxy.is_empty() {
+                        args.extend(["--proxy", context.proxy]);
+                    }
+                    args.push(url);
+                    args
+                })
+                .collect();
+            let responses = futures::future::join_all(requests.iter().map(|args| context.runner.run("curl", args))).await;
+
+            let mut updates = Vec::new();
+            let mut failed = Vec::new();
+            for ((venv, package, version), response) in installed.iter().zip(responses) {
+                let latest = match response {
+                    Ok(response) if response.success() => latest_version(&response.stdout),
+                    Ok(response) => {
+                        failed.push(format!("{}: {}", package, response.stderr.trim()));
+                        continue;
+                    }
+                    Err(e) => {
+                        failed.push(format!("{}: {}", package, e));
+                        continue;
+                    }
+                };
+                let Some(latest) = latest.filter(|latest| is_newer(latest, version)) else {
+                    continue;
+                };
+                updates.push(PackageUpdate {
+                    name: venv.clone(),
+                    current_version: version.clone(),
+                    new_version: latest,
+                    source: Source::Pipx,
+                    ..Default::default()
+                });
+            }
+
+            if !failed.is_empty() && failed.len() == installed.len() {
+                return Err(anyhow!("Failed to look up the pipx applications on PyPI: {}", failed.join("; ")));
+            }
+            for failure in &failed {
+                eprintln!("Failed to look up {} on PyPI", failure);
+            }
+            Ok(updates)
+        })
+    }
+
+    fn update_command(&self, _privilege: PrivilegeEscalation) -> Option<String> {
+        Some("pipx upgrade-all".to_string())
+    }
+
+    /// Releases before 1.5 upgrade one environment per call.
+    fn packages_update_command(&self, _privilege: PrivilegeEscalation, packages: &[String]) -> Option<String> {
+        if packages.is_empty() {
+            return None;
+        }
+        Some(packages.iter().map(|name| format!("pipx upgrade {}", name)).collect::<Vec<_>>().join(" && "))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; 'pipx reinstall <name>' rebuilds an environment after a Python upgrade."
+    }
+}
+
+/// Applications installed with pipx, as (environment, package, installed
+/// version). Packages installed from a URL or a local path have no release
+/// on PyPI to compare with and are left out.
+pub fn parse_installed(json: &str) -> Result<Vec<(String, String, String)>> {
+    let root: serde_json::Value = serde_json::from_str(json)?;
+    let venvs = root.get("venvs")
+        .and_then(|venvs| venvs.as_object())
+        .ok_or_else(|| anyhow!("pipx list --json output has no environments"))?;
+
+    let installed = venvs.iter()
+        .filter_map(|(venv, value)| {
+            let main = value.get("metadata")?.get("main_package")?;
+            let field = |name: &str| main.get(name).and_then(|v| v.as_str());
+            let source = field("package_or_url")?;
+            if source.contains('/') || source.contains(':') {
+                return None;
+            }
+            Some((venv.clone(), field("package")?.to_string(), field("package_version")?.to_string()))
+        })
+        .collect();
+    Ok(installed)
+}
+
+/// PyPI's JSON API entry of a project.
+pub fn pypi_url(package: &str) -> String {
+    format!("https://pypi.org/pypi/{}/json", package)
+}
+
+/// Latest release in a PyPI JSON API response, pre-releases excluded.
+pub fn latest_version(json: &str) -> Option<String> {
+    let root: serde_json::Value = serde_json::from_str(json).ok()?;
+    root.get("info")?.get("version")?.as_str().map(str::to_string)
+}
+
+/// Whether `latest` is an upgrade from `installed` by PEP 440's ordering.
+/// An installed pre-release of a later version is not downgraded, and
+/// versions that can't be ordered are never reported.
+pub fn is_newer(latest: &str, installed: &str) -> bool {
+    match (Version::parse(latest), Version::parse(installed)) {
+        (Some(latest), Some(installed)) => latest > installed,
+        _ => false,
+    }
+}
+
+/// A version as PEP 440 orders them. Fields compare in this order;
+/// local labels ("+ubuntu1") don't order releases and are dropped.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Version {
+    epoch: u64,
+    /// Without trailing zeros, 1.0 is 1.0.0
+    release: Vec<u64>,
+    /// (0, 0) for a development release of the release itself, then
+    /// alpha (1), beta (2), release candidate (3) and the final release (4)
+    pre: (u8, u64),
+    post: Option<u64>,
+    /// (0, n) for .devN, (1, 0) without
+    dev: (u8, u64),
+}
+
+impl Version {
+    /// "1!2.0.0rc1.post2.dev3+local", with PEP 440's alternative spellings
+    /// ("2.0-beta.1", "1.0-1" for a post-release).
+    fn parse(version: &str) -> Option<Self> {
+        // Longer labels first, "rc" before "r"
+        const LABELS: [&str; 12] = ["alpha", "a", "beta", "b", "rc", "c", "preview", "pre", "post", "rev", "r", "dev"];
+
+        let version = version.trim().to_ascii_lowercase();
+        let version = version.strip_prefix('v').unwrap_or(&version);
+        let version = version.split('+').next()?;
+        let (epoch, version) = match version.split_once('!') {
+            Some((epoch, rest)) => (epoch.parse().ok()?, rest),
+            None => (0, version),
+        };
+
+        let release_end = version.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(version.len());
+        let mut release: Vec<u64> = version[..release_end].trim_end_matches('.')
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect::<Option<_>>()?;
+        while release.len() > 1 && release.last() == Some(&0) {
+            release.pop();
+        }
+
+        let (mut pre, mut post, mut dev) = (None, None, None);
+        let mut rest = &version[release_end..];
+        while !rest.is_empty() {
+            // "1.0-1" is the first post-release
+            if let Some(number) = rest.strip_prefix('-').filter(|number| number.starts_with(|c: char| c.is_ascii_digit())) {
+                let end = number.find(|c: char| !c.is_ascii_digit()).unwrap_or(number.len());
+                post = Some(number[..end].parse().ok()?);
+                rest = &number[end..];
+                continue;
+            }
+            let segment = rest.trim_start_matches(['.', '-', '_']);
+            let label = LABELS.into_iter().find(|label| segment.starts_with(label))?;
+            let number = segment[label.len()..].trim_start_matches(['.', '-', '_']);
+            let end = number.find(|c: char| !c.is_ascii_digit()).unwrap_or(number.len());
+            let value: u64 = if end == 0 { 0 } else { number[..end].parse().ok()? };
+            match label {
+                "alpha" | "a" => pre = Some((1, value)),
+                "beta" | "b" => pre = Some((2, value)),
+                "rc" | "c" | "preview" | "pre" => pre = Some((3, value)),
+                "post" | "rev" | "r" => post = Some(value),
+                _ => dev = Some(value),
+            }
+            rest = &number[end..];
+        }
+
+        let pre = match (pre, post, dev) {
+            (Some(pre), _, _) => pre,
+            (None, None, Some(_)) => (0, 0),
+            (None, _, _) => (4, 0),
+        };
+        let dev = dev.map_or((1, 0), |dev| (0, dev));
+        Some(Self { epoch, release, pre, post, dev })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_releases() {
+        assert!(is_newer("24.2.0", "23.12.1"));
+        assert!(is_newer("1.10.0", "1.9.2"));
+        assert!(is_newer("2.0.0", "2.0.0rc1"));
+        assert!(!is_newer("3.2.2", "3.2.2"));
+        // A beta of the next release stays installed
+        assert!(!is_newer("1.7.1", "1.8.0b1"));
+        // Post-releases come after their release, development releases before
+        assert!(!is_newer("2.0.0", "2.0.0.post1"));
+        assert!(is_newer("2.0.0.post1", "2.0.0"));
+        assert!(is_newer("1.0-1", "1.0"));
+        assert!(is_newer("1.0a1", "1.0.dev3"));
+        assert!(is_newer("1.0rc1", "1.0b2"));
+        assert!(!is_newer("1.0", "1.0.0"));
+        assert!(is_newer("1!0.9", "2.0"));
+        // A local label is no update, a version that can't be ordered is skipped
+        assert!(!is_newer("2.0", "2.0+local.1"));
+        assert!(!is_newer("2.0", "nightly-2024"));
+        assert_eq!(latest_version(r#"{"info": {"name": "black", "version": "24.2.0"}}"#).as_deref(), Some("24.2.0"));
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::app_id;
+use crate::backend::{Backend, CheckContext};
+use crate::merge::Source;
+use crate::package_manager::{CheckMode, PackageUpdate};
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::{shell_quote, CommandOutput};
+
+/// Version of the plugin protocol the applet speaks. Plugins answer with
+/// the version they were written for; newer ones are rejected.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// How long `<plugin> check` may run before it is killed and its check
+/// fails, so a hanging plugin doesn't hold up the others.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Directories the plugins are discovered in: the data directory, then
+/// `sources.d` in the config directory for scripts kept with the settings.
+pub fn plugin_dirs() -> Vec<PathBuf> {
+    let home = || std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    let data_home = std::env::var("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(home()).join(".local/share"));
+    let config_home = std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(home()).join(".config"));
+    vec![
+        data_home.join(app_id::file_prefix()).join("plugins"),
+        config_home.join(app_id::file_prefix()).join("sources.d"),
+    ]
+}
+
+/// What a plugin prints for `check`: the versioned object, or for simple
+/// scripts just the list of packages.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Response {
+    Versioned {
+        protocol: u32,
+        #[serde(default)]
+        packages: Vec<Package>,
+    },
+    Packages(Vec<Package>),
+}
+
+#[derive(Debug, Deserialize)]
+struct Package {
+    name: String,
+    #[serde(default, alias = "current")]
+    current_version: Option<String>,
+    #[serde(alias = "new")]
+    new_version: String,
+    #[serde(default)]
+    is_security: bool,
+}
+
+/// An executable in a plugin directory, a backend outside the crate:
+/// `<plugin> check` prints the updates as JSON, `<plugin> update` installs
+/// them in the terminal.
+#[derive(Clone, Debug)]
+pub struct Plugin {
+    /// File name without extension, also the source its updates are
+    /// listed under
+    name: String,
+    path: PathBuf,
+}
+
+impl Plugin {
+    fn new(path: &Path) -> Option<Self> {
+        let name = path.file_stem()?.to_str()?.to_string();
+        Some(Self { name, path: path.to_path_buf() })
+    }
+
+    fn path(&self) -> &str {
+        self.path.to_str().unwrap_or_default()
+    }
+}
+
+impl Backend for Plugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn check_command(&self, _mode: CheckMode) -> Option<(&str, Vec<&str>)> {
+        Some((self.path(), vec!["check"]))
+    }
+
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(async move {
+            let output = tokio::time::timeout(CHECK_TIMEOUT, context.runner.run(self.path(), &["check"]))
+                .await
+                .map_err(|_| anyhow!("Plugin {} didn't finish its check within {} seconds", self.name, CHECK_TIMEOUT.as_secs()))??;
+            self.parse(&output)
+        })
+    }
+
+    fn parse(&self, output: &CommandOutput) -> Result<Vec<PackageUpdate>> {
+        if !output.success() {
+            return Err(anyhow!("Failed to check for updates (exit {}): {}", output.code.unwrap_or(-1), output.stderr));
+        }
+        let response: Response = serde_json::from_str(&output.stdout)
+            .map_err(|e| anyhow!("Plugin {} printed invalid JSON: {}", self.name, e))?;
+        let packages = match response {
+            Response::Versioned { protocol, .. } if protocol > PROTOCOL_VERSION => {
+                return Err(anyhow!(
+                    "Plugin {} needs protocol version {}, this applet supports {}",
+                    self.name,
+                    protocol,
+                    PROTOCOL_VERSION
+                ));
+            }
+            Response::Versioned { packages, .. } | Response::Packages(packages) => packages,
+        };
+
+        Ok(packages.into_iter()
+            .map(|package| PackageUpdate {
+                name: package.name,
+                current_version: package.current_version.unwrap_or_else(|| "unknown".to_string()),
+                new_version: package.new_version,
+                is_security: package.is_security,
+                source: Source::Custom(self.name.clone()),
+                ..Default::default()
+            })
+            .collect())
+    }
+
+    /// The plugin elevates itself if it needs to.
+    fn update_command(&self, _privilege: PrivilegeEscalation) -> Option<String> {
+        Some(format!("{} update", shell_quote(self.path())))
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    path.metadata().is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// The executables in `dir`, by name. Other files, e.g. a README next to
+/// the plugins, are skipped.
+pub fn discover(dir: &Path) -> Vec<Plugin> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut plugins: Vec<Plugin> = entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_executable(path))
+        .filter_map(|path| Plugin::new(&path))
+        .collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    plugins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovers_executables_and_reads_their_updates() {
+        let dir = std::env::temp_dir().join(format!("package-updater-plugins-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create directory");
+        for (name, mode) in [("conda", 0o755), ("flakes.sh", 0o755), ("README", 0o644)] {
+            std::fs::write(dir.join(name), "").expect("write file");
+            std::fs::set_permissions(dir.join(name), std::fs::Permissions::from_mode(mode)).expect("set permissions");
+        }
+        let plugins = discover(&dir);
+        std::fs::remove_dir_all(&dir).expect("remove directory");
+        assert_eq!(plugins.iter().map(Backend::name).collect::<Vec<_>>(), ["conda", "flakes"]);
+
+        let conda = &plugins[0];
+        let updates = conda.parse(&CommandOutput::new(0, include_str!("../tests/fixtures/plugin/check.json"), "")).expect("valid response");
+        let found: Vec<(&str, &str, &str, bool)> = updates.iter()
+            .map(|p| (p.name.as_str(), p.current_version.as_str(), p.new_version.as_str(), p.is_security))
+            .collect();
+        assert_eq!(found, [("numpy", "1.26.4", "2.0.1", false), ("openssl", "unknown", "3.3.2", true)]);
+        assert!(updates.iter().all(|p| p.source == Source::Custom("conda".to_string())));
+
+        let flakes = plugins[1].parse(&CommandOutput::new(0, r#"[{"name": "nixpkgs", "current": "a1b2c3d", "new": "e4f5a6b"}]"#, "")).expect("bare list");
+        assert_eq!((flakes[0].current_version.as_str(), flakes[0].new_version.as_str()), ("a1b2c3d", "e4f5a6b"));
+        assert_eq!(flakes[0].source, Source::Custom("flakes".to_string()));
+
+        assert!(conda.parse(&CommandOutput::new(0, r#"{"protocol": 2, "packages": []}"#, "")).is_err());
+        assert!(conda.parse(&CommandOutput::new(0, "not json", "")).is_err());
+        assert!(conda.parse(&CommandOutput::new(1, "", "")).is_err());
+    }
+}
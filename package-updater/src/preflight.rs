@@ -0,0 +1,203 @@
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::runner::{CommandRunner, SystemRunner};
+
+/// A file the update would install over an existing one it doesn't own,
+/// which makes pacman abort with "exists in filesystem".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileConflict {
+    pub package: String,
+    pub path: String,
+    /// Installed package owning the file, `None` for untracked files
+    pub owner: Option<String>,
+}
+
+impl FileConflict {
+    pub fn describe(&self) -> String {
+        match &self.owner {
+            Some(owner) => format!("{}: {} is owned by {}", self.package, self.path, owner),
+            None => format!("{}: {} exists in filesystem", self.package, self.path),
+        }
+    }
+}
+
+/// Result of a dry run of `pacman -Su` before the real one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preflight {
+    /// Dependency breakage and package conflicts pacman reports
+    pub problems: Vec<String>,
+    pub file_conflicts: Vec<FileConflict>,
+    /// A files database (`pacman -Fy`) was available to look for file conflicts
+    pub files_checked: bool,
+}
+
+impl Preflight {
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty() && self.file_conflicts.is_empty()
+    }
+}
+
+/// Resolves the pending upgrade without running it, against the databases
+/// checkupdates synced, and compares the file lists of the packages it
+/// would install with the files on disk.
+pub async fn check() -> Result<Preflight> {
+    let db_path = checkupdates_db().await;
+    let mut args = vec!["LC_ALL=C", "pacman", "-Su", "--print", "--print-format", "%n", "--noconfirm"];
+    if let Some(db_path) = &db_path {
+        args.extend(["--dbpath", db_path.as_str()]);
+    }
+
+    let output = SystemRunner.run("env", &args).await?;
+    if !output.success() {
+        let problems = parse_transaction_errors(&output.stderr);
+        if problems.is_empty() {
+            return Err(anyhow!("pacman transaction check failed: {}", output.stderr.trim()));
+        }
+        return Ok(Preflight { problems, ..Default::default() });
+    }
+
+    let targets: Vec<&str> = output.stdout.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("::"))
+        .collect();
+    if targets.is_empty() {
+        return Ok(Preflight::default());
+    }
+
+    // The system files database, if the user keeps one with `pacman -Fy`
+    let new_files = run_listing(&[&["LC_ALL=C", "pacman", "-Fl"][..], &targets].concat()).await;
+    if new_files.is_empty() {
+        return Ok(Preflight::default());
+    }
+    let owned: HashSet<(String, String)> = run_listing(&[&["LC_ALL=C", "pacman", "-Ql"][..], &targets].concat())
+        .await
+        .into_iter()
+        .collect();
+
+    let candidates: Vec<(String, String)> = new_files.into_iter()
+        .filter(|entry| !owned.contains(entry))
+        .filter(|(_, path)| Path::new(path).symlink_metadata().is_ok())
+        .collect();
+
+    let owners = if candidates.is_empty() {
+        HashMap::new()
+    } else {
+        let mut args = vec!["LC_ALL=C", "pacman", "-Qo"];
+        args.extend(candidates.iter().map(|(_, path)| path.as_str()));
+        // Exits non-zero if any file is unowned, the owned ones are still listed
+        SystemRunner.run("env", &args).await
+            .map(|output| parse_owners(&output.stdout))
+            .unwrap_or_default()
+    };
+
+    Ok(Preflight {
+        problems: Vec::new(),
+        file_conflicts: file_conflicts(candidates, &owners, &targets),
+        files_checked: true,
+    })
+}
+
+/// The temporary databases checkupdates keeps between runs.
+async fn checkupdates_db() -> Option<String> {
+    let path = match std::env::var("CHECKUPDATES_DB") {
+        Ok(path) => path,
+        Err(_) => {
+            let uid = SystemRunner.run("id", &["-u"]).await.ok()?.stdout.trim().to_string();
+            let tmp = std::env::var("TMPDIR").unwrap_or_else(|_| "/tmp".to_string());
+            format!("{}/checkup-db-{}", tmp, uid)
+        }
+    };
+    Path::new(&path).join("sync").is_dir().then_some(path)
+}
+
+async fn run_listing(args: &[&str]) -> Vec<(String, String)> {
+    match SystemRunner.run("env", args).await {
+        Ok(output) => parse_file_list(&output.stdout),
+        Err(e) => {
+            eprintln!("Failed to list package files: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Files of an update that would overwrite something the transaction
+/// doesn't own. Files moving between two upgraded packages are fine.
+fn file_conflicts(candidates: Vec<(String, String)>, owners: &HashMap<String, String>, targets: &[&str]) -> Vec<FileConflict> {
+    candidates.into_iter()
+        .filter_map(|(package, path)| {
+            let owner = owners.get(&path).cloned();
+            if owner.as_deref().is_some_and(|owner| targets.contains(&owner)) {
+                return None;
+            }
+            Some(FileConflict { package, path, owner })
+        })
+        .collect()
+}
+
+/// "error: failed to prepare transaction (could not satisfy dependencies)"
+/// followed by ":: installing foo (2.0-1) breaks dependency 'foo=1.0' required by bar"
+/// Other failures (locked database, missing mirror) carry no details.
+fn parse_transaction_errors(stderr: &str) -> Vec<String> {
+    stderr.lines()
+        .filter_map(|line| line.strip_prefix(":: "))
+        .map(str::to_string)
+        .collect()
+}
+
+/// `pacman -Fl` ("foo usr/bin/foo") and `pacman -Ql` ("foo /usr/bin/foo")
+/// output as package and absolute path, without directories.
+fn parse_file_list(output: &str) -> Vec<(String, String)> {
+    output.lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter(|(_, path)| !path.ends_with('/'))
+        .map(|(package, path)| {
+            let path = if path.starts_with('/') { path.to_string() } else { format!("/{}", path) };
+            (package.to_string(), path)
+        })
+        .collect()
+}
+
+/// `pacman -Qo` output: "/usr/bin/foo is owned by foo 1.0-1".
+fn parse_owners(output: &str) -> HashMap<String, String> {
+    output.lines()
+        .filter_map(|line| line.split_once(" is owned by "))
+        .filter_map(|(path, owner)| Some((path.to_string(), owner.split_whitespace().next()?.to_string())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_transaction_errors() {
+        assert_eq!(
+            parse_transaction_errors(include_str!("../tests/fixtures/pacman/sup_print_unsatisfied.txt")),
+            vec![
+                "installing libfoo (2.0-1) breaks dependency 'libfoo=1.0' required by bar",
+                "python-old-1.0-1 and python-new-2.0-1 are in conflict",
+            ]
+        );
+        assert!(parse_transaction_errors("error: failed to init transaction (unable to lock database)\n").is_empty());
+    }
+
+    #[test]
+    fn finds_file_conflicts() {
+        let new_files = parse_file_list("foo usr/\nfoo usr/bin/foo\nfoo usr/lib/libfoo.so\nbar usr/bin/bar\n");
+        assert_eq!(new_files[0], ("foo".to_string(), "/usr/bin/foo".to_string()));
+        assert_eq!(new_files.len(), 3);
+
+        let owners = parse_owners("/usr/lib/libfoo.so is owned by libfoo-legacy 1.0-1\n/usr/bin/bar is owned by baz 2.1-1\n");
+        let conflicts = file_conflicts(new_files, &owners, &["foo", "bar", "baz"]);
+        assert_eq!(conflicts, vec![
+            FileConflict { package: "foo".to_string(), path: "/usr/bin/foo".to_string(), owner: None },
+            FileConflict {
+                package: "foo".to_string(),
+                path: "/usr/lib/libfoo.so".to_string(),
+                owner: Some("libfoo-legacy".to_string()),
+            },
+        ]);
+    }
+}
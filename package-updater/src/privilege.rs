@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use tokio::process::Command as TokioCommand;
+
+use crate::backend;
+
+/// Groups that grant administrator rights through the default sudoers and
+/// polkit rules of the supported distributions.
+const ADMIN_GROUPS: [&str; 3] = ["wheel", "sudo", "admin"];
+
+/// Tool used to run the privileged parts of a system update.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PrivilegeEscalation {
+    #[default]
+    Sudo,
+    // systemd v256+
+    Run0,
+}
+
+impl PrivilegeEscalation {
+    pub const ALL: [PrivilegeEscalation; 2] = [PrivilegeEscalation::Sudo, PrivilegeEscalation::Run0];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PrivilegeEscalation::Sudo => "sudo",
+            PrivilegeEscalation::Run0 => "run0",
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        backend::on_path(self.name())
+    }
+
+    /// Prefixes a single command so it runs with elevated privileges.
+    ///
+    /// run0 does not read a sudoers-style option set, so the command is
+    /// passed after `--` to keep its flags from being taken as run0 options.
+    pub fn wrap(&self, command: &str) -> String {
+        match self {
+            PrivilegeEscalation::Sudo => format!("sudo {}", command),
+            PrivilegeEscalation::Run0 => format!("run0 -- {}", command),
+        }
+    }
+
+    /// Whether the current user can be expected to elevate with this tool.
+    ///
+    /// Never prompts: a passwordless `sudo -n` settles it directly, otherwise
+    /// membership in an administrator group is taken as the answer, since
+    /// asking sudo itself would need the password.
+    pub async fn user_can_elevate(&self) -> bool {
+        if *self == PrivilegeEscalation::Sudo {
+            let passwordless = TokioCommand::new("sudo")
+                .args(["-n", "true"])
+                .output()
+                .await
+                .map(|output| output.status.success())
+                .unwrap_or(false);
+            if passwordless {
+                return true;
+            }
+        }
+
+        match TokioCommand::new("id").arg("-Gn").output().await {
+            Ok(output) if output.status.success() => {
+                let groups = String::from_utf8_lossy(&output.stdout);
+                groups.split_whitespace().any(|group| ADMIN_GROUPS.contains(&group))
+            }
+            // Without an answer, keep the update actions rather than hiding them wrongly
+            _ => true,
+        }
+    }
+
+    /// Flag telling an AUR helper which tool to use for its own elevation.
+    pub fn aur_helper_flag(&self) -> Option<&'static str> {
+        match self {
+            PrivilegeEscalation::Sudo => None,
+            PrivilegeEscalation::Run0 => Some("--sudo run0"),
+        }
+    }
+}
+
+impl std::fmt::Display for PrivilegeEscalation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,81 @@
+use std::time::{Duration, Instant};
+
+use crate::runner::{CommandRunner, SystemRunner};
+
+/// The first check runs at the latest after this long, ready or not, so a
+/// machine that never reports online still gets checked.
+const READINESS_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Waits until the system can run a check after login or session restore:
+/// the network is online, optionally the graphical session has started (the
+/// keyring is unlocked with it), then `delay` more.
+pub async fn wait(delay: Duration, wait_for_session: bool) {
+    let started = Instant::now();
+    loop {
+        let online = is_online(network_target_active().await, connectivity().await.as_deref());
+        let session = !wait_for_session || graphical_session_active().await;
+        if online && session {
+            break;
+        }
+        if started.elapsed() >= READINESS_TIMEOUT {
+            eprintln!("System not ready after {} seconds, checking anyway", READINESS_TIMEOUT.as_secs());
+            break;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    tokio::time::sleep(delay).await;
+}
+
+/// Online if systemd reached `network-online.target` or NetworkManager has
+/// full connectivity. The target is only reached on systems where something
+/// pulls it in, so an inactive one only counts when NetworkManager says so
+/// too. Without NetworkManager (systemd-networkd, iwd, ConnMan) there is no
+/// way to tell, and waiting would only delay the check.
+fn is_online(target_active: Option<bool>, connectivity: Option<&str>) -> bool {
+    match (target_active, connectivity) {
+        (Some(true), _) => true,
+        (_, Some(state)) => state == "full",
+        (_, None) => true,
+    }
+}
+
+/// `None` without systemd.
+async fn network_target_active() -> Option<bool> {
+    let output = SystemRunner.run("systemctl", &["is-active", "--quiet", "network-online.target"]).await.ok()?;
+    Some(output.success())
+}
+
+/// NetworkManager's connectivity state ("full", "limited", "portal",
+/// "none"), `None` without NetworkManager.
+async fn connectivity() -> Option<String> {
+    let output = SystemRunner.run("nmcli", &["networking", "connectivity"]).await.ok()?;
+    output.success().then(|| output.stdout.trim().to_string())
+}
+
+/// Sessions started without systemd never reach the target, so its absence
+/// doesn't block.
+async fn graphical_session_active() -> bool {
+    match SystemRunner.run("systemctl", &["--user", "is-active", "--quiet", "graphical-session.target"]).await {
+        Ok(output) => output.success(),
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decides_whether_network_is_online() {
+        assert!(is_online(Some(true), None));
+        // network-online.target not pulled in, but NetworkManager is connected
+        assert!(is_online(Some(false), Some("full")));
+        // Captive portal
+        assert!(!is_online(Some(false), Some("portal")));
+        // Nothing pulls the target in and NetworkManager isn't running
+        assert!(is_online(Some(false), None));
+        assert!(is_online(None, None));
+    }
+}
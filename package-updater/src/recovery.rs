@@ -0,0 +1,118 @@
+use cosmic::iced::event::wayland::{self, LayerEvent, OutputEvent};
+use cosmic::iced::event::{Event, PlatformSpecific};
+use cosmic::iced::window::Id;
+use cosmic::widget::scrollable::RelativeOffset;
+use std::time::{Duration, Instant};
+
+use crate::app::PopupTab;
+
+/// Recoveries allowed within `ATTEMPT_WINDOW`. An error that keeps coming
+/// back is not fixed by recreating the surfaces, only by a restart.
+const MAX_ATTEMPTS: usize = 3;
+const ATTEMPT_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Time for the compositor to settle before the surfaces are recreated.
+pub const SETTLE_DELAY: Duration = Duration::from_secs(2);
+
+/// A surface the compositor took from the applet, as seen after it
+/// restarted or the display stack was updated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurfaceLoss {
+    /// The compositor closed a surface the applet didn't close
+    Closed(Id),
+    /// An output went away, with any surfaces on it
+    OutputRemoved,
+}
+
+impl SurfaceLoss {
+    /// Reads a loss from the windowing layer's events.
+    pub fn from_event(event: Event) -> Option<Self> {
+        match event {
+            Event::PlatformSpecific(PlatformSpecific::Wayland(wayland::Event::Layer(LayerEvent::Done, _, id))) => {
+                Some(SurfaceLoss::Closed(id))
+            }
+            Event::PlatformSpecific(PlatformSpecific::Wayland(wayland::Event::Output(OutputEvent::Removed, _))) => {
+                Some(SurfaceLoss::OutputRemoved)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            SurfaceLoss::Closed(_) => "the compositor closed the applet's surface",
+            SurfaceLoss::OutputRemoved => "the output of the applet's surfaces was removed",
+        }
+    }
+}
+
+/// What the user was looking at when the surfaces were lost.
+#[derive(Debug, Clone, Copy)]
+pub struct ViewSnapshot {
+    pub popup_open: bool,
+    pub tab: PopupTab,
+    pub package_list_offset: RelativeOffset,
+}
+
+#[derive(Debug, Default)]
+pub struct SurfaceRecovery {
+    attempts: Vec<Instant>,
+    /// View to restore once the surfaces are recreated
+    pending: Option<ViewSnapshot>,
+    /// Completed recoveries, part of the subscription IDs so each one
+    /// starts them anew
+    generation: u64,
+}
+
+impl SurfaceRecovery {
+    /// Starts a recovery, unless one is running or too many failed recently.
+    pub fn begin(&mut self, snapshot: ViewSnapshot, now: Instant) -> bool {
+        if self.pending.is_some() {
+            return true;
+        }
+        self.attempts.retain(|attempt| now.duration_since(*attempt) < ATTEMPT_WINDOW);
+        if self.attempts.len() >= MAX_ATTEMPTS {
+            return false;
+        }
+        self.attempts.push(now);
+        self.pending = Some(snapshot);
+        true
+    }
+
+    pub fn in_progress(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Ends the recovery, returning the view to restore.
+    pub fn finish(&mut self) -> Option<ViewSnapshot> {
+        let view = self.pending.take()?;
+        self.generation += 1;
+        Some(view)
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_recovery_attempts() {
+        let snapshot = ViewSnapshot { popup_open: true, tab: PopupTab::History, package_list_offset: RelativeOffset::START };
+        let mut recovery = SurfaceRecovery::default();
+        let start = Instant::now();
+
+        for i in 0..MAX_ATTEMPTS as u64 {
+            assert!(recovery.begin(snapshot, start + Duration::from_secs(i)));
+            assert!(recovery.in_progress());
+            assert_eq!(recovery.finish().map(|view| view.tab), Some(PopupTab::History));
+        }
+        assert!(!recovery.begin(snapshot, start + Duration::from_secs(60)));
+        assert!(!recovery.in_progress());
+        assert!(recovery.begin(snapshot, start + ATTEMPT_WINDOW + Duration::from_secs(1)));
+        assert_eq!(recovery.generation(), MAX_ATTEMPTS as u64);
+    }
+}
@@ -0,0 +1,150 @@
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::containers;
+use crate::package_manager::{PackageManager, PackageUpdate, UpdateChecker};
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::{shell_quote, CommandOutput, CommandRunner};
+use crate::sanitize::PLAIN_OUTPUT_ENV;
+
+/// Options that keep ssh from prompting: a host that would ask for a
+/// password or an unknown host key fails its check instead.
+const SSH_OPTIONS: [&str; 4] = ["-o", "BatchMode=yes", "-o", "ConnectTimeout=10"];
+
+/// How long the check of one host may take, connection and all, before it
+/// is given up: a host that stops answering mid-check fails on its own.
+const HOST_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// A machine checked over SSH after each check.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct RemoteHost {
+    /// Destination as given to ssh: a host, `user@host` or a `Host` of
+    /// `~/.ssh/config`
+    pub host: String,
+    /// Detected on the host when not set, like in containers
+    pub package_manager: Option<PackageManager>,
+}
+
+/// Result of checking one host.
+#[derive(Debug, Clone)]
+pub struct HostUpdates {
+    pub host: String,
+    pub package_manager: Option<PackageManager>,
+    pub packages: Vec<PackageUpdate>,
+    /// Why the check failed, e.g. the host is unreachable
+    pub error: Option<String>,
+}
+
+impl HostUpdates {
+    /// Command that updates the host's packages in the terminal, with a
+    /// terminal allocated for sudo's password prompt.
+    pub fn update_command(&self) -> Option<String> {
+        let command = self.package_manager?.system_update_command(PrivilegeEscalation::Sudo);
+        Some(format!("ssh -t {} {}", shell_quote(&self.host), shell_quote(&command)))
+    }
+}
+
+/// Runs commands on a host through ssh.
+pub struct SshRunner {
+    host: String,
+    local: Arc<dyn CommandRunner>,
+}
+
+impl CommandRunner for SshRunner {
+    fn run<'a>(&'a self, program: &'a str, args: &'a [&'a str]) -> BoxFuture<'a, io::Result<CommandOutput>> {
+        Box::pin(async move {
+            // ssh joins its arguments into one line for the remote shell,
+            // so each is quoted; the plain-output settings aren't forwarded
+            let env = PLAIN_OUTPUT_ENV.iter().map(|(name, value)| format!("{}={}", name, value));
+            let quoted = std::iter::once(program).chain(args.iter().copied()).map(shell_quote);
+            let command_line = std::iter::once("env".to_string()).chain(env).chain(quoted).collect::<Vec<_>>().join(" ");
+
+            let mut wrapped = SSH_OPTIONS.to_vec();
+            wrapped.extend([self.host.as_str(), "--", &command_line]);
+            let output = self.local.run("ssh", &wrapped).await?;
+            // 255 is ssh's own failure: no connection, refused key
+            if output.code == Some(255) {
+                return Err(io::Error::new(io::ErrorKind::ConnectionRefused, output.stderr.trim().to_string()));
+            }
+            Ok(output)
+        })
+    }
+}
+
+/// Checks the hosts one after another. A host that can't be reached or
+/// doesn't finish within [`HOST_TIMEOUT`] is reported with its error and
+/// doesn't stop the others.
+pub async fn check_all(local: Arc<dyn CommandRunner>, hosts: &[RemoteHost]) -> Vec<HostUpdates> {
+    let mut results = Vec::new();
+    for host in hosts.iter().filter(|host| !host.host.trim().is_empty()) {
+        let result = tokio::time::timeout(HOST_TIMEOUT, check(local.clone(), host))
+            .await
+            .unwrap_or_else(|_| HostUpdates {
+                host: host.host.clone(),
+                package_manager: host.package_manager,
+                packages: Vec::new(),
+                error: Some(format!("no answer within {} seconds", HOST_TIMEOUT.as_secs())),
+            });
+        results.push(result);
+    }
+    results
+}
+
+async fn check(local: Arc<dyn CommandRunner>, host: &RemoteHost) -> HostUpdates {
+    let runner = Arc::new(SshRunner { host: host.host.clone(), local });
+    let mut result = HostUpdates { host: host.host.clone(), package_manager: None, packages: Vec::new(), error: None };
+
+    let package_manager = match host.package_manager {
+        Some(package_manager) => package_manager,
+        None => match containers::detect_package_manager(runner.as_ref()).await {
+            Ok(Some(package_manager)) => package_manager,
+            Ok(None) => {
+                result.error = Some("no supported package manager".to_string());
+                return result;
+            }
+            Err(e) => {
+                result.error = Some(e.to_string());
+                return result;
+            }
+        },
+    };
+    result.package_manager = Some(package_manager);
+
+    match UpdateChecker::new(package_manager).runner(runner).check_updates(false).await {
+        Ok(update_info) => {
+            result.error = update_info.sources.into_iter().find_map(|source| source.error);
+            result.packages = update_info.packages;
+        }
+        Err(e) => result.error = Some(e.to_string()),
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::ScriptedRunner;
+
+    #[tokio::test]
+    async fn checks_hosts_over_ssh() {
+        let ssh = "ssh -o BatchMode=yes -o ConnectTimeout=10";
+        let local = ScriptedRunner::new([
+            (format!("{} admin@nas -- env NO_COLOR=1 TERM=dumb 'checkupdates'", ssh), CommandOutput::new(0, "curl 8.9.1-1 -> 8.10.0-1\n", "")),
+            (format!("{} offline -- env NO_COLOR=1 TERM=dumb 'sh' '-c' 'command -v pacman apt-get dnf zypper apk xbps-install emerge eopkg'", ssh), CommandOutput::new(255, "", "ssh: connect to host offline port 22: No route to host\n")),
+        ]);
+        let hosts = [
+            RemoteHost { host: "admin@nas".to_string(), package_manager: Some(PackageManager::Pacman) },
+            RemoteHost { host: "offline".to_string(), package_manager: None },
+        ];
+
+        let results = check_all(Arc::new(local), &hosts).await;
+        assert_eq!(results[0].error, None);
+        assert_eq!(results[0].packages.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), ["curl"]);
+        assert_eq!(results[0].update_command().as_deref(), Some("ssh -t 'admin@nas' 'sudo pacman -Syu'"));
+        assert_eq!(results[1].error.as_deref(), Some("ssh: connect to host offline port 22: No route to host"));
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::package_manager::{PackageManager, PackageUpdate};
+
+const PACMAN_CONF: &str = "/etc/pacman.conf";
+const YUM_REPOS_DIR: &str = "/etc/yum.repos.d";
+const ZYPPER_REPOS_DIR: &str = "/etc/zypp/repos.d";
+const APT_SOURCES_LIST: &str = "/etc/apt/sources.list";
+const APT_SOURCES_DIR: &str = "/etc/apt/sources.list.d";
+
+/// The configuration file of each repository the packages come from, for
+/// the repositories it can be found for.
+pub fn config_sources(package_manager: PackageManager, packages: &[PackageUpdate]) -> BTreeMap<String, PathBuf> {
+    let mut sources = BTreeMap::new();
+    for repository in packages.iter().filter_map(|p| p.repository.as_deref()) {
+        if sources.contains_key(repository) {
+            continue;
+        }
+        if let Some(path) = config_source(package_manager, repository) {
+            sources.insert(repository.to_string(), path);
+        }
+    }
+    sources
+}
+
+fn config_source(package_manager: PackageManager, repository: &str) -> Option<PathBuf> {
+    match package_manager {
+        PackageManager::Pacman | PackageManager::Paru | PackageManager::Yay => {
+            first_match(&[PathBuf::from(PACMAN_CONF)], |contents| defines_section(contents, repository))
+        }
+        PackageManager::Dnf => first_match(&files_in(YUM_REPOS_DIR, &["repo"]), |contents| defines_section(contents, repository)),
+        // list-updates shows the repository's name, not its alias
+        PackageManager::Zypper => first_match(&files_in(ZYPPER_REPOS_DIR, &["repo"]), |contents| {
+            defines_section(contents, repository)
+                || contents.lines().any(|line| line.trim().strip_prefix("name=") == Some(repository))
+        }),
+        PackageManager::Apt => {
+            let mut files = vec![PathBuf::from(APT_SOURCES_LIST)];
+            files.extend(files_in(APT_SOURCES_DIR, &["list", "sources"]));
+            first_match(&files, |contents| apt_source_matches(contents, repository))
+        }
+        _ => None,
+    }
+}
+
+/// Files with one of the extensions, sorted like the package managers
+/// read them.
+fn files_in(dir: &str, extensions: &[&str]) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    files.retain(|path| path.extension().and_then(|e| e.to_str()).is_some_and(|e| extensions.contains(&e)));
+    files.sort();
+    files
+}
+
+fn first_match(files: &[PathBuf], matches: impl Fn(&str) -> bool) -> Option<PathBuf> {
+    files.iter()
+        .find(|path| std::fs::read_to_string(Path::new(path)).is_ok_and(|contents| matches(&contents)))
+        .cloned()
+}
+
+/// An INI-style "[section]" header, as in pacman.conf and .repo files.
+fn defines_section(contents: &str, section: &str) -> bool {
+    contents.lines()
+        .filter_map(|line| line.trim().strip_prefix('[')?.strip_suffix(']'))
+        .any(|name| name == section)
+}
+
+/// apt names a repository by origin and suite ("Ubuntu:22.04/jammy-updates",
+/// "LP-PPA-mozillateam:22.04/jammy"), sources files by URL and suite.
+/// Launchpad PPAs are recognized by their URL, other repositories by the
+/// suite in a file without PPAs.
+fn apt_source_matches(contents: &str, repository: &str) -> bool {
+    let Some((origin, suite)) = repository.split_once(':').and_then(|(origin, rest)| Some((origin, rest.split_once('/')?.1))) else {
+        return false;
+    };
+    let lines: Vec<&str> = contents.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .collect();
+    let mentions_suite = lines.iter().any(|line| line.split_whitespace().any(|word| word == suite));
+
+    match origin.strip_prefix("LP-PPA-") {
+        // "LP-PPA-<owner>", or "LP-PPA-<owner>-<ppa>" unless the PPA is
+        // named "ppa"
+        Some(ppa) => mentions_suite && lines.iter()
+            .flat_map(|line| line.split_whitespace())
+            .filter_map(launchpad_ppa)
+            .any(|(owner, name)| ppa == owner || ppa == format!("{}-{}", owner, name)),
+        None => mentions_suite && !contents.contains("launchpad"),
+    }
+}
+
+/// Owner and name of a PPA URL ("https://ppa.launchpadcontent.net/<owner>/<ppa>/ubuntu").
+fn launchpad_ppa(url: &str) -> Option<(&str, &str)> {
+    let path = url.split_once("launchpad")?.1.split_once('/')?.1;
+    let mut segments = path.split('/');
+    Some((segments.next()?, segments.next()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_repository_configuration() {
+        let pacman_conf = "[options]\nHoldPkg = pacman glibc\n\n[core]\nInclude = /etc/pacman.d/mirrorlist\n\n[chaotic-aur]\nInclude = /etc/pacman.d/chaotic-mirrorlist\n";
+        assert!(defines_section(pacman_conf, "chaotic-aur"));
+        assert!(!defines_section(pacman_conf, "extra"));
+
+        let ppa = "deb https://ppa.launchpadcontent.net/mozillateam/ppa/ubuntu/ jammy main\n";
+        let mesa = "Types: deb\nURIs: https://ppa.launchpadcontent.net/kisak/kisak-mesa/ubuntu/\nSuites: jammy\n";
+        let ubuntu = "deb http://archive.ubuntu.com/ubuntu jammy main\ndeb http://archive.ubuntu.com/ubuntu jammy-updates main\n";
+        assert!(apt_source_matches(ppa, "LP-PPA-mozillateam:22.04/jammy"));
+        assert!(apt_source_matches(mesa, "LP-PPA-kisak-kisak-mesa:22.04/jammy"));
+        assert!(!apt_source_matches(mesa, "LP-PPA-mozillateam:22.04/jammy"));
+        assert!(apt_source_matches(ubuntu, "Ubuntu:22.04/jammy-updates"));
+        assert!(!apt_source_matches(ppa, "Ubuntu:22.04/jammy"));
+        assert!(!apt_source_matches(ubuntu, "Ubuntu:22.04/jammy-backports"));
+    }
+}
@@ -0,0 +1,140 @@
+use crate::package_manager::{is_kernel_package, PackageUpdate};
+
+/// Packages whose breakage usually leaves the session or boot unusable.
+/// Matched exactly or as a prefix followed by '-' (e.g. systemd-libs).
+const CORE_PACKAGES: [&str; 7] = ["glibc", "libc6", "systemd", "mesa", "nvidia", "grub", "gcc-libs"];
+
+/// Transactions larger than this are more likely to contain a surprise.
+const LARGE_TRANSACTION: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl RiskLevel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RiskLevel::Low => "Low",
+            RiskLevel::Medium => "Medium",
+            RiskLevel::High => "High",
+        }
+    }
+}
+
+/// How disruptive installing a set of updates is likely to be, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RiskAssessment {
+    pub level: RiskLevel,
+    pub reasons: Vec<String>,
+}
+
+/// Rates installing `packages`, which also removes the installed packages
+/// in `removals`.
+pub fn assess(packages: &[PackageUpdate], removals: &[String]) -> RiskAssessment {
+    let mut score = 0;
+    let mut reasons = Vec::new();
+
+    let kernels: Vec<&str> = packages.iter()
+        .filter(|p| is_kernel_package(&p.name))
+        .map(|p| p.name.as_str())
+        .collect();
+    if !kernels.is_empty() {
+        score += 3;
+        reasons.push(format!("Kernel update ({}), needs a reboot", kernels.join(", ")));
+    }
+
+    for package in packages.iter().filter(|p| is_core_package(&p.name)) {
+        score += 2;
+        reasons.push(format!("Core system component: {}", package.name));
+    }
+
+    let major_bumps: Vec<&str> = packages.iter()
+        .filter(|p| is_major_bump(&p.current_version, &p.new_version))
+        .map(|p| p.name.as_str())
+        .collect();
+    if !major_bumps.is_empty() {
+        // Many major bumps are common on rolling releases, so cap their weight
+        score += major_bumps.len().min(2);
+        reasons.push(format!("Major version change: {}", major_bumps.join(", ")));
+    }
+
+    // Something installed stops working unless its replacement covers it
+    if !removals.is_empty() {
+        score += 2;
+        reasons.push(format!("Removes installed packages: {}", removals.join(", ")));
+    }
+
+    if packages.len() > LARGE_TRANSACTION {
+        score += 1;
+        reasons.push(format!("Large transaction ({} packages)", packages.len()));
+    }
+
+    let level = match score {
+        0..=1 => RiskLevel::Low,
+        2..=3 => RiskLevel::Medium,
+        _ => RiskLevel::High,
+    };
+
+    RiskAssessment { level, reasons }
+}
+
+pub fn is_core_package(name: &str) -> bool {
+    CORE_PACKAGES.iter().any(|core| {
+        name == *core || name.strip_prefix(core).is_some_and(|rest| rest.starts_with('-'))
+    })
+}
+
+/// Leading number of a version, ignoring an epoch ("1:23.2.1-1" -> 23).
+fn major_version(version: &str) -> Option<u64> {
+    let version = version.split_once(':').map_or(version, |(_, v)| v);
+    version.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok()
+}
+
+fn is_major_bump(current: &str, new: &str) -> bool {
+    match (major_version(current), major_version(new)) {
+        (Some(current), Some(new)) => new > current,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_transactions() {
+        let low = assess(&[PackageUpdate::new("firefox", "118.0.1-1", "118.0.2-1")], &[]);
+        assert_eq!(low.level, RiskLevel::Low);
+        assert!(low.reasons.is_empty());
+
+        let medium = assess(&[PackageUpdate::new("mesa", "1:23.2.1-1", "1:23.2.1-2")], &[]);
+        assert_eq!(medium.level, RiskLevel::Medium);
+
+        let high = assess(&[
+            PackageUpdate::new("linux", "6.5.6.arch2-1", "6.5.7.arch1-1"),
+            PackageUpdate::new("systemd-libs", "254.4-1", "254.5-1"),
+        ], &[]);
+        assert_eq!(high.level, RiskLevel::High);
+        assert_eq!(high.reasons.len(), 2);
+    }
+
+    #[test]
+    fn counts_removals() {
+        let packages = [PackageUpdate::new("firefox", "118.0.1-1", "118.0.2-1")];
+        let removing = assess(&packages, &["libfoo1".to_string(), "foo-tools".to_string()]);
+        assert_eq!(removing.level, RiskLevel::Medium);
+        assert_eq!(removing.reasons, vec!["Removes installed packages: libfoo1, foo-tools".to_string()]);
+    }
+
+    #[test]
+    fn detects_major_bumps() {
+        assert!(is_major_bump("1:45.0-1", "1:46.0-1"));
+        assert!(!is_major_bump("118.0.1-1", "118.0.2-1"));
+        assert!(!is_major_bump("unknown", "2.0"));
+        assert!(is_core_package("systemd-libs"));
+        assert!(!is_core_package("systemdgenie"));
+    }
+}
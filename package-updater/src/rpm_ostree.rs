@@ -0,0 +1,120 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use std::path::Path;
+
+use crate::backend::{self, Backend, Capabilities, CheckContext};
+use crate::package_manager::{CheckMode, PackageUpdate};
+use crate::privilege::PrivilegeEscalation;
+
+/// Present on systems booted from an OSTree deployment.
+const OSTREE_BOOTED: &str = "/run/ostree-booted";
+
+/// `rpm-ostree upgrade --check` exit code for "no update available".
+const NO_UPDATE: i32 = 77;
+
+/// Fedora Atomic desktops (Silverblue, Kinoite): the upgrade is a new
+/// deployment, switched to as a whole on the next boot.
+pub struct RpmOstree;
+
+impl Backend for RpmOstree {
+    fn name(&self) -> &str {
+        "rpm-ostree"
+    }
+
+    /// rpm-ostree is also packaged for regular Fedora, where it can't upgrade.
+    fn detect(&self) -> bool {
+        Path::new(OSTREE_BOOTED).exists() && backend::on_path("rpm-ostree")
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { applies_on_reboot: true, needs_privileges: true, ..Capabilities::default() }
+    }
+
+    /// `--preview` is `--check` with the package diff of the deployment
+    /// the upgrade would create. Exit codes and advisories need their own
+    /// handling.
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(async move {
+            let mut args = vec!["upgrade", "--preview"];
+            if context.mode == CheckMode::Quick {
+                args.push("--cache-only");
+            }
+            let output = context.runner.run("rpm-ostree", &args).await?;
+            match output.code {
+                Some(0) => {}
+                Some(NO_UPDATE) => return Ok(Vec::new()),
+                code => {
+                    return Err(anyhow!("Failed to check for updates (exit {}): {}", code.unwrap_or(-1), output.stderr));
+                }
+            }
+
+            let advisories = advisory_packages(&output.stdout);
+            let mut packages = self.parse(&output)?;
+            for package in &mut packages {
+                package.is_security = advisories.contains(&package.name);
+            }
+            Ok(packages)
+        })
+    }
+
+    /// "       Upgraded: name 1.0-1.fc39 -> 1.1-1.fc39", further packages of
+    /// the section on continuation lines without the label
+    fn parse_line(&self, line: &str) -> Option<PackageUpdate> {
+        let line = line.trim_start();
+        let line = line.strip_prefix("Upgraded:")
+            .or_else(|| line.strip_prefix("Downgraded:"))
+            .unwrap_or(line);
+        match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [name, current_version, "->", new_version] => Some(PackageUpdate::new(name, current_version, new_version)),
+            _ => None,
+        }
+    }
+
+    /// Deployment metadata, added and removed packages.
+    fn is_noise(&self, line: &str) -> bool {
+        !line.contains(" -> ")
+    }
+
+    /// rpm-ostreed authorizes through polkit and refuses to run under sudo.
+    fn update_command(&self, _privilege: PrivilegeEscalation) -> Option<String> {
+        Some("rpm-ostree upgrade".to_string())
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Check 'rpm-ostree status'; 'rpm-ostree cleanup -p' removes a pending deployment that is stuck."
+    }
+}
+
+/// Names of the packages fixed by the security advisories `rpm-ostree
+/// upgrade --check` lists as "ID  Severity  name-version-release.arch".
+fn advisory_packages(output: &str) -> Vec<String> {
+    let mut packages = Vec::new();
+    let mut in_advisories = false;
+    for line in output.lines() {
+        let line = line.trim_start();
+        let entry = match line.strip_prefix("SecAdvisories:") {
+            Some(entry) => {
+                in_advisories = true;
+                entry
+            }
+            // Each section starts with a "Label:"
+            None if line.split_whitespace().next().is_some_and(|word| word.ends_with(':')) => {
+                in_advisories = false;
+                continue;
+            }
+            None => line,
+        };
+        if !in_advisories {
+            continue;
+        }
+        if let Some(nevra) = entry.split_whitespace().nth(2) {
+            // Drop ".arch", then "-release" and "-version"
+            let nevr = nevra.rsplit_once('.').map_or(nevra, |(nevr, _arch)| nevr);
+            let mut dashes = nevr.rmatch_indices('-').map(|(idx, _)| idx);
+            if let (Some(_), Some(idx)) = (dashes.next(), dashes.next()) {
+                packages.push(nevr[..idx].to_string());
+            }
+        }
+    }
+    packages
+}
@@ -0,0 +1,92 @@
+use futures::future::BoxFuture;
+#[cfg(test)]
+use std::collections::HashMap;
+use std::io;
+use tokio::process::Command as TokioCommand;
+
+use crate::sanitize::{strip_ansi_lines, PLAIN_OUTPUT_ENV};
+
+/// Captured result of running an external command.
+#[derive(Clone, Debug, Default)]
+pub struct CommandOutput {
+    /// Exit code, `None` if the process was killed by a signal
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    /// Output of a process that exited with `code`.
+    #[cfg(test)]
+    pub fn new(code: i32, stdout: &str, stderr: &str) -> Self {
+        Self { code: Some(code), stdout: stdout.to_string(), stderr: stderr.to_string() }
+    }
+
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+/// Quotes `value` as one word for `sh`.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Runs the external commands the update checker depends on. Production code
+/// uses [`SystemRunner`]; tests substitute recorded output.
+pub trait CommandRunner: Send + Sync {
+    fn run<'a>(&'a self, program: &'a str, args: &'a [&'a str]) -> BoxFuture<'a, io::Result<CommandOutput>>;
+}
+
+/// Spawns the real processes with plain-output settings and strips any
+/// escape sequences that still come through.
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run<'a>(&'a self, program: &'a str, args: &'a [&'a str]) -> BoxFuture<'a, io::Result<CommandOutput>> {
+        Box::pin(async move {
+            let output = TokioCommand::new(program)
+                .args(args)
+                .envs(PLAIN_OUTPUT_ENV)
+                // Callers that give up on a command, e.g. after a timeout,
+                // don't leave it running
+                .kill_on_drop(true)
+                .output()
+                .await?;
+
+            Ok(CommandOutput {
+                code: output.status.code(),
+                stdout: strip_ansi_lines(&String::from_utf8_lossy(&output.stdout)),
+                stderr: strip_ansi_lines(&String::from_utf8_lossy(&output.stderr)),
+            })
+        })
+    }
+}
+
+/// Replays recorded output keyed by the full command line, or by program
+/// name for any arguments. Other commands fail as if not installed.
+#[cfg(test)]
+pub struct ScriptedRunner {
+    outputs: HashMap<String, CommandOutput>,
+}
+
+#[cfg(test)]
+impl ScriptedRunner {
+    pub fn new<S: Into<String>>(outputs: impl IntoIterator<Item = (S, CommandOutput)>) -> Self {
+        Self { outputs: outputs.into_iter().map(|(command, output)| (command.into(), output)).collect() }
+    }
+}
+
+#[cfg(test)]
+impl CommandRunner for ScriptedRunner {
+    fn run<'a>(&'a self, program: &'a str, args: &'a [&'a str]) -> BoxFuture<'a, io::Result<CommandOutput>> {
+        let command_line = std::iter::once(program).chain(args.iter().copied()).collect::<Vec<_>>().join(" ");
+        let result = self
+            .outputs
+            .get(&command_line)
+            .or_else(|| self.outputs.get(program))
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, command_line));
+        Box::pin(async move { result })
+    }
+}
@@ -0,0 +1,68 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Environment for commands whose output is parsed: asks tools not to
+/// colorize or draw progress bars.
+pub const PLAIN_OUTPUT_ENV: [(&str, &str); 2] = [("NO_COLOR", "1"), ("TERM", "dumb")];
+
+/// [`strip_ansi`] applied to every line of a command's output, for tools
+/// that ignore [`PLAIN_OUTPUT_ENV`] and `--color=never`.
+pub fn strip_ansi_lines(output: &str) -> String {
+    output.lines().map(strip_ansi).collect::<Vec<_>>().join("\n")
+}
+
+/// Removes ANSI escape sequences (colors, cursor movement, terminal titles)
+/// and other control characters from a line of command output. Tabs are kept
+/// since some backends use them as column separators, and progress bars that
+/// redraw themselves with `\r` are reduced to their final state.
+pub fn strip_ansi(line: &str) -> String {
+    let line = line
+        .rsplit('\r')
+        .find(|segment| !segment.trim().is_empty())
+        .unwrap_or("");
+
+    let mut cleaned = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => skip_escape(&mut chars),
+            // Single-character (C1) form of CSI
+            '\u{9b}' => skip_csi(&mut chars),
+            '\t' => cleaned.push(c),
+            c if c.is_control() => {}
+            c => cleaned.push(c),
+        }
+    }
+
+    cleaned
+}
+
+fn skip_escape(chars: &mut Peekable<Chars<'_>>) {
+    match chars.next() {
+        Some('[') => skip_csi(chars),
+        // OSC, DCS, APC and PM strings end with BEL or ESC \
+        Some(']' | 'P' | '_' | '^') => {
+            while let Some(c) = chars.next() {
+                if c == '\x07' {
+                    break;
+                }
+                if c == '\x1b' {
+                    chars.next_if_eq(&'\\');
+                    break;
+                }
+            }
+        }
+        // Character set designation, e.g. ESC ( B
+        Some('(' | ')' | '*' | '+') => {
+            chars.next();
+        }
+        _ => {}
+    }
+}
+
+fn skip_csi(chars: &mut Peekable<Chars<'_>>) {
+    // Parameter and intermediate bytes, then a single final byte
+    while chars.next_if(|c| ('\x20'..='\x3f').contains(c)).is_some() {}
+    chars.next_if(|c| ('\x40'..='\x7e').contains(c));
+}
@@ -0,0 +1,173 @@
+use chrono::{DateTime, Days, Local, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::config::PackageUpdaterConfig;
+
+/// Hours of the day during which automatic checks may run.
+///
+/// The window starts at `start_hour:00` and ends at `end_hour:00`. A window
+/// whose end is before its start wraps around midnight (e.g. 22–6), and equal
+/// hours allow the whole day.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CheckWindow {
+    pub start_hour: u8,
+    pub end_hour: u8,
+}
+
+impl Default for CheckWindow {
+    fn default() -> Self {
+        Self {
+            start_hour: 8,
+            end_hour: 22,
+        }
+    }
+}
+
+impl CheckWindow {
+    pub fn contains_hour(&self, hour: u32) -> bool {
+        let start = u32::from(self.start_hour);
+        let end = u32::from(self.end_hour);
+
+        if start == end {
+            true
+        } else if start < end {
+            hour >= start && hour < end
+        } else {
+            hour >= start || hour < end
+        }
+    }
+
+    /// Time left until the window next opens, zero if it is open now.
+    pub fn duration_until_open(&self, now: DateTime<Local>) -> Duration {
+        if self.contains_hour(now.hour()) {
+            return Duration::ZERO;
+        }
+
+        let now_minutes = now.hour() * 60 + now.minute();
+        let start_minutes = u32::from(self.start_hour) * 60;
+        let minutes = (start_minutes + 24 * 60 - now_minutes) % (24 * 60);
+
+        Duration::from_secs(u64::from(minutes) * 60).saturating_sub(Duration::from_secs(u64::from(now.second())))
+    }
+}
+
+/// When to be reminded about updates that were put off.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Reminder {
+    InTwoHours,
+    Tonight,
+    Tomorrow,
+}
+
+impl Reminder {
+    pub const ALL: [Reminder; 3] = [Reminder::InTwoHours, Reminder::Tonight, Reminder::Tomorrow];
+
+    const TONIGHT: (u32, u32) = (20, 0);
+    const TOMORROW_MORNING: (u32, u32) = (9, 0);
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Reminder::InTwoHours => "In 2 hours",
+            Reminder::Tonight => "Tonight",
+            Reminder::Tomorrow => "Tomorrow",
+        }
+    }
+
+    /// Notification action key
+    pub fn action_key(&self) -> &'static str {
+        match self {
+            Reminder::InTwoHours => "remind-2h",
+            Reminder::Tonight => "remind-tonight",
+            Reminder::Tomorrow => "remind-tomorrow",
+        }
+    }
+
+    pub fn from_action_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|reminder| reminder.action_key() == key)
+    }
+
+    /// "Tonight" means 20:00, or two hours from now once that has passed;
+    /// "Tomorrow" means 09:00 the next day.
+    pub fn deadline(&self, now: DateTime<Local>) -> DateTime<Local> {
+        let in_two_hours = now + chrono::Duration::hours(2);
+        let at = |days: u64, (hour, minute): (u32, u32)| {
+            now.date_naive()
+                .checked_add_days(Days::new(days))
+                .and_then(|date| date.and_time(NaiveTime::from_hms_opt(hour, minute, 0)?).and_local_timezone(Local).earliest())
+        };
+
+        match self {
+            Reminder::InTwoHours => in_two_hours,
+            Reminder::Tonight => at(0, Self::TONIGHT)
+                .filter(|tonight| *tonight > now)
+                .unwrap_or(in_two_hours),
+            Reminder::Tomorrow => at(1, Self::TOMORROW_MORNING).unwrap_or(in_two_hours),
+        }
+    }
+}
+
+/// Whether an automatic (timer or startup) check may run right now.
+/// Manual checks are never restricted.
+pub fn automatic_check_allowed(config: &PackageUpdaterConfig) -> bool {
+    !config.check_window_enabled || config.check_window.contains_hour(Local::now().hour())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32, second: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 3, 12, hour, minute, second).unwrap()
+    }
+
+    #[test]
+    fn windows_wrap_past_midnight() {
+        let night = CheckWindow { start_hour: 22, end_hour: 6 };
+        assert!(night.contains_hour(23));
+        assert!(night.contains_hour(0));
+        assert!(night.contains_hour(5));
+        assert!(!night.contains_hour(6));
+        assert!(!night.contains_hour(12));
+
+        let day = CheckWindow::default();
+        assert!(day.contains_hour(8));
+        assert!(!day.contains_hour(22));
+    }
+
+    #[test]
+    fn equal_hours_are_always_open() {
+        let always = CheckWindow { start_hour: 9, end_hour: 9 };
+        assert!((0..24).all(|hour| always.contains_hour(hour)));
+        assert_eq!(always.duration_until_open(at(3, 15, 0)), Duration::ZERO);
+    }
+
+    #[test]
+    fn waits_until_the_window_opens() {
+        let day = CheckWindow::default();
+        assert_eq!(day.duration_until_open(at(12, 0, 0)), Duration::ZERO);
+        // 22:30:15 to 08:00 the next morning
+        assert_eq!(day.duration_until_open(at(22, 30, 15)), Duration::from_secs(9 * 3600 + 29 * 60 + 45));
+        assert_eq!(day.duration_until_open(at(7, 59, 30)), Duration::from_secs(30));
+
+        let night = CheckWindow { start_hour: 22, end_hour: 6 };
+        assert_eq!(night.duration_until_open(at(6, 0, 0)), Duration::from_secs(16 * 3600));
+    }
+
+    #[test]
+    fn reminders_fall_due() {
+        let afternoon = at(15, 10, 0);
+        assert_eq!(Reminder::InTwoHours.deadline(afternoon), at(17, 10, 0));
+        assert_eq!(Reminder::Tonight.deadline(afternoon), at(20, 0, 0));
+
+        // Past 20:00, tonight is two hours from now
+        let evening = at(21, 30, 0);
+        assert_eq!(Reminder::Tonight.deadline(evening), at(23, 30, 0));
+        assert_eq!(Reminder::Tonight.deadline(at(20, 0, 0)), at(22, 0, 0));
+
+        let tomorrow = Local.with_ymd_and_hms(2024, 3, 13, 9, 0, 0).unwrap();
+        assert_eq!(Reminder::Tomorrow.deadline(evening), tomorrow);
+        assert_eq!(Reminder::Tomorrow.deadline(at(0, 5, 0)), tomorrow);
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::BTreeSet;
+
+use crate::package_manager::PackageUpdate;
+
+/// Packages picked in the list for a selective update, by name.
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    names: BTreeSet<String>,
+    /// Last package clicked without shift, where a range starts
+    anchor: Option<String>,
+}
+
+impl Selection {
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// A click on a package: toggles it, or with shift gives every package
+    /// from the anchor to it the anchor's state. `displayed` is the order
+    /// the list shows the packages in, sections and groups applied.
+    pub fn click(&mut self, displayed: &[&PackageUpdate], name: &str, shift: bool) {
+        let position = |name: &str| displayed.iter().position(|p| p.name == name);
+        let range = match (shift, self.anchor.as_deref().and_then(position), position(name)) {
+            (true, Some(anchor), Some(clicked)) => Some((anchor.min(clicked), anchor.max(clicked))),
+            _ => None,
+        };
+        let Some((first, last)) = range else {
+            if !self.names.remove(name) {
+                self.names.insert(name.to_string());
+            }
+            self.anchor = Some(name.to_string());
+            return;
+        };
+
+        let select = self.anchor.as_deref().is_some_and(|anchor| self.names.contains(anchor));
+        for package in &displayed[first..=last] {
+            if select {
+                self.names.insert(package.name.clone());
+            } else {
+                self.names.remove(&package.name);
+            }
+        }
+    }
+
+    pub fn select_where(&mut self, packages: &[PackageUpdate], matches: impl Fn(&PackageUpdate) -> bool) {
+        self.names.extend(packages.iter().filter(|p| matches(p)).map(|p| p.name.clone()));
+    }
+
+    pub fn deselect_where(&mut self, packages: &[PackageUpdate], matches: impl Fn(&PackageUpdate) -> bool) {
+        for package in packages.iter().filter(|p| matches(p)) {
+            self.names.remove(&package.name);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.names.clear();
+        self.anchor = None;
+    }
+
+    /// Drops packages that are no longer pending after a check.
+    pub fn retain_pending(&mut self, packages: &[PackageUpdate]) {
+        self.names.retain(|name| packages.iter().any(|p| &p.name == name));
+    }
+
+    /// The selected packages, in list order.
+    pub fn packages<'a>(&self, packages: &'a [PackageUpdate]) -> Vec<&'a PackageUpdate> {
+        packages.iter().filter(|p| self.names.contains(&p.name)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::Source;
+
+    fn packages(names: &[&str]) -> Vec<PackageUpdate> {
+        names.iter()
+            .map(|name| {
+                let source = if name.ends_with("-git") { Source::Aur } else { Source::System };
+                PackageUpdate { source, ..PackageUpdate::new(name, "1", "2") }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn selects_ranges_and_bulk() {
+        let list = packages(&["bash", "curl", "firefox", "neovim-git", "zsh"]);
+        let names = |selection: &Selection| -> Vec<String> {
+            selection.packages(&list).into_iter().map(|p| p.name.clone()).collect()
+        };
+        let mut selection = Selection::default();
+        let displayed: Vec<&PackageUpdate> = list.iter().collect();
+
+        selection.click(&displayed, "curl", false);
+        selection.click(&displayed, "neovim-git", true);
+        assert_eq!(names(&selection), ["curl", "firefox", "neovim-git"]);
+
+        // A range takes the anchor's state, also upwards
+        selection.click(&displayed, "firefox", false);
+        selection.click(&displayed, "bash", true);
+        assert_eq!(names(&selection), ["neovim-git"]);
+
+        // Ranges follow the order on screen, not the one the check found
+        selection.clear();
+        let reordered: Vec<&PackageUpdate> = [4, 0, 3, 1, 2].iter().map(|&i| &list[i]).collect();
+        selection.click(&reordered, "zsh", false);
+        selection.click(&reordered, "neovim-git", true);
+        assert_eq!(names(&selection), ["bash", "neovim-git", "zsh"]);
+
+        selection.select_where(&list, |p| p.source != Source::Aur);
+        selection.deselect_where(&list, |p| p.source == Source::Aur);
+        assert_eq!(selection.len(), 4);
+
+        selection.retain_pending(&packages(&["bash", "zsh"]));
+        assert_eq!(names(&selection), ["bash", "zsh"]);
+    }
+}
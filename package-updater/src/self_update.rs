@@ -0,0 +1,42 @@
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Suffix the kernel appends to `/proc/self/exe` once the file is gone.
+const DELETED_SUFFIX: &str = " (deleted)";
+
+/// Where the new binary is if the running one was replaced on disk, as
+/// package managers do when they update the applet's own package.
+pub fn replaced_binary() -> Option<PathBuf> {
+    let exe = std::fs::read_link("/proc/self/exe").ok()?;
+    let path = replaced_path(exe.to_str()?)?;
+    path.exists().then_some(path)
+}
+
+fn replaced_path(exe: &str) -> Option<PathBuf> {
+    exe.strip_suffix(DELETED_SUFFIX).map(PathBuf::from)
+}
+
+/// Replaces this process with the new binary, keeping the arguments and
+/// environment. The state file needs no care: everything in it is saved when
+/// it changes and read again on startup. Only returns on failure.
+pub fn restart(binary: PathBuf) -> io::Error {
+    Command::new(binary)
+        .args(std::env::args_os().skip(1))
+        .exec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_replaced_binary() {
+        assert_eq!(
+            replaced_path("/usr/bin/cosmic-ext-applet-package-updater (deleted)"),
+            Some(PathBuf::from("/usr/bin/cosmic-ext-applet-package-updater"))
+        );
+        assert_eq!(replaced_path("/usr/bin/cosmic-ext-applet-package-updater"), None);
+    }
+}
@@ -0,0 +1,167 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::backend::{Backend, Capabilities, CheckContext};
+use crate::package_manager::PackageUpdate;
+use crate::privilege::PrivilegeEscalation;
+
+/// In /usr/sbin, which isn't on users' PATH on Slackware.
+const BINARY: &str = "/usr/sbin/slackpkg";
+
+/// Installed packages, one file each, named like the package it came from.
+pub const INSTALLED_DIR: &str = "/var/log/packages";
+
+/// The mirror's packages as of the last `slackpkg update`.
+pub const PKGLIST: &str = "/var/lib/slackpkg/pkglist";
+
+/// Packages `slackpkg upgrade-all` leaves alone.
+pub const BLACKLIST: &str = "/etc/slackpkg/blacklist";
+
+/// Slackware.
+pub struct Slackpkg;
+
+impl Backend for Slackpkg {
+    fn name(&self) -> &str {
+        "slackpkg"
+    }
+
+    fn detect(&self) -> bool {
+        Path::new(BINARY).exists()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { selective_update: true, needs_privileges: true, ..Capabilities::default() }
+    }
+
+    /// slackpkg only runs as root and has no dry run of `upgrade-all`, so
+    /// its comparison is repeated: installed packages against the package
+    /// list of the last `slackpkg update`. The files are read through the
+    /// runner, so this works inside containers too.
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(async move {
+            let installed = context.runner.run("ls", &[INSTALLED_DIR]).await?;
+            let pkglist = context.runner.run("cat", &[PKGLIST]).await?;
+            if !installed.success() || !pkglist.success() {
+                return Err(anyhow!(
+                    "Failed to read the package lists, has 'slackpkg update' run? {}{}",
+                    installed.stderr,
+                    pkglist.stderr
+                ));
+            }
+            // Optional
+            let blacklist = match context.runner.run("cat", &[BLACKLIST]).await {
+                Ok(output) if output.success() => output.stdout,
+                _ => String::new(),
+            };
+
+            Ok(updates(&installed.stdout, &pkglist.stdout, &blacklist))
+        })
+    }
+
+    fn update_command(&self, privilege: PrivilegeEscalation) -> Option<String> {
+        Some(format!("{} && {}", privilege.wrap("slackpkg update"), privilege.wrap("slackpkg upgrade-all")))
+    }
+
+    fn packages_update_command(&self, privilege: PrivilegeEscalation, packages: &[String]) -> Option<String> {
+        if packages.is_empty() {
+            return None;
+        }
+        Some(privilege.wrap(&format!("slackpkg upgrade {}", packages.join(" "))))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; 'slackpkg update gpg' imports the mirror's key again, and 'slackpkg install-new' adds packages the upgrade depends on."
+    }
+}
+
+/// A package as "name-version-arch-build", the build with its tag, e.g.
+/// "openssl-3.0.13-x86_64-1_slack15.0".
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Package<'a> {
+    name: &'a str,
+    version: &'a str,
+    build: &'a str,
+}
+
+impl<'a> Package<'a> {
+    fn parse(full_name: &'a str) -> Option<Self> {
+        let mut parts = full_name.trim().rsplitn(4, '-');
+        let build = parts.next()?;
+        let _arch = parts.next()?;
+        let version = parts.next()?;
+        let name = parts.next()?;
+        Some(Self { name, version, build })
+    }
+
+    /// Version and build, which changes for rebuilds and patches.
+    fn release(&self) -> String {
+        format!("{}-{}", self.version, self.build)
+    }
+}
+
+/// What `slackpkg upgrade-all` would upgrade: installed packages whose
+/// version or build differs from the mirror's, preferring the patches
+/// directory like slackpkg does. Packages the mirror doesn't have, e.g.
+/// from SlackBuilds, are left out; blacklisted ones are marked ignored.
+///
+/// `installed` lists the files of [`INSTALLED_DIR`], one per line, and
+/// `pkglist` has lines of "DIR NAME VERSION ARCH BUILD FULLNAME PATH EXT".
+pub fn updates(installed: &str, pkglist: &str, blacklist: &str) -> Vec<PackageUpdate> {
+    let mut available: HashMap<&str, Package> = HashMap::new();
+    for line in pkglist.lines() {
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        let (Some(directory), Some(package)) = (columns.first(), columns.get(5).and_then(|name| Package::parse(name))) else {
+            continue;
+        };
+        if *directory == "patches" || !available.contains_key(package.name) {
+            available.insert(package.name, package);
+        }
+    }
+    let blacklisted: Vec<&str> = blacklist.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+
+    installed.lines()
+        .filter_map(Package::parse)
+        .filter_map(|current| {
+            let latest = available.get(current.name)?;
+            (latest.release() != current.release()).then(|| PackageUpdate {
+                name: current.name.to_string(),
+                current_version: current.release(),
+                new_version: latest.release(),
+                is_ignored: blacklisted.contains(&current.name),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_patches_and_skips_packages_not_on_the_mirror() {
+        let installed = "openssl-3.0.13-x86_64-1_slack15.0\nkernel-generic-5.15.145-x86_64-1\nneovim-0.9.5-x86_64-1_SBo\n";
+        let pkglist = "\
+slackware64 openssl 3.0.12 x86_64 1 openssl-3.0.12-x86_64-1 ./slackware64/n txz
+patches openssl 3.0.14 x86_64 1_slack15.0 openssl-3.0.14-x86_64-1_slack15.0 ./patches/packages txz
+slackware64 kernel-generic 5.15.145 x86_64 1 kernel-generic-5.15.145-x86_64-1 ./slackware64/a txz
+patches kernel-generic 5.15.161 x86_64 1 kernel-generic-5.15.161-x86_64-1 ./patches/packages/linux-5.15.161 txz
+";
+        let updates = updates(installed, pkglist, "# Kernel\nkernel-generic\n");
+        let found: Vec<(&str, &str, &str, bool)> = updates.iter()
+            .map(|p| (p.name.as_str(), p.current_version.as_str(), p.new_version.as_str(), p.is_ignored))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("openssl", "3.0.13-1_slack15.0", "3.0.14-1_slack15.0", false),
+                ("kernel-generic", "5.15.145-1", "5.15.161-1", true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Result};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use tokio::process::Command as TokioCommand;
+
+use crate::busy::{self, BusyReason};
+use crate::config::PackageUpdaterConfig;
+
+/// Sounds of the freedesktop theme, played when no libcanberra player is
+/// installed.
+const THEME_DIR: &str = "/usr/share/sounds/freedesktop/stereo";
+const THEME_EXTENSIONS: [&str; 3] = ["oga", "ogg", "wav"];
+
+/// Players for a sound file, PipeWire's first.
+const FILE_PLAYERS: [&str; 2] = ["pw-play", "paplay"];
+
+/// Events a sound can be played for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cue {
+    UpdatesFound,
+    UpdateFinished,
+}
+
+impl Cue {
+    /// Name from the freedesktop sound naming specification, which sound
+    /// themes provide their own sound for.
+    fn sound_name(&self) -> &'static str {
+        match self {
+            Cue::UpdatesFound => "software-update-available",
+            Cue::UpdateFinished => "complete",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Cue::UpdatesFound => "Updates available",
+            Cue::UpdateFinished => "Update finished",
+        }
+    }
+}
+
+/// Whether sounds may play at this hour of the day.
+pub fn allowed_at(config: &PackageUpdaterConfig, hour: u32) -> bool {
+    !config.sound_quiet_hours_enabled || !config.sound_quiet_hours.contains_hour(hour)
+}
+
+/// Plays the cue from the user's sound theme through libcanberra, or the
+/// freedesktop theme's file through a sound server's player. Nothing is
+/// played while do not disturb is on.
+pub async fn play(cue: Cue) -> Result<()> {
+    if busy::session_busy().await == Some(BusyReason::DoNotDisturb) {
+        return Ok(());
+    }
+
+    match run("canberra-gtk-play", &["-i", cue.sound_name(), "-d", cue.description()]).await {
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        result => return Ok(result?),
+    }
+
+    let file = theme_file(cue)
+        .ok_or_else(|| anyhow!("No sound file for '{}' in {}", cue.sound_name(), THEME_DIR))?;
+    let file = file.to_string_lossy();
+    for player in FILE_PLAYERS {
+        match run(player, &[&file]).await {
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            result => return Ok(result?),
+        }
+    }
+    Err(anyhow!("No sound player found (canberra-gtk-play, pw-play or paplay)"))
+}
+
+fn theme_file(cue: Cue) -> Option<PathBuf> {
+    THEME_EXTENSIONS.iter()
+        .map(|extension| PathBuf::from(THEME_DIR).join(format!("{}.{}", cue.sound_name(), extension)))
+        .find(|path| path.exists())
+}
+
+/// Runs the player, keeping a missing program apart from a failed playback.
+async fn run(program: &str, args: &[&str]) -> Result<(), std::io::Error> {
+    let output = TokioCommand::new(program).args(args).output().await?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::CheckWindow;
+
+    #[test]
+    fn quiet_hours_wrap_around_midnight() {
+        let mut config = PackageUpdaterConfig {
+            sound_quiet_hours_enabled: true,
+            sound_quiet_hours: CheckWindow { start_hour: 22, end_hour: 7 },
+            ..PackageUpdaterConfig::default()
+        };
+        assert!(!allowed_at(&config, 23));
+        assert!(!allowed_at(&config, 6));
+        assert!(allowed_at(&config, 7));
+        assert!(allowed_at(&config, 21));
+
+        config.sound_quiet_hours_enabled = false;
+        assert!(allowed_at(&config, 23));
+    }
+}
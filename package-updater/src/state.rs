@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::app_id;
+use crate::diagnostics::Diagnostics;
+use crate::firmware::StagedFirmware;
+use crate::health::SourceHealth;
+use crate::history::{CountChange, RunOutcome};
+use crate::package_manager::{PackageUpdate, UpdateInfo};
+use crate::update_all::RunProgress;
+
+/// Directory for files the applet writes for itself (state, history).
+pub fn state_dir() -> PathBuf {
+    let state_home = std::env::var("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+            PathBuf::from(home).join(".local/state")
+        });
+    state_home.join(app_id::file_prefix())
+}
+
+/// Updates the user asked to be reminded about later.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct Snooze {
+    /// Unix timestamp of the reminder
+    pub until: i64,
+    /// Keys of the snoozed updates, see [`update_keys`]
+    pub updates: Vec<String>,
+}
+
+/// Identifies a pending update by name and target version, so a newer
+/// version of a snoozed package counts as a new update.
+pub fn update_keys(packages: &[PackageUpdate]) -> Vec<String> {
+    let mut keys: Vec<String> = packages.iter()
+        .map(|p| format!("{} {}", p.name, p.new_version))
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Runtime state that has to survive applet restarts. Unlike the config it
+/// is written by the applet itself, so it lives in the XDG state directory.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default)]
+pub struct PersistentState {
+    pub staged_firmware: Vec<StagedFirmware>,
+    /// Unix timestamp of the last weekly summary notification
+    pub last_weekly_summary: Option<i64>,
+    pub snooze: Option<Snooze>,
+    /// Updates the last "updates available" notification was sent for
+    pub notified_updates: Vec<String>,
+    /// The report-only explanation was acknowledged
+    pub report_only_intro_dismissed: bool,
+    /// Unix timestamp of the last webhook report sent by any instance
+    pub last_webhook_report: Option<i64>,
+    /// Seconds the last update run took, for the metrics export
+    pub last_update_duration: Option<u64>,
+    /// Unix timestamp of the last metadata refresh requested by the user
+    pub last_forced_refresh: Option<i64>,
+    /// Last successful and failed check per source, by source name
+    pub source_health: BTreeMap<String, SourceHealth>,
+    /// Download failures per repository named in check errors, by
+    /// repository name
+    pub repository_health: BTreeMap<String, SourceHealth>,
+    /// Steps of an "Update Everything" run that stopped before the end
+    pub update_all_progress: Option<RunProgress>,
+    /// Check statistics, recorded while diagnostics are enabled
+    pub diagnostics: Diagnostics,
+    /// The user's own notes on packages, by package name
+    pub package_notes: BTreeMap<String, String>,
+    /// When the user last marked the changes made outside the applet as
+    /// reviewed (Unix timestamp)
+    pub changes_reviewed_at: Option<i64>,
+    /// Recent changes of the pending updates between checks, oldest first
+    pub count_changes: Vec<CountChange>,
+}
+
+impl PersistentState {
+    fn get_state_path() -> PathBuf {
+        state_dir().join("state.toml")
+    }
+
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::get_state_path())
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let path = Self::get_state_path();
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+
+        match toml::to_string(self) {
+            Ok(contents) => {
+                if let Err(e) = std::fs::write(&path, contents) {
+                    eprintln!("Failed to write state file {}: {}", path.display(), e);
+                }
+            }
+            Err(e) => eprintln!("Failed to serialize state: {}", e),
+        }
+    }
+}
+
+/// What the applet only keeps in memory, written when it is stopped (panel
+/// restart, logout) and taken over by the next start, so a restart doesn't
+/// lose the last check.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Session {
+    /// Unix timestamp of the last check
+    pub last_check: Option<i64>,
+    pub update_info: Option<UpdateInfo>,
+    /// Pending packages when the last update run finished, if no check followed yet
+    pub pre_update_packages: Option<Vec<PackageUpdate>>,
+    pub last_run_outcome: Option<RunOutcome>,
+}
+
+impl Session {
+    /// Each panel instance keeps its own session, keyed by its panel and
+    /// output, so every instance of a restarted panel restores its own.
+    fn get_session_path() -> PathBuf {
+        let instance: Vec<String> = [std::env::var("COSMIC_PANEL_NAME"), std::env::var("COSMIC_PANEL_OUTPUT")]
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|part| !part.is_empty())
+            .map(|part| part.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' }).collect())
+            .collect();
+        if instance.is_empty() {
+            state_dir().join("session.json")
+        } else {
+            state_dir().join(format!("session-{}.json", instance.join("-")))
+        }
+    }
+
+    pub fn save(&self) {
+        let path = Self::get_session_path();
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+
+        match serde_json::to_string(self) {
+            Ok(contents) => {
+                if let Err(e) = std::fs::write(&path, contents) {
+                    eprintln!("Failed to write session file {}: {}", path.display(), e);
+                }
+            }
+            Err(e) => eprintln!("Failed to serialize session: {}", e),
+        }
+    }
+
+    /// Reads the saved session and removes it, so it is restored once.
+    pub fn take() -> Option<Self> {
+        let path = Self::get_session_path();
+        let contents = std::fs::read_to_string(&path).ok()?;
+        let _ = std::fs::remove_file(&path);
+        serde_json::from_str(&contents)
+            .map_err(|e| eprintln!("Failed to read session file {}: {}", path.display(), e))
+            .ok()
+    }
+}
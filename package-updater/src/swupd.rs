@@ -0,0 +1,67 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+
+use crate::backend::{self, Backend, Capabilities, CheckContext};
+use crate::package_manager::PackageUpdate;
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::CommandRunner;
+
+/// Name of the single update a new Clear Linux OS version is listed as.
+const OS_NAME: &str = "Clear Linux OS";
+
+/// Clear Linux, updated a whole OS version at a time.
+pub struct Swupd;
+
+impl Backend for Swupd {
+    fn name(&self) -> &str {
+        "swupd"
+    }
+
+    fn detect(&self) -> bool {
+        backend::on_path("swupd")
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { needs_privileges: true, ..Capabilities::default() }
+    }
+
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(get_updates(context.runner))
+    }
+
+    fn update_command(&self, privilege: PrivilegeEscalation) -> Option<String> {
+        Some(privilege.wrap("swupd update"))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; 'sudo swupd repair' fixes modified or missing OS files."
+    }
+}
+
+/// Clear Linux versions the OS as a whole, so a new version is listed as
+/// one update of all installed bundles.
+pub async fn get_updates(runner: &dyn CommandRunner) -> Result<Vec<PackageUpdate>> {
+    let output = runner.run("swupd", &["check-update"]).await?;
+    let Some((current, latest)) = parse_versions(&output.stdout) else {
+        return Err(anyhow!(
+            "Failed to check for updates (exit {}): {}",
+            output.code.unwrap_or(-1),
+            output.stderr
+        ));
+    };
+    // Exits with 1 when there is no update
+    if current == latest {
+        return Ok(Vec::new());
+    }
+
+    Ok(vec![PackageUpdate::new(OS_NAME, current, latest)])
+}
+
+/// Current and latest OS version of `swupd check-update`
+/// ("Current OS version: 40060", "Latest server version: 40090").
+fn parse_versions(output: &str) -> Option<(&str, &str)> {
+    let field = |label: &str| {
+        output.lines().find_map(|line| line.trim().strip_prefix(label)).map(str::trim)
+    };
+    Some((field("Current OS version:")?, field("Latest server version:")?))
+}
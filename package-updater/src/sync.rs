@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::fs::OpenOptions;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::app_id;
+use crate::merge::Source;
+use crate::package_manager::{CheckOrigin, UpdateInfo};
+
+/// Format of the sync file. Instances ignore messages of other versions
+/// rather than misreading them.
+const SYNC_VERSION: u32 = 1;
+
+/// A completed check, written to the sync file so the other instances can
+/// take over its results instead of running their own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncMessage {
+    pub version: u32,
+    /// Unique per check, so every instance incorporates it once
+    pub check_id: String,
+    pub timestamp: i64,
+    /// Fingerprint of the pending updates the check found
+    pub results_hash: u64,
+    pub origin: CheckOrigin,
+}
+
+impl SyncMessage {
+    pub fn new(update_info: &UpdateInfo) -> Self {
+        let origin = CheckOrigin::current();
+        let now = chrono::Local::now();
+        Self {
+            version: SYNC_VERSION,
+            check_id: format!(
+                "{}-{}-{}",
+                origin.hostname,
+                origin.pid,
+                now.timestamp_nanos_opt().unwrap_or_else(|| now.timestamp())
+            ),
+            timestamp: now.timestamp(),
+            results_hash: results_hash(update_info),
+            origin,
+        }
+    }
+
+    /// Whether an instance still has to re-check for this message: it is
+    /// not its own, not already incorporated, newer than its own last check
+    /// and found something else than it did.
+    pub fn is_news(&self, incorporated: Option<&str>, last_check_timestamp: Option<i64>, own_hash: u64) -> bool {
+        !self.origin.is_this_instance()
+            && incorporated != Some(self.check_id.as_str())
+            && last_check_timestamp.map_or(true, |last| self.timestamp > last)
+            && self.results_hash != own_hash
+    }
+}
+
+/// Pending packages and versions, independent of the order the backends
+/// listed them in.
+pub fn results_hash(update_info: &UpdateInfo) -> u64 {
+    let mut packages: Vec<(&str, &str, bool)> = update_info.packages.iter()
+        .map(|package| (package.name.as_str(), package.new_version.as_str(), package.source == Source::Aur))
+        .collect();
+    packages.sort_unstable();
+
+    let mut hasher = DefaultHasher::new();
+    packages.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn path() -> PathBuf {
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+        .unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(runtime_dir).join(format!("{}.sync", app_id::file_prefix()))
+}
+
+/// Tells the other instances about a completed check.
+pub fn publish(update_info: &UpdateInfo) {
+    let message = SyncMessage::new(update_info);
+    let Ok(payload) = serde_json::to_string(&message) else {
+        return;
+    };
+    // A single write, so readers never see a partial message
+    if let Ok(mut file) = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path())
+    {
+        let _ = file.write_all(format!("{}\n", payload).as_bytes());
+    }
+}
+
+/// The last message, `None` for an empty file, a write in progress or
+/// another protocol version.
+pub fn read() -> Option<SyncMessage> {
+    let contents = std::fs::read_to_string(path()).ok()?;
+    serde_json::from_str::<SyncMessage>(contents.trim())
+        .ok()
+        .filter(|message| message.version == SYNC_VERSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_manager::PackageUpdate;
+
+    fn update_info(packages: &[(&str, &str)]) -> UpdateInfo {
+        UpdateInfo {
+            packages: packages.iter()
+                .map(|(name, version)| PackageUpdate::new(name, "1.0", version))
+                .collect(),
+            ..UpdateInfo::new()
+        }
+    }
+
+    fn message(check_id: &str, timestamp: i64, results_hash: u64) -> SyncMessage {
+        SyncMessage {
+            version: SYNC_VERSION,
+            check_id: check_id.to_string(),
+            timestamp,
+            results_hash,
+            origin: CheckOrigin { pid: 0, hostname: "other-host".to_string(), ..CheckOrigin::current() },
+        }
+    }
+
+    #[test]
+    fn hashes_results_independent_of_order() {
+        let hash = results_hash(&update_info(&[("foo", "2.0"), ("bar", "1.1")]));
+        assert_eq!(hash, results_hash(&update_info(&[("bar", "1.1"), ("foo", "2.0")])));
+        assert_ne!(hash, results_hash(&update_info(&[("bar", "1.1"), ("foo", "2.1")])));
+    }
+
+    #[test]
+    fn ignores_known_and_outdated_checks() {
+        let own_hash = results_hash(&update_info(&[("foo", "2.0")]));
+        let other_hash = results_hash(&update_info(&[("foo", "2.1")]));
+
+        assert!(message("a", 100, other_hash).is_news(None, Some(50), own_hash));
+        // Already incorporated
+        assert!(!message("a", 100, other_hash).is_news(Some("a"), Some(50), own_hash));
+        // Older than our own last check
+        assert!(!message("a", 100, other_hash).is_news(None, Some(150), own_hash));
+        // Found the same updates we already show
+        assert!(!message("a", 100, own_hash).is_news(None, Some(50), own_hash));
+
+        let own = SyncMessage { origin: CheckOrigin::current(), ..message("b", 100, other_hash) };
+        assert!(!own.is_news(None, None, own_hash));
+    }
+}
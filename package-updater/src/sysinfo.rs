@@ -0,0 +1,174 @@
+use crate::os_release::OsRelease;
+use crate::package_manager::PackageManager;
+use crate::runner::{CommandRunner, SystemRunner};
+use crate::slackpkg;
+
+/// Overview of the system shown above the update status.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SystemSummary {
+    pub distro: String,
+    pub installed_packages: Option<usize>,
+    /// Installed size of all packages, in bytes
+    pub package_disk_bytes: Option<u64>,
+}
+
+impl SystemSummary {
+    pub fn describe_packages(&self) -> Option<String> {
+        let count = self.installed_packages?;
+        Some(match self.package_disk_bytes {
+            Some(bytes) => format!("{} packages installed · {} on disk", count, format_bytes(bytes)),
+            None => format!("{} packages installed", count),
+        })
+    }
+}
+
+pub async fn probe(package_manager: Option<PackageManager>) -> SystemSummary {
+    let mut summary = SystemSummary {
+        distro: distro_name(),
+        ..Default::default()
+    };
+
+    let Some(package_manager) = package_manager else {
+        return summary;
+    };
+
+    // The system database the backend manages; AUR helpers use pacman's
+    let (program, args): (&str, &[&str]) = match package_manager {
+        // Field names of `pacman -Qi` are translated
+        PackageManager::Pacman | PackageManager::Paru | PackageManager::Yay => ("env", &["LC_ALL=C", "pacman", "-Qi"]),
+        PackageManager::Apt => ("dpkg-query", &["-W", "-f", "${Installed-Size}\\n"]),
+        PackageManager::Dnf | PackageManager::Zypper | PackageManager::RpmOstree => ("rpm", &["-qa", "--queryformat", "%{SIZE}\\n"]),
+        PackageManager::Apk => ("apk", &["info"]),
+        PackageManager::Xbps => ("xbps-query", &["-l"]),
+        // From portage-utils, which most installs have
+        PackageManager::Emerge => ("qlist", &["-I"]),
+        PackageManager::Eopkg => ("eopkg", &["list-installed", "--no-color"]),
+        // One file per installed package
+        PackageManager::Slackpkg => ("ls", &[slackpkg::INSTALLED_DIR]),
+        // Bundles, the unit swupd installs
+        PackageManager::Swupd => ("swupd", &["bundle-list"]),
+        PackageManager::Flatpak => ("flatpak", &["list", "--columns=application"]),
+        PackageManager::Brew => ("brew", &["list", "--formula", "-1"]),
+        // Crates unindented, their binaries indented below
+        PackageManager::Cargo => ("cargo", &["install", "--list"]),
+        PackageManager::Pipx => ("pipx", &["list", "--short"]),
+        // The global node_modules directory first, then one path per package
+        PackageManager::Npm => ("npm", &["ls", "--global", "--depth=0", "--parseable"]),
+        // Store paths are not packages in this sense, neither firmware nor
+        // sysupdate images have a package database and PackageKit's depends
+        // on its backend
+        PackageManager::NixosRebuild | PackageManager::Sysupdate | PackageManager::PackageKit | PackageManager::Fwupd => return summary,
+    };
+
+    let output = match SystemRunner.run(program, args).await {
+        Ok(output) if output.success() => output.stdout,
+        Ok(output) => {
+            eprintln!("{} failed: {}", program, output.stderr);
+            return summary;
+        }
+        Err(e) => {
+            eprintln!("Failed to run {}: {}", program, e);
+            return summary;
+        }
+    };
+
+    match package_manager {
+        PackageManager::Pacman | PackageManager::Paru | PackageManager::Yay => {
+            let (count, bytes) = parse_pacman_info(&output);
+            summary.installed_packages = Some(count);
+            summary.package_disk_bytes = Some(bytes);
+        }
+        PackageManager::Apt => {
+            // Installed-Size is in KiB
+            let sizes: Vec<u64> = output.lines().map(|l| l.trim().parse().unwrap_or(0)).collect();
+            summary.installed_packages = Some(sizes.len());
+            summary.package_disk_bytes = Some(sizes.iter().sum::<u64>() * 1024);
+        }
+        PackageManager::Dnf | PackageManager::Zypper | PackageManager::RpmOstree => {
+            let sizes: Vec<u64> = output.lines().map(|l| l.trim().parse().unwrap_or(0)).collect();
+            summary.installed_packages = Some(sizes.len());
+            summary.package_disk_bytes = Some(sizes.iter().sum());
+        }
+        PackageManager::Apk
+        | PackageManager::Xbps
+        | PackageManager::Emerge
+        | PackageManager::Eopkg
+        | PackageManager::Slackpkg
+        | PackageManager::Flatpak
+        | PackageManager::Brew
+        | PackageManager::Pipx => {
+            summary.installed_packages = Some(output.lines().filter(|l| !l.trim().is_empty()).count());
+        }
+        PackageManager::Swupd => {
+            summary.installed_packages = Some(output.lines().filter(|l| l.trim_start().starts_with("- ")).count());
+        }
+        PackageManager::Cargo => {
+            summary.installed_packages = Some(output.lines().filter(|l| !l.is_empty() && !l.starts_with(char::is_whitespace)).count());
+        }
+        PackageManager::Npm => {
+            summary.installed_packages = Some(output.lines().skip(1).filter(|l| !l.trim().is_empty()).count());
+        }
+        PackageManager::NixosRebuild | PackageManager::Sysupdate | PackageManager::PackageKit | PackageManager::Fwupd => {}
+    }
+
+    summary
+}
+
+/// Name of this machine, used to tell reports from several machines apart.
+pub fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|name| name.trim().to_string())
+        .unwrap_or_else(|_| "localhost".to_string())
+}
+
+/// PRETTY_NAME from os-release, e.g. "Arch Linux" or "Fedora Linux 39 (Workstation Edition)".
+fn distro_name() -> String {
+    OsRelease::load()
+        .and_then(|os_release| os_release.pretty_name)
+        .unwrap_or_else(|| "Linux".to_string())
+}
+
+/// Package count and total "Installed Size" from `pacman -Qi`.
+fn parse_pacman_info(info: &str) -> (usize, u64) {
+    let mut count = 0;
+    let mut bytes = 0.0;
+
+    for line in info.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        match key.trim() {
+            "Name" => count += 1,
+            "Installed Size" => {
+                let mut parts = value.split_whitespace();
+                let number: f64 = parts.next().and_then(|n| n.parse().ok()).unwrap_or(0.0);
+                let unit = match parts.next() {
+                    Some("KiB") => 1024.0,
+                    Some("MiB") => 1024.0 * 1024.0,
+                    Some("GiB") => 1024.0 * 1024.0 * 1024.0,
+                    _ => 1.0,
+                };
+                bytes += number * unit;
+            }
+            _ => {}
+        }
+    }
+
+    (count, bytes as u64)
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crate::backend::{Backend, Capabilities, CheckContext};
+use crate::package_manager::PackageUpdate;
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::CommandRunner;
+
+/// systemd-sysupdate itself, for systems without updatectl (systemd < 257).
+pub const BINARY: &str = "/usr/lib/systemd/systemd-sysupdate";
+
+/// Where systemd-sysupdate looks for its transfer definitions, which only
+/// image-based systems ship.
+const DIRS: [&str; 4] = ["/etc/sysupdate.d", "/run/sysupdate.d", "/usr/local/lib/sysupdate.d", "/usr/lib/sysupdate.d"];
+
+/// Image-based systems updated with systemd-sysupdate (GNOME OS,
+/// ParticleOS): a new image is switched to as a whole on the next boot.
+pub struct Sysupdate;
+
+impl Backend for Sysupdate {
+    fn name(&self) -> &str {
+        "systemd-sysupdate"
+    }
+
+    fn detect(&self) -> bool {
+        is_available()
+    }
+
+    /// systemd-sysupdated authorizes through polkit.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { applies_on_reboot: true, ..Capabilities::default() }
+    }
+
+    fn check<'a>(&'a self, context: &'a CheckContext<'a>) -> BoxFuture<'a, Result<Vec<PackageUpdate>>> {
+        Box::pin(get_updates(context.runner))
+    }
+
+    fn update_command(&self, _privilege: PrivilegeEscalation) -> Option<String> {
+        Some("updatectl update".to_string())
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; 'updatectl list' shows the installed and available versions."
+    }
+}
+
+/// systemd ships sysupdate everywhere, it only updates systems with
+/// transfer definitions.
+fn is_available() -> bool {
+    let has_transfers = DIRS.iter().any(|dir| {
+        std::fs::read_dir(dir).is_ok_and(|entries| {
+            entries.flatten().any(|entry| entry.path().extension().is_some_and(|ext| ext == "transfer"))
+        })
+    });
+    has_transfers && Path::new(BINARY).exists()
+}
+
+/// A newer version of each sysupdate target: the host image and any
+/// components. updatectl asks systemd-sysupdated, older systems only have
+/// systemd-sysupdate itself, which lists the host's versions.
+pub async fn get_updates(runner: &dyn CommandRunner) -> Result<Vec<PackageUpdate>> {
+    let (output, versions) = match runner.run("updatectl", &["check"]).await {
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let output = runner.run(BINARY, &["list", "--no-pager"]).await?;
+            let versions = parse_list(&output.stdout).into_iter().collect();
+            (output, versions)
+        }
+        result => {
+            let output = result?;
+            let versions = parse_updatectl_check(&output.stdout);
+            (output, versions)
+        }
+    };
+    if !output.success() {
+        return Err(anyhow!("Failed to check for updates (exit {}): {}", output.code.unwrap_or(-1), output.stderr));
+    }
+
+    Ok(versions.into_iter()
+        .map(|(target, current, new)| PackageUpdate {
+            name: target,
+            current_version: current,
+            new_version: new,
+            ..Default::default()
+        })
+        .collect())
+}
+
+/// Targets with a newer version in `updatectl check` output, as (target,
+/// current, new): "host: 56 → 57", with "->" outside UTF-8 locales.
+fn parse_updatectl_check(output: &str) -> Vec<(String, String, String)> {
+    output.lines()
+        .filter_map(|line| {
+            let (target, versions) = line.split_once(": ")?;
+            let (current, new) = versions.split_once(" → ").or_else(|| versions.split_once(" -> "))?;
+            Some((target.trim().to_string(), current.trim().to_string(), new.trim().to_string()))
+        })
+        .collect()
+}
+
+/// The host's update in the `systemd-sysupdate list` table: the version
+/// assessed "current" and the "candidate", if there is one.
+/// "VERSION INSTALLED AVAILABLE ASSESSMENT", after a state symbol.
+fn parse_list(output: &str) -> Option<(String, String, String)> {
+    let mut current = None;
+    let mut candidate = None;
+    for line in output.lines().skip(1) {
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        // The state symbol and the ✓ marks hold no letters or digits
+        let Some(version) = columns.iter().find(|c| c.chars().any(|ch| ch.is_ascii_alphanumeric())) else {
+            continue;
+        };
+        match columns.last() {
+            Some(assessment) if assessment.starts_with("current") => current = Some(version.to_string()),
+            Some(&"candidate") => candidate = Some(version.to_string()),
+            _ => {}
+        }
+    }
+    Some(("host".to_string(), current.unwrap_or_else(|| "unknown".to_string()), candidate?))
+}
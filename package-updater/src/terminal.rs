@@ -0,0 +1,100 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tokio::process::Command as TokioCommand;
+
+pub fn default_fallbacks() -> Vec<String> {
+    ["cosmic-term", "alacritty", "foot", "xterm"]
+        .iter()
+        .map(|t| t.to_string())
+        .collect()
+}
+
+/// Ordered list of terminals to try: the preferred one first, then the
+/// configured fallbacks without duplicates.
+pub fn candidates(preferred: &str, fallbacks: &[String]) -> Vec<String> {
+    let mut candidates: Vec<String> = Vec::new();
+
+    for terminal in std::iter::once(preferred).chain(fallbacks.iter().map(String::as_str)) {
+        let terminal = terminal.trim();
+        if !terminal.is_empty() && !candidates.iter().any(|c| c == terminal) {
+            candidates.push(terminal.to_string());
+        }
+    }
+
+    candidates
+}
+
+pub fn marker_path() -> PathBuf {
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+        .unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(runtime_dir).join(format!("cosmic-package-updater-terminal-{}.marker", std::process::id()))
+}
+
+// Most terminals follow xterm's `-e`, a few expect the command after `--` or bare
+fn exec_args(terminal: &str) -> &'static [&'static str] {
+    let binary = Path::new(terminal)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(terminal);
+
+    match binary {
+        "foot" => &[],
+        "gnome-terminal" | "kgx" | "ptyxis" => &["--"],
+        _ => &["-e"],
+    }
+}
+
+/// Spawns `command` in the first terminal that starts successfully and
+/// returns its name. The marker file is removed by the shell once the
+/// command has finished, so callers can poll for it.
+///
+/// When no terminal can be started, the error describes the preferred
+/// terminal's failure in a form that can be shown to the user.
+pub fn spawn_first_available(candidates: &[String], command: &str, marker: &Path) -> Result<String, String> {
+    let wrapped_command = format!(
+        "{} && echo \"Update completed. Press Enter to exit...\" && read; rm -f \"{}\"",
+        command.replace("\"", "\\\""),
+        marker.display()
+    );
+
+    let mut first_error = None;
+
+    for terminal in candidates {
+        match TokioCommand::new(terminal)
+            .args(exec_args(terminal))
+            .arg("sh")
+            .arg("-c")
+            .arg(&wrapped_command)
+            .spawn()
+        {
+            Ok(_) => return Ok(terminal.clone()),
+            Err(e) => {
+                eprintln!("Failed to launch terminal '{}': {}", terminal, e);
+                if first_error.is_none() {
+                    first_error = Some(if e.kind() == ErrorKind::NotFound {
+                        format!("terminal '{}' not found", terminal)
+                    } else {
+                        format!("terminal '{}' failed to start ({})", terminal, e)
+                    });
+                }
+            }
+        }
+    }
+
+    let reason = first_error.unwrap_or_else(|| "no terminal configured".to_string());
+    if candidates.len() > 1 {
+        Err(format!(
+            "{} (also tried: {}) — configure in Settings",
+            reason,
+            candidates[1..].join(", ")
+        ))
+    } else {
+        Err(format!("{} — configure in Settings", reason))
+    }
+}
+
+pub async fn wait_for_marker_removal(marker: &Path) {
+    while marker.exists() {
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::{anyhow, Result};
+use std::io::ErrorKind;
+
+use crate::merge::Source;
+use crate::package_manager::{FlatpakInstallation, PackageUpdate};
+use crate::runner::CommandRunner;
+
+/// Runs every step topgrade finds, in the terminal.
+pub const UPDATE_COMMAND: &str = "topgrade";
+
+/// A step `topgrade --dry-run` would run, with the commands it printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub name: String,
+    pub commands: Vec<String>,
+}
+
+impl Step {
+    /// Whether the step has work left, judged by the updates the applet's
+    /// own checks found for what the step updates. None for steps the
+    /// applet has no check for, e.g. rustup.
+    pub fn pending(&self, packages: &[PackageUpdate]) -> Option<bool> {
+        let any = |matches: &dyn Fn(&Source) -> bool| packages.iter().any(|p| matches(&p.source));
+        match self.name.as_str() {
+            "System update" => Some(any(&|source| matches!(source, Source::System | Source::Aur))),
+            "Flatpak" => Some(any(&|source| matches!(source, Source::Flatpak(_)))),
+            "Flatpak User Packages" => Some(any(&|source| *source == Source::Flatpak(FlatpakInstallation::User))),
+            "Flatpak System Packages" => Some(any(&|source| *source == Source::Flatpak(FlatpakInstallation::System))),
+            "Firmware upgrades" => Some(any(&|source| *source == Source::Firmware)),
+            _ => None,
+        }
+    }
+}
+
+/// Steps of the dry run with work left: the count of topgrade mode.
+pub fn pending_count(steps: &[Step], packages: &[PackageUpdate]) -> usize {
+    steps.iter().filter(|step| step.pending(packages) == Some(true)).count()
+}
+
+/// The steps the next topgrade run would take. The dry run only prints
+/// commands, it doesn't list pending updates; see [`Step::pending`].
+pub async fn dry_run(runner: &dyn CommandRunner) -> Result<Vec<Step>> {
+    let output = match runner.run("topgrade", &["--dry-run"]).await {
+        Ok(output) => output,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Err(anyhow!("topgrade is not installed")),
+        Err(e) => return Err(e.into()),
+    };
+    if !output.success() {
+        return Err(anyhow!("topgrade --dry-run failed (exit {}): {}", output.code.unwrap_or(-1), output.stderr.trim()));
+    }
+    Ok(parse_dry_run(&output.stdout))
+}
+
+/// Each step starts with a header like "―― 10:15:40 - System update ――",
+/// followed by "Dry running: <command>" lines; the summary at the end
+/// repeats the steps and is left out.
+fn parse_dry_run(output: &str) -> Vec<Step> {
+    let mut steps: Vec<Step> = Vec::new();
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(command) = line.strip_prefix("Dry running:") {
+            if let Some(step) = steps.last_mut() {
+                step.commands.push(command.trim().to_string());
+            }
+            continue;
+        }
+        let Some(name) = step_header(line) else {
+            continue;
+        };
+        if name == "Summary" {
+            break;
+        }
+        steps.push(Step { name: name.to_string(), commands: Vec::new() });
+    }
+    steps.retain(|step| !step.commands.is_empty());
+    steps
+}
+
+fn step_header(line: &str) -> Option<&str> {
+    let rule = |c: char| matches!(c, '―' | '─' | '-' | ' ');
+    if !line.starts_with(['―', '─']) {
+        return None;
+    }
+    let (time, name) = line.trim_matches(rule).split_once(" - ")?;
+    time.chars().all(|c| c.is_ascii_digit() || c == ':').then_some(name.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_steps_of_a_dry_run() {
+        let steps = parse_dry_run(include_str!("../tests/fixtures/topgrade/dry_run.txt"));
+        let names: Vec<&str> = steps.iter().map(|step| step.name.as_str()).collect();
+        assert_eq!(names, ["System update", "Flatpak User Packages", "Flatpak System Packages", "rustup", "Firmware upgrades"]);
+        assert_eq!(steps[0].commands, ["/usr/bin/paru -Syu"]);
+        assert_eq!(steps[4].commands.len(), 2);
+    }
+
+    #[test]
+    fn steps_without_updates_have_no_pending_work() {
+        let steps = parse_dry_run(include_str!("../tests/fixtures/topgrade/dry_run.txt"));
+        let pending: Vec<Option<bool>> = steps.iter().map(|step| step.pending(&[])).collect();
+        assert_eq!(pending, [Some(false), Some(false), Some(false), None, Some(false)]);
+        assert_eq!(pending_count(&steps, &[]), 0);
+    }
+}
@@ -0,0 +1,249 @@
+use crate::http;
+use crate::package_manager::PackageManager;
+use crate::runner::{CommandRunner, SystemRunner};
+
+/// Skew beyond which the clock is a likely cause of certificate and
+/// signature failures ("not yet valid", "signature made in the future").
+const LARGE_SKEW_SECS: i64 = 5 * 60;
+
+/// Reference time source if the package manager's mirror is unknown; any
+/// server reporting a `Date` header works.
+const FALLBACK_REFERENCE_URL: &str = "https://cdn.kernel.org/";
+
+/// Backend messages for repository metadata that failed TLS verification,
+/// from curl (pacman, dnf, zypper), GnuTLS (apt) and OpenSSL (apk).
+const TLS_PATTERNS: [&str; 9] = [
+    "ssl certificate problem",
+    "certificate verify failed",
+    "certificate verification failed",
+    "certificate is not yet valid",
+    "certificate has expired",
+    "self-signed certificate",
+    "self signed certificate",
+    "unable to get local issuer certificate",
+    "curl error (60)",
+];
+
+/// Messages for metadata or packages whose GPG signature did not verify.
+const SIGNATURE_PATTERNS: [&str; 10] = [
+    "invalid or corrupted database (pgp signature)",
+    "invalid or corrupted package (pgp signature)",
+    "signature is invalid",
+    "is unknown trust",
+    "signatures couldn't be verified",
+    "signatures were invalid",
+    "no_pubkey",
+    "gpg check failed",
+    "signature verification failed",
+    "untrusted signature",
+];
+
+/// Why repository metadata could not be trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustFailure {
+    /// The mirror's certificate did not verify: wrong clock or an
+    /// intercepted connection
+    Tls,
+    /// A GPG signature did not verify: outdated keyring, wrong clock or
+    /// tampered data
+    Signature,
+    /// apt's "Release file ... is not valid yet", which only a clock
+    /// behind the mirror's produces
+    NotYetValid,
+}
+
+impl TrustFailure {
+    pub fn detect(stderr: &str) -> Option<Self> {
+        let stderr = stderr.to_lowercase();
+        if stderr.contains("is not valid yet") {
+            Some(TrustFailure::NotYetValid)
+        } else if TLS_PATTERNS.iter().any(|pattern| stderr.contains(pattern)) {
+            Some(TrustFailure::Tls)
+        } else if SIGNATURE_PATTERNS.iter().any(|pattern| stderr.contains(pattern)) {
+            Some(TrustFailure::Signature)
+        } else {
+            None
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            TrustFailure::Tls => "Repository certificate could not be verified",
+            TrustFailure::Signature => "Repository signature check failed",
+            TrustFailure::NotYetValid => "Repository metadata is dated in the future",
+        }
+    }
+
+    pub fn guidance(&self) -> &'static str {
+        match self {
+            TrustFailure::Tls => "A wrong system clock breaks certificate checks. Otherwise something intercepts \
+                the connection (captive portal, inspecting proxy, man-in-the-middle): sign in to the network or \
+                try another mirror, but don't disable verification.",
+            TrustFailure::Signature => "Update the distribution keyring (e.g. archlinux-keyring) and check the \
+                system clock. If both are fine, the mirror may serve tampered data: switch mirrors.",
+            TrustFailure::NotYetValid => "The system clock is behind the mirror's. Correct it and check again.",
+        }
+    }
+}
+
+/// What could be found out about the system clock.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClockStatus {
+    /// systemd-timesyncd (or another NTP client) reports the clock as synchronized
+    pub ntp_synchronized: Option<bool>,
+    /// Local time minus the reference server's time, in seconds
+    pub skew_secs: Option<i64>,
+}
+
+impl ClockStatus {
+    pub fn large_skew(&self) -> Option<i64> {
+        self.skew_secs.filter(|skew| skew.abs() > LARGE_SKEW_SECS)
+    }
+
+    /// Hint about the clock, if it is a likely cause.
+    pub fn describe(&self) -> Option<String> {
+        if let Some(skew) = self.large_skew() {
+            let direction = if skew > 0 { "ahead" } else { "behind" };
+            return Some(format!(
+                "The system clock is {} {}; enable time sync with `timedatectl set-ntp true`",
+                format_skew(skew.abs()),
+                direction
+            ));
+        }
+        match self.ntp_synchronized {
+            Some(false) => Some("The system clock is not synchronized with a time server".to_string()),
+            _ => None,
+        }
+    }
+}
+
+fn format_skew(secs: i64) -> String {
+    if secs >= 86400 {
+        format!("{} days", secs / 86400)
+    } else if secs >= 3600 {
+        format!("{} hours", secs / 3600)
+    } else {
+        format!("{} minutes", secs / 60)
+    }
+}
+
+/// Root of the first configured mirror, so the clock is compared with the
+/// server the metadata comes from.
+pub fn reference_url(package_manager: Option<PackageManager>) -> String {
+    let sources: &[&str] = match package_manager {
+        Some(PackageManager::Pacman | PackageManager::Paru | PackageManager::Yay) => &["/etc/pacman.d/mirrorlist"],
+        Some(PackageManager::Apt) => &[
+            "/etc/apt/sources.list",
+            "/etc/apt/sources.list.d/debian.sources",
+            "/etc/apt/sources.list.d/ubuntu.sources",
+        ],
+        Some(PackageManager::Apk) => &["/etc/apk/repositories"],
+        // dnf and zypper mostly resolve mirrors through metalinks
+        _ => &[],
+    };
+
+    sources.iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .find_map(|contents| first_mirror(&contents))
+        .unwrap_or_else(|| FALLBACK_REFERENCE_URL.to_string())
+}
+
+/// `scheme://host/` of the first URL in a mirror list, sources.list,
+/// deb822 `.sources` file or apk repositories file.
+fn first_mirror(contents: &str) -> Option<String> {
+    let url = contents.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == '='))
+        .find(|word| word.starts_with("http://") || word.starts_with("https://"))?;
+
+    let (scheme, rest) = url.split_once("://")?;
+    let host = rest.split('/').next().filter(|host| !host.is_empty())?;
+    Some(format!("{}://{}/", scheme, host))
+}
+
+pub async fn check_clock(reference_url: &str, proxy: &str) -> ClockStatus {
+    let ntp_synchronized = match SystemRunner.run("timedatectl", &["show", "--property=NTPSynchronized", "--value"]).await {
+        Ok(output) if output.success() => match output.stdout.trim() {
+            "yes" => Some(true),
+            "no" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    // Only the Date header is read, and a skewed clock is exactly what
+    // makes certificate validation fail
+    let skew_secs = match http::head_insecure(reference_url, proxy).await {
+        Ok(headers) => parse_date_header(&headers)
+            .map(|server_time| chrono::Utc::now().timestamp() - server_time),
+        Err(e) => {
+            eprintln!("Failed to query reference time: {}", e);
+            None
+        }
+    };
+
+    ClockStatus { ntp_synchronized, skew_secs }
+}
+
+/// Unix time of an HTTP `Date` header ("Sat, 17 Oct 2026 10:00:00 GMT").
+fn parse_date_header(headers: &str) -> Option<i64> {
+    headers.lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("date"))
+        .and_then(|(_, value)| chrono::DateTime::parse_from_rfc2822(value.trim()).ok())
+        .map(|date| date.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_backend_trust_failures() {
+        assert_eq!(
+            TrustFailure::detect("error: failed retrieving file 'core.db' from mirror.example.org : SSL certificate problem: certificate is not yet valid"),
+            Some(TrustFailure::Tls)
+        );
+        assert_eq!(
+            TrustFailure::detect("error: core: signature from \"Arch Linux\" is unknown trust\nerror: failed to synchronize all databases (invalid or corrupted database (PGP signature))"),
+            Some(TrustFailure::Signature)
+        );
+        assert_eq!(
+            TrustFailure::detect("W: GPG error: http://deb.debian.org/debian bookworm InRelease: The following signatures couldn't be verified because the public key is not available: NO_PUBKEY 6ED0E7B82643E131"),
+            Some(TrustFailure::Signature)
+        );
+        assert_eq!(
+            TrustFailure::detect("E: Release file for http://deb.debian.org/debian/dists/bookworm/InRelease is not valid yet (invalid for another 2h 3min 4s)."),
+            Some(TrustFailure::NotYetValid)
+        );
+        assert_eq!(TrustFailure::detect("error: could not resolve host: mirror.example.org"), None);
+    }
+
+    #[test]
+    fn finds_mirror_to_compare_clock_with() {
+        assert_eq!(
+            first_mirror("## Worldwide\n#Server = https://geo.mirror.pkgbuild.com/$repo/os/$arch\nServer = https://mirror.example.org/archlinux/$repo/os/$arch\n").as_deref(),
+            Some("https://mirror.example.org/")
+        );
+        assert_eq!(
+            first_mirror("deb [arch=amd64] http://deb.debian.org/debian bookworm main\n").as_deref(),
+            Some("http://deb.debian.org/")
+        );
+        assert_eq!(
+            first_mirror("Types: deb\nURIs: http://archive.ubuntu.com/ubuntu/\nSuites: noble\n").as_deref(),
+            Some("http://archive.ubuntu.com/")
+        );
+        assert_eq!(first_mirror("/media/cdrom/apks\n"), None);
+    }
+
+    #[test]
+    fn measures_clock_skew_from_date_header() {
+        let headers = "HTTP/2 200\r\ncontent-type: text/html\r\ndate: Sat, 17 Oct 2026 10:00:00 GMT\r\n";
+        assert_eq!(parse_date_header(headers), Some(1_792_231_200));
+        assert_eq!(parse_date_header("HTTP/2 200\r\n"), None);
+
+        let status = ClockStatus { ntp_synchronized: Some(true), skew_secs: Some(-3 * 3600) };
+        assert_eq!(status.describe().as_deref(), Some("The system clock is 3 hours behind; enable time sync with `timedatectl set-ntp true`"));
+        assert_eq!(ClockStatus { ntp_synchronized: Some(true), skew_secs: Some(30) }.describe(), None);
+    }
+}
@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::package_manager::PackageManager;
+use crate::privilege::PrivilegeEscalation;
+
+/// One source updated by "Update Everything".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    System(PackageManager),
+    /// AUR packages only, through the given helper
+    Aur(PackageManager),
+    Flatpak,
+    Firmware,
+}
+
+impl Step {
+    pub fn label(&self) -> String {
+        match self {
+            Step::System(pm) => format!("System ({})", pm.name()),
+            Step::Aur(helper) => format!("AUR ({})", helper.name()),
+            Step::Flatpak => "Flatpak".to_string(),
+            Step::Firmware => "Firmware".to_string(),
+        }
+    }
+
+    /// Identifies the step in the progress file and the persisted state.
+    pub fn key(&self) -> String {
+        match self {
+            Step::System(pm) => format!("system-{}", pm.name()),
+            Step::Aur(helper) => format!("aur-{}", helper.name()),
+            Step::Flatpak => "flatpak".to_string(),
+            Step::Firmware => "firmware".to_string(),
+        }
+    }
+
+    // System → AUR → Flatpak → firmware
+    fn rank(&self) -> u8 {
+        match self {
+            Step::System(_) => 0,
+            Step::Aur(_) => 1,
+            Step::Flatpak => 2,
+            Step::Firmware => 3,
+        }
+    }
+
+    fn command(&self, privilege: PrivilegeEscalation) -> String {
+        match self {
+            Step::System(pm) => pm.system_update_command(privilege),
+            Step::Aur(helper) => match privilege.aur_helper_flag() {
+                Some(flag) => format!("{} {} -Sua", helper.name(), flag),
+                None => format!("{} -Sua", helper.name()),
+            },
+            Step::Flatpak => PackageManager::Flatpak.system_update_command(privilege),
+            // fwupdmgr exits with 2 when there is nothing to update, and a
+            // refresh within the metadata's lifetime is refused the same way
+            Step::Firmware => "fwupdmgr refresh >/dev/null 2>&1; fwupdmgr update || [ $? -eq 2 ]".to_string(),
+        }
+    }
+}
+
+/// Steps for the configured package managers, in the order they run. An
+/// AUR helper's update is split so the official packages go first.
+pub fn plan(package_managers: &[PackageManager], include_aur: bool, firmware: bool) -> Vec<Step> {
+    let mut steps: Vec<Step> = Vec::new();
+    for &pm in package_managers {
+        let pm_steps = match pm {
+            PackageManager::Flatpak => vec![Step::Flatpak],
+            PackageManager::Fwupd => vec![Step::Firmware],
+            helper if helper.capabilities().aur => {
+                let mut pm_steps = vec![Step::System(PackageManager::Pacman)];
+                if include_aur {
+                    pm_steps.push(Step::Aur(helper));
+                }
+                pm_steps
+            }
+            pm => vec![Step::System(pm)],
+        };
+        for step in pm_steps {
+            if !steps.contains(&step) {
+                steps.push(step);
+            }
+        }
+    }
+    if firmware && !steps.contains(&Step::Firmware) {
+        steps.push(Step::Firmware);
+    }
+    steps.sort_by_key(Step::rank);
+    steps
+}
+
+/// File the steps append their outcome to while running in the terminal.
+pub fn progress_path() -> PathBuf {
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+        .unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(runtime_dir).join(format!("cosmic-package-updater-update-all-{}.progress", std::process::id()))
+}
+
+/// Shell command running the steps one after another, stopping at the
+/// first failure. The terminal stays open on a failure so the error can
+/// be read.
+pub fn command(steps: &[Step], privilege: PrivilegeEscalation, progress: &Path) -> String {
+    let progress = progress.display();
+    let chain: Vec<String> = steps.iter()
+        .map(|step| format!(
+            "{{ echo '==> {label}' && {{ {command}; }} && echo '{key} ok' >> '{progress}' || {{ echo '{key} failed' >> '{progress}'; false; }}; }}",
+            label = step.label(),
+            command = step.command(privilege),
+            key = step.key(),
+        ))
+        .collect();
+    format!(
+        "{} || {{ echo 'Stopped at a failed step. Press Enter to exit...'; read _; false; }}",
+        chain.join(" && ")
+    )
+}
+
+/// Outcome of the steps run so far, kept while a run is unfinished so it
+/// can be resumed.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default)]
+pub struct RunProgress {
+    /// Keys of the steps that succeeded
+    pub completed: Vec<String>,
+    /// Key of the step the run stopped at
+    pub failed: Option<String>,
+}
+
+impl RunProgress {
+    pub fn parse(contents: &str) -> Self {
+        let mut progress = Self::default();
+        for line in contents.lines() {
+            match line.trim().rsplit_once(' ') {
+                Some((key, "ok")) => progress.completed.push(key.to_string()),
+                Some((key, "failed")) => progress.failed = Some(key.to_string()),
+                _ => {}
+            }
+        }
+        progress
+    }
+
+    pub fn read(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .map(|contents| Self::parse(&contents))
+            .unwrap_or_default()
+    }
+
+    /// Adds a resumed run's outcome to the steps completed before.
+    pub fn resumed(mut self, run: RunProgress) -> Self {
+        self.completed.extend(run.completed);
+        self.failed = run.failed;
+        self
+    }
+
+    pub fn is_completed(&self, step: &Step) -> bool {
+        self.completed.contains(&step.key())
+    }
+
+    /// The planned steps still to run.
+    pub fn remaining(&self, steps: &[Step]) -> Vec<Step> {
+        steps.iter().filter(|step| !self.is_completed(step)).copied().collect()
+    }
+
+    pub fn summary(&self, steps: &[Step]) -> String {
+        let labels = |keep: &dyn Fn(&Step) -> bool| -> String {
+            steps.iter().filter(|step| keep(step)).map(Step::label).collect::<Vec<_>>().join(", ")
+        };
+
+        let mut parts = Vec::new();
+        let updated = labels(&|step| self.is_completed(step));
+        if !updated.is_empty() {
+            parts.push(format!("Updated: {}", updated));
+        }
+        let failed = labels(&|step| self.failed == Some(step.key()));
+        if !failed.is_empty() {
+            parts.push(format!("Failed: {}", failed));
+        }
+        let skipped = labels(&|step| !self.is_completed(step) && self.failed != Some(step.key()));
+        if !skipped.is_empty() {
+            parts.push(format!("Not run: {}", skipped));
+        }
+        parts.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plans_steps_in_source_order() {
+        let steps = plan(&[PackageManager::Flatpak, PackageManager::Paru], true, true);
+        assert_eq!(steps, vec![
+            Step::System(PackageManager::Pacman),
+            Step::Aur(PackageManager::Paru),
+            Step::Flatpak,
+            Step::Firmware,
+        ]);
+
+        let steps = plan(&[PackageManager::Paru, PackageManager::Pacman], false, false);
+        assert_eq!(steps, vec![Step::System(PackageManager::Pacman)]);
+
+        // fwupd as a source is the firmware step
+        let steps = plan(&[PackageManager::Dnf, PackageManager::Fwupd], false, true);
+        assert_eq!(steps, vec![Step::System(PackageManager::Dnf), Step::Firmware]);
+    }
+
+    #[test]
+    fn resumes_after_failed_step() {
+        let steps = plan(&[PackageManager::Paru, PackageManager::Flatpak], true, false);
+        let first = RunProgress::parse("system-pacman ok\naur-paru failed\n");
+        assert_eq!(first.remaining(&steps), vec![Step::Aur(PackageManager::Paru), Step::Flatpak]);
+        assert_eq!(first.summary(&steps), "Updated: System (pacman)\nFailed: AUR (paru)\nNot run: Flatpak");
+
+        let progress = first.resumed(RunProgress::parse("aur-paru ok\nflatpak ok\n"));
+        assert!(progress.remaining(&steps).is_empty());
+        assert_eq!(progress.failed, None);
+    }
+}
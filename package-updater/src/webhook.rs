@@ -0,0 +1,105 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::export::StatusSnapshot;
+use crate::http;
+use crate::package_manager::UpdateInfo;
+
+/// How the report is delivered to the configured URL.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookFormat {
+    /// The [`Report`] as a JSON body, for custom collectors
+    #[default]
+    Json,
+    /// A short plain-text message for an ntfy topic URL
+    Ntfy,
+}
+
+impl WebhookFormat {
+    pub const ALL: [WebhookFormat; 2] = [WebhookFormat::Json, WebhookFormat::Ntfy];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookFormat::Json => "JSON",
+            WebhookFormat::Ntfy => "ntfy",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportedPackage {
+    pub name: String,
+    pub current_version: String,
+    pub new_version: String,
+    pub is_security: bool,
+}
+
+/// Update status of one machine. The status fields are the ones of
+/// [`StatusSnapshot`], so collectors can share a parser with the status file.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub host: String,
+    #[serde(flatten)]
+    pub status: StatusSnapshot,
+    pub packages: Vec<ReportedPackage>,
+}
+
+impl Report {
+    pub fn new(host: String, status: StatusSnapshot, update_info: &UpdateInfo) -> Self {
+        let packages = update_info.packages.iter()
+            .map(|p| ReportedPackage {
+                name: p.name.clone(),
+                current_version: p.current_version.clone(),
+                new_version: p.new_version.clone(),
+                is_security: p.is_security,
+            })
+            .collect();
+
+        Self { host, status, packages }
+    }
+
+    fn ntfy_message(&self) -> String {
+        if let Some(error) = &self.status.error {
+            return format!("Update check failed: {}", error);
+        }
+        if self.packages.is_empty() {
+            return "System is up to date".to_string();
+        }
+
+        let mut names: Vec<&str> = self.packages.iter().take(10).map(|p| p.name.as_str()).collect();
+        if self.packages.len() > names.len() {
+            names.push("…");
+        }
+        let security = self.packages.iter().filter(|p| p.is_security).count();
+        let mut message = format!("{} updates available: {}", self.status.update_count, names.join(", "));
+        if security > 0 {
+            message.push_str(&format!(" ({} security)", security));
+        }
+        message
+    }
+}
+
+/// POSTs the report with curl, which the kernel changelog fetch relies on too.
+pub async fn post(url: &str, format: WebhookFormat, report: &Report, proxy: &str) -> Result<()> {
+    let mut command = http::curl(proxy);
+    command.args(["-X", "POST"]);
+
+    match format {
+        WebhookFormat::Json => {
+            command
+                .args(["-H", "Content-Type: application/json"])
+                .args(["--data-binary", &serde_json::to_string(report)?]);
+        }
+        WebhookFormat::Ntfy => {
+            command
+                .args(["-H", &format!("Title: {}", report.host)])
+                .args(["-H", "Tags: package"])
+                .args(["--data-binary", &report.ntfy_message()]);
+        }
+    }
+
+    http::send(command.arg(url))
+        .await
+        .map_err(|e| anyhow!("Webhook request failed: {}", e))?;
+    Ok(())
+}
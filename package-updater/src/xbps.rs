@@ -0,0 +1,80 @@
+use anyhow::Result;
+
+use crate::backend::{self, Backend, Capabilities};
+use crate::package_manager::{CheckMode, PackageUpdate};
+use crate::privilege::PrivilegeEscalation;
+use crate::runner::shell_quote;
+
+/// Void Linux.
+pub struct Xbps;
+
+impl Backend for Xbps {
+    fn name(&self) -> &str {
+        "xbps"
+    }
+
+    fn detect(&self) -> bool {
+        backend::on_path("xbps-install")
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { selective_update: true, needs_privileges: true, ..Capabilities::default() }
+    }
+
+    /// Dry-run of `xbps-install -Su`. Syncing the repository index (-S)
+    /// needs root, -M fetches it into memory instead; quick checks use the
+    /// index on disk.
+    fn check_command(&self, mode: CheckMode) -> Option<(&str, Vec<&str>)> {
+        Some(("xbps-install", if mode == CheckMode::Quick { vec!["-un"] } else { vec!["-Mun"] }))
+    }
+
+    /// "name-version_revision action arch repository installed-size download-size"
+    fn parse_line(&self, line: &str) -> Option<PackageUpdate> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        // New dependencies are listed as "install"
+        if parts.get(1) != Some(&"update") {
+            return None;
+        }
+        let (name, new_version) = parts[0].rsplit_once('-')?;
+        Some(PackageUpdate::new(name, "unknown", new_version))
+    }
+
+    fn is_noise(&self, line: &str) -> bool {
+        matches!(
+            line.split_whitespace().nth(1),
+            Some("install" | "remove" | "reinstall" | "configure" | "hold")
+        )
+    }
+
+    /// "name-version_revision remove ..."
+    fn parse_removal(&self, line: &str) -> Option<String> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.get(1) != Some(&"remove") {
+            return None;
+        }
+        parts[0].rsplit_once('-').map(|(name, _)| name.to_string())
+    }
+
+    fn root_check_command(&self, root: &str) -> Result<(&str, Vec<String>)> {
+        Ok(("xbps-install", ["-r", root, "-Mun"].map(str::to_string).to_vec()))
+    }
+
+    fn update_command(&self, privilege: PrivilegeEscalation) -> Option<String> {
+        Some(privilege.wrap("xbps-install -Su"))
+    }
+
+    fn packages_update_command(&self, privilege: PrivilegeEscalation, packages: &[String]) -> Option<String> {
+        if packages.is_empty() {
+            return None;
+        }
+        Some(privilege.wrap(&format!("xbps-install -u {}", packages.join(" "))))
+    }
+
+    fn root_update_command(&self, privilege: PrivilegeEscalation, root: &str) -> Option<String> {
+        Some(privilege.wrap(&format!("xbps-install -r {} -Su", shell_quote(root))))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; 'xbps-pkgdb -a' checks the package database."
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{anyhow, Result};
+
+use crate::backend::{self, Backend, Capabilities};
+use crate::package_manager::{CheckMode, PackageUpdate};
+use crate::privilege::PrivilegeEscalation;
+
+/// openSUSE and SUSE Linux Enterprise.
+pub struct Zypper;
+
+impl Backend for Zypper {
+    fn name(&self) -> &str {
+        "zypper"
+    }
+
+    fn detect(&self) -> bool {
+        backend::on_path("zypper")
+    }
+
+    /// zypper keeps its metadata in root-owned caches.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { selective_update: true, needs_privileges: true, ..Capabilities::default() }
+    }
+
+    fn check_command(&self, _mode: CheckMode) -> Option<(&str, Vec<&str>)> {
+        Some(("zypper", vec!["--no-color", "list-updates"]))
+    }
+
+    /// "S | Repository | Name | Current Version | Available Version | Arch"
+    fn parse_line(&self, line: &str) -> Option<PackageUpdate> {
+        let parts: Vec<&str> = line.split('|').map(str::trim).collect();
+        if parts.len() < 5 {
+            return None;
+        }
+        Some(PackageUpdate {
+            repository: Some(parts[1].to_string()),
+            ..PackageUpdate::new(parts[2], parts[3], parts[4])
+        })
+    }
+
+    /// Progress messages around the table.
+    fn is_noise(&self, line: &str) -> bool {
+        !line.contains('|')
+    }
+
+    /// dnf and zypper refresh their caches under the root, which needs
+    /// root and changes the root, even when only checking.
+    fn root_check_command(&self, _root: &str) -> Result<(&str, Vec<String>)> {
+        Err(anyhow!("zypper roots aren't supported: checking one writes its caches under the root"))
+    }
+
+    fn update_command(&self, privilege: PrivilegeEscalation) -> Option<String> {
+        Some(privilege.wrap("zypper update"))
+    }
+
+    fn packages_update_command(&self, privilege: PrivilegeEscalation, packages: &[String]) -> Option<String> {
+        if packages.is_empty() {
+            return None;
+        }
+        Some(privilege.wrap(&format!("zypper update {}", packages.join(" "))))
+    }
+
+    fn security_update_command(&self, privilege: PrivilegeEscalation, _security_packages: &[String]) -> Option<String> {
+        Some(privilege.wrap("zypper patch --category security"))
+    }
+
+    fn failed_update_guidance(&self) -> &str {
+        "Run the update again and read the error; 'zypper verify' lists broken dependencies."
+    }
+}